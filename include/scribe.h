@@ -0,0 +1,80 @@
+/*
+ * C API for scribe buffers, exported by the crate's shared library when it's
+ * built with the `ffi` feature (using `cargo rustc --release --features ffi
+ * --crate-type cdylib`). See the `ffi` module's documentation for the
+ * ownership rules of the pointers and strings passed to these functions.
+ */
+
+#ifndef SCRIBE_H
+#define SCRIBE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* An opaque buffer handle. */
+typedef struct ScribeBuffer ScribeBuffer;
+
+/* An opaque token iterator handle. */
+typedef struct ScribeTokens ScribeTokens;
+
+/* A position within a buffer, whose offset is measured in grapheme clusters. */
+typedef struct {
+    size_t line;
+    size_t offset;
+} ScribePosition;
+
+/* A range within a buffer (end exclusive). */
+typedef struct {
+    ScribePosition start;
+    ScribePosition end;
+} ScribeRange;
+
+typedef enum {
+    SCRIBE_TOKEN_KIND_LEXEME,
+    SCRIBE_TOKEN_KIND_NEWLINE,
+} ScribeTokenKind;
+
+/*
+ * A token produced by scribe_tokens_next. For lexemes, value holds their
+ * content and scope their innermost scope (e.g. "keyword.control.rust"), both
+ * of which remain valid until the token iterator is released. For newlines,
+ * both are null.
+ */
+typedef struct {
+    ScribeTokenKind kind;
+    const char *value;
+    const char *scope;
+    ScribePosition position;
+} ScribeToken;
+
+ScribeBuffer *scribe_buffer_new(void);
+void scribe_buffer_free(ScribeBuffer *buffer);
+bool scribe_buffer_set_syntax(ScribeBuffer *buffer, const char *extension);
+
+char *scribe_buffer_data(const ScribeBuffer *buffer);
+char *scribe_buffer_read(const ScribeBuffer *buffer, ScribeRange range);
+
+ScribePosition scribe_buffer_cursor(const ScribeBuffer *buffer);
+bool scribe_buffer_move_cursor(ScribeBuffer *buffer, ScribePosition position);
+
+bool scribe_buffer_insert(ScribeBuffer *buffer, const char *content);
+bool scribe_buffer_delete(ScribeBuffer *buffer);
+bool scribe_buffer_delete_range(ScribeBuffer *buffer, ScribeRange range);
+bool scribe_buffer_undo(ScribeBuffer *buffer);
+bool scribe_buffer_redo(ScribeBuffer *buffer);
+
+ScribeTokens *scribe_buffer_tokens(const ScribeBuffer *buffer);
+const ScribeToken *scribe_tokens_next(ScribeTokens *tokens);
+void scribe_tokens_free(ScribeTokens *tokens);
+
+void scribe_string_free(char *content);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SCRIBE_H */
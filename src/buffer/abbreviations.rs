@@ -0,0 +1,120 @@
+//! Expansion of abbreviations (e.g. "teh" into "the") as they're typed.
+
+use buffer::{Buffer, Distance};
+
+impl Buffer {
+    /// Inserts the character at the cursor and moves the cursor past it. If
+    /// the character is a word boundary (i.e. not alphanumeric or an
+    /// underscore) and the word preceding the cursor is one of the buffer's
+    /// `abbreviations`, the word is replaced with its expansion. The expansion
+    /// and the boundary are recorded as a single operation, so that undoing it
+    /// restores the abbreviation. Returns true if an expansion took place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.abbreviations.insert("lib".to_string(), "library".to_string());
+    /// for character in "scribe lib".chars() {
+    ///     buffer.insert_expanding(character);
+    /// }
+    ///
+    /// assert!(buffer.insert_expanding(' '));
+    /// assert_eq!(buffer.data(), "scribe library ");
+    ///
+    /// buffer.undo();
+    /// assert_eq!(buffer.data(), "scribe lib");
+    /// ```
+    pub fn insert_expanding(&mut self, character: char) -> bool {
+        let position = *self.cursor;
+        let boundary = character.to_string();
+        let is_boundary = !(character.is_alphanumeric() || character == '_');
+
+        let word = if is_boundary {
+            self.word_ranges(position.line).into_iter()
+                .find(|range| range.end() == position)
+        } else {
+            None
+        };
+        let expansion = word.and_then(|range| {
+            self.read(&range)
+                .and_then(|word| self.abbreviations.get(&word).cloned())
+                .map(|expansion| (range, expansion))
+        });
+
+        match expansion {
+            Some((range, expansion)) => {
+                let content = format!("{}{}", expansion, boundary);
+                let end = range.start() + Distance::of_str(&content);
+                self.replace(range, content);
+                self.cursor.move_to(end);
+
+                true
+            },
+            None => {
+                let end = position + Distance::of_str(&boundary);
+                self.insert(boundary);
+                self.cursor.move_to(end);
+
+                false
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, Position};
+
+    fn buffer_with_abbreviations() -> Buffer {
+        let mut buffer = Buffer::new();
+        buffer.abbreviations.insert("sig".to_string(), "Regards,\nscribe".to_string());
+        buffer.abbreviations.insert("lib".to_string(), "library".to_string());
+
+        buffer
+    }
+
+    fn type_into(buffer: &mut Buffer, content: &str) {
+        for character in content.chars() {
+            buffer.insert_expanding(character);
+        }
+    }
+
+    #[test]
+    fn insert_expanding_only_expands_whole_words() {
+        let mut buffer = buffer_with_abbreviations();
+        type_into(&mut buffer, "libs mylib lib_ lib.");
+
+        assert_eq!(buffer.data(), "libs mylib lib_ library.");
+    }
+
+    #[test]
+    fn insert_expanding_handles_multi_line_expansions() {
+        let mut buffer = buffer_with_abbreviations();
+        type_into(&mut buffer, "sig\n");
+
+        assert_eq!(buffer.data(), "Regards,\nscribe\n");
+        assert_eq!(*buffer.cursor, Position{ line: 2, offset: 0 });
+    }
+
+    #[test]
+    fn insert_expanding_does_not_expand_without_a_boundary() {
+        let mut buffer = buffer_with_abbreviations();
+
+        assert!(!buffer.insert_expanding('l'));
+        assert!(!buffer.insert_expanding('i'));
+        assert!(!buffer.insert_expanding('b'));
+        assert_eq!(buffer.data(), "lib");
+        assert_eq!(*buffer.cursor, Position{ line: 0, offset: 3 });
+    }
+    #[test]
+    fn insert_expanding_moves_to_the_next_line_after_unexpanded_newlines() {
+        let mut buffer = buffer_with_abbreviations();
+        type_into(&mut buffer, "scribe\nlib");
+
+        assert_eq!(buffer.data(), "scribe\nlib");
+        assert_eq!(*buffer.cursor, Position{ line: 1, offset: 3 });
+    }
+}
@@ -0,0 +1,269 @@
+//! Per-line annotations (e.g. blame or coverage data) supplied by external sources.
+
+use buffer::{Buffer, LineRange, Position, Range, TrackingId};
+
+/// A string attached to a line by a source of annotations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Annotation {
+    /// The (zero-based) line number.
+    pub line: usize,
+
+    /// The name of the annotation's source (e.g. "blame").
+    pub source: String,
+
+    pub text: String,
+}
+
+/// The end of an annotation's tracked range that lies on its line. Empty
+/// ranges are never invalidated, so annotations on empty lines also track
+/// the newline that follows them (or precedes them, for the last line).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineAnchor {
+    Start,
+    End,
+}
+
+/// Supplies a set of line annotations for a buffer (see `annotate_with`).
+pub trait AnnotationProvider {
+    /// The name identifying the provider's annotations.
+    fn source(&self) -> &str;
+
+    /// Returns (line number, text) pairs for the buffer's annotated lines.
+    fn annotations(&self, buffer: &Buffer) -> Vec<(usize, String)>;
+}
+
+impl Buffer {
+    /// Attaches a string to the specified line. The line's content is tracked
+    /// (see `track_range`), so the annotation follows the line as the buffer
+    /// is edited, and is removed if the line is deleted. Returns an ID that
+    /// can be used to remove the annotation, or `None` if the line doesn't
+    /// exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{Annotation, LineRange};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("library");
+    /// buffer.annotate(0, "coverage", "3 hits");
+    /// buffer.insert("scribe\n");
+    ///
+    /// assert_eq!(buffer.annotations(&LineRange::new(0, 2)), vec![Annotation{
+    ///     line: 1,
+    ///     source: "coverage".to_string(),
+    ///     text: "3 hits".to_string(),
+    /// }]);
+    /// ```
+    pub fn annotate<S: Into<String>, T: Into<String>>(&mut self, line: usize, source: S, text: T) -> Option<TrackingId> {
+        let end = self.clamp(Position{ line, offset: usize::max_value() });
+        if end.line != line {
+            return None
+        }
+
+        self.prune_annotations();
+
+        let start = Position{ line, offset: 0 };
+        let next_line = Position{ line: line + 1, offset: 0 };
+        let (range, anchor) =
+            if end.offset > 0 {
+                (Range::new(start, end), LineAnchor::Start)
+            } else if self.clamp(next_line) == next_line {
+                (Range::new(start, next_line), LineAnchor::Start)
+            } else if line > 0 {
+                let previous_line_end = self.clamp(Position{ line: line - 1, offset: usize::max_value() });
+                (Range::new(previous_line_end, start), LineAnchor::End)
+            } else {
+                // The buffer is empty; there's no line to delete.
+                (Range::new(start, start), LineAnchor::Start)
+            };
+
+        let id = self.track_range(range);
+        self.annotations.push((id, anchor, source.into(), text.into()));
+
+        Some(id)
+    }
+
+    /// Replaces the annotations from the provider's
+    /// source with those it currently supplies.
+    pub fn annotate_with(&mut self, provider: &AnnotationProvider) {
+        self.clear_annotations(provider.source());
+
+        for (line, text) in provider.annotations(self) {
+            self.annotate(line, provider.source(), text);
+        }
+    }
+
+    /// Removes the specified annotation.
+    pub fn remove_annotation(&mut self, id: TrackingId) {
+        self.annotations.retain(|&(annotation_id, _, _, _)| annotation_id != id);
+        self.untrack(id);
+    }
+
+    /// Removes all annotations from the specified source.
+    pub fn clear_annotations(&mut self, source: &str) {
+        self.prune_annotations();
+
+        let ids: Vec<TrackingId> = self.annotations.iter()
+            .filter(|&&(_, _, ref annotation_source, _)| annotation_source == source)
+            .map(|&(id, _, _, _)| id)
+            .collect();
+
+        for id in ids {
+            self.remove_annotation(id);
+        }
+    }
+
+    // Removes annotations whose lines have been deleted, and stops tracking them.
+    fn prune_annotations(&mut self) {
+        let ids: Vec<TrackingId> = self.annotations.iter()
+            .filter(|&&(id, _, _, _)| self.tracked_range(id).is_none())
+            .map(|&(id, _, _, _)| id)
+            .collect();
+
+        for id in ids {
+            self.remove_annotation(id);
+        }
+    }
+
+    /// Returns the annotations for lines in the specified range (excluding the
+    /// line on which it ends), ordered by line and then the order in which
+    /// they were added.
+    pub fn annotations(&self, lines: &LineRange) -> Vec<Annotation> {
+        let mut annotations: Vec<Annotation> = self.annotations.iter()
+            .filter_map(|&(id, anchor, ref source, ref text)| {
+                self.tracked_range(id).map(|range| Annotation{
+                    line: match anchor {
+                        LineAnchor::Start => range.start().line,
+                        LineAnchor::End => range.end().line,
+                    },
+                    source: source.clone(),
+                    text: text.clone(),
+                })
+            })
+            .filter(|annotation| lines.includes(annotation.line))
+            .collect();
+        annotations.sort_by_key(|annotation| annotation.line);
+
+        annotations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Annotation, AnnotationProvider, Buffer, LineRange, Position, Range};
+
+    struct LineLengths;
+
+    impl AnnotationProvider for LineLengths {
+        fn source(&self) -> &str {
+            "length"
+        }
+
+        fn annotations(&self, buffer: &Buffer) -> Vec<(usize, String)> {
+            buffer.data().split('\n').map(|line| line.len().to_string()).enumerate().collect()
+        }
+    }
+
+    fn texts(buffer: &Buffer) -> Vec<(usize, String)> {
+        buffer.annotations(&LineRange::new(0, 100)).into_iter()
+            .map(|annotation| (annotation.line, annotation.text))
+            .collect()
+    }
+
+    #[test]
+    fn annotate_returns_none_for_lines_beyond_the_buffer() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+
+        assert!(buffer.annotate(1, "blame", "author").is_none());
+    }
+
+    #[test]
+    fn annotations_are_removed_when_their_lines_are_deleted() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary\neditor");
+        buffer.annotate(1, "blame", "library author");
+        buffer.annotate(2, "blame", "editor author");
+        buffer.delete_range(Range::new(Position{ line: 1, offset: 0 }, Position{ line: 2, offset: 0 }));
+
+        assert_eq!(buffer.annotations(&LineRange::new(0, 3)), vec![Annotation{
+            line: 1,
+            source: "blame".to_string(),
+            text: "editor author".to_string(),
+        }]);
+    }
+
+    #[test]
+    fn annotations_on_empty_lines_are_removed_when_their_lines_are_deleted() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\n\nlibrary\n");
+        buffer.annotate(1, "blame", "empty line author");
+        buffer.annotate(3, "blame", "last line author");
+        buffer.annotate(2, "blame", "library author");
+        buffer.delete_range(Range::new(Position{ line: 1, offset: 0 }, Position{ line: 2, offset: 0 }));
+
+        assert_eq!(texts(&buffer), vec![
+            (1, "library author".to_string()),
+            (2, "last line author".to_string()),
+        ]);
+
+        buffer.delete_range(Range::new(Position{ line: 1, offset: 7 }, Position{ line: 2, offset: 0 }));
+
+        assert_eq!(texts(&buffer), vec![(1, "library author".to_string())]);
+    }
+
+    #[test]
+    fn annotations_on_empty_lines_follow_their_lines() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\n\n");
+        buffer.annotate(1, "blame", "empty line author");
+        buffer.annotate(2, "blame", "last line author");
+        buffer.cursor.move_to(Position{ line: 0, offset: 0 });
+        buffer.insert("\n");
+
+        assert_eq!(texts(&buffer), vec![
+            (2, "empty line author".to_string()),
+            (3, "last line author".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn annotations_are_pruned_once_their_lines_are_deleted() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+        buffer.annotate(1, "blame", "author");
+        buffer.delete_range(Range::new(Position{ line: 0, offset: 6 }, Position{ line: 1, offset: 7 }));
+        buffer.annotate(0, "blame", "author");
+
+        assert_eq!(buffer.annotations.len(), 1);
+    }
+
+    #[test]
+    fn annotate_with_replaces_the_providers_annotations() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+        buffer.annotate(0, "blame", "author");
+        buffer.annotate_with(&LineLengths);
+        buffer.insert("text ");
+        buffer.annotate_with(&LineLengths);
+
+        assert_eq!(texts(&buffer), vec![
+            (0, "author".to_string()),
+            (0, "11".to_string()),
+            (1, "7".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn clear_annotations_only_removes_annotations_from_the_source() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        buffer.annotate(0, "blame", "author");
+        buffer.annotate(0, "coverage", "3 hits");
+        buffer.clear_annotations("blame");
+
+        assert_eq!(texts(&buffer), vec![(0, "3 hits".to_string())]);
+    }
+}
@@ -0,0 +1,117 @@
+//! Position validation against buffer content.
+
+use buffer::{Buffer, Position};
+use std::cmp;
+use unicode_segmentation::UnicodeSegmentation;
+
+impl Buffer {
+    /// Whether or not the position refers to a location in the buffer. Offsets
+    /// at the end of a line (after its last character) are valid, as is the
+    /// position at the end of the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::Position;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\n");
+    ///
+    /// assert!(buffer.is_valid(&Position{ line: 0, offset: 6 }));
+    /// assert!(buffer.is_valid(&Position{ line: 1, offset: 0 }));
+    /// assert!(!buffer.is_valid(&Position{ line: 0, offset: 7 }));
+    /// ```
+    pub fn is_valid(&self, position: &Position) -> bool {
+        self.data.borrow().in_bounds(position)
+    }
+
+    /// Returns the closest valid position to the one specified. Offsets
+    /// beyond the end of a line are moved to its end, and positions beyond
+    /// the last line are moved to the end of the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::Position;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\nlibrary");
+    ///
+    /// assert_eq!(
+    ///     buffer.clamp(Position{ line: 0, offset: 10 }),
+    ///     Position{ line: 0, offset: 6 }
+    /// );
+    /// assert_eq!(
+    ///     buffer.clamp(Position{ line: 5, offset: 0 }),
+    ///     Position{ line: 1, offset: 7 }
+    /// );
+    /// ```
+    pub fn clamp(&self, position: Position) -> Position {
+        let data = self.data();
+        let lines: Vec<&str> = data.split('\n').collect();
+
+        // There's always at least one (possibly empty) line.
+        let last_line = lines.len() - 1;
+        if position.line > last_line {
+            return Position{ line: last_line, offset: lines[last_line].graphemes(true).count() }
+        }
+
+        Position{
+            line: position.line,
+            offset: cmp::min(position.offset, lines[position.line].graphemes(true).count())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, Position};
+
+    #[test]
+    fn clamp_leaves_valid_positions_unchanged() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nनी library");
+
+        let position = Position{ line: 1, offset: 2 };
+        assert_eq!(buffer.clamp(position), position);
+    }
+
+    #[test]
+    fn clamp_uses_grapheme_offsets() {
+        let mut buffer = Buffer::new();
+        buffer.insert("नी");
+
+        assert_eq!(buffer.clamp(Position{ line: 0, offset: 5 }), Position{ line: 0, offset: 1 });
+    }
+
+    #[test]
+    fn clamp_handles_trailing_newlines() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\n");
+
+        assert_eq!(buffer.clamp(Position{ line: 3, offset: 3 }), Position{ line: 1, offset: 0 });
+    }
+
+    #[test]
+    fn clamped_positions_are_valid() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+
+        for line in 0..4 {
+            for offset in 0..10 {
+                let position = buffer.clamp(Position{ line, offset });
+                assert!(buffer.is_valid(&position));
+            }
+        }
+    }
+
+    #[test]
+    fn clamp_handles_empty_buffers() {
+        let buffer = Buffer::new();
+
+        assert_eq!(buffer.clamp(Position{ line: 1, offset: 1 }), Position{ line: 0, offset: 0 });
+        assert!(buffer.is_valid(&Position{ line: 0, offset: 0 }));
+    }
+}
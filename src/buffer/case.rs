@@ -0,0 +1,162 @@
+//! Commands for changing the case of buffer content.
+
+use buffer::{Buffer, Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A letter case that content can be converted to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Case {
+    Upper,
+    Lower,
+    Title,
+}
+
+impl Case {
+    /// Converts the specified content to this case. Conversions are
+    /// Unicode-aware, and may change the content's length (e.g. "ß"
+    /// is upper-cased to "SS"). Title case capitalizes the first letter
+    /// of each word and lower-cases the rest.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::Case;
+    ///
+    /// assert_eq!(Case::Upper.convert("straße"), "STRASSE");
+    /// assert_eq!(Case::Title.convert("scribe LIBRARY"), "Scribe Library");
+    /// ```
+    pub fn convert(&self, content: &str) -> String {
+        match *self {
+            Case::Upper => content.to_uppercase(),
+            Case::Lower => content.to_lowercase(),
+            Case::Title => {
+                content.split_word_bounds().map(|word| {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) => {
+                            let mut title_word: String = first.to_uppercase().collect();
+                            title_word.push_str(&chars.as_str().to_lowercase());
+                            title_word
+                        },
+                        None => String::new(),
+                    }
+                }).collect()
+            },
+        }
+    }
+}
+
+impl Buffer {
+    /// Converts the content within the specified range to the specified case,
+    /// as a single undoable operation. The cursor is left in place, unless
+    /// the conversion shortens its line such that it'd be out of bounds, in
+    /// which case it's moved to the start of the range.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{Case, Position, Range};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe library");
+    /// buffer.change_case(
+    ///     Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 6 }),
+    ///     Case::Upper
+    /// );
+    ///
+    /// assert_eq!(buffer.data(), "SCRIBE library");
+    /// ```
+    pub fn change_case(&mut self, range: Range, case: Case) {
+        let content = match self.read(&range) {
+            Some(content) => content,
+            None => return,
+        };
+
+        // Don't add a no-op to the history.
+        let converted_content = case.convert(&content);
+        if converted_content == content {
+            return
+        }
+
+        let position = *self.cursor;
+        self.replace(range.clone(), converted_content);
+        if !self.cursor.move_to(position) {
+            self.cursor.move_to(range.start());
+        }
+    }
+
+    /// Converts the word at the cursor (see `word_range_at`) to the
+    /// specified case, as a single undoable operation. If there's no
+    /// word at the cursor, the buffer is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{Case, Position};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe library");
+    /// buffer.cursor.move_to(Position{ line: 0, offset: 9 });
+    /// buffer.change_word_case(Case::Title);
+    ///
+    /// assert_eq!(buffer.data(), "scribe Library");
+    /// ```
+    pub fn change_word_case(&mut self, case: Case) {
+        let position = *self.cursor;
+        if let Some(range) = self.word_range_at(position) {
+            self.change_case(range, case);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, Case, Position, Range};
+
+    #[test]
+    fn convert_title_cases_words_separated_by_punctuation() {
+        assert_eq!(Case::Title.convert("émile's scribe-library"), "Émile's Scribe-Library");
+    }
+
+    #[test]
+    fn change_case_is_undone_as_a_single_operation() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+        buffer.change_case(
+            Range::new(Position{ line: 0, offset: 3 }, Position{ line: 1, offset: 3 }),
+            Case::Upper
+        );
+        assert_eq!(buffer.data(), "scrIBE\nLIBrary");
+
+        buffer.undo();
+        assert_eq!(buffer.data(), "scribe\nlibrary");
+    }
+
+    #[test]
+    fn change_case_handles_conversions_that_change_the_content_length() {
+        let mut buffer = Buffer::new();
+        buffer.insert("straße");
+        buffer.cursor.move_to(Position{ line: 0, offset: 6 });
+        buffer.change_case(
+            Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 6 }),
+            Case::Upper
+        );
+        assert_eq!(buffer.data(), "STRASSE");
+        assert_eq!(*buffer.cursor, Position{ line: 0, offset: 6 });
+
+        buffer.undo();
+        assert_eq!(buffer.data(), "straße");
+    }
+
+    #[test]
+    fn change_word_case_does_nothing_without_a_word_at_the_cursor() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe  library");
+        buffer.cursor.move_to(Position{ line: 0, offset: 7 });
+        buffer.change_word_case(Case::Upper);
+
+        assert_eq!(buffer.data(), "scribe  library");
+    }
+}
@@ -0,0 +1,152 @@
+//! Conversions between grapheme offsets and display columns.
+
+use buffer::{Buffer, Position};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
+
+impl Buffer {
+    /// Returns the display column of the specified position, expanding tabs
+    /// to the next multiple of the specified width. Returns `None` if the
+    /// position is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::Position;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("a\tscribe");
+    ///
+    /// assert_eq!(buffer.display_column(&Position{ line: 0, offset: 2 }, 4), Some(4));
+    /// assert_eq!(buffer.display_column(&Position{ line: 0, offset: 9 }, 4), None);
+    /// ```
+    pub fn display_column(&self, position: &Position, tab_width: usize) -> Option<usize> {
+        let line = self.line(position.line)?;
+
+        let mut column = 0;
+        let mut graphemes = line.graphemes(true);
+        for _ in 0..position.offset {
+            column += grapheme_width(graphemes.next()?, column, tab_width);
+        }
+
+        Some(column)
+    }
+
+    /// Returns the offset on the specified line that's displayed at the
+    /// specified column, expanding tabs to the next multiple of the specified
+    /// width. Columns within a grapheme (e.g. an expanded tab) are snapped to
+    /// its start, and columns beyond the end of the line are snapped to its
+    /// end. Returns `None` if the line doesn't exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("a\tscribe");
+    ///
+    /// assert_eq!(buffer.offset_at_display_column(0, 3, 4), Some(1));
+    /// assert_eq!(buffer.offset_at_display_column(0, 4, 4), Some(2));
+    /// assert_eq!(buffer.offset_at_display_column(0, 20, 4), Some(8));
+    /// ```
+    pub fn offset_at_display_column(&self, line: usize, column: usize, tab_width: usize) -> Option<usize> {
+        let line = self.line(line)?;
+
+        let mut current_column = 0;
+        for (offset, grapheme) in line.graphemes(true).enumerate() {
+            current_column += grapheme_width(grapheme, current_column, tab_width);
+            if current_column > column {
+                return Some(offset)
+            }
+        }
+
+        Some(line.graphemes(true).count())
+    }
+}
+
+/// Returns the number of columns used to display the
+/// grapheme, when it's displayed at the specified column.
+///
+/// East Asian wide characters occupy two columns. Combining marks and
+/// joined sequences (e.g. emoji joined with zero-width joiners) are rendered
+/// as a single glyph, so a grapheme is as wide as its widest character.
+/// Control characters and other zero-width graphemes occupy no columns.
+pub fn grapheme_width(grapheme: &str, column: usize, tab_width: usize) -> usize {
+    if grapheme == "\t" {
+        let tab_width = tab_width.max(1);
+        tab_width - column % tab_width
+    } else {
+        grapheme.chars()
+            .filter_map(|c| c.width())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, Position};
+
+    #[test]
+    fn display_column_expands_consecutive_tabs() {
+        let mut buffer = Buffer::new();
+        buffer.insert("\t\tscribe");
+
+        assert_eq!(buffer.display_column(&Position{ line: 0, offset: 2 }, 8), Some(16));
+    }
+
+    #[test]
+    fn display_column_counts_graphemes() {
+        let mut buffer = Buffer::new();
+        buffer.insert("नी\ta");
+
+        assert_eq!(buffer.display_column(&Position{ line: 0, offset: 3 }, 4), Some(5));
+    }
+
+    #[test]
+    fn display_column_counts_wide_characters_as_two_columns() {
+        let mut buffer = Buffer::new();
+        buffer.insert("漢字\tscribe");
+
+        assert_eq!(buffer.display_column(&Position{ line: 0, offset: 1 }, 4), Some(2));
+        assert_eq!(buffer.display_column(&Position{ line: 0, offset: 3 }, 4), Some(8));
+    }
+
+    #[test]
+    fn display_column_treats_joined_sequences_as_a_single_glyph() {
+        let mut buffer = Buffer::new();
+        buffer.insert("\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}a");
+
+        assert_eq!(buffer.display_column(&Position{ line: 0, offset: 1 }, 4), Some(2));
+    }
+
+    #[test]
+    fn offset_at_display_column_snaps_to_the_start_of_wide_characters() {
+        let mut buffer = Buffer::new();
+        buffer.insert("a漢字");
+
+        assert_eq!(buffer.offset_at_display_column(0, 2, 4), Some(1));
+        assert_eq!(buffer.offset_at_display_column(0, 3, 4), Some(2));
+    }
+
+    #[test]
+    fn display_column_and_offset_at_display_column_are_inverses() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\n  \tlibrary\t editor");
+
+        for offset in 0..18 {
+            let column = buffer.display_column(&Position{ line: 1, offset }, 4).unwrap();
+            assert_eq!(buffer.offset_at_display_column(1, column, 4), Some(offset));
+        }
+    }
+
+    #[test]
+    fn offset_at_display_column_returns_none_for_missing_lines() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+
+        assert_eq!(buffer.offset_at_display_column(1, 0, 4), None);
+    }
+}
@@ -0,0 +1,407 @@
+//! Syntax-aware comment toggling.
+
+use buffer::{Buffer, LineRange, Position, Range};
+#[cfg(feature = "syntax")]
+use buffer::{Scope, Token};
+use errors::*;
+use std::cmp;
+use unicode_segmentation::UnicodeSegmentation;
+
+// Comment tokens for known languages, keyed by their top-level scope.
+#[cfg(feature = "syntax")]
+const COMMENT_TOKENS: &[(&str, Option<&str>, Option<(&str, &str)>)] = &[
+    ("source.rust",         Some("//"), Some(("/*", "*/"))),
+    ("source.c",            Some("//"), Some(("/*", "*/"))),
+    ("source.c++",          Some("//"), Some(("/*", "*/"))),
+    ("source.objc",         Some("//"), Some(("/*", "*/"))),
+    ("source.objc++",       Some("//"), Some(("/*", "*/"))),
+    ("source.cs",           Some("//"), Some(("/*", "*/"))),
+    ("source.d",            Some("//"), Some(("/*", "*/"))),
+    ("source.go",           Some("//"), Some(("/*", "*/"))),
+    ("source.java",         Some("//"), Some(("/*", "*/"))),
+    ("source.js",           Some("//"), Some(("/*", "*/"))),
+    ("source.ts",           Some("//"), Some(("/*", "*/"))),
+    ("source.scala",        Some("//"), Some(("/*", "*/"))),
+    ("source.swift",        Some("//"), Some(("/*", "*/"))),
+    ("source.php",          Some("//"), Some(("/*", "*/"))),
+    ("source.css",          None,       Some(("/*", "*/"))),
+    ("source.sql",          Some("--"), Some(("/*", "*/"))),
+    ("source.lua",          Some("--"), Some(("--[[", "]]"))),
+    ("source.haskell",      Some("--"), Some(("{-", "-}"))),
+    ("source.ocaml",        None,       Some(("(*", "*)"))),
+    ("source.python",       Some("#"),  None),
+    ("source.ruby",         Some("#"),  Some(("=begin", "=end"))),
+    ("source.shell",        Some("#"),  None),
+    ("source.perl",         Some("#"),  None),
+    ("source.r",            Some("#"),  None),
+    ("source.yaml",         Some("#"),  None),
+    ("source.toml",         Some("#"),  None),
+    ("source.makefile",     Some("#"),  None),
+    ("source.elixir",       Some("#"),  None),
+    ("source.erlang",       Some("%"),  None),
+    ("text.tex",            Some("%"),  None),
+    ("source.clojure",      Some(";"),  None),
+    ("source.lisp",         Some(";"),  None),
+    ("text.html",           None,       Some(("<!--", "-->"))),
+    ("text.xml",            None,       Some(("<!--", "-->"))),
+];
+
+/// The tokens used to comment out content in a particular language.
+/// Languages may support line comments, block comments, or both.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CommentTokens {
+    pub line: Option<&'static str>,
+    pub block: Option<(&'static str, &'static str)>,
+}
+
+#[cfg(feature = "syntax")]
+impl CommentTokens {
+    /// Looks up the comment tokens for the language represented by the
+    /// specified scope (or any of its more specific descendants).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::{CommentTokens, Scope};
+    ///
+    /// let tokens = CommentTokens::for_scope(Scope::new("source.python").unwrap());
+    /// assert_eq!(tokens, Some(CommentTokens{ line: Some("#"), block: None }));
+    /// ```
+    pub fn for_scope(scope: Scope) -> Option<CommentTokens> {
+        COMMENT_TOKENS.iter().find(|&&(language_scope, _, _)| {
+            Scope::new(language_scope)
+                .map(|s| s.is_prefix_of(scope))
+                .unwrap_or(false)
+        }).map(|&(_, line, block)| CommentTokens{ line, block })
+    }
+}
+
+impl Buffer {
+    /// Returns the comment tokens for the language at the start of the
+    /// specified line. Embedded languages (e.g. JavaScript in HTML) are
+    /// considered by inspecting the line's scope stack, falling back to the
+    /// buffer's syntax definition if nothing more specific is found.
+    ///
+    /// Comment tokens set using `set_comment_tokens` are used for the
+    /// buffer's own language, in place of the built-in ones.
+    #[cfg(feature = "syntax")]
+    pub fn comment_tokens(&self, line: usize) -> Result<CommentTokens> {
+        let tokens = match self.tokens() {
+            Ok(tokens) => tokens,
+            Err(error) => return self.comment_tokens.ok_or(error),
+        };
+
+        for token in tokens.iter() {
+            if let Token::Lexeme(lexeme) = token {
+                if lexeme.position.line > line {
+                    break;
+                }
+
+                // Skip leading whitespace, which is often
+                // scoped to the surrounding language.
+                if lexeme.position.line < line || lexeme.value.trim().is_empty() {
+                    continue;
+                }
+
+                // Prefer the innermost (most specific) language.
+                let language_tokens = lexeme.scope.as_slice().iter().rev()
+                    .filter_map(|&scope| self.language_comment_tokens(scope))
+                    .next();
+                if let Some(comment_tokens) = language_tokens {
+                    return Ok(comment_tokens)
+                }
+
+                break;
+            }
+        }
+
+        self.syntax_definition.as_ref()
+            .and_then(|def| self.language_comment_tokens(def.scope))
+            .ok_or_else(|| ErrorKind::MissingCommentTokens.into())
+    }
+
+    /// Returns the comment tokens set using `set_comment_tokens`. Without
+    /// the `syntax` feature, buffers have no syntax definition from which
+    /// to determine their language, and so no built-in comment tokens.
+    #[cfg(not(feature = "syntax"))]
+    pub fn comment_tokens(&self, _: usize) -> Result<CommentTokens> {
+        self.comment_tokens.ok_or_else(|| ErrorKind::MissingSyntaxDefinition.into())
+    }
+
+    /// Sets the comment tokens used for the buffer's own language (as
+    /// determined by its syntax definition), or restores the built-in ones.
+    pub fn set_comment_tokens(&mut self, comment_tokens: Option<CommentTokens>) {
+        self.comment_tokens = comment_tokens;
+    }
+
+    // Looks up the comment tokens for the language represented by the scope,
+    // preferring those set for the buffer's own language.
+    #[cfg(feature = "syntax")]
+    fn language_comment_tokens(&self, scope: Scope) -> Option<CommentTokens> {
+        let buffer_language = self.syntax_definition.as_ref().map(|def| def.scope == scope).unwrap_or(false);
+        if buffer_language && self.comment_tokens.is_some() {
+            return self.comment_tokens
+        }
+
+        CommentTokens::for_scope(scope)
+    }
+
+    /// Comments out the specified lines (excluding the line on which the
+    /// range ends) or, if they're all commented out already, uncomments them.
+    ///
+    /// Line comments are preferred over block comments, which are applied to
+    /// each line individually. Comment markers are aligned at the lines'
+    /// common indentation, and blank lines are left untouched. The change is
+    /// recorded as a single undoable operation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate scribe;
+    /// # #[cfg(feature = "syntax")]
+    /// # extern crate syntect;
+    /// use scribe::Buffer;
+    /// use scribe::buffer::LineRange;
+    /// # #[cfg(feature = "syntax")]
+    /// # use syntect::parsing::SyntaxSet;
+    ///
+    /// # #[cfg(feature = "syntax")]
+    /// # fn main() {
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("fn main() {\n    let x = 1;\n\n        x + 1\n}");
+    ///
+    /// // Omitted code to set up the buffer's syntax definition.
+    /// # let syntax_set = SyntaxSet::load_defaults_newlines();
+    /// # buffer.syntax_definition = syntax_set.find_syntax_by_extension("rs").cloned();
+    ///
+    /// buffer.toggle_comments(&LineRange::new(1, 4)).unwrap();
+    /// assert_eq!(buffer.data(), "fn main() {\n    // let x = 1;\n\n    //     x + 1\n}");
+    ///
+    /// buffer.toggle_comments(&LineRange::new(1, 4)).unwrap();
+    /// assert_eq!(buffer.data(), "fn main() {\n    let x = 1;\n\n        x + 1\n}");
+    /// # }
+    /// # #[cfg(not(feature = "syntax"))]
+    /// # fn main() {}
+    /// ```
+    pub fn toggle_comments(&mut self, lines: &LineRange) -> Result<()> {
+        let comment_tokens = self.comment_tokens(lines.start())?;
+        let (start_token, end_token) =
+            match (comment_tokens.line, comment_tokens.block) {
+                (Some(line_token), _) => (line_token, None),
+                (None, Some((start, end))) => (start, Some(end)),
+                (None, None) => bail!(ErrorKind::MissingCommentTokens),
+            };
+
+        let data = self.data();
+        let buffer_lines: Vec<&str> = data.split('\n').collect();
+        // The range excludes the line on which it ends.
+        let end_line = cmp::min(lines.end(), buffer_lines.len());
+        if lines.start() >= end_line {
+            return Ok(())
+        }
+        let original_lines = &buffer_lines[lines.start()..end_line];
+
+        // Blank lines are neither commented nor considered when deciding
+        // whether or not the lines have already been commented out.
+        let commented = original_lines.iter()
+            .filter(|line| !line.trim().is_empty())
+            .all(|line| {
+                let content = line.trim();
+                content.starts_with(start_token) &&
+                    end_token.map(|t| content.ends_with(t)).unwrap_or(true)
+            });
+        // Lines may be indented using different whitespace characters,
+        // so comment tokens are placed after the indentation they share.
+        let indentation = original_lines.iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| &line[..line.len() - line.trim_start().len()])
+            .fold(None, |shared: Option<&str>, line_indentation| match shared {
+                Some(shared) => Some(common_prefix(shared, line_indentation)),
+                None => Some(line_indentation),
+            });
+        let indentation = match indentation {
+            Some(indentation) => indentation.len(),
+            None => return Ok(()),
+        };
+
+        let updated_lines: Vec<String> = original_lines.iter().map(|line| {
+            if line.trim().is_empty() {
+                line.to_string()
+            } else if commented {
+                uncomment(line, start_token, end_token)
+            } else {
+                let mut commented_line = format!(
+                    "{}{} {}",
+                    &line[..indentation],
+                    start_token,
+                    &line[indentation..]
+                );
+                if let Some(token) = end_token {
+                    commented_line.push(' ');
+                    commented_line.push_str(token);
+                }
+
+                commented_line
+            }
+        }).collect();
+
+        let range = Range::new(
+            Position{ line: lines.start(), offset: 0 },
+            Position{ line: end_line - 1, offset: buffer_lines[end_line - 1].graphemes(true).count() }
+        );
+        self.replace(range, updated_lines.join("\n"));
+
+        // Keep the cursor within the bounds of its (possibly shortened) line.
+        let position = self.clamp(*self.cursor);
+        self.cursor.move_to(position);
+
+        Ok(())
+    }
+}
+
+// Removes comment tokens (and the spaces that pad them) from a line.
+fn uncomment(line: &str, start_token: &str, end_token: Option<&str>) -> String {
+    let content_start = line.len() - line.trim_start().len();
+    let mut content = &line[content_start + start_token.len()..];
+    if content.starts_with(' ') {
+        content = &content[1..];
+    }
+
+    if let Some(token) = end_token {
+        content = content.trim_end();
+        if content.ends_with(token) {
+            content = &content[..content.len() - token.len()];
+        }
+        if content.ends_with(' ') {
+            content = &content[..content.len() - 1];
+        }
+    }
+
+    format!("{}{}", &line[..content_start], content)
+}
+
+// Returns the longest prefix (on a character boundary) shared by both strings.
+fn common_prefix<'a>(a: &'a str, b: &str) -> &'a str {
+    let length = a.char_indices().zip(b.chars())
+        .take_while(|&((_, a_char), b_char)| a_char == b_char)
+        .last()
+        .map(|((index, character), _)| index + character.len_utf8())
+        .unwrap_or(0);
+
+    &a[..length]
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "syntax")]
+    use syntect::parsing::SyntaxSet;
+    use buffer::{Buffer, CommentTokens, LineRange};
+    #[cfg(feature = "syntax")]
+    use buffer::{Position, Scope};
+
+    #[cfg(feature = "syntax")]
+    fn buffer_with_syntax(data: &str, extension: &str) -> Buffer {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let mut buffer = Buffer::new();
+        buffer.insert(data);
+        buffer.syntax_definition = syntax_set.find_syntax_by_extension(extension).cloned();
+
+        buffer
+    }
+
+    #[test]
+    #[cfg(feature = "syntax")]
+    fn for_scope_matches_more_specific_scopes() {
+        assert_eq!(
+            CommentTokens::for_scope(Scope::new("source.js.embedded.html").unwrap()),
+            Some(CommentTokens{ line: Some("//"), block: Some(("/*", "*/")) })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "syntax")]
+    fn for_scope_does_not_match_unknown_scopes() {
+        assert!(CommentTokens::for_scope(Scope::new("text.plain").unwrap()).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "syntax")]
+    fn toggle_comments_comments_lines_if_any_are_uncommented() {
+        let mut buffer = buffer_with_syntax("# scribe\nlibrary", "py");
+        buffer.toggle_comments(&LineRange::new(0, 2)).unwrap();
+
+        assert_eq!(buffer.data(), "# # scribe\n# library");
+    }
+
+    #[test]
+    #[cfg(feature = "syntax")]
+    fn toggle_comments_uses_block_comments_when_line_comments_are_unavailable() {
+        let mut buffer = buffer_with_syntax("a {\n  color: red;\n}", "css");
+        buffer.toggle_comments(&LineRange::new(1, 2)).unwrap();
+        assert_eq!(buffer.data(), "a {\n  /* color: red; */\n}");
+
+        buffer.toggle_comments(&LineRange::new(1, 2)).unwrap();
+        assert_eq!(buffer.data(), "a {\n  color: red;\n}");
+    }
+
+    #[test]
+    #[cfg(feature = "syntax")]
+    fn toggle_comments_is_undone_as_a_single_operation() {
+        let mut buffer = buffer_with_syntax("scribe\nlibrary", "rs");
+        buffer.toggle_comments(&LineRange::new(0, 2)).unwrap();
+        buffer.undo();
+
+        assert_eq!(buffer.data(), "scribe\nlibrary");
+    }
+
+    #[test]
+    #[cfg(feature = "syntax")]
+    fn toggle_comments_keeps_the_cursor_in_bounds() {
+        let mut buffer = buffer_with_syntax("// scribe", "rs");
+        buffer.cursor.move_to(Position{ line: 0, offset: 9 });
+        buffer.toggle_comments(&LineRange::new(0, 1)).unwrap();
+
+        assert_eq!(buffer.data(), "scribe");
+        assert_eq!(*buffer.cursor, Position{ line: 0, offset: 6 });
+    }
+
+    #[test]
+    fn toggle_comments_requires_a_syntax_definition() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+
+        assert!(buffer.toggle_comments(&LineRange::new(0, 1)).is_err());
+    }
+
+    #[test]
+    fn toggle_comments_uses_comment_tokens_set_without_a_syntax_definition() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        buffer.set_comment_tokens(Some(CommentTokens{ line: Some("#"), block: None }));
+        buffer.toggle_comments(&LineRange::new(0, 1)).unwrap();
+
+        assert_eq!(buffer.data(), "# scribe");
+    }
+
+    #[test]
+    #[cfg(feature = "syntax")]
+    fn toggle_comments_excludes_the_line_on_which_the_range_ends() {
+        let mut buffer = buffer_with_syntax("scribe\nlibrary\neditor", "rs");
+        buffer.toggle_comments(&LineRange::new(1, 2)).unwrap();
+        assert_eq!(buffer.data(), "scribe\n// library\neditor");
+
+        buffer.toggle_comments(&LineRange::new(1, 1)).unwrap();
+        assert_eq!(buffer.data(), "scribe\n// library\neditor");
+    }
+
+    #[test]
+    #[cfg(feature = "syntax")]
+    fn toggle_comments_handles_lines_indented_with_different_whitespace() {
+        let mut buffer = buffer_with_syntax("\u{3000}scribe\n  library\n \u{3000}editor", "rs");
+        buffer.toggle_comments(&LineRange::new(0, 3)).unwrap();
+        assert_eq!(buffer.data(), "// \u{3000}scribe\n//   library\n//  \u{3000}editor");
+
+        buffer.toggle_comments(&LineRange::new(1, 3)).unwrap();
+        assert_eq!(buffer.data(), "// \u{3000}scribe\n  library\n \u{3000}editor");
+    }
+}
@@ -0,0 +1,208 @@
+//! Detection and resolution of version control merge conflicts.
+
+use buffer::{Buffer, LineRange, Position, Range};
+
+/// A region of conflicting changes, delimited by `<<<<<<<`, `=======`, and
+/// `>>>>>>>` marker lines (with an optional `|||||||` base section). All of
+/// the line ranges exclude the line on which they end, and those of the
+/// individual sections exclude their marker lines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict {
+    /// The entire region, including its marker lines.
+    pub lines: LineRange,
+
+    /// Our side of the conflict.
+    pub ours: LineRange,
+
+    /// The common ancestor's content, if present (e.g. using
+    /// git's "diff3" conflict style).
+    pub base: Option<LineRange>,
+
+    /// Their side of the conflict.
+    pub theirs: LineRange,
+}
+
+/// The content with which to replace a conflict.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConflictResolution {
+    Ours,
+    Theirs,
+
+    /// Our side of the conflict, followed by theirs.
+    Both,
+}
+
+impl Buffer {
+    /// Returns the merge conflicts in the buffer, in the order in which they
+    /// appear. Incomplete conflicts (e.g. missing their closing marker) are
+    /// ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{Conflict, LineRange};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\n<<<<<<< HEAD\nlibrary\n=======\neditor\n>>>>>>> branch\n");
+    ///
+    /// assert_eq!(buffer.conflicts(), vec![Conflict{
+    ///     lines: LineRange::new(1, 6),
+    ///     ours: LineRange::new(2, 3),
+    ///     base: None,
+    ///     theirs: LineRange::new(4, 5),
+    /// }]);
+    /// ```
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let data = self.data();
+        let mut conflicts = Vec::new();
+        let mut start = None;
+        let mut base_start = None;
+        let mut separator = None;
+
+        for (line, content) in data.split('\n').enumerate() {
+            if is_marker(content, "<<<<<<<") {
+                start = Some(line);
+                base_start = None;
+                separator = None;
+            } else if is_marker(content, "|||||||") && start.is_some() && separator.is_none() {
+                base_start = Some(line);
+            } else if is_marker(content, "=======") && start.is_some() && separator.is_none() {
+                separator = Some(line);
+            } else if is_marker(content, ">>>>>>>") {
+                if let (Some(start), Some(separator)) = (start, separator) {
+                    let ours_end = base_start.unwrap_or(separator);
+
+                    conflicts.push(Conflict{
+                        lines: LineRange::new(start, line + 1),
+                        ours: LineRange::new(start + 1, ours_end),
+                        base: base_start.map(|base_start| LineRange::new(base_start + 1, separator)),
+                        theirs: LineRange::new(separator + 1, line),
+                    });
+                }
+
+                start = None;
+                base_start = None;
+                separator = None;
+            }
+        }
+
+        conflicts
+    }
+
+    /// Replaces the conflict containing the specified line with the content
+    /// of one or both of its sides, as a single undoable operation, moving
+    /// the cursor to the start of the replacement. Returns false if the
+    /// line isn't part of a conflict.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::ConflictResolution;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\n<<<<<<< HEAD\nlibrary\n=======\neditor\n>>>>>>> branch\n");
+    ///
+    /// assert!(buffer.resolve_conflict(3, ConflictResolution::Both));
+    /// assert_eq!(buffer.data(), "scribe\nlibrary\neditor\n");
+    ///
+    /// buffer.undo();
+    /// assert!(buffer.resolve_conflict(1, ConflictResolution::Theirs));
+    /// assert_eq!(buffer.data(), "scribe\neditor\n");
+    /// ```
+    pub fn resolve_conflict(&mut self, line: usize, resolution: ConflictResolution) -> bool {
+        let conflict = match self.conflicts().into_iter().find(|conflict| conflict.lines.includes(line)) {
+            Some(conflict) => conflict,
+            None => return false,
+        };
+
+        let sections = match resolution {
+            ConflictResolution::Ours => vec![conflict.ours],
+            ConflictResolution::Theirs => vec![conflict.theirs],
+            ConflictResolution::Both => vec![conflict.ours, conflict.theirs],
+        };
+
+        let data = self.data();
+        let lines: Vec<&str> = data.split('\n').collect();
+        let mut content = String::new();
+        for section in sections {
+            for line in section.iter() {
+                content.push_str(lines[line]);
+                content.push('\n');
+            }
+        }
+
+        // A conflict on the last line has no trailing newline to replace.
+        let end = Position{ line: conflict.lines.end(), offset: 0 };
+        if end.line >= lines.len() {
+            content.pop();
+        }
+
+        let start = Position{ line: conflict.lines.start(), offset: 0 };
+        self.replace(Range::new(start, self.clamp(end)), content);
+        self.cursor.move_to(start);
+
+        true
+    }
+}
+
+// Whether or not the line is a conflict marker of the specified type,
+// optionally followed by a label (e.g. a branch name).
+fn is_marker(line: &str, marker: &str) -> bool {
+    line.starts_with(marker) && line[marker.len()..].chars().next().map_or(true, |c| c.is_whitespace())
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, Conflict, ConflictResolution, LineRange, Position};
+
+    #[test]
+    fn conflicts_include_base_sections() {
+        let mut buffer = Buffer::new();
+        buffer.insert("<<<<<<< ours\nscribe\n||||||| base\nlibrary\n=======\n>>>>>>> theirs");
+
+        assert_eq!(buffer.conflicts(), vec![Conflict{
+            lines: LineRange::new(0, 6),
+            ours: LineRange::new(1, 2),
+            base: Some(LineRange::new(3, 4)),
+            theirs: LineRange::new(5, 5),
+        }]);
+    }
+
+    #[test]
+    fn conflicts_ignores_incomplete_and_lookalike_markers() {
+        let mut buffer = Buffer::new();
+        buffer.insert("<<<<<<< ours\nscribe\n>>>>>>> theirs\n========\nlibrary\n<<<<<<<<\n");
+
+        assert!(buffer.conflicts().is_empty());
+    }
+
+    #[test]
+    fn resolve_conflict_handles_conflicts_ending_on_the_last_line() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\n<<<<<<<\nlibrary\n=======\neditor\n>>>>>>>");
+
+        assert!(buffer.resolve_conflict(5, ConflictResolution::Ours));
+        assert_eq!(buffer.data(), "scribe\nlibrary");
+        assert_eq!(*buffer.cursor, Position{ line: 1, offset: 0 });
+    }
+
+    #[test]
+    fn resolve_conflict_ignores_base_sections() {
+        let mut buffer = Buffer::new();
+        buffer.insert("<<<<<<<\nscribe\n|||||||\nlibrary\n=======\neditor\n>>>>>>>\n");
+
+        assert!(buffer.resolve_conflict(0, ConflictResolution::Both));
+        assert_eq!(buffer.data(), "scribe\neditor\n");
+    }
+
+    #[test]
+    fn resolve_conflict_returns_false_outside_of_conflicts() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\n<<<<<<<\nlibrary\n=======\neditor\n>>>>>>>\n");
+
+        assert!(!buffer.resolve_conflict(0, ConflictResolution::Ours));
+        assert_eq!(buffer.conflicts().len(), 1);
+    }
+}
@@ -0,0 +1,310 @@
+//! Line-based differences between buffer content and a baseline.
+
+use buffer::{Buffer, LineEnding, LineRange};
+#[cfg(feature = "system")]
+use std::fs::File;
+#[cfg(feature = "system")]
+use std::io::{self, Read};
+
+/// The type of change represented by a hunk.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HunkKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A contiguous set of changed lines. The line ranges exclude the line on
+/// which they end; added hunks have an empty range of baseline lines
+/// (starting at the line before which they were added), and removed hunks
+/// have an empty range of buffer lines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hunk {
+    pub kind: HunkKind,
+
+    /// The affected lines in the baseline content.
+    pub old_lines: LineRange,
+
+    /// The affected lines in the buffer.
+    pub new_lines: LineRange,
+}
+
+// A single step in a line-based edit script.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Edit {
+    Equal,
+    Delete,
+    Insert,
+}
+
+impl Buffer {
+    /// Compares the buffer's content to the specified baseline,
+    /// returning the hunks needed to turn the latter into the former.
+    /// Carriage returns in the baseline's line endings are removed first,
+    /// since they are in the buffer's content (see `line_ending`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{Hunk, HunkKind, LineRange};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\ntext\neditor");
+    ///
+    /// assert_eq!(buffer.diff_against("scribe\nlibrary\neditor\ntoolkit"), vec![
+    ///     Hunk{
+    ///         kind: HunkKind::Changed,
+    ///         old_lines: LineRange::new(1, 2),
+    ///         new_lines: LineRange::new(1, 2),
+    ///     },
+    ///     Hunk{
+    ///         kind: HunkKind::Removed,
+    ///         old_lines: LineRange::new(3, 4),
+    ///         new_lines: LineRange::new(3, 3),
+    ///     },
+    /// ]);
+    /// ```
+    pub fn diff_against(&self, baseline: &str) -> Vec<Hunk> {
+        let data = self.data();
+        let baseline = LineEnding::Lf.apply(baseline);
+        let old_lines: Vec<&str> = baseline.split('\n').collect();
+        let new_lines: Vec<&str> = data.split('\n').collect();
+
+        hunks(&edit_script(&old_lines, &new_lines))
+    }
+
+    /// Compares the buffer's content to that of its file on disk (see
+    /// `diff_against`). Returns an error if the buffer doesn't have a
+    /// path, or the file can't be read.
+    #[cfg(feature = "system")]
+    pub fn diff_against_disk(&self) -> io::Result<Vec<Hunk>> {
+        let path = self.path.as_ref().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "buffer doesn't have a path")
+        })?;
+
+        let mut saved_data = String::new();
+        File::open(path)?.read_to_string(&mut saved_data)?;
+
+        Ok(self.diff_against(&saved_data))
+    }
+}
+
+// Groups consecutive non-equal edits into hunks.
+pub fn hunks(edits: &[Edit]) -> Vec<Hunk> {
+    let mut hunks = Vec::new();
+    let mut old_line = 0;
+    let mut new_line = 0;
+    let mut index = 0;
+
+    while index < edits.len() {
+        if edits[index] == Edit::Equal {
+            old_line += 1;
+            new_line += 1;
+            index += 1;
+            continue;
+        }
+
+        let (old_start, new_start) = (old_line, new_line);
+        while index < edits.len() && edits[index] != Edit::Equal {
+            match edits[index] {
+                Edit::Delete => old_line += 1,
+                Edit::Insert => new_line += 1,
+                Edit::Equal => (),
+            }
+            index += 1;
+        }
+
+        let kind =
+            if old_line == old_start {
+                HunkKind::Added
+            } else if new_line == new_start {
+                HunkKind::Removed
+            } else {
+                HunkKind::Changed
+            };
+
+        hunks.push(Hunk{
+            kind,
+            old_lines: LineRange::new(old_start, old_line),
+            new_lines: LineRange::new(new_start, new_line),
+        });
+    }
+
+    hunks
+}
+
+// Computes the shortest edit script between two sets of lines, using
+// Myers' algorithm.
+pub fn edit_script(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    // Trim common prefixes and suffixes, which are
+    // cheap to find and reduce the work done below.
+    let prefix = old.iter().zip(new.iter()).take_while(|&(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|&(a, b)| a == b)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let mut edits = vec![Edit::Equal; prefix];
+    edits.extend(middle_edit_script(old_middle, new_middle));
+    edits.extend(vec![Edit::Equal; suffix]);
+
+    edits
+}
+
+fn middle_edit_script(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = (n + m) as usize;
+    let offset = max as isize + 1;
+    let mut v = vec![0isize; 2 * max + 3];
+    let mut trace = Vec::new();
+
+    // Find the furthest reaching path for each number of edits, recording
+    // the state before each step so we can backtrack. Step d only reads the
+    // diagonals adjacent to those between -d and d, so only they're recorded.
+    'search: for d in 0..(max as isize + 1) {
+        trace.push(v[(offset - d - 1) as usize..(offset + d + 2) as usize].to_vec());
+
+        for k in (-d..d + 1).step_by(2) {
+            let index = (k + offset) as usize;
+            let mut x =
+                if k == -d || (k != d && v[index - 1] < v[index + 1]) {
+                    v[index + 1]
+                } else {
+                    v[index - 1] + 1
+                };
+            let mut y = x - k;
+
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index] = x;
+
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    // Walk back through the recorded states to build the script.
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let index = (k + d + 1) as usize;
+        let previous_k =
+            if k == -d || (k != d && v[index - 1] < v[index + 1]) {
+                k + 1
+            } else {
+                k - 1
+            };
+        let previous_x = v[(previous_k + d + 1) as usize];
+        let previous_y = previous_x - previous_k;
+
+        while x > previous_x && y > previous_y {
+            edits.push(Edit::Equal);
+            x -= 1;
+            y -= 1;
+        }
+
+        if d > 0 {
+            edits.push(if x == previous_x { Edit::Insert } else { Edit::Delete });
+        }
+
+        x = previous_x;
+        y = previous_y;
+    }
+    edits.reverse();
+
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{edit_script, Edit};
+    use buffer::{Buffer, Hunk, HunkKind, LineRange};
+    #[cfg(feature = "system")]
+    use std::fs;
+    #[cfg(feature = "system")]
+    use std::path::Path;
+
+    #[test]
+    fn edit_script_handles_empty_input() {
+        assert_eq!(edit_script(&[], &[]), vec![]);
+        assert_eq!(edit_script(&[], &["scribe"]), vec![Edit::Insert]);
+        assert_eq!(edit_script(&["scribe"], &[]), vec![Edit::Delete]);
+    }
+
+    #[test]
+    fn edit_script_finds_the_shortest_script() {
+        let old = ["a", "b", "c", "a", "b", "b", "a"];
+        let new = ["c", "b", "a", "b", "a", "c"];
+        let edits = edit_script(&old, &new);
+
+        assert_eq!(edits.iter().filter(|&&edit| edit != Edit::Equal).count(), 5);
+        assert_eq!(edits.iter().filter(|&&edit| edit != Edit::Insert).count(), old.len());
+        assert_eq!(edits.iter().filter(|&&edit| edit != Edit::Delete).count(), new.len());
+    }
+
+    #[test]
+    fn diff_against_reports_added_lines() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary\neditor");
+
+        assert_eq!(buffer.diff_against("scribe\neditor"), vec![Hunk{
+            kind: HunkKind::Added,
+            old_lines: LineRange::new(1, 1),
+            new_lines: LineRange::new(1, 2),
+        }]);
+    }
+
+    #[test]
+    fn diff_against_returns_nothing_for_identical_content() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary\n");
+
+        assert!(buffer.diff_against("scribe\nlibrary\n").is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "system")]
+    fn diff_against_disk_compares_to_the_saved_file() {
+        let mut buffer = Buffer::from_file(Path::new("tests/sample/file")).unwrap();
+        buffer.insert("scribe\n");
+
+        assert_eq!(buffer.diff_against_disk().unwrap(), vec![Hunk{
+            kind: HunkKind::Added,
+            old_lines: LineRange::new(0, 0),
+            new_lines: LineRange::new(0, 1),
+        }]);
+    }
+
+    #[test]
+    fn diff_against_ignores_crlf_line_endings_in_the_baseline() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary\n");
+
+        assert!(buffer.diff_against("scribe\r\nlibrary\r\n").is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "system")]
+    fn diff_against_disk_ignores_crlf_line_endings() {
+        fs::write("tests/sample/crlf_diff", "scribe\r\nlibrary\r\n").unwrap();
+        let buffer = Buffer::from_file(Path::new("tests/sample/crlf_diff")).unwrap();
+        let hunks = buffer.diff_against_disk().unwrap();
+        fs::remove_file("tests/sample/crlf_diff").unwrap();
+
+        assert!(hunks.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "system")]
+    fn diff_against_disk_requires_a_path() {
+        assert!(Buffer::new().diff_against_disk().is_err());
+    }
+}
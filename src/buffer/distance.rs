@@ -1,15 +1,20 @@
+use buffer::Position;
+use memchr::{memchr_iter, memrchr};
+use unicode_segmentation::UnicodeSegmentation;
+
 /// A vector value representing a span in a buffer. Unlike the
 /// Range type, whose two positions are absolutes, a Distance
 /// is meant to be used relative to a Position.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
 pub struct Distance {
     pub lines: usize,
     pub offset: usize,
 }
 
 impl Distance {
-    /// Calculates the distance covered by a string. The
-    /// offset is calculated from the last line of the string.
+    /// Calculates the distance covered by a string. The offset is
+    /// calculated from the last line of the string, in grapheme clusters.
     ///
     /// # Examples
     ///
@@ -24,8 +29,37 @@ impl Distance {
     /// ```
     pub fn of_str(from: &str) -> Distance {
         Distance{
-            lines: from.chars().filter(|&c| c == '\n').count(),
-            offset: from.split('\n').last().map(|l| l.len()).unwrap_or(0)
+            lines: memchr_iter(b'\n', from.as_bytes()).count(),
+            offset: memrchr(b'\n', from.as_bytes())
+                .map(|newline| &from[newline + 1..])
+                .unwrap_or(from)
+                .graphemes(true).count()
+        }
+    }
+
+    /// Calculates the distance between two positions, such that adding it
+    /// to the earlier position produces the later one. The order in which
+    /// the positions are provided doesn't matter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::{Distance, Position};
+    ///
+    /// let start = Position{ line: 1, offset: 5 };
+    /// let end = Position{ line: 3, offset: 2 };
+    /// let distance = Distance::between(&start, &end);
+    ///
+    /// assert_eq!(distance, Distance{ lines: 2, offset: 2 });
+    /// assert_eq!(start + distance, end);
+    /// ```
+    pub fn between(start: &Position, end: &Position) -> Distance {
+        let (start, end) = if start <= end { (start, end) } else { (end, start) };
+
+        if start.line == end.line {
+            Distance{ lines: 0, offset: end.offset - start.offset }
+        } else {
+            Distance{ lines: end.line - start.line, offset: end.offset }
         }
     }
 }
@@ -33,6 +67,23 @@ impl Distance {
 #[cfg(test)]
 mod tests {
     use super::Distance;
+    use buffer::Position;
+
+    #[test]
+    fn between_works_with_positions_on_the_same_line() {
+        let start = Position{ line: 2, offset: 3 };
+        let end = Position{ line: 2, offset: 7 };
+
+        assert_eq!(Distance::between(&start, &end), Distance{ lines: 0, offset: 4 });
+    }
+
+    #[test]
+    fn between_works_with_reversed_positions() {
+        let start = Position{ line: 2, offset: 3 };
+        let end = Position{ line: 4, offset: 1 };
+
+        assert_eq!(Distance::between(&end, &start), Distance{ lines: 2, offset: 1 });
+    }
 
     #[test]
     fn of_str_works_with_a_single_line_of_data() {
@@ -42,6 +93,14 @@ mod tests {
         });
     }
 
+    #[test]
+    fn of_str_counts_graphemes() {
+        assert_eq!(Distance::of_str("scribe\nनी"), Distance{
+            lines: 1,
+            offset: 1
+        });
+    }
+
     #[test]
     fn of_str_works_with_a_trailing_newline() {
         assert_eq!(Distance::of_str("trailing newline\n"), Distance{
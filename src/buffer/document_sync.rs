@@ -0,0 +1,205 @@
+//! Language Server Protocol document synchronization lifecycle.
+
+use buffer::{Buffer, ContentChange, DocumentChanges};
+
+/// How document changes are sent to a language server, as negotiated
+/// using the `TextDocumentSyncKind` server capability.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub enum SyncKind {
+    /// Each batch of changes is sent as the document's full content.
+    Full,
+
+    /// Each batch of changes is sent as a series of ranged edits.
+    Incremental,
+}
+
+/// Whether or not a document has been opened with a language server.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DocumentState {
+    Closed,
+    Open,
+}
+
+/// A notification a language server client should send about a document.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub enum DocumentNotification {
+    DidOpen { version: i64, text: String },
+    DidChange(DocumentChanges),
+    DidSave { text: Option<String> },
+    DidClose,
+}
+
+/// Tracks a buffer's synchronization with a language server, producing the
+/// `textDocument/did*` notifications required as it's opened, changed, saved,
+/// and closed. Changes are batched until they're flushed (e.g. before a
+/// request that depends on the document's content, or after a pause in
+/// typing), and are sent incrementally or in full, depending on the
+/// negotiated `SyncKind`.
+///
+/// # Examples
+///
+/// ```
+/// use scribe::Buffer;
+/// use scribe::buffer::{ContentChange, DocumentNotification, DocumentSync, SyncKind};
+///
+/// let mut buffer = Buffer::new();
+/// let mut sync = DocumentSync::new(SyncKind::Full);
+///
+/// assert_eq!(sync.open(&mut buffer), vec![
+///     DocumentNotification::DidOpen{ version: 0, text: String::new() }
+/// ]);
+///
+/// buffer.insert("scribe");
+/// buffer.insert("library ");
+///
+/// match sync.flush(&mut buffer).pop() {
+///     Some(DocumentNotification::DidChange(changes)) => {
+///         assert_eq!(changes.version, 1);
+///         assert_eq!(changes.changes, vec![
+///             ContentChange{ range: None, text: "library scribe".to_string() }
+///         ]);
+///     },
+///     _ => panic!("expected a change notification"),
+/// }
+///
+/// assert_eq!(sync.close(&mut buffer), vec![DocumentNotification::DidClose]);
+/// ```
+pub struct DocumentSync {
+    kind: SyncKind,
+    state: DocumentState,
+
+    /// Whether save notifications include the document's content, as
+    /// requested using the `includeText` server capability.
+    pub include_text_on_save: bool,
+}
+
+impl DocumentSync {
+    /// Creates a synchronization state machine for a closed document.
+    pub fn new(kind: SyncKind) -> DocumentSync {
+        DocumentSync{ kind, state: DocumentState::Closed, include_text_on_save: false }
+    }
+
+    /// Returns whether or not the document is open.
+    pub fn state(&self) -> DocumentState {
+        self.state
+    }
+
+    /// Opens the document, recording the buffer's subsequent changes. Changes
+    /// made while the document was closed are discarded, since the document's
+    /// entire content is included when it's opened. Does nothing if the
+    /// document is already open.
+    pub fn open(&mut self, buffer: &mut Buffer) -> Vec<DocumentNotification> {
+        if self.state == DocumentState::Open {
+            return Vec::new()
+        }
+
+        buffer.record_content_changes();
+        buffer.take_content_changes();
+        self.state = DocumentState::Open;
+
+        vec![DocumentNotification::DidOpen{ version: buffer.document_version(), text: buffer.data() }]
+    }
+
+    /// Sends the changes made to the buffer since they were last flushed,
+    /// if the document is open and there are any.
+    pub fn flush(&mut self, buffer: &mut Buffer) -> Vec<DocumentNotification> {
+        if self.state == DocumentState::Closed {
+            return Vec::new()
+        }
+
+        match buffer.take_content_changes() {
+            Some(mut changes) => {
+                if self.kind == SyncKind::Full {
+                    changes.changes = vec![ContentChange{ range: None, text: buffer.data() }];
+                }
+
+                vec![DocumentNotification::DidChange(changes)]
+            },
+            None => Vec::new(),
+        }
+    }
+
+    /// Notes that the buffer has been saved, flushing pending changes first.
+    pub fn saved(&mut self, buffer: &mut Buffer) -> Vec<DocumentNotification> {
+        if self.state == DocumentState::Closed {
+            return Vec::new()
+        }
+
+        let mut notifications = self.flush(buffer);
+        notifications.push(DocumentNotification::DidSave{
+            text: if self.include_text_on_save { Some(buffer.data()) } else { None }
+        });
+
+        notifications
+    }
+
+    /// Closes the document, discarding pending changes, which
+    /// are superseded when the document is next opened.
+    pub fn close(&mut self, buffer: &mut Buffer) -> Vec<DocumentNotification> {
+        if self.state == DocumentState::Closed {
+            return Vec::new()
+        }
+
+        buffer.take_content_changes();
+        self.state = DocumentState::Closed;
+
+        vec![DocumentNotification::DidClose]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DocumentNotification, DocumentState, DocumentSync, SyncKind};
+    use buffer::{Buffer, ContentChange, DocumentChanges, EncodedPosition, EncodedRange};
+
+    #[test]
+    fn notifications_are_only_sent_for_open_documents() {
+        let mut buffer = Buffer::new();
+        let mut sync = DocumentSync::new(SyncKind::Incremental);
+        buffer.insert("scribe");
+
+        assert!(sync.flush(&mut buffer).is_empty());
+        assert!(sync.saved(&mut buffer).is_empty());
+        assert!(sync.close(&mut buffer).is_empty());
+        assert_eq!(sync.state(), DocumentState::Closed);
+    }
+
+    #[test]
+    fn open_includes_changes_made_while_closed_in_the_content() {
+        let mut buffer = Buffer::new();
+        let mut sync = DocumentSync::new(SyncKind::Incremental);
+        sync.open(&mut buffer);
+        buffer.insert("scribe");
+        sync.close(&mut buffer);
+        buffer.insert("library ");
+
+        assert_eq!(sync.open(&mut buffer), vec![
+            DocumentNotification::DidOpen{ version: 2, text: "library scribe".to_string() }
+        ]);
+        assert!(sync.open(&mut buffer).is_empty());
+        assert!(sync.flush(&mut buffer).is_empty());
+    }
+
+    #[test]
+    fn saved_flushes_incremental_changes_before_the_save() {
+        let mut buffer = Buffer::new();
+        let mut sync = DocumentSync::new(SyncKind::Incremental);
+        sync.include_text_on_save = true;
+        sync.open(&mut buffer);
+        buffer.insert("scribe");
+
+        let position = EncodedPosition{ line: 0, character: 0 };
+        assert_eq!(sync.saved(&mut buffer), vec![
+            DocumentNotification::DidChange(DocumentChanges{
+                version: 1,
+                changes: vec![ContentChange{
+                    range: Some(EncodedRange{ start: position, end: position }),
+                    text: "scribe".to_string(),
+                }],
+            }),
+            DocumentNotification::DidSave{ text: Some("scribe".to_string()) },
+        ]);
+    }
+}
@@ -0,0 +1,104 @@
+//! Commands for duplicating buffer content.
+
+use buffer::{Buffer, Position, Range};
+
+impl Buffer {
+    /// Inserts a copy of the specified range immediately after it, and moves
+    /// the cursor to the start of the copy. If the range cannot be read
+    /// (i.e. it's out of bounds), the buffer is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{Position, Range};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe library");
+    /// buffer.duplicate(Range::new(
+    ///     Position{ line: 0, offset: 0 },
+    ///     Position{ line: 0, offset: 7 }
+    /// ));
+    ///
+    /// assert_eq!(buffer.data(), "scribe scribe library");
+    /// assert_eq!(*buffer.cursor, Position{ line: 0, offset: 7 });
+    /// ```
+    pub fn duplicate(&mut self, range: Range) {
+        if let Some(content) = self.read(&range) {
+            self.cursor.move_to(range.end());
+            self.insert(content);
+        }
+    }
+
+    /// Inserts a copy of the current line below it, and moves
+    /// the cursor to the same offset on the copied line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::Position;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\nlibrary");
+    /// buffer.cursor.move_to(Position{ line: 0, offset: 3 });
+    /// buffer.duplicate_line();
+    ///
+    /// assert_eq!(buffer.data(), "scribe\nscribe\nlibrary");
+    /// assert_eq!(*buffer.cursor, Position{ line: 1, offset: 3 });
+    /// ```
+    pub fn duplicate_line(&mut self) {
+        let position = *self.cursor;
+        let content = match self.line(position.line) {
+            Some(line) => format!("\n{}", line),
+            None => return,
+        };
+
+        // Insert the copy after the current line's content, which
+        // works regardless of whether or not it has a trailing newline.
+        self.cursor.move_to_end_of_line();
+        self.insert(content);
+        self.cursor.move_to(Position{ line: position.line + 1, offset: position.offset });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, Position, Range};
+
+    #[test]
+    fn duplicate_is_undone_as_a_single_operation() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+        buffer.duplicate(Range::new(
+            Position{ line: 0, offset: 0 },
+            Position{ line: 1, offset: 0 }
+        ));
+        assert_eq!(buffer.data(), "scribe\nscribe\nlibrary");
+
+        buffer.undo();
+        assert_eq!(buffer.data(), "scribe\nlibrary");
+    }
+
+    #[test]
+    fn duplicate_line_works_on_the_last_line() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+        buffer.cursor.move_to(Position{ line: 1, offset: 7 });
+        buffer.duplicate_line();
+
+        assert_eq!(buffer.data(), "scribe\nlibrary\nlibrary");
+        assert_eq!(*buffer.cursor, Position{ line: 2, offset: 7 });
+    }
+
+    #[test]
+    fn duplicate_line_works_on_empty_trailing_lines() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\n");
+        buffer.cursor.move_to(Position{ line: 1, offset: 0 });
+        buffer.duplicate_line();
+
+        assert_eq!(buffer.data(), "scribe\n\n");
+        assert_eq!(*buffer.cursor, Position{ line: 2, offset: 0 });
+    }
+}
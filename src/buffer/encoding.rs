@@ -0,0 +1,246 @@
+//! Column units used to report positions to external peers (e.g. language servers).
+
+use buffer::{Buffer, GapBuffer, Position, Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The unit in which an encoded position's column is measured. Internally,
+/// buffers measure offsets in grapheme clusters, whereas protocols (e.g. the
+/// Language Server Protocol) typically count code units of an encoding.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub enum PositionEncoding {
+    /// UTF-8 code units (bytes).
+    Utf8,
+
+    /// UTF-16 code units, the Language Server Protocol's default.
+    Utf16,
+
+    /// UTF-32 code units (Unicode scalar values).
+    Utf32,
+}
+
+impl Default for PositionEncoding {
+    fn default() -> Self {
+        PositionEncoding::Utf16
+    }
+}
+
+impl PositionEncoding {
+    /// Returns the number of code units used to encode the content.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::PositionEncoding;
+    ///
+    /// assert_eq!(PositionEncoding::Utf8.length_of("a😀"), 5);
+    /// assert_eq!(PositionEncoding::Utf16.length_of("a😀"), 3);
+    /// assert_eq!(PositionEncoding::Utf32.length_of("a😀"), 2);
+    /// ```
+    pub fn length_of(&self, content: &str) -> usize {
+        match *self {
+            PositionEncoding::Utf8 => content.len(),
+            PositionEncoding::Utf16 => content.encode_utf16().count(),
+            PositionEncoding::Utf32 => content.chars().count(),
+        }
+    }
+}
+
+/// A position whose column (`character`) is measured
+/// in code units of a particular `PositionEncoding`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct EncodedPosition {
+    pub line: usize,
+    pub character: usize,
+}
+
+/// A range delimited by encoded positions (end exclusive).
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct EncodedRange {
+    pub start: EncodedPosition,
+    pub end: EncodedPosition,
+}
+
+impl Buffer {
+    /// Converts a position (whose offset is measured in grapheme clusters) to
+    /// one whose column is measured using the buffer's `position_encoding`,
+    /// for reporting to external peers (e.g. a cursor or lexeme position
+    /// sent to a language server). Returns `None` if the position is out
+    /// of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{EncodedPosition, Position, PositionEncoding};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("😀 scribe");
+    /// let position = Position{ line: 0, offset: 2 };
+    ///
+    /// assert_eq!(buffer.encode_position(position), Some(EncodedPosition{ line: 0, character: 3 }));
+    ///
+    /// buffer.position_encoding = PositionEncoding::Utf8;
+    /// assert_eq!(buffer.encode_position(position), Some(EncodedPosition{ line: 0, character: 5 }));
+    /// assert_eq!(buffer.decode_position(EncodedPosition{ line: 0, character: 5 }), Some(position));
+    /// ```
+    pub fn encode_position(&self, position: Position) -> Option<EncodedPosition> {
+        encode_position(&self.data.borrow(), position, self.position_encoding)
+    }
+
+    /// Converts a position whose column is measured using the buffer's
+    /// `position_encoding` to a buffer position. Columns beyond the end of
+    /// their line refer to its end. Returns `None` if the line doesn't exist,
+    /// or the column falls within a character (e.g. between its code units).
+    pub fn decode_position(&self, position: EncodedPosition) -> Option<Position> {
+        let line = self.line(position.line)?;
+
+        decode_offset(&line, position.character, self.position_encoding)
+            .map(|offset| Position{ line: position.line, offset })
+    }
+
+    /// Converts a range to an encoded one (see `encode_position`).
+    pub fn encode_range(&self, range: &Range) -> Option<EncodedRange> {
+        Some(EncodedRange{
+            start: self.encode_position(range.start())?,
+            end: self.encode_position(range.end())?,
+        })
+    }
+
+    /// Converts an encoded range to a buffer range (see `decode_position`).
+    pub fn decode_range(&self, range: &EncodedRange) -> Option<Range> {
+        Some(Range::new(self.decode_position(range.start)?, self.decode_position(range.end)?))
+    }
+
+    /// Searches the buffer for occurrences of `needle` (see `search`), returning
+    /// their positions encoded using the buffer's `position_encoding`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::EncodedPosition;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("😀 scribe\nlibrary");
+    ///
+    /// assert_eq!(
+    ///     buffer.search_encoded("ib"),
+    ///     vec![
+    ///         EncodedPosition{ line: 0, character: 6 },
+    ///         EncodedPosition{ line: 1, character: 1 }
+    ///     ]
+    /// );
+    /// ```
+    pub fn search_encoded(&self, needle: &str) -> Vec<EncodedPosition> {
+        self.search(needle).into_iter()
+            .filter_map(|position| self.encode_position(position))
+            .collect()
+    }
+}
+
+/// Encodes the specified position using the content of its line,
+/// returning `None` if the position is out of bounds.
+pub fn encode_position(data: &GapBuffer, position: Position, encoding: PositionEncoding) -> Option<EncodedPosition> {
+    let line_prefix = data.read(&Range::new(Position{ line: position.line, offset: 0 }, position))?;
+
+    Some(EncodedPosition{ line: position.line, character: encoding.length_of(&line_prefix) })
+}
+
+/// Converts an encoded column on the specified line to a grapheme offset.
+/// Columns beyond the end of the line are clamped to it, whereas columns
+/// within a grapheme (e.g. between its code units) yield `None`.
+pub fn decode_offset(line: &str, character: usize, encoding: PositionEncoding) -> Option<usize> {
+    let mut length = 0;
+
+    for (offset, grapheme) in line.graphemes(true).enumerate() {
+        if length == character {
+            return Some(offset)
+        } else if length > character {
+            return None
+        }
+
+        length += encoding.length_of(grapheme);
+    }
+
+    // The column either follows the line, or falls within its last grapheme.
+    if character >= length {
+        Some(line.graphemes(true).count())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_offset, encode_position, EncodedPosition, EncodedRange, PositionEncoding};
+    use buffer::{Buffer, GapBuffer, Position, Range};
+
+    #[test]
+    fn encode_position_measures_the_preceding_content_on_the_line() {
+        let data = GapBuffer::new("scribe\nनी😀 library".to_string());
+        let position = Position{ line: 1, offset: 3 };
+
+        assert_eq!(
+            encode_position(&data, position, PositionEncoding::Utf8),
+            Some(EncodedPosition{ line: 1, character: 11 })
+        );
+        assert_eq!(
+            encode_position(&data, position, PositionEncoding::Utf16),
+            Some(EncodedPosition{ line: 1, character: 5 })
+        );
+        assert_eq!(
+            encode_position(&data, position, PositionEncoding::Utf32),
+            Some(EncodedPosition{ line: 1, character: 4 })
+        );
+    }
+
+    #[test]
+    fn encode_position_returns_none_for_out_of_bounds_positions() {
+        let data = GapBuffer::new("scribe".to_string());
+
+        assert_eq!(encode_position(&data, Position{ line: 0, offset: 7 }, PositionEncoding::Utf16), None);
+    }
+
+    #[test]
+    fn decode_range_rejects_lines_beyond_the_buffer() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        let range = EncodedRange{
+            start: EncodedPosition{ line: 0, character: 0 },
+            end: EncodedPosition{ line: 1, character: 0 },
+        };
+
+        assert_eq!(buffer.decode_range(&range), None);
+    }
+
+    #[test]
+    fn encode_range_honours_the_position_encoding() {
+        let mut buffer = Buffer::new();
+        buffer.insert("नी scribe");
+        buffer.position_encoding = PositionEncoding::Utf32;
+        let range = Range::new(Position{ line: 0, offset: 2 }, Position{ line: 0, offset: 8 });
+
+        assert_eq!(buffer.encode_range(&range), Some(EncodedRange{
+            start: EncodedPosition{ line: 0, character: 3 },
+            end: EncodedPosition{ line: 0, character: 9 },
+        }));
+        assert_eq!(buffer.decode_range(&buffer.encode_range(&range).unwrap()), Some(range));
+    }
+
+    #[test]
+    fn decode_offset_clamps_columns_beyond_the_line() {
+        assert_eq!(decode_offset("a😀b", 3, PositionEncoding::Utf16), Some(2));
+        assert_eq!(decode_offset("a😀b", 4, PositionEncoding::Utf16), Some(3));
+        assert_eq!(decode_offset("a😀b", 10, PositionEncoding::Utf16), Some(3));
+    }
+
+    #[test]
+    fn decode_offset_rejects_columns_within_graphemes() {
+        assert_eq!(decode_offset("a😀b", 2, PositionEncoding::Utf16), None);
+        assert_eq!(decode_offset("a😀b", 2, PositionEncoding::Utf8), None);
+        assert_eq!(decode_offset("a😀b", 2, PositionEncoding::Utf32), Some(2));
+    }
+}
@@ -0,0 +1,128 @@
+//! Exporting styled content as HTML or ANSI-escaped terminal output.
+
+use buffer::{Buffer, LineRange, StyledLine};
+use errors::*;
+use std::rc::Rc;
+use syntect::highlighting::{Color, Style, Theme};
+use syntect::html::{start_coloured_html_snippet, styles_to_coloured_html, IncludeBackground};
+use syntect::util::as_24_bit_terminal_escaped;
+
+impl Buffer {
+    /// Renders the specified lines (see `styled_lines`) as an HTML `pre`
+    /// element, using inline styles from the specified theme. Backgrounds
+    /// are only styled where they differ from the theme's background.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate scribe;
+    /// # extern crate syntect;
+    /// use scribe::Buffer;
+    /// use scribe::buffer::LineRange;
+    /// use syntect::highlighting::ThemeSet;
+    /// # use syntect::parsing::SyntaxSet;
+    ///
+    /// # fn main() {
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("a < b");
+    ///
+    /// // Omitted code to set up the buffer's syntax definition.
+    /// # let syntax_set = SyntaxSet::load_defaults_newlines();
+    /// # buffer.syntax_definition = Some(syntax_set.find_syntax_plain_text().clone());
+    ///
+    /// let theme_set = ThemeSet::load_defaults();
+    /// let theme = &theme_set.themes["base16-ocean.dark"];
+    /// let html = buffer.to_html(&LineRange::new(0, 1), theme, "base16-ocean.dark").unwrap();
+    ///
+    /// assert_eq!(
+    ///     html,
+    ///     "<pre style=\"background-color:#2b303b\">\n\
+    ///      <span style=\"color:#c0c5ce;\">a &lt; b</span>\n\
+    ///      </pre>\n"
+    /// );
+    /// # }
+    /// ```
+    pub fn to_html(&self, lines: &LineRange, theme: &Theme, theme_id: &str) -> Result<String> {
+        let background = theme.settings.background.unwrap_or(Color::WHITE);
+        let mut html = start_coloured_html_snippet(theme);
+
+        for line in self.styled_lines(lines, theme, theme_id)? {
+            html.push_str(&styles_to_coloured_html(&regions(&line), IncludeBackground::IfDifferent(background)));
+            html.push('\n');
+        }
+        html.push_str("</pre>\n");
+
+        Ok(html)
+    }
+
+    /// Renders the specified lines (see `styled_lines`) using 24-bit ANSI
+    /// terminal escape codes for the specified theme's colours, optionally
+    /// including backgrounds. Styling is reset at the end of each line.
+    pub fn to_ansi(&self, lines: &LineRange, theme: &Theme, theme_id: &str, background: bool) -> Result<String> {
+        let mut output = String::new();
+
+        for line in self.styled_lines(lines, theme, theme_id)? {
+            output.push_str(&as_24_bit_terminal_escaped(&regions(&line), background));
+            output.push_str("\x1b[0m\n");
+        }
+
+        Ok(output)
+    }
+}
+
+// Converts a styled line into the regions used by syntect's exporters.
+fn regions(line: &Rc<StyledLine>) -> Vec<(Style, &str)> {
+    line.spans.iter().map(|span| (span.style, span.value.as_str())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, LineRange};
+    use syntect::highlighting::ThemeSet;
+    use syntect::parsing::SyntaxSet;
+
+    fn rust_buffer(data: &str) -> Buffer {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let mut buffer = Buffer::new();
+        buffer.insert(data);
+        buffer.syntax_definition = syntax_set.find_syntax_by_extension("rs").cloned();
+
+        buffer
+    }
+
+    #[test]
+    fn to_ansi_escapes_and_resets_each_line() {
+        let buffer = rust_buffer("fn main() {\n}");
+        let theme_set = ThemeSet::load_defaults();
+        let theme = &theme_set.themes["base16-ocean.dark"];
+        let output = buffer.to_ansi(&LineRange::new(0, 2), theme, "dark", false).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("\x1b[38;2;"));
+        assert!(lines[0].contains("fn"));
+        assert!(lines.iter().all(|line| line.ends_with("\x1b[0m")));
+    }
+
+    #[test]
+    fn to_html_excludes_lines_beyond_the_range() {
+        let buffer = rust_buffer("fn main() {\n}");
+        let theme_set = ThemeSet::load_defaults();
+        let theme = &theme_set.themes["base16-ocean.dark"];
+        let html = buffer.to_html(&LineRange::new(1, 2), theme, "dark").unwrap();
+
+        assert!(!html.contains("fn"));
+        assert_eq!(html.matches("\n").count(), 3);
+    }
+
+    #[test]
+    fn exporting_requires_a_syntax_definition() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        let theme_set = ThemeSet::load_defaults();
+        let theme = &theme_set.themes["base16-ocean.dark"];
+
+        assert!(buffer.to_html(&LineRange::new(0, 1), theme, "dark").is_err());
+        assert!(buffer.to_ansi(&LineRange::new(0, 1), theme, "dark", true).is_err());
+    }
+}
@@ -0,0 +1,127 @@
+//! Commands for filtering buffer content through external processes.
+
+use buffer::{Buffer, Range};
+use std::io;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+
+impl Buffer {
+    /// Sends the content within the specified range to the command's standard
+    /// input, and replaces the range with its standard output, as a single
+    /// undoable operation. The cursor is moved to the start of the range.
+    ///
+    /// The command's standard I/O streams are configured by this method.
+    /// If the process can't be run, exits unsuccessfully (in which case the
+    /// error includes its standard error output), or produces output that
+    /// isn't valid UTF-8, an error is returned and the buffer is unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{Position, Range};
+    /// use std::process::Command;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\nlibrary\neditor\n");
+    /// buffer.filter_range(
+    ///     Range::new(Position{ line: 0, offset: 0 }, Position{ line: 3, offset: 0 }),
+    ///     &mut Command::new("sort")
+    /// ).unwrap();
+    ///
+    /// assert_eq!(buffer.data(), "editor\nlibrary\nscribe\n");
+    /// ```
+    pub fn filter_range(&mut self, range: Range, command: &mut Command) -> io::Result<()> {
+        let content = self.read(&range).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "range is out of bounds")
+        })?;
+
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        // Write the input from a separate thread, so that a process
+        // producing output before it has read all of its input
+        // can't fill its stdout pipe and deadlock.
+        let mut stdin = child.stdin.take().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::BrokenPipe, "couldn't open process stdin")
+        })?;
+        let writer = thread::spawn(move || stdin.write_all(content.as_bytes()));
+
+        let output = child.wait_with_output()?;
+        match writer.join() {
+            // Processes aren't required to read all of their input (e.g. head).
+            Ok(Err(ref e)) if e.kind() == io::ErrorKind::BrokenPipe => (),
+            Ok(result) => result?,
+            Err(_) => return Err(io::Error::new(io::ErrorKind::Other, "couldn't write to process stdin")),
+        }
+
+        if !output.status.success() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!(
+                    "process exited with {}: {}",
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )
+            ))
+        }
+        let filtered_content = String::from_utf8(output.stdout)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        self.replace(range.clone(), filtered_content);
+        self.cursor.move_to(range.start());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, Position, Range};
+    use std::process::Command;
+
+    #[test]
+    fn filter_range_only_replaces_the_range() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe library");
+        buffer.filter_range(
+            Range::new(Position{ line: 0, offset: 7 }, Position{ line: 0, offset: 14 }),
+            Command::new("tr").arg("a-z").arg("A-Z")
+        ).unwrap();
+        assert_eq!(buffer.data(), "scribe LIBRARY");
+
+        // The change is undone as a single operation.
+        buffer.undo();
+        assert_eq!(buffer.data(), "scribe library");
+    }
+
+    #[test]
+    fn filter_range_leaves_the_buffer_unchanged_when_the_process_fails() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        let result = buffer.filter_range(
+            Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 6 }),
+            Command::new("sh").arg("-c").arg("echo failed >&2; exit 1")
+        );
+
+        assert!(result.unwrap_err().to_string().contains("failed"));
+        assert_eq!(buffer.data(), "scribe");
+    }
+
+    #[test]
+    fn filter_range_returns_an_error_for_missing_commands() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        let result = buffer.filter_range(
+            Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 6 }),
+            &mut Command::new("scribe-missing-command")
+        );
+
+        assert!(result.is_err());
+        assert_eq!(buffer.data(), "scribe");
+    }
+}
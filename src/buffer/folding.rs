@@ -0,0 +1,457 @@
+//! Foldable region detection and folded line state.
+
+use buffer::{Buffer, LineRange, Position, Range, TrackingId};
+#[cfg(feature = "syntax")]
+use buffer::{Scope, Token};
+use buffer::columns::grapheme_width;
+use errors::*;
+use std::cmp;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The method used to determine which regions of a buffer can be folded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FoldStrategy {
+    /// Lines followed by more deeply indented lines can be folded.
+    Indentation,
+
+    /// Bracketed blocks spanning multiple lines, as well as consecutive
+    /// comment lines, can be folded. Requires a syntax definition.
+    Syntax,
+}
+
+impl Buffer {
+    /// Returns the buffer's foldable regions, ordered by their first line.
+    /// Regions include the line that introduces them (e.g. a function
+    /// signature), which remains visible when the region is folded, and
+    /// exclude the line on which they end, like other line ranges (so that
+    /// they can be passed to `fold`). Regions may be nested within one another.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{FoldStrategy, LineRange};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\n  library\n\n  editor\ntext");
+    ///
+    /// assert_eq!(
+    ///     buffer.foldable_regions(FoldStrategy::Indentation).unwrap(),
+    ///     vec![LineRange::new(0, 4)]
+    /// );
+    /// ```
+    pub fn foldable_regions(&self, strategy: FoldStrategy) -> Result<Vec<LineRange>> {
+        let mut regions = match strategy {
+            FoldStrategy::Indentation => self.indentation_regions(),
+            FoldStrategy::Syntax => self.syntax_regions()?,
+        };
+        regions.sort_by_key(|region| (region.start(), region.end()));
+        regions.dedup();
+
+        Ok(regions)
+    }
+
+    /// Folds the specified lines (excluding the line on which the range
+    /// ends), hiding all but the first. The fold is anchored to the buffer's
+    /// content, and is opened if that content is deleted. The cursor is moved
+    /// to the first line if it would otherwise be hidden.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{LineRange, Position};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\nlibrary\neditor\ntext");
+    /// buffer.fold(&LineRange::new(1, 3));
+    ///
+    /// assert_eq!(buffer.visible_lines(), vec![0, 1, 3]);
+    ///
+    /// // Inserting content before the fold moves it.
+    /// buffer.cursor.move_to(Position{ line: 0, offset: 0 });
+    /// buffer.insert("\n");
+    /// assert_eq!(buffer.visible_lines(), vec![0, 1, 2, 4]);
+    /// ```
+    pub fn fold(&mut self, lines: &LineRange) {
+        let data = self.data();
+        let buffer_lines: Vec<&str> = data.split('\n').collect();
+        if lines.start() >= buffer_lines.len() {
+            return
+        }
+
+        // The range excludes the line on which it ends.
+        let end_line = cmp::min(lines.end().saturating_sub(1), buffer_lines.len() - 1);
+        if end_line <= lines.start() {
+            return
+        }
+
+        let id = self.track_range(Range::new(
+            Position{ line: lines.start(), offset: 0 },
+            Position{ line: end_line, offset: buffer_lines[end_line].graphemes(true).count() }
+        ));
+        self.folds.push(id);
+
+        if self.is_hidden(self.cursor.line) {
+            self.cursor.move_to(Position{ line: lines.start(), offset: 0 });
+        }
+    }
+
+    /// Opens all folds that include the specified line.
+    pub fn unfold(&mut self, line: usize) {
+        let ids: Vec<_> = self.folds.drain(..).collect();
+
+        for id in ids {
+            let includes_line = self.fold_lines(id)
+                .map(|lines| lines.includes(line))
+                .unwrap_or(false);
+
+            if includes_line {
+                self.untrack(id);
+            } else {
+                self.folds.push(id);
+            }
+        }
+    }
+
+    /// Opens all folds.
+    pub fn unfold_all(&mut self) {
+        for id in self.folds.drain(..).collect::<Vec<_>>() {
+            self.untrack(id);
+        }
+    }
+
+    /// Returns the buffer's folds, ordered by their first line.
+    pub fn folds(&self) -> Vec<LineRange> {
+        let mut folds: Vec<LineRange> = self.folds.iter()
+            .filter_map(|&id| self.fold_lines(id))
+            .collect();
+        folds.sort_by_key(|fold| (fold.start(), fold.end()));
+
+        folds
+    }
+
+    /// Whether or not the specified line is hidden by a fold.
+    pub fn is_hidden(&self, line: usize) -> bool {
+        self.folds().iter().any(|fold| fold.start() < line && line < fold.end())
+    }
+
+    /// Returns the numbers of the lines that aren't hidden by folds.
+    /// Renderers can use this to skip folded content.
+    pub fn visible_lines(&self) -> Vec<usize> {
+        let folds = self.folds();
+        let line_count = self.data().split('\n').count();
+
+        (0..line_count).filter(|&line| {
+            !folds.iter().any(|fold| fold.start() < line && line < fold.end())
+        }).collect()
+    }
+
+    /// Moves the cursor to the previous visible line, skipping over folded
+    /// content. Returns `false` if there's no visible line above the cursor.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{LineRange, Position};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\nlibrary\neditor\ntext");
+    /// buffer.fold(&LineRange::new(0, 3));
+    ///
+    /// buffer.cursor.move_to(Position{ line: 3, offset: 2 });
+    /// assert!(buffer.move_cursor_up());
+    /// assert_eq!(*buffer.cursor, Position{ line: 0, offset: 2 });
+    /// ```
+    pub fn move_cursor_up(&mut self) -> bool {
+        let original_position = *self.cursor;
+
+        loop {
+            let previous_line = self.cursor.line;
+            self.cursor.move_up();
+            if self.cursor.line == previous_line {
+                self.cursor.move_to(original_position);
+                return false
+            }
+
+            if !self.is_hidden(self.cursor.line) {
+                return true
+            }
+        }
+    }
+
+    /// Moves the cursor to the next visible line, skipping over folded
+    /// content. Returns `false` if there's no visible line below the cursor.
+    pub fn move_cursor_down(&mut self) -> bool {
+        let original_position = *self.cursor;
+
+        loop {
+            let previous_line = self.cursor.line;
+            self.cursor.move_down();
+            if self.cursor.line == previous_line {
+                self.cursor.move_to(original_position);
+                return false
+            }
+
+            if !self.is_hidden(self.cursor.line) {
+                return true
+            }
+        }
+    }
+
+    // Returns the lines covered by a fold, provided that it's still valid
+    // and spans more than one line (edits can collapse it onto a single line).
+    fn fold_lines(&self, id: TrackingId) -> Option<LineRange> {
+        self.tracked_range(id)
+            .map(|range| LineRange::inclusive(range.start().line, range.end().line))
+            .filter(|lines| lines.len() > 1)
+    }
+
+    // Finds lines followed by more deeply indented lines, extending each
+    // region to the last such line (ignoring trailing blank lines).
+    fn indentation_regions(&self) -> Vec<LineRange> {
+        let data = self.data();
+        let tab_width = self.indentation.width;
+        let levels: Vec<Option<usize>> = data.split('\n').map(|line| {
+            if line.trim().is_empty() {
+                return None
+            }
+
+            Some(line.graphemes(true)
+                .take_while(|g| g.trim().is_empty())
+                .fold(0, |column, g| column + grapheme_width(g, column, tab_width)))
+        }).collect();
+
+        let mut regions = Vec::new();
+        for (line, level) in levels.iter().enumerate() {
+            let level = match *level {
+                Some(level) => level,
+                None => continue,
+            };
+
+            let mut end = line;
+            for (following_line, following_level) in levels.iter().enumerate().skip(line + 1) {
+                match *following_level {
+                    Some(following_level) if following_level > level => end = following_line,
+                    Some(_) => break,
+                    None => (),
+                }
+            }
+
+            if end > line {
+                regions.push(LineRange::inclusive(line, end));
+            }
+        }
+
+        regions
+    }
+
+    // Finds bracketed blocks spanning multiple lines (excluding the line with
+    // the closing bracket, so that it remains visible), as well as runs of
+    // lines containing nothing but comments.
+    #[cfg(feature = "syntax")]
+    fn syntax_regions(&self) -> Result<Vec<LineRange>> {
+        let tokens = self.tokens()?;
+        let comment = Scope::new("comment").unwrap();
+        let string = Scope::new("string").unwrap();
+
+        let mut regions = Vec::new();
+        let mut open_brackets = Vec::new();
+        let mut comment_lines = Vec::new();
+        let mut code_lines = Vec::new();
+
+        for token in tokens.iter() {
+            if let Token::Lexeme(lexeme) = token {
+                let is_comment = lexeme.scope.as_slice().iter().any(|&s| comment.is_prefix_of(s));
+                let is_string = lexeme.scope.as_slice().iter().any(|&s| string.is_prefix_of(s));
+
+                for (index, content) in lexeme.value.split('\n').enumerate() {
+                    let line = lexeme.position.line + index;
+                    if content.trim().is_empty() {
+                        continue;
+                    }
+
+                    if is_comment {
+                        comment_lines.push(line);
+                        continue;
+                    }
+                    code_lines.push(line);
+
+                    if is_string {
+                        continue;
+                    }
+
+                    for c in content.chars() {
+                        match c {
+                            '{' | '(' | '[' => open_brackets.push(line),
+                            '}' | ')' | ']' => {
+                                if let Some(start) = open_brackets.pop() {
+                                    if line > start + 1 {
+                                        regions.push(LineRange::inclusive(start, line - 1));
+                                    }
+                                }
+                            },
+                            _ => (),
+                        }
+                    }
+                }
+            }
+        }
+
+        // Group consecutive comment-only lines.
+        comment_lines.retain(|line| !code_lines.contains(line));
+        comment_lines.dedup();
+        let mut run_start = None;
+        for (index, &line) in comment_lines.iter().enumerate() {
+            let start = *run_start.get_or_insert(line);
+            let run_continues = comment_lines.get(index + 1)
+                .map(|&next| next == line + 1)
+                .unwrap_or(false);
+
+            if !run_continues {
+                if line > start {
+                    regions.push(LineRange::inclusive(start, line));
+                }
+                run_start = None;
+            }
+        }
+
+        Ok(regions)
+    }
+
+    // Without lexing support, buffers never have a syntax definition.
+    #[cfg(not(feature = "syntax"))]
+    fn syntax_regions(&self) -> Result<Vec<LineRange>> {
+        bail!(ErrorKind::MissingSyntaxDefinition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "syntax")]
+    use syntect::parsing::SyntaxSet;
+    use buffer::{Buffer, FoldStrategy, LineRange, Position, Range};
+
+    #[cfg(feature = "syntax")]
+    fn buffer_with_syntax(data: &str, extension: &str) -> Buffer {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let mut buffer = Buffer::new();
+        buffer.insert(data);
+        buffer.syntax_definition = syntax_set.find_syntax_by_extension(extension).cloned();
+
+        buffer
+    }
+
+    #[test]
+    fn indentation_regions_are_nested() {
+        let mut buffer = Buffer::new();
+        buffer.insert("a\n  b\n    c\n  d\ne");
+
+        assert_eq!(
+            buffer.foldable_regions(FoldStrategy::Indentation).unwrap(),
+            vec![LineRange::new(0, 4), LineRange::new(1, 3)]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "syntax")]
+    fn syntax_regions_include_multi_line_blocks_and_comments() {
+        let buffer = buffer_with_syntax(
+            "// scribe\n// library\nfn main() {\n    let x = \"{\";\n    x\n}",
+            "rs"
+        );
+
+        assert_eq!(
+            buffer.foldable_regions(FoldStrategy::Syntax).unwrap(),
+            vec![LineRange::new(0, 2), LineRange::new(2, 5)]
+        );
+    }
+
+    #[test]
+    fn syntax_regions_require_a_syntax_definition() {
+        let mut buffer = Buffer::new();
+        buffer.insert("fn main() {\n}");
+
+        assert!(buffer.foldable_regions(FoldStrategy::Syntax).is_err());
+    }
+
+    #[test]
+    fn fold_moves_the_cursor_out_of_hidden_lines() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary\neditor");
+        buffer.fold(&LineRange::new(0, 3));
+
+        assert_eq!(*buffer.cursor, Position{ line: 0, offset: 0 });
+        assert!(buffer.is_hidden(2));
+        assert!(!buffer.is_hidden(0));
+    }
+
+    #[test]
+    fn fold_hides_the_lines_of_foldable_regions() {
+        let mut buffer = Buffer::new();
+        buffer.insert("a\n  b\n  c\nd");
+        let region = buffer.foldable_regions(FoldStrategy::Indentation).unwrap()[0];
+        buffer.fold(&region);
+
+        assert_eq!(buffer.folds(), vec![region]);
+        assert_eq!(buffer.visible_lines(), vec![0, 3]);
+    }
+
+    #[test]
+    fn fold_ignores_single_line_ranges() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+        buffer.fold(&LineRange::new(1, 5));
+
+        assert!(buffer.folds().is_empty());
+    }
+
+    #[test]
+    fn unfold_opens_folds_including_the_line() {
+        let mut buffer = Buffer::new();
+        buffer.insert("a\nb\nc\nd\ne");
+        buffer.fold(&LineRange::new(0, 3));
+        buffer.fold(&LineRange::new(3, 5));
+        buffer.unfold(1);
+
+        assert_eq!(buffer.folds(), vec![LineRange::new(3, 5)]);
+    }
+
+    #[test]
+    fn folds_are_opened_when_their_content_is_deleted() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary\neditor");
+        buffer.fold(&LineRange::new(1, 3));
+        buffer.replace(
+            Range::new(Position{ line: 0, offset: 6 }, Position{ line: 2, offset: 6 }),
+            ""
+        );
+
+        assert!(buffer.folds().is_empty());
+        assert_eq!(buffer.visible_lines(), vec![0]);
+    }
+
+    #[test]
+    fn move_cursor_down_skips_hidden_lines() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary\neditor\ntext");
+        buffer.fold(&LineRange::new(0, 3));
+        buffer.cursor.move_to(Position{ line: 0, offset: 5 });
+
+        assert!(buffer.move_cursor_down());
+        assert_eq!(*buffer.cursor, Position{ line: 3, offset: 4 });
+        assert!(!buffer.move_cursor_down());
+        assert_eq!(*buffer.cursor, Position{ line: 3, offset: 4 });
+    }
+
+    #[test]
+    fn move_cursor_up_fails_at_the_first_line() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+        buffer.cursor.move_to(Position{ line: 0, offset: 3 });
+
+        assert!(!buffer.move_cursor_up());
+        assert_eq!(*buffer.cursor, Position{ line: 0, offset: 3 });
+    }
+}
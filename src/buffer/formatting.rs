@@ -0,0 +1,307 @@
+//! Applying formatter output as a minimal set of changes.
+
+use buffer::diff::{edit_script, hunks};
+use buffer::{Buffer, Distance, LineEnding, LineRange, Position, Range};
+use std::ops;
+use unicode_segmentation::UnicodeSegmentation;
+
+// Changed lines are compared grapheme by grapheme, unless their combined
+// length exceeds this, since the edit script's cost grows with both their
+// length and the number of differences between them.
+const MAX_GRAPHEME_DIFF_LENGTH: usize = 1_000;
+
+impl Buffer {
+    /// Replaces the buffer's content with the specified (e.g. formatted)
+    /// content, changing only the text that differs between them, as a
+    /// single undoable operation. Unlike replacing the entire buffer, this
+    /// keeps the cursor, marks, viewports, and other tracked positions
+    /// anchored to the content surrounding them. Returns false if the buffer
+    /// is read-only or its content is unchanged. Carriage returns in the
+    /// content's line endings are removed, like those of loaded files (see
+    /// `line_ending`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::Position;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("fn main(){\nlet x=1;\n}");
+    /// buffer.cursor.move_to(Position{ line: 1, offset: 4 });
+    /// buffer.set_mark('a', Position{ line: 2, offset: 0 });
+    ///
+    /// assert!(buffer.apply_formatted("fn main() {\n    let x = 1;\n}\n"));
+    /// assert_eq!(buffer.data(), "fn main() {\n    let x = 1;\n}\n");
+    ///
+    /// // The cursor and mark stay on the same content.
+    /// assert_eq!(*buffer.cursor, Position{ line: 1, offset: 8 });
+    /// assert_eq!(buffer.mark('a'), Some(Position{ line: 2, offset: 0 }));
+    ///
+    /// buffer.undo();
+    /// assert_eq!(buffer.data(), "fn main(){\nlet x=1;\n}");
+    /// ```
+    pub fn apply_formatted(&mut self, content: &str) -> bool {
+        let content = LineEnding::Lf.apply(content);
+        let content = content.as_ref();
+        let data = self.data();
+        if self.read_only || data == content {
+            return false
+        }
+
+        let old_lines: Vec<&str> = data.split('\n').collect();
+        let new_lines: Vec<&str> = content.split('\n').collect();
+        let old_line_starts = line_starts(&old_lines);
+        let new_line_starts = line_starts(&new_lines);
+
+        let cursor = self.track_position(*self.cursor);
+        let in_operation_group = self.operation_group.is_some();
+        self.start_operation_group();
+
+        // Apply hunks from the end of the buffer backwards,
+        // so that they don't affect each other's positions.
+        for hunk in hunks(&edit_script(&old_lines, &new_lines)).iter().rev() {
+            let old_text = &data[hunk_region(&old_line_starts, &hunk.old_lines)];
+            let new_text = &content[hunk_region(&new_line_starts, &hunk.new_lines)];
+
+            // Hunks ending at the last line start at the end of the preceding
+            // line, since they include the newline that precedes them.
+            let hunk_start = match hunk.old_lines.start() {
+                line if line > 0 && hunk.old_lines.end() == old_lines.len() => Position{
+                    line: line - 1,
+                    offset: old_lines[line - 1].graphemes(true).count(),
+                },
+                line => Position{ line, offset: 0 },
+            };
+
+            for (start, end, change) in grapheme_changes(old_text, new_text).into_iter().rev() {
+                self.replace(Range::new(hunk_start + start, hunk_start + end), change);
+            }
+        }
+
+        if !in_operation_group {
+            self.end_operation_group();
+        }
+
+        if let Some(position) = self.tracked_position(cursor) {
+            self.cursor.move_to(position);
+        }
+        self.untrack(cursor);
+
+        true
+    }
+}
+
+// Returns the byte range of the content spanned by the lines, including
+// the newlines that separate them from adjacent lines. Ranges of lines
+// ending at the last line include the newline preceding them, rather than
+// the (non-existent) newline following them.
+fn hunk_region(line_starts: &[usize], lines: &LineRange) -> ops::Range<usize> {
+    let content_length = line_starts[line_starts.len() - 1] - 1;
+
+    if lines.end() == line_starts.len() - 1 {
+        line_starts[lines.start()].saturating_sub(1)..content_length
+    } else {
+        line_starts[lines.start()]..line_starts[lines.end()]
+    }
+}
+
+// Returns the byte offset at which each line starts when they're joined
+// using (and terminated by) newlines, followed by the total length
+// (including a final newline).
+fn line_starts(lines: &[&str]) -> Vec<usize> {
+    let mut starts = vec![0];
+    for line in lines {
+        let start = starts[starts.len() - 1];
+        starts.push(start + line.len() + 1);
+    }
+
+    starts
+}
+
+// Returns the changes needed to turn the old text into the new text, as the
+// distances (from the start of the old text) of the ranges to replace,
+// along with their replacements, in order.
+fn grapheme_changes(old: &str, new: &str) -> Vec<(Distance, Distance, String)> {
+    let old_graphemes: Vec<&str> = old.graphemes(true).collect();
+    let new_graphemes: Vec<&str> = new.graphemes(true).collect();
+
+    if old_graphemes.len() + new_graphemes.len() > MAX_GRAPHEME_DIFF_LENGTH {
+        let (prefix, old_change, new_change) = trim_common_affixes(old, new);
+        let start = Distance::of_str(prefix);
+        let end = Distance::of_str(&old[..prefix.len() + old_change.len()]);
+
+        return vec![(start, end, new_change.to_string())]
+    }
+
+    // Grapheme hunks' "line" ranges are grapheme indices.
+    let distances = grapheme_distances(&old_graphemes);
+    hunks(&edit_script(&old_graphemes, &new_graphemes)).into_iter().map(|hunk| {
+        (
+            distances[hunk.old_lines.start()],
+            distances[hunk.old_lines.end()],
+            new_graphemes[hunk.new_lines.start()..hunk.new_lines.end()].concat(),
+        )
+    }).collect()
+}
+
+// Returns the distance from the start of the graphemes to each of
+// them, followed by the distance to the end of the last one.
+fn grapheme_distances(graphemes: &[&str]) -> Vec<Distance> {
+    let mut distance = Distance{ lines: 0, offset: 0 };
+    let mut distances = vec![distance];
+    for &grapheme in graphemes {
+        if grapheme == "\n" {
+            distance = Distance{ lines: distance.lines + 1, offset: 0 };
+        } else {
+            distance.offset += 1;
+        }
+        distances.push(distance);
+    }
+
+    distances
+}
+
+// Splits two strings into their common (grapheme-aligned) prefix, and the
+// parts of each that differ, once the prefix and common suffix are removed.
+fn trim_common_affixes<'a, 'b>(old: &'a str, new: &'b str) -> (&'a str, &'a str, &'b str) {
+    let prefix_length: usize = old.graphemes(true).zip(new.graphemes(true))
+        .take_while(|&(a, b)| a == b)
+        .map(|(grapheme, _)| grapheme.len())
+        .sum();
+    let (old_rest, new_rest) = (&old[prefix_length..], &new[prefix_length..]);
+
+    let suffix_length: usize = old_rest.graphemes(true).rev().zip(new_rest.graphemes(true).rev())
+        .take_while(|&(a, b)| a == b)
+        .map(|(grapheme, _)| grapheme.len())
+        .sum();
+
+    (
+        &old[..prefix_length],
+        &old_rest[..old_rest.len() - suffix_length],
+        &new_rest[..new_rest.len() - suffix_length],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{grapheme_changes, trim_common_affixes, MAX_GRAPHEME_DIFF_LENGTH};
+    use buffer::{Buffer, Distance, Position, Viewport};
+
+    #[test]
+    fn trim_common_affixes_respects_grapheme_boundaries() {
+        assert_eq!(trim_common_affixes("ab\n", "aXb\n"), ("a", "", "X"));
+        assert_eq!(trim_common_affixes("e\u{301}\n", "e\n"), ("", "e\u{301}", "e"));
+        assert_eq!(trim_common_affixes("same\n", "same\n"), ("same\n", "", ""));
+    }
+
+    #[test]
+    fn grapheme_changes_replaces_differing_graphemes_across_lines() {
+        assert_eq!(grapheme_changes("a(b)\nc\n", "a( b )\nc;\n"), vec![
+            (Distance{ lines: 0, offset: 2 }, Distance{ lines: 0, offset: 2 }, " ".to_string()),
+            (Distance{ lines: 0, offset: 3 }, Distance{ lines: 0, offset: 3 }, " ".to_string()),
+            (Distance{ lines: 1, offset: 1 }, Distance{ lines: 1, offset: 1 }, ";".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn grapheme_changes_replaces_the_differing_span_of_long_lines() {
+        let old = format!("{}a\n", "x".repeat(MAX_GRAPHEME_DIFF_LENGTH));
+        let new = format!("{}b\n", "x".repeat(MAX_GRAPHEME_DIFF_LENGTH));
+        let offset = MAX_GRAPHEME_DIFF_LENGTH;
+
+        assert_eq!(grapheme_changes(&old, &new), vec![
+            (Distance{ lines: 0, offset }, Distance{ lines: 0, offset: offset + 1 }, "b".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn apply_formatted_normalizes_crlf_line_endings() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+        buffer.apply_formatted("scribe\r\nlibrary\r\n");
+
+        assert_eq!(buffer.data(), "scribe\nlibrary\n");
+    }
+
+    #[test]
+    fn apply_formatted_handles_changes_at_the_end_of_the_buffer() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+
+        buffer.apply_formatted("scribe\nlibrary\ntoolkit\n");
+        assert_eq!(buffer.data(), "scribe\nlibrary\ntoolkit\n");
+
+        buffer.apply_formatted("scribe");
+        assert_eq!(buffer.data(), "scribe");
+
+        buffer.apply_formatted("");
+        assert_eq!(buffer.data(), "");
+    }
+
+    #[test]
+    fn apply_formatted_produces_the_formatted_content() {
+        let contents = ["", "\n", "a", "a\n", "\na", "a\nb", "b\na\n", "a\n\nb\n", "नी\n😀 a", "a\nनी\n"];
+
+        for old in &contents {
+            for new in &contents {
+                let mut buffer = Buffer::new();
+                buffer.insert(*old);
+                assert_eq!(buffer.apply_formatted(new), old != new);
+                assert_eq!(buffer.data(), *new);
+
+                if old != new {
+                    buffer.undo();
+                }
+                assert_eq!(buffer.data(), *old);
+            }
+        }
+    }
+
+    #[test]
+    fn apply_formatted_only_replaces_changed_content() {
+        let mut buffer = Buffer::new();
+        buffer.insert("a\nb\nc\nd\ne");
+        buffer.apply_formatted("a\nB\nc\nd\n\ne");
+
+        // The lines between the changes were left in place.
+        buffer.undo();
+        assert_eq!(buffer.data(), "a\nb\nc\nd\ne");
+        buffer.redo();
+        assert_eq!(buffer.data(), "a\nB\nc\nd\n\ne");
+    }
+
+    #[test]
+    fn apply_formatted_keeps_viewports_anchored() {
+        let mut buffer = Buffer::new();
+        buffer.insert("a\nb\nc\nd");
+        let mut viewport = Viewport::new(&mut buffer, 2);
+        viewport.scroll_to(&mut buffer, 2);
+
+        buffer.apply_formatted("header\n\na\nb\nc\nd");
+
+        assert_eq!(viewport.first_line(&buffer), 4);
+    }
+
+    #[test]
+    fn apply_formatted_keeps_the_cursor_on_unchanged_lines() {
+        let mut buffer = Buffer::new();
+        buffer.insert("use a;\nuse b;\n\nfn main() {}");
+        buffer.cursor.move_to(Position{ line: 3, offset: 3 });
+
+        buffer.apply_formatted("use a;\n\nfn main() {}\n");
+
+        assert_eq!(*buffer.cursor, Position{ line: 2, offset: 3 });
+    }
+
+    #[test]
+    fn apply_formatted_leaves_read_only_and_unchanged_buffers_alone() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        assert!(!buffer.apply_formatted("scribe"));
+
+        buffer.read_only = true;
+        assert!(!buffer.apply_formatted("library"));
+        assert_eq!(buffer.data(), "scribe");
+    }
+}
@@ -2,7 +2,11 @@
 
 use super::Position;
 use super::Range;
-use std::borrow::Borrow;
+use memchr::{memchr, memchr_iter, memrchr};
+use std::borrow::{Borrow, Cow};
+use std::mem;
+#[cfg(debug_assertions)]
+use std::str;
 use unicode_segmentation::UnicodeSegmentation;
 
 /// A UTF-8 string buffer designed to minimize reallocations,
@@ -11,6 +15,10 @@ pub struct GapBuffer {
     data: Vec<u8>,
     gap_start: usize,
     gap_length: usize,
+
+    // The offset (excluding the gap) at which each line starts,
+    // maintained as the buffer is modified for direct line access.
+    line_starts: Vec<usize>,
 }
 
 impl GapBuffer {
@@ -29,11 +37,13 @@ impl GapBuffer {
         let capacity = bytes.capacity();
         let gap_start = bytes.len();
         let gap_length = capacity - gap_start;
+        let mut line_starts = vec![0];
+        line_starts.extend(memchr_iter(b'\n', &bytes).map(|offset| offset + 1));
         unsafe {
             bytes.set_len(capacity);
         }
 
-        GapBuffer{ data: bytes, gap_start, gap_length }
+        GapBuffer{ data: bytes, gap_start, gap_length, line_starts }
     }
 
     /// Inserts the specified data into the buffer at the specified position.
@@ -51,24 +61,7 @@ impl GapBuffer {
     /// ```
     pub fn insert(&mut self, data: &str, position: &Position) {
         // Ensure we have the capacity to insert this data.
-        if data.len() > self.gap_length {
-            // We're about to add space to the end of the buffer, so move the gap
-            // there beforehand so that we're essentially just increasing the
-            // gap size, and preventing a split/two-segment gap.
-            let offset = self.data.capacity();
-            self.move_gap(offset);
-
-            // Re-allocate the gap buffer, increasing its size.
-            self.data.reserve(data.len());
-
-            // Update the tracked gap size and tell the vector that
-            // we're using all of the new space immediately.
-            let capacity = self.data.capacity();
-            self.gap_length = capacity - self.gap_start;
-            unsafe {
-                self.data.set_len(capacity);
-            }
-        }
+        self.reserve_gap(data.len());
 
         let offset = match self.find_offset(position) {
             Some(o) => o,
@@ -76,6 +69,7 @@ impl GapBuffer {
         };
 
         self.move_gap(offset);
+        self.index_insertion(data);
         self.write_to_gap(data);
     }
 
@@ -142,6 +136,85 @@ impl GapBuffer {
         &*String::from_utf8_lossy(&self.data[self.gap_start+self.gap_length..])
     }
 
+    /// Returns an iterator over the buffer's lines (see `GapBufferLines`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::{GapBuffer, Position};
+    ///
+    /// let mut data = GapBuffer::new("scribe\nlibrary".to_string());
+    /// data.insert("text ", &Position{ line: 1, offset: 0 });
+    ///
+    /// let lines: Vec<(usize, String)> = data.lines()
+    ///     .map(|(number, line)| (number, line.into_owned()))
+    ///     .collect();
+    /// assert_eq!(lines, vec![
+    ///     (0, "scribe\n".to_string()),
+    ///     (1, "text library".to_string())
+    /// ]);
+    /// ```
+    pub fn lines(&self) -> GapBufferLines {
+        GapBufferLines{
+            first_half: &self.data[..self.gap_start],
+            second_half: &self.data[self.gap_start+self.gap_length..],
+            line_number: 0,
+            remaining_lines: self.line_count(),
+            reversed: false,
+        }
+    }
+
+    /// Returns the specified line's content (excluding its trailing newline),
+    /// located using the buffer's line index rather than by scanning the
+    /// preceding lines. The content is borrowed from the buffer, unless the
+    /// line straddles its gap. Returns `None` if the line doesn't exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::{GapBuffer, Position};
+    ///
+    /// let mut data = GapBuffer::new("scribe\nlibrary\n".to_string());
+    /// data.insert("text ", &Position{ line: 1, offset: 0 });
+    ///
+    /// assert_eq!(data.line(1).unwrap(), "text library");
+    /// assert_eq!(data.line(2).unwrap(), "");
+    /// assert!(data.line(3).is_none());
+    /// ```
+    pub fn line(&self, line: usize) -> Option<Cow<str>> {
+        let start = *self.line_starts.get(line)?;
+        let end = match self.line_starts.get(line + 1) {
+            Some(next_line_start) => next_line_start - 1,
+            None => self.data.len() - self.gap_length,
+        };
+
+        // Map the line's range onto either side of the gap.
+        let gap_end = self.gap_start + self.gap_length;
+        let content = if end <= self.gap_start {
+            String::from_utf8_lossy(&self.data[start..end])
+        } else if start >= self.gap_start {
+            String::from_utf8_lossy(&self.data[start + self.gap_length..end + self.gap_length])
+        } else {
+            join(&self.data[start..self.gap_start], &self.data[gap_end..end + self.gap_length])
+        };
+
+        Some(content)
+    }
+
+    /// Returns the number of lines in the buffer, including trailing newlines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::GapBuffer;
+    ///
+    /// let buffer = GapBuffer::new("scribe\nlibrary\n".to_string());
+    /// assert_eq!(buffer.line_count(), 3);
+    /// ```
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
     /// Removes the specified range of data from the buffer.
     ///
     /// # Examples
@@ -163,7 +236,52 @@ impl GapBuffer {
             Some(o) => o,
             None => return,
         };
+
+        self.delete_from(start_offset, range);
+    }
+
+    /// Replaces the specified range of data with the provided data. Unlike
+    /// a deletion followed by an insertion, the range's start is only located
+    /// once, and the replacement is written into the gap left by the removed
+    /// content, relocating the gap at most once. If the range's start is
+    /// out of bounds, the buffer contents will remain unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::{GapBuffer, Position, Range};
+    ///
+    /// let mut buffer = GapBuffer::new("scribe library".to_string());
+    /// let range = Range::new(
+    ///   Position{ line: 0, offset: 7 },
+    ///   Position{ line: 0, offset: 14 }
+    /// );
+    ///
+    /// buffer.replace(&range, "toolkit\ndata");
+    /// assert_eq!(buffer.to_string(), "scribe toolkit\ndata");
+    /// ```
+    pub fn replace(&mut self, range: &Range, data: &str) {
+        let start_offset = match self.find_offset(&range.start()) {
+            Some(o) => o,
+            None => return,
+        };
+
+        // Removing the range leaves the gap right where the replacement
+        // belongs; restore its position if it's relocated to make room.
+        self.delete_from(start_offset, range);
+        let offset = self.gap_start;
+        self.reserve_gap(data.len());
+        self.move_gap(offset);
+
+        self.index_insertion(data);
+        self.write_to_gap(data);
+    }
+
+    // Moves the gap to the specified offset (the start
+    // of the range), and widens it to cover the range.
+    fn delete_from(&mut self, start_offset: usize, range: &Range) {
         self.move_gap(start_offset);
+        let gap_length = self.gap_length;
 
         match self.find_offset(&range.end()) {
             Some(offset) => {
@@ -189,6 +307,9 @@ impl GapBuffer {
                 }
             }
         };
+
+        let deleted_length = self.gap_length - gap_length;
+        self.index_deletion(deleted_length);
     }
 
     /// Checks whether or not the specified position is in bounds of the buffer data.
@@ -209,6 +330,26 @@ impl GapBuffer {
         self.find_offset(position) != None
     }
 
+    /// Panics if the buffer's internal state is inconsistent: its gap must lie
+    /// within its allocation, the content on either side of the gap must be
+    /// valid UTF-8, and its line index must match its content. Only available
+    /// in debug builds.
+    #[cfg(debug_assertions)]
+    pub fn check_invariants(&self) {
+        assert_eq!(self.data.len(), self.data.capacity(), "gap buffer doesn't use its entire allocation");
+        assert!(self.gap_start + self.gap_length <= self.data.len(), "gap extends beyond the gap buffer's data");
+
+        let first_half = &self.data[..self.gap_start];
+        let second_half = &self.data[self.gap_start+self.gap_length..];
+        assert!(str::from_utf8(first_half).is_ok(), "content preceding the gap isn't valid UTF-8");
+        assert!(str::from_utf8(second_half).is_ok(), "content following the gap isn't valid UTF-8");
+
+        let mut line_starts = vec![0];
+        line_starts.extend(memchr_iter(b'\n', first_half).map(|offset| offset + 1));
+        line_starts.extend(memchr_iter(b'\n', second_half).map(|offset| self.gap_start + offset + 1));
+        assert_eq!(self.line_starts, line_starts, "line index doesn't match the gap buffer's content");
+    }
+
     // Maps a position to its offset equivalent in the data.
     fn find_offset(&self, position: &Position) -> Option<usize> {
         let first_half = String::from_utf8_lossy(&self.data[..self.gap_start]);
@@ -262,6 +403,29 @@ impl GapBuffer {
         None
     }
 
+    // Ensures the gap can hold the specified number of bytes, reallocating
+    // the buffer (and moving the gap to its end) if it can't.
+    fn reserve_gap(&mut self, length: usize) {
+        if length > self.gap_length {
+            // We're about to add space to the end of the buffer, so move the gap
+            // there beforehand so that we're essentially just increasing the
+            // gap size, and preventing a split/two-segment gap.
+            let offset = self.data.capacity();
+            self.move_gap(offset);
+
+            // Re-allocate the gap buffer, increasing its size.
+            self.data.reserve(length);
+
+            // Update the tracked gap size and tell the vector that
+            // we're using all of the new space immediately.
+            let capacity = self.data.capacity();
+            self.gap_length = capacity - self.gap_start;
+            unsafe {
+                self.data.set_len(capacity);
+            }
+        }
+    }
+
     fn move_gap(&mut self, offset: usize) {
         // We don't need to move any data if the buffer is at capacity.
         if self.gap_length == 0 {
@@ -290,6 +454,40 @@ impl GapBuffer {
         }
     }
 
+    // Updates the line index for data about to be written to the gap.
+    fn index_insertion(&mut self, data: &str) {
+        let offset = self.gap_start;
+
+        // Lines starting after the insertion point are pushed back by it.
+        let index = match self.line_starts.binary_search(&offset) {
+            Ok(index) => index + 1,
+            Err(index) => index,
+        };
+        for line_start in &mut self.line_starts[index..] {
+            *line_start += data.len();
+        }
+
+        let inserted_line_starts = memchr_iter(b'\n', data.as_bytes())
+            .map(|newline_offset| offset + newline_offset + 1);
+        self.line_starts.splice(index..index, inserted_line_starts);
+    }
+
+    // Updates the line index for data that's been
+    // removed from the start of the gap by widening it.
+    fn index_deletion(&mut self, deleted_length: usize) {
+        let start = self.gap_start;
+        let end = start + deleted_length;
+
+        // Lines starting within the deleted data (after the
+        // start of the line it begins on) no longer exist.
+        self.line_starts.retain(|&line_start| line_start <= start || line_start > end);
+        for line_start in &mut self.line_starts {
+            if *line_start > end {
+                *line_start -= deleted_length;
+            }
+        }
+    }
+
     fn write_to_gap(&mut self, data: &str) {
         for byte in data.bytes() {
             self.data[self.gap_start] = byte;
@@ -299,9 +497,207 @@ impl GapBuffer {
     }
 }
 
+/// Iterates over a gap buffer's lines (including their trailing newlines),
+/// paired with their line numbers, without copying its content. Lines are
+/// borrowed from the buffer, except for a line that straddles its gap,
+/// whose two halves are joined into an owned string. Like `LineIterator`,
+/// content ending with a newline produces a final, empty line.
+///
+/// Lines can also be iterated over in reverse, from the end of the buffer
+/// (e.g. for backward searches), without scanning the preceding lines'
+/// content beyond counting their newlines.
+pub struct GapBufferLines<'a> {
+    // The unvisited content preceding and following the gap.
+    first_half: &'a [u8],
+    second_half: &'a [u8],
+    line_number: usize,
+    remaining_lines: usize,
+
+    // Whether or not a line has been taken from the end, in which case
+    // the unvisited content ends with the newline that terminated it.
+    reversed: bool,
+}
+
+impl<'a> GapBufferLines<'a> {
+    // Takes all of the unvisited content, joining the halves if necessary.
+    fn take_remaining(&mut self) -> Cow<'a, str> {
+        let head = mem::replace(&mut self.first_half, &[]);
+        let tail = mem::replace(&mut self.second_half, &[]);
+
+        join(head, tail)
+    }
+}
+
+impl<'a> Iterator for GapBufferLines<'a> {
+    type Item = (usize, Cow<'a, str>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = match self.remaining_lines {
+            0 => return None,
+            1 => self.take_remaining(),
+            _ => match memchr(b'\n', self.first_half) {
+                Some(newline_offset) => {
+                    let (line, rest) = self.first_half.split_at(newline_offset + 1);
+                    self.first_half = rest;
+
+                    String::from_utf8_lossy(line)
+                },
+                None => {
+                    // The line ends after the gap; another line follows it,
+                    // so the content following the gap includes a newline.
+                    let line_end = memchr(b'\n', self.second_half).map(|offset| offset + 1).unwrap_or(0);
+                    let (tail, rest) = self.second_half.split_at(line_end);
+                    let head = mem::replace(&mut self.first_half, &[]);
+                    self.second_half = rest;
+
+                    join(head, tail)
+                },
+            },
+        };
+
+        self.remaining_lines -= 1;
+        self.line_number += 1;
+
+        Some((self.line_number - 1, line))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining_lines, Some(self.remaining_lines))
+    }
+}
+
+impl<'a> DoubleEndedIterator for GapBufferLines<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let line = match self.remaining_lines {
+            0 => return None,
+            1 => self.take_remaining(),
+            _ => {
+                // Exclude the newline terminating the line (if we've already
+                // taken the line following it) when looking for its start.
+                let (mut first_half, mut second_half) = (self.first_half, self.second_half);
+                if self.reversed {
+                    if second_half.is_empty() {
+                        first_half = &first_half[..first_half.len() - 1];
+                    } else {
+                        second_half = &second_half[..second_half.len() - 1];
+                    }
+                }
+
+                match memrchr(b'\n', second_half) {
+                    Some(newline_offset) => {
+                        let (rest, line) = self.second_half.split_at(newline_offset + 1);
+                        self.second_half = rest;
+
+                        String::from_utf8_lossy(line)
+                    },
+                    None => {
+                        // The line starts before the gap; another line precedes
+                        // it, so the content preceding the gap includes a newline.
+                        let line_start = memrchr(b'\n', first_half).map(|offset| offset + 1).unwrap_or(0);
+                        let (rest, head) = self.first_half.split_at(line_start);
+                        let tail = mem::replace(&mut self.second_half, &[]);
+                        self.first_half = rest;
+
+                        join(head, tail)
+                    },
+                }
+            },
+        };
+
+        self.remaining_lines -= 1;
+        self.reversed = true;
+
+        Some((self.line_number + self.remaining_lines, line))
+    }
+}
+
+impl<'a> ExactSizeIterator for GapBufferLines<'a> {}
+
+// Borrows the content if it's entirely on one side of the gap.
+fn join<'a>(head: &'a [u8], tail: &'a [u8]) -> Cow<'a, str> {
+    if head.is_empty() {
+        String::from_utf8_lossy(tail)
+    } else if tail.is_empty() {
+        String::from_utf8_lossy(head)
+    } else {
+        Cow::Owned(String::from_utf8_lossy(head).into_owned() + &String::from_utf8_lossy(tail))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use buffer::{GapBuffer, Position, Range};
+    use std::borrow::Cow;
+
+    fn lines(data: &GapBuffer) -> Vec<String> {
+        data.lines().map(|(_, line)| line.into_owned()).collect()
+    }
+
+    #[test]
+    fn lines_produces_a_line_for_empty_data() {
+        assert_eq!(lines(&GapBuffer::new(String::new())), vec![String::new()]);
+    }
+
+    #[test]
+    fn lines_includes_a_final_empty_line_after_a_trailing_newline() {
+        let mut data = GapBuffer::new("scribe".to_string());
+        data.insert("\n", &Position{ line: 0, offset: 6 });
+
+        assert_eq!(lines(&data), vec!["scribe\n".to_string(), String::new()]);
+    }
+
+    #[test]
+    fn lines_joins_lines_straddling_the_gap() {
+        let mut data = GapBuffer::new("scribe\nlibrary\neditor".to_string());
+        data.insert("x", &Position{ line: 1, offset: 3 });
+
+        assert_eq!(lines(&data), vec!["scribe\n", "libxrary\n", "editor"]);
+    }
+
+    #[test]
+    fn lines_can_be_iterated_in_reverse() {
+        let mut data = GapBuffer::new("scribe\nlibrary\neditor\n".to_string());
+        data.insert("x", &Position{ line: 1, offset: 3 });
+
+        let lines: Vec<(usize, String)> = data.lines().rev().map(|(number, line)| (number, line.into_owned())).collect();
+        assert_eq!(lines, vec![
+            (3, String::new()),
+            (2, "editor\n".to_string()),
+            (1, "libxrary\n".to_string()),
+            (0, "scribe\n".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn lines_can_be_iterated_from_both_ends() {
+        for gap_line in 0..4 {
+            let mut data = GapBuffer::new("a\nb\n\nc".to_string());
+            data.insert("x", &Position{ line: gap_line, offset: 0 });
+            let expected: Vec<(usize, String)> = data.lines().map(|(number, line)| (number, line.into_owned())).collect();
+
+            for front_lines in 0..5 {
+                let mut lines = data.lines();
+                let mut front: Vec<(usize, String)> = lines.by_ref().take(front_lines)
+                    .map(|(number, line)| (number, line.into_owned()))
+                    .collect();
+                let back: Vec<(usize, String)> = lines.rev().map(|(number, line)| (number, line.into_owned())).collect();
+                front.extend(back.into_iter().rev());
+
+                assert_eq!(front, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn lines_borrows_lines_that_do_not_straddle_the_gap() {
+        let mut data = GapBuffer::new("scribe\nlibrary".to_string());
+        data.insert("\n", &Position{ line: 0, offset: 6 });
+
+        assert!(data.lines().all(|(_, line)| match line {
+            Cow::Borrowed(_) => true,
+            Cow::Owned(_) => false,
+        }));
+    }
 
     #[test]
     fn move_gap_works() {
@@ -428,6 +824,39 @@ mod tests {
         assert_eq!(gb.to_string(), "scribe नी");
     }
 
+    #[test]
+    fn replacing_reuses_the_gap_left_by_the_removed_content() {
+        let mut gb = GapBuffer::new("scribe library editor".to_string());
+        gb.delete(&Range::new(Position{ line: 0, offset: 6 }, Position{ line: 0, offset: 7 }));
+        let gap_length = gb.gap_length;
+
+        gb.replace(&Range::new(Position{ line: 0, offset: 6 }, Position{ line: 0, offset: 13 }), " toolkit");
+        assert_eq!(gb.to_string(), "scribe toolkit editor");
+        assert_eq!(gb.gap_start, 14);
+        assert_eq!(gb.gap_length, gap_length - 1);
+    }
+
+    #[test]
+    fn replacing_with_more_data_than_the_gap_can_hold_works() {
+        let mut gb = GapBuffer::new("scribe\nlibrary\neditor".to_string());
+        gb.replace(
+            &Range::new(Position{ line: 0, offset: 3 }, Position{ line: 1, offset: 3 }),
+            "amp\ntext editor toolkit\nlib"
+        );
+
+        assert_eq!(gb.to_string(), "scramp\ntext editor toolkit\nlibrary\neditor");
+        assert_eq!(gb.line(2).unwrap(), "library");
+        assert_eq!(gb.line_count(), 4);
+    }
+
+    #[test]
+    fn replacing_at_an_invalid_position_does_nothing() {
+        let mut gb = GapBuffer::new("scribe".to_string());
+        gb.replace(&Range::new(Position{ line: 1, offset: 0 }, Position{ line: 1, offset: 3 }), "library");
+
+        assert_eq!(gb.to_string(), "scribe");
+    }
+
     #[test]
     fn read_does_not_include_gap_contents_when_gap_is_at_start_of_range() {
         // Create a buffer and a range that captures the first character.
@@ -475,6 +904,64 @@ mod tests {
         assert_eq!(gb.read(&range).unwrap(), " library");
     }
 
+    #[test]
+    fn line_count_includes_newlines_on_both_sides_of_the_gap() {
+        let mut gb = GapBuffer::new("scribe\nlibrary".to_string());
+        gb.insert("\n", &Position{ line: 0, offset: 3 });
+        gb.insert("\n", &Position{ line: 0, offset: 1 });
+
+        assert_eq!(gb.line_count(), 4);
+    }
+
+    #[test]
+    fn line_reflects_insertions_and_deletions() {
+        let mut gb = GapBuffer::new("scribe\nlibrary\neditor".to_string());
+        gb.insert("text\nfor ", &Position{ line: 1, offset: 3 });
+        assert_eq!(gb.line(1).unwrap(), "libtext");
+        assert_eq!(gb.line(2).unwrap(), "for rary");
+        assert_eq!(gb.line(3).unwrap(), "editor");
+
+        gb.delete(&Range::new(Position{ line: 0, offset: 3 }, Position{ line: 2, offset: 1 }));
+        assert_eq!(gb.line(0).unwrap(), "scror rary");
+        assert_eq!(gb.line(1).unwrap(), "editor");
+        assert!(gb.line(2).is_none());
+    }
+
+    #[test]
+    fn line_matches_split_content_around_the_gap() {
+        let data = "a\nbc\n\nd\n";
+        for line in 0..5 {
+            for offset in 0..2 {
+                let mut gb = GapBuffer::new(data.to_string());
+                gb.insert("x", &Position{ line, offset });
+                let content = gb.to_string();
+
+                let lines: Vec<String> = (0..gb.line_count()).map(|line| gb.line(line).unwrap().into_owned()).collect();
+                assert_eq!(lines, content.split('\n').collect::<Vec<&str>>());
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn check_invariants_accepts_modified_buffers() {
+        let mut gb = GapBuffer::new("scribe\nlibrary".to_string());
+        gb.insert("नी\n", &Position{ line: 1, offset: 3 });
+        gb.replace(&Range::new(Position{ line: 0, offset: 2 }, Position{ line: 1, offset: 1 }), "\n");
+
+        gb.check_invariants();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "line index doesn't match")]
+    fn check_invariants_detects_stale_line_indices() {
+        let mut gb = GapBuffer::new("scribe\nlibrary".to_string());
+        gb.line_starts.pop();
+
+        gb.check_invariants();
+    }
+
     #[test]
     fn in_bounds_considers_grapheme_clusters() {
         let gb = GapBuffer::new("scribe नी library".to_string());
@@ -0,0 +1,155 @@
+//! Per-line gutter annotations for frontends.
+
+use buffer::{Buffer, FoldStrategy, LineChange, LineRange};
+use errors::*;
+
+/// Indicates that a line introduces a foldable region or a fold.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FoldMarker {
+    /// The line introduces a region that can be folded.
+    Open,
+
+    /// The line introduces a fold, whose remaining lines are hidden.
+    Closed,
+}
+
+/// The annotations displayed in the gutter alongside a visible line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GutterLine {
+    /// The (zero-based) line number.
+    pub line: usize,
+
+    /// Whether the line introduces a foldable region or a fold.
+    pub fold: Option<FoldMarker>,
+
+    /// The line's state relative to the buffer's baseline (see `line_changes`).
+    pub change: Option<LineChange>,
+
+    /// The names of marks set on the line, in order.
+    pub marks: Vec<char>,
+}
+
+impl Buffer {
+    /// Returns gutter annotations for the lines in the specified range
+    /// (excluding the line on which it ends), skipping lines hidden by folds
+    /// and those beyond the end of the buffer. Foldable regions are found
+    /// using the specified strategy, and line changes are determined using
+    /// the buffer's baseline provider, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{FoldMarker, FoldStrategy, GutterLine, LineRange};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\n  library\n  editor\ntext\n  toolkit");
+    /// buffer.fold(&LineRange::new(0, 3));
+    ///
+    /// assert_eq!(
+    ///     buffer.gutter(&LineRange::new(0, 10), FoldStrategy::Indentation).unwrap(),
+    ///     vec![
+    ///         GutterLine{ line: 0, fold: Some(FoldMarker::Closed), change: None, marks: vec![] },
+    ///         GutterLine{ line: 3, fold: Some(FoldMarker::Open), change: None, marks: vec![] },
+    ///         GutterLine{ line: 4, fold: None, change: None, marks: vec![] },
+    ///     ]
+    /// );
+    /// ```
+    pub fn gutter(&self, lines: &LineRange, strategy: FoldStrategy) -> Result<Vec<GutterLine>> {
+        let regions = self.foldable_regions(strategy)?;
+        let folds = self.folds();
+        let changes = self.line_changes();
+        let marks = self.marks();
+
+        Ok(self.visible_lines().into_iter()
+            .filter(|&line| lines.includes(line))
+            .map(|line| {
+                let fold =
+                    if folds.iter().any(|fold| fold.start() == line) {
+                        Some(FoldMarker::Closed)
+                    } else if regions.iter().any(|region| region.start() == line) {
+                        Some(FoldMarker::Open)
+                    } else {
+                        None
+                    };
+
+                GutterLine{
+                    line,
+                    fold,
+                    change: changes.get(&line).cloned(),
+                    marks: marks.iter()
+                        .filter(|&&(_, position)| position.line == line)
+                        .map(|&(name, _)| name)
+                        .collect(),
+                }
+            }).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, FoldStrategy, LineRange, Position};
+    #[cfg(feature = "system")]
+    use buffer::{BaselineProvider, LineChange};
+    #[cfg(feature = "system")]
+    use std::path::Path;
+
+    #[test]
+    fn gutter_only_includes_lines_within_the_range() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary\neditor\ntext");
+        let lines: Vec<usize> = buffer.gutter(&LineRange::new(1, 3), FoldStrategy::Indentation)
+            .unwrap()
+            .iter()
+            .map(|gutter_line| gutter_line.line)
+            .collect();
+
+        assert_eq!(lines, vec![1, 2]);
+    }
+
+    #[test]
+    fn gutter_requires_a_syntax_definition_for_syntax_folding() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+
+        assert!(buffer.gutter(&LineRange::new(0, 1), FoldStrategy::Syntax).is_err());
+    }
+
+    #[test]
+    fn gutter_includes_marks() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+        buffer.set_mark('b', Position{ line: 1, offset: 2 });
+        buffer.set_mark('a', Position{ line: 1, offset: 0 });
+        let marks: Vec<Vec<char>> = buffer.gutter(&LineRange::new(0, 2), FoldStrategy::Indentation)
+            .unwrap()
+            .into_iter()
+            .map(|gutter_line| gutter_line.marks)
+            .collect();
+
+        assert_eq!(marks, vec![vec![], vec!['a', 'b']]);
+    }
+
+    #[test]
+    #[cfg(feature = "system")]
+    fn gutter_includes_line_changes() {
+        struct SavedBaseline;
+
+        impl BaselineProvider for SavedBaseline {
+            fn baseline(&self, _: &Path) -> Option<String> {
+                Some("it works!\n".to_string())
+            }
+        }
+
+        let mut buffer = Buffer::from_file(Path::new("tests/sample/file")).unwrap();
+        buffer.baseline_provider = Some(Box::new(SavedBaseline));
+        buffer.insert("scribe\n");
+        let changes: Vec<Option<LineChange>> = buffer.gutter(&LineRange::new(0, 3), FoldStrategy::Indentation)
+            .unwrap()
+            .iter()
+            .map(|gutter_line| gutter_line.change)
+            .collect();
+
+        assert_eq!(changes, vec![Some(LineChange::Added), None, None]);
+    }
+}
@@ -0,0 +1,241 @@
+//! Commands for incrementing and decrementing numbers.
+
+use buffer::{Buffer, Position, Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+// An integer found on a line, described by the grapheme
+// offsets of its start (including its sign or hex prefix) and end.
+struct Number {
+    start: usize,
+    end: usize,
+    hex: bool,
+}
+
+impl Buffer {
+    /// Finds the integer at or after the cursor on its line and adds the
+    /// specified delta to it (use a negative delta to subtract), as a single
+    /// undoable operation. The cursor is moved to the number's last character.
+    ///
+    /// Decimal numbers can be negative (a leading `-` is treated as their sign),
+    /// and hexadecimal numbers are recognized by their `0x` prefix. Leading
+    /// zeros are preserved, as is the case of hexadecimal digits. Returns
+    /// `false` (leaving the buffer unchanged) if no number is found, or if
+    /// the result would overflow.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::Position;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("version = 007;");
+    /// buffer.increment_number(1);
+    ///
+    /// assert_eq!(buffer.data(), "version = 008;");
+    /// assert_eq!(*buffer.cursor, Position{ line: 0, offset: 12 });
+    ///
+    /// buffer.increment_number(-10);
+    /// assert_eq!(buffer.data(), "version = -002;");
+    /// ```
+    pub fn increment_number(&mut self, delta: i64) -> bool {
+        let position = *self.cursor;
+        let line_content = self.line(position.line);
+        let line: Vec<&str> = match line_content {
+            Some(ref line) => line.graphemes(true).collect(),
+            None => return false,
+        };
+
+        let number = match find_number(&line, position.offset) {
+            Some(number) => number,
+            None => return false,
+        };
+        let content = line[number.start..number.end].concat();
+        let updated_content =
+            if number.hex {
+                increment_hex(&content, delta)
+            } else {
+                increment_decimal(&content, delta)
+            };
+        let updated_content = match updated_content {
+            Some(updated_content) => updated_content,
+            None => return false,
+        };
+
+        self.replace(
+            Range::new(
+                Position{ line: position.line, offset: number.start },
+                Position{ line: position.line, offset: number.end }
+            ),
+            updated_content.as_str()
+        );
+        self.cursor.move_to(Position{
+            line: position.line,
+            offset: number.start + updated_content.len() - 1
+        });
+
+        true
+    }
+}
+
+// Finds the first number on the line that contains or follows the offset.
+fn find_number(line: &[&str], offset: usize) -> Option<Number> {
+    let is_digit = |index: usize| line.get(index).map(|g| is_ascii_digit(g, 10)).unwrap_or(false);
+    let is_hex_digit = |index: usize| line.get(index).map(|g| is_ascii_digit(g, 16)).unwrap_or(false);
+
+    let mut index = 0;
+    while index < line.len() {
+        let hex = line[index] == "0" &&
+            line.get(index + 1).map(|&g| g == "x" || g == "X").unwrap_or(false) &&
+            is_hex_digit(index + 2);
+
+        let number =
+            if hex {
+                let mut end = index + 2;
+                while is_hex_digit(end) {
+                    end += 1;
+                }
+
+                Some(Number{ start: index, end, hex: true })
+            } else if is_digit(index) {
+                let start = if index > 0 && line[index - 1] == "-" { index - 1 } else { index };
+                let mut end = index;
+                while is_digit(end) {
+                    end += 1;
+                }
+
+                Some(Number{ start, end, hex: false })
+            } else {
+                None
+            };
+
+        match number {
+            Some(number) => {
+                if number.end > offset {
+                    return Some(number)
+                }
+                index = number.end;
+            },
+            None => index += 1,
+        }
+    }
+
+    None
+}
+
+fn is_ascii_digit(grapheme: &str, radix: u32) -> bool {
+    let mut chars = grapheme.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => c.is_ascii() && c.is_digit(radix),
+        _ => false,
+    }
+}
+
+fn increment_decimal(content: &str, delta: i64) -> Option<String> {
+    let digits = content.trim_start_matches('-');
+    let value = content.parse::<i64>().ok()?.checked_add(delta)?;
+
+    // Pad the result to the original width if it had leading zeros.
+    let width = if digits.len() > 1 && digits.starts_with('0') { digits.len() } else { 0 };
+    let sign = if value < 0 { "-" } else { "" };
+
+    Some(format!("{}{:0width$}", sign, value.unsigned_abs(), width = width))
+}
+
+fn increment_hex(content: &str, delta: i64) -> Option<String> {
+    let digits = &content[2..];
+    let value = u64::from_str_radix(digits, 16).ok()?;
+    let value =
+        if delta < 0 {
+            value.checked_sub(delta.unsigned_abs())?
+        } else {
+            value.checked_add(delta as u64)?
+        };
+    let width = digits.len();
+
+    if digits.chars().any(|c| c.is_uppercase()) {
+        Some(format!("{}{:0width$X}", &content[..2], value, width = width))
+    } else {
+        Some(format!("{}{:0width$x}", &content[..2], value, width = width))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, Position};
+
+    #[test]
+    fn increment_number_prefers_the_number_under_the_cursor() {
+        let mut buffer = Buffer::new();
+        buffer.insert("1 22 3");
+        buffer.cursor.move_to(Position{ line: 0, offset: 3 });
+
+        assert!(buffer.increment_number(1));
+        assert_eq!(buffer.data(), "1 23 3");
+    }
+
+    #[test]
+    fn increment_number_preserves_hex_case_and_width() {
+        let mut buffer = Buffer::new();
+        buffer.insert("color: 0x00FF;");
+
+        assert!(buffer.increment_number(1));
+        assert_eq!(buffer.data(), "color: 0x0100;");
+        assert_eq!(*buffer.cursor, Position{ line: 0, offset: 12 });
+    }
+
+    #[test]
+    fn increment_number_handles_sign_changes() {
+        let mut buffer = Buffer::new();
+        buffer.insert("x = -1");
+
+        assert!(buffer.increment_number(2));
+        assert_eq!(buffer.data(), "x = 1");
+
+        // The change is undone as a single operation.
+        buffer.undo();
+        assert_eq!(buffer.data(), "x = -1");
+    }
+
+    #[test]
+    fn increment_number_ignores_numbers_before_the_cursor_and_on_other_lines() {
+        let mut buffer = Buffer::new();
+        buffer.insert("1 scribe\n2");
+        buffer.cursor.move_to(Position{ line: 0, offset: 2 });
+
+        assert!(!buffer.increment_number(1));
+        assert_eq!(buffer.data(), "1 scribe\n2");
+    }
+
+    #[test]
+    fn increment_number_does_not_overflow() {
+        let mut buffer = Buffer::new();
+        buffer.insert("9223372036854775807");
+
+        assert!(!buffer.increment_number(1));
+        assert_eq!(buffer.data(), "9223372036854775807");
+    }
+
+    #[test]
+    fn increment_number_rejects_hex_overflow() {
+        let mut buffer = Buffer::new();
+        buffer.insert("0xffffffffffffffff 0x0");
+
+        assert!(!buffer.increment_number(1));
+        assert_eq!(buffer.data(), "0xffffffffffffffff 0x0");
+
+        buffer.cursor.move_to(Position{ line: 0, offset: 19 });
+        assert!(!buffer.increment_number(-1));
+        assert_eq!(buffer.data(), "0xffffffffffffffff 0x0");
+    }
+
+    #[test]
+    fn increment_number_handles_the_minimum_decimal_value() {
+        let mut buffer = Buffer::new();
+        buffer.insert("-9223372036854775807");
+
+        assert!(buffer.increment_number(-1));
+        assert_eq!(buffer.data(), "-9223372036854775808");
+        assert!(!buffer.increment_number(-1));
+    }
+}
@@ -0,0 +1,556 @@
+//! Buffer indentation settings and commands.
+
+use buffer::{Buffer, LineRange, Position, Range};
+#[cfg(feature = "syntax")]
+use buffer::{Scope, Token};
+use std::cmp;
+use std::collections::HashMap;
+use std::default::Default;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The whitespace character used to indent lines.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IndentationStyle {
+    Spaces,
+    Tabs,
+}
+
+/// A buffer's unit of indentation. For space-based indentation, the width is
+/// the number of spaces in a single level; for tab-based indentation, it's
+/// the number of columns a tab character is considered to occupy.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Indentation {
+    pub style: IndentationStyle,
+    pub width: usize,
+}
+
+impl Default for Indentation {
+    fn default() -> Self {
+        Indentation{ style: IndentationStyle::Spaces, width: 4 }
+    }
+}
+
+impl Indentation {
+    /// Returns the whitespace for a single level of indentation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::{Indentation, IndentationStyle};
+    ///
+    /// let spaces = Indentation{ style: IndentationStyle::Spaces, width: 2 };
+    /// assert_eq!(spaces.unit(), "  ");
+    ///
+    /// let tabs = Indentation{ style: IndentationStyle::Tabs, width: 8 };
+    /// assert_eq!(tabs.unit(), "\t");
+    /// ```
+    pub fn unit(&self) -> String {
+        match self.style {
+            IndentationStyle::Spaces => " ".repeat(self.width),
+            IndentationStyle::Tabs => "\t".to_string(),
+        }
+    }
+
+    /// Infers the indentation used by the specified content by analyzing the
+    /// leading whitespace of its lines. Tabs are detected if more lines are
+    /// indented with tabs than with spaces (and are assigned the default
+    /// width). Otherwise, the width is the most common change in indentation
+    /// between consecutive lines. Returns `None` if no lines are indented.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::{Indentation, IndentationStyle};
+    ///
+    /// let indentation = Indentation::detect("scribe {\n  library {\n    editor\n  }\n}");
+    /// assert_eq!(indentation, Some(Indentation{ style: IndentationStyle::Spaces, width: 2 }));
+    ///
+    /// assert_eq!(Indentation::detect("scribe\nlibrary"), None);
+    /// ```
+    pub fn detect(data: &str) -> Option<Indentation> {
+        let mut tab_lines = 0;
+        let mut space_lines = 0;
+        let mut previous_width = 0;
+        let mut width_changes: HashMap<usize, usize> = HashMap::new();
+
+        for line in data.lines() {
+            // Blank lines don't convey any indentation.
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            if line.starts_with('\t') {
+                tab_lines += 1;
+                previous_width = 0;
+                continue;
+            }
+
+            let width = line.chars().take_while(|&c| c == ' ').count();
+            if width > 0 {
+                space_lines += 1;
+            }
+            if width > previous_width {
+                *width_changes.entry(width - previous_width).or_insert(0) += 1;
+            }
+            previous_width = width;
+        }
+
+        if tab_lines == 0 && space_lines == 0 {
+            None
+        } else if tab_lines > space_lines {
+            Some(Indentation{ style: IndentationStyle::Tabs, ..Indentation::default() })
+        } else {
+            // Prefer the narrower width when changes are equally common.
+            width_changes.into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+                .map(|(width, _)| Indentation{ style: IndentationStyle::Spaces, width })
+        }
+    }
+}
+
+impl Buffer {
+    /// Returns the buffer's unit of indentation, used by editing commands.
+    /// Buffers loaded from a file use the indentation detected in its
+    /// contents, falling back to the default (four spaces).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{Indentation, IndentationStyle};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.set_indentation(Indentation{ style: IndentationStyle::Tabs, width: 4 });
+    ///
+    /// assert_eq!(buffer.indentation().style, IndentationStyle::Tabs);
+    /// ```
+    pub fn indentation(&self) -> Indentation {
+        self.indentation
+    }
+
+    /// Sets the buffer's unit of indentation, used by editing commands.
+    pub fn set_indentation(&mut self, indentation: Indentation) {
+        self.indentation = indentation;
+    }
+
+    /// Indents the specified lines (excluding the line on which the range
+    /// ends) by the buffer's unit of indentation, as a single undoable
+    /// operation. Blank lines are left untouched.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::LineRange;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\n\nlibrary");
+    /// buffer.indent(&LineRange::new(0, 3));
+    ///
+    /// assert_eq!(buffer.data(), "    scribe\n\n    library");
+    /// ```
+    pub fn indent(&mut self, lines: &LineRange) {
+        let unit = self.indentation.unit();
+        let unit_length = unit.graphemes(true).count() as isize;
+
+        self.transform_lines(lines, |line| {
+            if line.trim().is_empty() {
+                (line.to_string(), 0)
+            } else {
+                (format!("{}{}", unit, line), unit_length)
+            }
+        });
+    }
+
+    /// Removes up to one level of indentation from the specified lines
+    /// (excluding the line on which the range ends), as a single undoable
+    /// operation. Leading tabs are always removed; otherwise, as many leading
+    /// spaces as there are in the buffer's unit of indentation are removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::LineRange;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("      scribe\n\tlibrary\n  editor");
+    /// buffer.outdent(&LineRange::new(0, 3));
+    ///
+    /// assert_eq!(buffer.data(), "  scribe\nlibrary\neditor");
+    /// ```
+    pub fn outdent(&mut self, lines: &LineRange) {
+        let width = self.indentation.width;
+
+        self.transform_lines(lines, |line| {
+            let removed =
+                if line.starts_with('\t') {
+                    1
+                } else {
+                    line.chars().take(width).take_while(|&c| c == ' ').count()
+                };
+            let content_start = line.char_indices()
+                .nth(removed)
+                .map(|(index, _)| index)
+                .unwrap_or_else(|| line.len());
+
+            (line[content_start..].to_string(), -(removed as isize))
+        });
+    }
+
+    /// Rewrites the leading whitespace of every line to use the specified
+    /// indentation style and width, as a single undoable operation, and
+    /// updates the buffer's unit of indentation to match.
+    ///
+    /// Existing indentation is measured in columns using the buffer's current
+    /// unit of indentation (tabs advance to the next tab stop), so mixed tabs
+    /// and spaces are handled. Columns that don't make up a full level of
+    /// indentation are considered alignment, and are preserved as spaces.
+    /// Whitespace following the first non-whitespace character is untouched.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::IndentationStyle;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\n    library\n  \t  editor");
+    /// buffer.reindent(IndentationStyle::Tabs, 4);
+    ///
+    /// assert_eq!(buffer.data(), "scribe\n\tlibrary\n\t  editor");
+    /// ```
+    pub fn reindent(&mut self, style: IndentationStyle, width: usize) {
+        let current_width = cmp::max(self.indentation.width, 1);
+        let indentation = Indentation{ style, width: cmp::max(width, 1) };
+        let line_count = self.line_count();
+
+        self.transform_lines(&LineRange::new(0, line_count), |line| {
+            let content = line.trim_start_matches(|c| c == ' ' || c == '\t');
+            let leading_whitespace = &line[..line.len() - content.len()];
+
+            // Measure the existing indentation in columns.
+            let columns = leading_whitespace.chars().fold(0, |columns, c| {
+                if c == '\t' {
+                    (columns / current_width + 1) * current_width
+                } else {
+                    columns + 1
+                }
+            });
+            let levels = columns / current_width;
+            let alignment = columns % current_width;
+
+            let updated_line = format!(
+                "{}{}{}",
+                indentation.unit().repeat(levels),
+                " ".repeat(alignment),
+                content
+            );
+            let length_change = updated_line.len() as isize - line.len() as isize;
+
+            (updated_line, length_change)
+        });
+
+        self.indentation = indentation;
+    }
+
+    /// Inserts a newline at the cursor position, indenting the new line using
+    /// the current line's leading whitespace, and moves the cursor after it.
+    ///
+    /// If the buffer has a syntax definition and the content preceding the
+    /// cursor ends with a scope-opening character (`{`, `(`, `[` or `:`) that
+    /// isn't part of a comment or string, the new line is indented an
+    /// additional level, using the buffer's unit of indentation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate scribe;
+    /// # #[cfg(feature = "syntax")]
+    /// # extern crate syntect;
+    /// use scribe::Buffer;
+    /// use scribe::buffer::Position;
+    /// # #[cfg(feature = "syntax")]
+    /// # use syntect::parsing::SyntaxSet;
+    ///
+    /// # #[cfg(feature = "syntax")]
+    /// # fn main() {
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("fn main() {");
+    /// buffer.cursor.move_to(Position{ line: 0, offset: 11 });
+    ///
+    /// // Omitted code to set up the buffer's syntax definition.
+    /// # let syntax_set = SyntaxSet::load_defaults_newlines();
+    /// # buffer.syntax_definition = syntax_set.find_syntax_by_extension("rs").cloned();
+    ///
+    /// buffer.insert_newline();
+    /// assert_eq!(buffer.data(), "fn main() {\n    ");
+    /// assert_eq!(*buffer.cursor, Position{ line: 1, offset: 4 });
+    /// # }
+    /// # #[cfg(not(feature = "syntax"))]
+    /// # fn main() {}
+    /// ```
+    pub fn insert_newline(&mut self) {
+        let position = *self.cursor;
+        let line = self.line(position.line).unwrap_or_default();
+
+        // Only consider content preceding the cursor.
+        let preceding_content: String = line.graphemes(true).take(position.offset).collect();
+        let content = preceding_content.trim_start();
+        let mut indentation = preceding_content[..preceding_content.len() - content.len()].to_string();
+
+        if self.opens_scope(position.line, &preceding_content) {
+            indentation.push_str(&self.indentation.unit());
+        }
+
+        self.insert(format!("\n{}", indentation));
+        self.cursor.move_to(Position{
+            line: position.line + 1,
+            offset: indentation.graphemes(true).count()
+        });
+    }
+
+    // Whether or not the content (the start of the specified
+    // line) ends with a scope-opening character.
+    #[cfg(feature = "syntax")]
+    fn opens_scope(&self, line: usize, content: &str) -> bool {
+        let content = content.trim_end();
+        if !content.ends_with(|c| c == '{' || c == '(' || c == '[' || c == ':') {
+            return false
+        }
+
+        // Use the token stream to ignore characters in comments and strings.
+        let tokens = match self.tokens() {
+            Ok(tokens) => tokens,
+            Err(_) => return false,
+        };
+        let comment = Scope::new("comment").unwrap();
+        let string = Scope::new("string").unwrap();
+        let offset = content.graphemes(true).count() - 1;
+
+        for token in tokens.iter() {
+            if let Token::Lexeme(lexeme) = token {
+                if lexeme.position.line < line {
+                    continue;
+                } else if lexeme.position.line > line || lexeme.position.offset > offset {
+                    break;
+                }
+
+                let lexeme_end = lexeme.position.offset + lexeme.value.graphemes(true).count();
+                if offset < lexeme_end {
+                    return !lexeme.scope.as_slice().iter().any(|&scope| {
+                        comment.is_prefix_of(scope) || string.is_prefix_of(scope)
+                    })
+                }
+            }
+        }
+
+        false
+    }
+
+    // Without lexing support, buffers never have a syntax definition.
+    #[cfg(not(feature = "syntax"))]
+    fn opens_scope(&self, _: usize, _: &str) -> bool {
+        false
+    }
+
+    // Replaces the specified lines (excluding the line on which the range ends)
+    // with the output of the transform, in a single operation. The transform
+    // returns the updated line, along with the change in its length, which is
+    // used to keep the cursor on the same content, and assumes that changes are
+    // made to the start of the line (i.e. its indentation).
+    fn transform_lines<F>(&mut self, lines: &LineRange, transform: F)
+        where F: Fn(&str) -> (String, isize)
+    {
+        let data = self.data();
+        let buffer_lines: Vec<&str> = data.split('\n').collect();
+        let end_line = cmp::min(lines.end(), buffer_lines.len());
+        if lines.start() >= end_line {
+            return
+        }
+
+        let mut cursor_offset = self.cursor.offset;
+        let updated_lines: Vec<String> = buffer_lines[lines.start()..end_line]
+            .iter()
+            .enumerate()
+            .map(|(index, line)| {
+                let (updated_line, length_change) = transform(line);
+                if lines.start() + index == self.cursor.line {
+                    cursor_offset = cmp::max(cursor_offset as isize + length_change, 0) as usize;
+                }
+
+                updated_line
+            }).collect();
+
+        // Don't add a no-op to the history.
+        if updated_lines.iter().zip(&buffer_lines[lines.start()..end_line]).all(|(a, b)| a == b) {
+            return
+        }
+
+        let range = Range::new(
+            Position{ line: lines.start(), offset: 0 },
+            Position{ line: end_line - 1, offset: buffer_lines[end_line - 1].graphemes(true).count() }
+        );
+        self.replace(range, updated_lines.join("\n"));
+
+        let line = self.cursor.line;
+        self.cursor.move_to(Position{ line, offset: cursor_offset });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "syntax")]
+    use syntect::parsing::SyntaxSet;
+    use buffer::{Buffer, Indentation, IndentationStyle, LineRange, Position};
+
+    #[test]
+    fn detect_prefers_the_more_common_style() {
+        let indentation = Indentation::detect("a\n\tb\n\tc\n  d");
+        assert_eq!(indentation, Some(Indentation{ style: IndentationStyle::Tabs, width: 4 }));
+    }
+
+    #[test]
+    fn detect_uses_the_most_common_change_in_indentation() {
+        // Deeply nested lines shouldn't be mistaken for a wider indentation.
+        let data = "a\n    b\n        c\n            d\n    e\n    f\n        g";
+        let indentation = Indentation::detect(data);
+
+        assert_eq!(indentation, Some(Indentation{ style: IndentationStyle::Spaces, width: 4 }));
+    }
+
+    #[test]
+    fn detect_ignores_blank_lines() {
+        let indentation = Indentation::detect("a\n\n  b\n      \n  c");
+        assert_eq!(indentation, Some(Indentation{ style: IndentationStyle::Spaces, width: 2 }));
+    }
+
+    #[test]
+    fn indent_uses_the_buffer_indentation_unit() {
+        let mut buffer = Buffer::new();
+        buffer.set_indentation(Indentation{ style: IndentationStyle::Tabs, width: 4 });
+        buffer.insert("scribe\nlibrary");
+        buffer.indent(&LineRange::new(1, 2));
+
+        assert_eq!(buffer.data(), "scribe\n\tlibrary");
+    }
+
+    #[test]
+    fn indent_is_undone_as_a_single_operation() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+        buffer.indent(&LineRange::new(0, 2));
+        buffer.undo();
+
+        assert_eq!(buffer.data(), "scribe\nlibrary");
+    }
+
+    #[test]
+    fn indent_and_outdent_keep_the_cursor_on_the_same_content() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+        buffer.cursor.move_to(Position{ line: 1, offset: 2 });
+
+        buffer.indent(&LineRange::new(0, 2));
+        assert_eq!(*buffer.cursor, Position{ line: 1, offset: 6 });
+
+        buffer.outdent(&LineRange::new(0, 2));
+        assert_eq!(*buffer.cursor, Position{ line: 1, offset: 2 });
+    }
+
+    #[test]
+    fn outdent_does_nothing_to_lines_without_indentation() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        buffer.outdent(&LineRange::new(0, 1));
+        buffer.undo();
+
+        // The original insert was undone, since outdent didn't add to the history.
+        assert_eq!(buffer.data(), "");
+    }
+
+    #[test]
+    fn indent_excludes_the_line_on_which_the_range_ends() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary\neditor");
+        buffer.indent(&LineRange::new(1, 2));
+
+        assert_eq!(buffer.data(), "scribe\n    library\neditor");
+    }
+
+    #[test]
+    fn outdent_handles_multibyte_content_following_indentation() {
+        let mut buffer = Buffer::new();
+        buffer.insert(" \u{3000}scribe\n😀");
+        buffer.outdent(&LineRange::new(0, 2));
+
+        assert_eq!(buffer.data(), "\u{3000}scribe\n😀");
+    }
+
+    #[test]
+    fn reindent_converts_between_space_widths_and_preserves_alignment() {
+        let mut buffer = Buffer::new();
+        buffer.set_indentation(Indentation{ style: IndentationStyle::Spaces, width: 2 });
+        buffer.insert("a\n  b\n     c\td");
+        buffer.reindent(IndentationStyle::Spaces, 4);
+
+        assert_eq!(buffer.data(), "a\n    b\n         c\td");
+        assert_eq!(buffer.indentation(), Indentation{ style: IndentationStyle::Spaces, width: 4 });
+    }
+
+    #[test]
+    fn reindent_is_undone_as_a_single_operation() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\n\tlibrary\n\teditor");
+        buffer.reindent(IndentationStyle::Spaces, 2);
+        assert_eq!(buffer.data(), "scribe\n  library\n  editor");
+
+        buffer.undo();
+        assert_eq!(buffer.data(), "scribe\n\tlibrary\n\teditor");
+    }
+
+    #[test]
+    fn insert_newline_copies_the_current_line_indentation() {
+        let mut buffer = Buffer::new();
+        buffer.insert("\t  scribe");
+        buffer.cursor.move_to(Position{ line: 0, offset: 9 });
+        buffer.insert_newline();
+
+        assert_eq!(buffer.data(), "\t  scribe\n\t  ");
+        assert_eq!(*buffer.cursor, Position{ line: 1, offset: 3 });
+    }
+
+    #[test]
+    fn insert_newline_only_considers_content_preceding_the_cursor() {
+        let mut buffer = Buffer::new();
+        buffer.insert("  scribe {");
+        buffer.cursor.move_to(Position{ line: 0, offset: 1 });
+        buffer.insert_newline();
+
+        assert_eq!(buffer.data(), " \n  scribe {");
+        assert_eq!(*buffer.cursor, Position{ line: 1, offset: 1 });
+    }
+
+    #[test]
+    fn insert_newline_does_not_add_indentation_without_a_syntax_definition() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe {");
+        buffer.cursor.move_to(Position{ line: 0, offset: 8 });
+        buffer.insert_newline();
+
+        assert_eq!(buffer.data(), "scribe {\n");
+    }
+
+    #[test]
+    #[cfg(feature = "syntax")]
+    fn insert_newline_ignores_scope_openers_in_comments() {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let mut buffer = Buffer::new();
+        buffer.insert("    // scribe {");
+        buffer.syntax_definition = syntax_set.find_syntax_by_extension("rs").cloned();
+        buffer.cursor.move_to(Position{ line: 0, offset: 15 });
+        buffer.insert_newline();
+
+        assert_eq!(buffer.data(), "    // scribe {\n    ");
+    }
+}
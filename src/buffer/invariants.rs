@@ -0,0 +1,98 @@
+//! Consistency checks for catching buffer corruption in tests and fuzzers.
+
+use buffer::Buffer;
+use std::rc::Rc;
+
+impl Buffer {
+    /// Panics if the buffer's state is inconsistent. Its underlying data
+    /// and line index are checked (see `GapBuffer::check_invariants`), its
+    /// cursor must share its data and be within its bounds, and undoing and
+    /// redoing its history (using a copy of the buffer) must restore its
+    /// content. Only available in debug builds.
+    ///
+    /// Since edits don't move the cursor, callers are responsible for
+    /// keeping it in bounds (e.g. after deleting the content preceding it).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\nlibrary");
+    /// buffer.delete();
+    ///
+    /// # #[cfg(debug_assertions)]
+    /// buffer.check_invariants();
+    /// ```
+    pub fn check_invariants(&self) {
+        self.data.borrow().check_invariants();
+
+        assert!(Rc::ptr_eq(&self.data, &self.cursor.data), "cursor doesn't share the buffer's data");
+        assert!(
+            self.data.borrow().in_bounds(&self.cursor),
+            "cursor at {:?} is out of bounds", *self.cursor
+        );
+
+        self.check_history();
+    }
+
+    // Checks that operations can be undone and redone (and vice versa),
+    // restoring the buffer's current content, using a copy of the buffer.
+    fn check_history(&self) {
+        let state = self.state();
+        let content = state.content.clone();
+        let (undo_count, redo_count) = (state.undo.len(), state.redo.len());
+        if let Some(saved_at) = state.saved_at {
+            assert!(saved_at <= undo_count, "saved history position is beyond the undo history");
+        }
+
+        let mut buffer = Buffer::restore(state)
+            .expect("buffer states can always be restored");
+        for _ in 0..undo_count {
+            buffer.undo();
+        }
+        for _ in 0..undo_count {
+            buffer.redo();
+        }
+        assert!(buffer.data() == content, "undoing and redoing the buffer's history changes its content");
+
+        for _ in 0..redo_count {
+            buffer.redo();
+        }
+        for _ in 0..redo_count {
+            buffer.undo();
+        }
+        assert!(buffer.data() == content, "redoing and undoing the buffer's history changes its content");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, Position, Range};
+
+    #[test]
+    fn check_invariants_accepts_edited_buffers_with_undo_and_redo_history() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+        buffer.replace(Range::new(Position{ line: 0, offset: 0 }, Position{ line: 1, offset: 3 }), "amp ");
+        buffer.start_operation_group();
+        buffer.insert("text ");
+        buffer.insert("editor ");
+        buffer.end_operation_group();
+        buffer.undo();
+
+        buffer.check_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn check_invariants_rejects_cursors_beyond_the_buffer_content() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        buffer.cursor.move_to(Position{ line: 0, offset: 6 });
+        buffer.delete_range(Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 3 }));
+
+        buffer.check_invariants();
+    }
+}
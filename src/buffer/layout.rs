@@ -0,0 +1,298 @@
+//! Soft-wrapped visual layout of buffer content.
+
+use buffer::{Buffer, Position};
+use buffer::columns::grapheme_width;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A location in a layout, expressed as a visual row and display column.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VisualPosition {
+    pub row: usize,
+    pub column: usize,
+}
+
+/// A single row of a layout, covering a span of a logical line's graphemes.
+/// Continuation rows (those following the first row of a wrapped line) are
+/// indented to align with the line's content.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VisualRow {
+    /// The logical line the row belongs to.
+    pub line: usize,
+
+    /// The grapheme offset at which the row starts.
+    pub start: usize,
+
+    /// The grapheme offset at which the row ends (exclusive).
+    pub end: usize,
+
+    /// The number of columns the row is indented by.
+    pub indent: usize,
+
+    // The display column of each grapheme boundary in the row,
+    // starting with the indentation and ending after its last grapheme.
+    columns: Vec<usize>,
+}
+
+/// Buffer content broken into visual rows that fit within a width, with
+/// tabs expanded to the next tab stop. Lines are wrapped after whitespace
+/// where possible, and mid-word otherwise. Whitespace is allowed to extend
+/// beyond the width, rather than being wrapped onto the next row.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layout {
+    rows: Vec<VisualRow>,
+}
+
+impl Layout {
+    /// Lays out the specified content, wrapping lines at the specified
+    /// width (in columns), and expanding tabs to the specified width.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::Layout;
+    ///
+    /// let layout = Layout::new("  scribe library\neditor", 10, 4);
+    /// let rows: Vec<(usize, usize, usize)> = layout.rows().iter()
+    ///     .map(|row| (row.line, row.start, row.end))
+    ///     .collect();
+    ///
+    /// assert_eq!(rows, vec![(0, 0, 9), (0, 9, 16), (1, 0, 6)]);
+    /// assert_eq!(layout.rows()[1].indent, 2);
+    /// ```
+    pub fn new(data: &str, width: usize, tab_width: usize) -> Layout {
+        let mut rows = Vec::new();
+
+        for (line_number, line) in data.split('\n').enumerate() {
+            let graphemes: Vec<&str> = line.graphemes(true).collect();
+
+            // Align continuation rows with the line's content, provided
+            // that doing so leaves a reasonable amount of room.
+            let indentation = graphemes.iter()
+                .take_while(|g| is_whitespace(g))
+                .fold(0, |column, g| column + grapheme_width(g, column, tab_width));
+            let indent = if indentation * 2 < width { indentation } else { 0 };
+
+            let mut start = 0;
+            let mut row_indent = 0;
+            loop {
+                let (end, columns) = wrap_row(&graphemes[start..], row_indent, width, tab_width);
+                rows.push(VisualRow{
+                    line: line_number,
+                    start,
+                    end: start + end,
+                    indent: row_indent,
+                    columns,
+                });
+
+                start += end;
+                row_indent = indent;
+                if start >= graphemes.len() {
+                    break;
+                }
+            }
+        }
+
+        Layout{ rows }
+    }
+
+    /// The layout's visual rows, in order.
+    pub fn rows(&self) -> &[VisualRow] {
+        &self.rows
+    }
+
+    /// Maps a buffer position to its visual equivalent. Positions at the
+    /// boundary between two rows of a wrapped line are placed at the start
+    /// of the latter. Returns `None` if the position is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::{Layout, Position, VisualPosition};
+    ///
+    /// let layout = Layout::new("scribe library", 10, 4);
+    ///
+    /// assert_eq!(
+    ///     layout.visual_position(&Position{ line: 0, offset: 9 }),
+    ///     Some(VisualPosition{ row: 1, column: 2 })
+    /// );
+    /// ```
+    pub fn visual_position(&self, position: &Position) -> Option<VisualPosition> {
+        self.rows.iter().enumerate().find(|&(index, row)| {
+            let last_row = self.rows.get(index + 1).map(|next| next.line != row.line).unwrap_or(true);
+
+            row.line == position.line && row.start <= position.offset &&
+                (position.offset < row.end || (last_row && position.offset == row.end))
+        }).map(|(index, row)| {
+            VisualPosition{ row: index, column: row.columns[position.offset - row.start] }
+        })
+    }
+
+    /// Maps a visual position to the closest buffer position. Columns within
+    /// a grapheme (e.g. an expanded tab) or its indentation are snapped to its
+    /// start, and columns beyond the end of a row are snapped to its end (or,
+    /// for rows followed by a continuation, to the start of their last
+    /// grapheme). Returns `None` if the row doesn't exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::{Layout, Position, VisualPosition};
+    ///
+    /// let layout = Layout::new("\tscribe", 80, 4);
+    ///
+    /// assert_eq!(
+    ///     layout.position(&VisualPosition{ row: 0, column: 2 }),
+    ///     Some(Position{ line: 0, offset: 0 })
+    /// );
+    /// assert_eq!(
+    ///     layout.position(&VisualPosition{ row: 0, column: 20 }),
+    ///     Some(Position{ line: 0, offset: 7 })
+    /// );
+    /// ```
+    pub fn position(&self, visual_position: &VisualPosition) -> Option<Position> {
+        let row = self.rows.get(visual_position.row)?;
+        let continued = self.rows.get(visual_position.row + 1)
+            .map(|next| next.line == row.line)
+            .unwrap_or(false);
+
+        let mut offset = row.columns.iter()
+            .rposition(|&column| column <= visual_position.column)
+            .unwrap_or(0);
+
+        // The end of a continued row is the start of the next one.
+        if continued && offset == row.end - row.start && offset > 0 {
+            offset -= 1;
+        }
+
+        Some(Position{ line: row.line, offset: row.start + offset })
+    }
+}
+
+impl Buffer {
+    /// Lays out the buffer's content using the specified wrap and tab widths.
+    /// See `Layout` for details.
+    pub fn layout(&self, width: usize, tab_width: usize) -> Layout {
+        Layout::new(&self.data(), width, tab_width)
+    }
+}
+
+// Determines where a row starting with the specified graphemes should end,
+// returning the number of graphemes that fit within it, along with their
+// display column boundaries. At least one grapheme is always included.
+fn wrap_row(graphemes: &[&str], indent: usize, width: usize, tab_width: usize) -> (usize, Vec<usize>) {
+    let mut columns = vec![indent];
+    let mut column = indent;
+    let mut last_break = None;
+
+    for (index, grapheme) in graphemes.iter().enumerate() {
+        let grapheme_width = grapheme_width(grapheme, column, tab_width);
+        if is_whitespace(grapheme) {
+            // Allow whitespace to extend beyond the width; the row can
+            // be broken after it when the next word doesn't fit.
+            last_break = Some(index + 1);
+        } else if column + grapheme_width > width && index > 0 {
+            let end = last_break.unwrap_or(index);
+            columns.truncate(end + 1);
+
+            return (end, columns)
+        }
+
+        column += grapheme_width;
+        columns.push(column);
+    }
+
+    (graphemes.len(), columns)
+}
+
+fn is_whitespace(grapheme: &str) -> bool {
+    grapheme.chars().all(|c| c.is_whitespace())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Layout;
+    use buffer::{Buffer, Position, VisualPosition};
+
+    fn row_spans(layout: &Layout) -> Vec<(usize, usize, usize)> {
+        layout.rows().iter().map(|row| (row.line, row.start, row.end)).collect()
+    }
+
+    #[test]
+    fn new_breaks_words_that_do_not_fit_within_the_width() {
+        let layout = Layout::new("scribelibrary", 5, 4);
+
+        assert_eq!(row_spans(&layout), vec![(0, 0, 5), (0, 5, 10), (0, 10, 13)]);
+    }
+
+    #[test]
+    fn new_includes_rows_for_empty_lines() {
+        let layout = Layout::new("scribe\n\n", 80, 4);
+
+        assert_eq!(row_spans(&layout), vec![(0, 0, 6), (1, 0, 0), (2, 0, 0)]);
+    }
+
+    #[test]
+    fn new_expands_tabs_to_the_next_tab_stop() {
+        let layout = Layout::new("ab\tc", 80, 4);
+
+        assert_eq!(
+            layout.visual_position(&Position{ line: 0, offset: 3 }),
+            Some(VisualPosition{ row: 0, column: 4 })
+        );
+    }
+
+    #[test]
+    fn new_wraps_wide_characters_that_do_not_fit_within_the_width() {
+        let layout = Layout::new("a漢字", 4, 4);
+
+        assert_eq!(row_spans(&layout), vec![(0, 0, 2), (0, 2, 3)]);
+    }
+
+    #[test]
+    fn new_skips_continuation_indents_that_would_leave_too_little_room() {
+        let layout = Layout::new("      scribe", 10, 4);
+
+        assert_eq!(layout.rows()[1].indent, 0);
+    }
+
+    #[test]
+    fn visual_position_places_row_boundaries_on_the_following_row() {
+        let layout = Layout::new("scribelibrary", 6, 4);
+
+        assert_eq!(
+            layout.visual_position(&Position{ line: 0, offset: 6 }),
+            Some(VisualPosition{ row: 1, column: 0 })
+        );
+        assert_eq!(
+            layout.visual_position(&Position{ line: 0, offset: 13 }),
+            Some(VisualPosition{ row: 2, column: 1 })
+        );
+        assert_eq!(layout.visual_position(&Position{ line: 0, offset: 14 }), None);
+    }
+
+    #[test]
+    fn position_keeps_positions_on_continued_rows() {
+        let layout = Layout::new("scribe library", 10, 4);
+
+        assert_eq!(
+            layout.position(&VisualPosition{ row: 0, column: 9 }),
+            Some(Position{ line: 0, offset: 6 })
+        );
+        assert_eq!(layout.position(&VisualPosition{ row: 2, column: 0 }), None);
+    }
+
+    #[test]
+    fn positions_survive_a_round_trip() {
+        let mut buffer = Buffer::new();
+        buffer.insert("\tscribe is a text\n    editor library with\tsoft wrapping");
+        let layout = buffer.layout(12, 4);
+
+        for (line, length) in vec![(0, 17), (1, 36)] {
+            for offset in 0..length + 1 {
+                let position = Position{ line, offset };
+                let visual_position = layout.visual_position(&position).unwrap();
+                assert_eq!(layout.position(&visual_position), Some(position));
+            }
+        }
+    }
+}
@@ -1,12 +1,32 @@
-use buffer::{Position, Range};
+use buffer::{Buffer, Position, Range};
+use std::cmp;
+use std::ops;
 
 /// A more concise expression for ranges spanning complete lines.
-#[derive(PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialization", serde(from = "LineRangeFields"))]
 pub struct LineRange {
     start: usize,
     end:   usize,
 }
 
+// Deserialized line ranges are built using LineRange::new,
+// so that their lines are ordered.
+#[cfg(feature = "serialization")]
+#[derive(Deserialize)]
+struct LineRangeFields {
+    start: usize,
+    end:   usize,
+}
+
+#[cfg(feature = "serialization")]
+impl From<LineRangeFields> for LineRange {
+    fn from(fields: LineRangeFields) -> LineRange {
+        LineRange::new(fields.start, fields.end)
+    }
+}
+
 impl LineRange {
     /// Creates a new buffer line range. Checks and swaps
     /// arguments in the event that the end precedes the start.
@@ -18,6 +38,25 @@ impl LineRange {
         }
     }
 
+    /// Creates a new buffer line range that includes the line on which it
+    /// ends. Checks and swaps arguments in the event that the end precedes
+    /// the start.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::LineRange;
+    ///
+    /// let line_range = LineRange::inclusive(10, 14);
+    /// assert_eq!(line_range, LineRange::new(10, 15));
+    /// assert!(line_range.includes(14));
+    /// ```
+    pub fn inclusive(start: usize, end: usize) -> LineRange {
+        let range = LineRange::new(start, end);
+
+        LineRange{ start: range.start, end: range.end + 1 }
+    }
+
     pub fn start(&self) -> usize {
         self.start
     }
@@ -90,11 +129,97 @@ impl LineRange {
     pub fn includes(&self, line: usize) -> bool {
         line >= self.start() && line < self.end()
     }
+
+    /// The number of lines in the range, excluding its ending line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::LineRange;
+    ///
+    /// assert_eq!(LineRange::new(10, 14).len(), 4);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Whether or not the range is devoid of any lines.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the line numbers in the range,
+    /// excluding its ending line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::LineRange;
+    ///
+    /// let lines: Vec<usize> = LineRange::new(10, 14).iter().collect();
+    /// assert_eq!(lines, vec![10, 11, 12, 13]);
+    /// ```
+    pub fn iter(&self) -> ops::Range<usize> {
+        self.start..self.end
+    }
+
+    /// Returns the lines shared by both ranges (e.g. those of a selection
+    /// visible in a viewport), or `None` if they don't overlap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::LineRange;
+    ///
+    /// let viewport = LineRange::new(10, 20);
+    /// assert_eq!(
+    ///     LineRange::new(5, 15).intersection(&viewport),
+    ///     Some(LineRange::new(10, 15))
+    /// );
+    /// assert_eq!(LineRange::new(0, 10).intersection(&viewport), None);
+    /// ```
+    pub fn intersection(&self, other: &LineRange) -> Option<LineRange> {
+        let start = cmp::max(self.start, other.start);
+        let end = cmp::min(self.end, other.end);
+
+        if start < end {
+            Some(LineRange{ start, end })
+        } else {
+            None
+        }
+    }
+
+    /// Converts the line range to a character range covering its lines'
+    /// content in the specified buffer, excluding the ending line. Lines
+    /// beyond the end of the buffer are clamped to its end, so the range
+    /// covering its last line(s) ends at the end of the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{LineRange, Position, Range};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\nlibrary");
+    ///
+    /// assert_eq!(LineRange::new(1, 2).to_buffer_range(&buffer), Range::new(
+    ///     Position{ line: 1, offset: 0 },
+    ///     Position{ line: 1, offset: 7 }
+    /// ));
+    /// ```
+    pub fn to_buffer_range(&self, buffer: &Buffer) -> Range {
+        Range::new(
+            buffer.clamp(Position{ line: self.start, offset: 0 }),
+            buffer.clamp(Position{ line: self.end, offset: 0 })
+        )
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::LineRange;
+    use buffer::{Buffer, Position, Range};
 
     #[test]
     fn new_does_not_swap_values_if_end_does_not_precede_start() {
@@ -104,6 +229,30 @@ mod tests {
         assert_eq!(range.end(), 1);
     }
 
+    #[test]
+    fn inclusive_swaps_start_and_end_when_end_precedes_start() {
+        let range = LineRange::inclusive(3, 1);
+
+        assert_eq!(range.start(), 1);
+        assert_eq!(range.end(), 4);
+    }
+
+    #[test]
+    fn intersection_of_adjacent_ranges_is_none() {
+        assert!(LineRange::new(0, 2).intersection(&LineRange::new(2, 4)).is_none());
+    }
+
+    #[test]
+    fn to_buffer_range_includes_the_newline_preceding_the_ending_line() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary\neditor");
+
+        assert_eq!(LineRange::new(0, 1).to_buffer_range(&buffer), Range::new(
+            Position{ line: 0, offset: 0 },
+            Position{ line: 1, offset: 0 }
+        ));
+    }
+
     #[test]
     fn new_swaps_start_and_end_when_end_precedes_start() {
         let range = LineRange::new(1, 0);
@@ -111,4 +260,15 @@ mod tests {
         assert_eq!(range.start(), 0);
         assert_eq!(range.end(), 1);
     }
+
+    #[cfg(feature = "serialization")]
+    #[test]
+    fn deserialize_orders_the_range_lines() {
+        use serde_json;
+
+        let line_range: LineRange = serde_json::from_str(r#"{"start":5,"end":2}"#).unwrap();
+
+        assert_eq!(line_range, LineRange::new(2, 5));
+        assert_eq!(serde_json::to_string(&line_range).unwrap(), r#"{"start":2,"end":5}"#);
+    }
 }
@@ -0,0 +1,98 @@
+//! Line iteration that reads directly from a buffer's content.
+
+use buffer::{Buffer, GapBuffer, GapBufferLines};
+use std::borrow::Cow;
+use std::cell::Ref;
+
+/// A buffer's content, borrowed for line iteration (see `Buffer::lines`).
+/// The buffer can't be modified while this is held.
+pub struct Lines<'a> {
+    data: Ref<'a, GapBuffer>,
+}
+
+impl<'a> Lines<'a> {
+    /// Returns an iterator over the buffer's lines (see `GapBufferLines`).
+    pub fn iter(&self) -> GapBufferLines {
+        self.data.lines()
+    }
+
+    /// Returns the specified line's content (see `GapBuffer::line`).
+    pub fn line(&self, line: usize) -> Option<Cow<str>> {
+        self.data.line(line)
+    }
+}
+
+impl Buffer {
+    /// Borrows the buffer's content for line iteration, without copying
+    /// it into a string first (unlike `data`). Lines can be iterated over
+    /// from the end of the buffer using `rev`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\nlibrary\n");
+    ///
+    /// let lengths: Vec<usize> = buffer.lines().iter().map(|(_, line)| line.len()).collect();
+    /// assert_eq!(lengths, vec![7, 8, 0]);
+    ///
+    /// let last_line = buffer.lines().iter().rev()
+    ///     .find(|&(_, ref line)| !line.is_empty())
+    ///     .map(|(number, _)| number);
+    /// assert_eq!(last_line, Some(1));
+    /// ```
+    pub fn lines(&self) -> Lines {
+        Lines{ data: self.data.borrow() }
+    }
+
+    /// Returns the specified line's content, excluding its trailing newline.
+    /// Lines are found using an index, rather than by scanning the buffer's
+    /// content from its start. Returns `None` if the line doesn't exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\nlibrary\n");
+    ///
+    /// assert_eq!(buffer.line(1), Some("library".to_string()));
+    /// assert_eq!(buffer.line(2), Some(String::new()));
+    /// assert_eq!(buffer.line(3), None);
+    /// ```
+    pub fn line(&self, line: usize) -> Option<String> {
+        self.data.borrow().line(line).map(Cow::into_owned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::Buffer;
+
+    #[test]
+    fn lines_reflect_the_buffer_content() {
+        let mut buffer = Buffer::new();
+        buffer.insert("library");
+        buffer.insert("scribe\n");
+
+        let lines: Vec<(usize, String)> = buffer.lines().iter()
+            .map(|(number, line)| (number, line.into_owned()))
+            .collect();
+        assert_eq!(lines, vec![(0, "scribe\n".to_string()), (1, "library".to_string())]);
+    }
+
+    #[test]
+    fn line_reflects_undone_changes() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+        buffer.insert("text\n");
+        assert_eq!(buffer.line(1), Some("scribe".to_string()));
+
+        buffer.undo();
+        assert_eq!(buffer.line(0), Some("scribe".to_string()));
+        assert_eq!(buffer.line(1), Some("library".to_string()));
+    }
+}
@@ -0,0 +1,478 @@
+//! Adapters between buffer changes and the Language Server Protocol.
+
+use buffer::{Buffer, EncodedPosition, EncodedRange, GapBuffer, Position, PositionEncoding, Range};
+use buffer::encoding::{decode_offset, encode_position};
+
+/// A change to a document's content, shaped like the Language Server
+/// Protocol's `TextDocumentContentChangeEvent`: the text replaces the content
+/// within the range, or the entire document if there isn't one. Ranges refer
+/// to the document as it was after the preceding change was applied.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct ContentChange {
+    pub range: Option<EncodedRange>,
+    pub text: String,
+}
+
+/// A batch of content changes and the document version resulting from them.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct DocumentChanges {
+    pub version: i64,
+    pub changes: Vec<ContentChange>,
+}
+
+/// A replacement of the content within a range, shaped like the Language
+/// Server Protocol's `TextEdit` (e.g. from formatting or rename responses).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct TextEdit {
+    pub range: EncodedRange,
+    #[cfg_attr(feature = "serialization", serde(rename = "newText"))]
+    pub new_text: String,
+}
+
+/// The reason a text edit couldn't be applied.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextEditFailure {
+    /// The edit's range starts after it ends.
+    ReversedRange,
+
+    /// The edit's range refers to a line beyond the end of the buffer.
+    OutOfBounds,
+
+    /// A position falls within a character (e.g. between a surrogate pair).
+    SplitsCharacter,
+
+    /// The edit's range overlaps that of another edit.
+    Overlapping,
+}
+
+/// Content changes recorded as operations are applied, and not yet taken.
+/// Changes are only recorded once enabled, as they'd otherwise accumulate.
+#[derive(Default)]
+pub struct ChangeLog {
+    version: i64,
+    pending: Option<Vec<ContentChange>>,
+    full_sync: bool,
+}
+
+impl ChangeLog {
+    /// Records the insertion of content. This must be called before
+    /// the content is inserted, as positions refer to the prior state.
+    pub fn insert(&mut self, data: &GapBuffer, content: &str, position: &Position, encoding: PositionEncoding) {
+        if content.is_empty() {
+            return
+        }
+
+        if let Some(ref mut pending) = self.pending {
+            // Out of bounds insertions are ignored by the gap buffer.
+            if let Some(start) = encode_position(data, *position, encoding) {
+                pending.push(ContentChange{
+                    range: Some(EncodedRange{ start, end: start }),
+                    text: content.to_string(),
+                });
+            }
+        }
+    }
+
+    /// Records the deletion of a range. This must be called before
+    /// the range is deleted, as positions refer to the prior state.
+    pub fn delete(&mut self, data: &GapBuffer, range: &Range, encoding: PositionEncoding) {
+        if range.start() == range.end() {
+            return
+        }
+
+        if let Some(ref mut pending) = self.pending {
+            let start = encode_position(data, range.start(), encoding);
+            let end = encode_position(data, range.end(), encoding);
+
+            match (start, end) {
+                (Some(start), Some(end)) => pending.push(ContentChange{
+                    range: Some(EncodedRange{ start, end }),
+                    text: String::new(),
+                }),
+                // Ranges ending out of bounds are truncated by the gap
+                // buffer; rather than replicate that, sync everything.
+                (Some(_), None) => self.full_sync = true,
+                _ => (),
+            }
+        }
+    }
+
+    /// Requires the next batch of changes to replace the entire document
+    /// (e.g. when the buffer's content is replaced by a reload).
+    #[cfg_attr(not(feature = "system"), allow(dead_code))]
+    pub fn invalidate(&mut self) {
+        if self.pending.is_some() {
+            self.full_sync = true;
+        }
+    }
+}
+
+impl Buffer {
+    /// Starts recording content changes, to be retrieved using
+    /// `take_content_changes`. Changes made before this is
+    /// called aren't recorded.
+    pub fn record_content_changes(&mut self) {
+        if self.content_changes.pending.is_none() {
+            self.content_changes.pending = Some(Vec::new());
+        }
+    }
+
+    /// Returns the content changes recorded since the last call, with their
+    /// positions encoded using the buffer's `position_encoding`, and increments
+    /// the document version. This allows language server clients to sync
+    /// documents incrementally. Returns `None` if changes aren't being
+    /// recorded (see `record_content_changes`), or there aren't any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{ContentChange, EncodedPosition, EncodedRange};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("😀");
+    /// buffer.record_content_changes();
+    /// buffer.cursor.move_right();
+    /// buffer.insert(" scribe");
+    ///
+    /// let changes = buffer.take_content_changes().unwrap();
+    /// assert_eq!(changes.version, 1);
+    /// assert_eq!(changes.changes, vec![ContentChange{
+    ///     range: Some(EncodedRange{
+    ///         start: EncodedPosition{ line: 0, character: 2 },
+    ///         end: EncodedPosition{ line: 0, character: 2 },
+    ///     }),
+    ///     text: " scribe".to_string(),
+    /// }]);
+    /// assert!(buffer.take_content_changes().is_none());
+    /// ```
+    pub fn take_content_changes(&mut self) -> Option<DocumentChanges> {
+        let changes =
+            if self.content_changes.full_sync {
+                self.content_changes.full_sync = false;
+                self.content_changes.pending.as_mut()?.clear();
+
+                vec![ContentChange{ range: None, text: self.data() }]
+            } else {
+                self.content_changes.pending.as_mut()?.drain(..).collect()
+            };
+        if changes.is_empty() {
+            return None
+        }
+
+        self.content_changes.version += 1;
+
+        Some(DocumentChanges{ version: self.content_changes.version, changes })
+    }
+
+    /// Applies a batch of text edits, whose positions are encoded using the
+    /// buffer's `position_encoding` and refer to the buffer's content before
+    /// any of them are applied. Edits inserting content at the same position
+    /// (including the start of another edit's range) are applied in the order
+    /// given. The batch is applied atomically, as a single undoable operation:
+    /// if any edit is invalid, none are applied, and the (zero-based) indices
+    /// of invalid edits are returned alongside the reasons they were rejected.
+    /// The cursor stays anchored to the content surrounding it.
+    ///
+    /// Columns beyond the end of a line refer to its end, and edits ending
+    /// at the start of the line following the buffer's last line (commonly
+    /// used to replace an entire document) end at the end of the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{EncodedPosition, EncodedRange, TextEdit};
+    ///
+    /// fn edit(start: usize, end: usize, new_text: &str) -> TextEdit {
+    ///     TextEdit{
+    ///         range: EncodedRange{
+    ///             start: EncodedPosition{ line: 0, character: start },
+    ///             end: EncodedPosition{ line: 0, character: end },
+    ///         },
+    ///         new_text: new_text.to_string(),
+    ///     }
+    /// }
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("😀 scribe  library");
+    ///
+    /// let edits = vec![edit(11, 11, "text "), edit(3, 3, "the "), edit(9, 11, " ")];
+    /// assert!(buffer.apply_lsp_edits(&edits).is_empty());
+    /// assert_eq!(buffer.data(), "😀 the scribe text library");
+    ///
+    /// buffer.undo();
+    /// assert_eq!(buffer.data(), "😀 scribe  library");
+    /// ```
+    pub fn apply_lsp_edits(&mut self, edits: &[TextEdit]) -> Vec<(usize, TextEditFailure)> {
+        let data = self.data();
+        let lines: Vec<&str> = data.split('\n').collect();
+        let mut failures = Vec::new();
+        let mut ranges = Vec::new();
+
+        for (index, edit) in edits.iter().enumerate() {
+            let start = decode_position(&lines, edit.range.start, self.position_encoding);
+            let end = decode_position(&lines, edit.range.end, self.position_encoding);
+
+            match (start, end) {
+                (Ok(start), Ok(end)) => {
+                    if start > end {
+                        failures.push((index, TextEditFailure::ReversedRange));
+                    } else {
+                        ranges.push((index, start, end));
+                    }
+                },
+                (Err(failure), _) | (_, Err(failure)) => failures.push((index, failure)),
+            }
+        }
+
+        // Order edits by position, preserving the order of those that start
+        // at the same position, which are combined into a single replacement.
+        // Edits are rejected if they start within an earlier edit's range, or
+        // share their start with another non-empty edit (empty ranges, i.e.
+        // insertions, can share their start with any edit).
+        ranges.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap().then(a.0.cmp(&b.0)));
+        let mut replacements: Vec<(Position, Position, String)> = Vec::new();
+        let mut preceding_end: Option<Position> = None;
+
+        for &(index, start, end) in &ranges {
+            let shares_start = replacements.last()
+                .map(|&(replacement_start, _, _)| replacement_start == start)
+                .unwrap_or(false);
+            if !shares_start {
+                preceding_end = replacements.last().map(|&(_, replacement_end, _)| {
+                    match preceding_end {
+                        Some(preceding_end) if preceding_end > replacement_end => preceding_end,
+                        _ => replacement_end,
+                    }
+                });
+            }
+
+            let overlaps_preceding = preceding_end.map(|preceding_end| start < preceding_end).unwrap_or(false);
+            let overlaps_shared = shares_start && start < end &&
+                replacements.last().map(|&(_, replacement_end, _)| start < replacement_end).unwrap_or(false);
+            if overlaps_preceding || overlaps_shared {
+                failures.push((index, TextEditFailure::Overlapping));
+            } else if shares_start {
+                let replacement = replacements.last_mut().unwrap();
+                if end > replacement.1 {
+                    replacement.1 = end;
+                }
+                replacement.2.push_str(&edits[index].new_text);
+            } else {
+                replacements.push((start, end, edits[index].new_text.clone()));
+            }
+        }
+
+        if !failures.is_empty() {
+            failures.sort_by_key(|&(index, _)| index);
+            return failures
+        }
+
+        let cursor = self.track_position(*self.cursor);
+        let in_operation_group = self.operation_group.is_some();
+        self.start_operation_group();
+
+        // Apply edits from the end of the buffer backwards,
+        // so that they don't affect each other's positions.
+        for (start, end, content) in replacements.into_iter().rev() {
+            self.replace(Range::new(start, end), content);
+        }
+
+        if !in_operation_group {
+            self.end_operation_group();
+        }
+
+        if let Some(position) = self.tracked_position(cursor) {
+            self.cursor.move_to(position);
+        }
+        self.untrack(cursor);
+
+        failures
+    }
+
+    /// Returns the version of the document most recently produced
+    /// by `take_content_changes`, starting at zero.
+    pub fn document_version(&self) -> i64 {
+        self.content_changes.version
+    }
+}
+
+// Converts an encoded position to a buffer position, using the buffer's lines.
+fn decode_position(lines: &[&str], position: EncodedPosition, encoding: PositionEncoding) -> ::std::result::Result<Position, TextEditFailure> {
+    match lines.get(position.line) {
+        Some(line) => {
+            decode_offset(line, position.character, encoding)
+                .map(|offset| Position{ line: position.line, offset })
+                .ok_or(TextEditFailure::SplitsCharacter)
+        },
+        None if position.line == lines.len() && position.character == 0 => {
+            let last_line = lines.len() - 1;
+            Ok(Position{
+                line: last_line,
+                offset: decode_offset(lines[last_line], usize::max_value(), encoding).unwrap_or(0)
+            })
+        },
+        None => Err(TextEditFailure::OutOfBounds),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, ContentChange, EncodedPosition, EncodedRange, Position, PositionEncoding, Range};
+    use buffer::{TextEdit, TextEditFailure};
+    #[cfg(feature = "system")]
+    use std::path::Path;
+
+    fn edit(start: (usize, usize), end: (usize, usize), new_text: &str) -> TextEdit {
+        TextEdit{ range: range(start, end).unwrap(), new_text: new_text.to_string() }
+    }
+
+    fn range(start: (usize, usize), end: (usize, usize)) -> Option<EncodedRange> {
+        Some(EncodedRange{
+            start: EncodedPosition{ line: start.0, character: start.1 },
+            end: EncodedPosition{ line: end.0, character: end.1 },
+        })
+    }
+
+    #[test]
+    fn take_content_changes_returns_none_unless_recording() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+
+        assert!(buffer.take_content_changes().is_none());
+        assert_eq!(buffer.document_version(), 0);
+    }
+
+    #[test]
+    fn take_content_changes_includes_replacements_and_undos() {
+        let mut buffer = Buffer::new();
+        buffer.position_encoding = PositionEncoding::Utf8;
+        buffer.insert("é scribe\nlibrary");
+        buffer.record_content_changes();
+        buffer.replace(Range::new(Position{ line: 0, offset: 2 }, Position{ line: 1, offset: 3 }), "");
+        buffer.undo();
+
+        assert_eq!(buffer.take_content_changes().unwrap().changes, vec![
+            ContentChange{ range: range((0, 3), (1, 3)), text: String::new() },
+            ContentChange{ range: range((0, 3), (0, 3)), text: "scribe\nlib".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn take_content_changes_syncs_the_full_document_after_truncated_deletions() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        buffer.record_content_changes();
+        buffer.delete_range(Range::new(Position{ line: 0, offset: 3 }, Position{ line: 0, offset: 10 }));
+
+        assert_eq!(buffer.take_content_changes().unwrap().changes, vec![
+            ContentChange{ range: None, text: "scr".to_string() },
+        ]);
+    }
+
+    #[test]
+    #[cfg(feature = "system")]
+    fn take_content_changes_syncs_the_full_document_after_reloads() {
+        let mut buffer = Buffer::from_file(Path::new("tests/sample/file")).unwrap();
+        buffer.record_content_changes();
+        buffer.insert("scribe");
+        buffer.reload().unwrap();
+
+        let changes = buffer.take_content_changes().unwrap();
+        assert_eq!(changes.version, 1);
+        assert_eq!(changes.changes, vec![
+            ContentChange{ range: None, text: "it works!\n".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn apply_lsp_edits_inserts_content_at_the_same_position_in_order() {
+        let mut buffer = Buffer::new();
+        buffer.insert("library");
+        let edits = vec![edit((0, 0), (0, 0), "scribe"), edit((0, 0), (0, 0), " ")];
+
+        assert!(buffer.apply_lsp_edits(&edits).is_empty());
+        assert_eq!(buffer.data(), "scribe library");
+    }
+
+    #[test]
+    fn apply_lsp_edits_inserts_content_at_the_start_of_replacements_in_order() {
+        let mut buffer = Buffer::new();
+        buffer.insert("editor library");
+        let edits = vec![
+            edit((0, 0), (0, 0), "the "),
+            edit((0, 0), (0, 6), "scribe"),
+            edit((0, 0), (0, 0), " text"),
+        ];
+
+        assert!(buffer.apply_lsp_edits(&edits).is_empty());
+        assert_eq!(buffer.data(), "the scribe text library");
+    }
+
+    #[test]
+    fn apply_lsp_edits_rejects_edits_overlapping_earlier_non_adjacent_edits() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe library");
+        let edits = vec![
+            edit((0, 0), (0, 10), ""),
+            edit((0, 2), (0, 3), ""),
+            edit((0, 5), (0, 6), ""),
+            edit((0, 0), (0, 1), ""),
+            edit((0, 10), (0, 10), "text"),
+        ];
+
+        assert_eq!(buffer.apply_lsp_edits(&edits), vec![
+            (1, TextEditFailure::Overlapping),
+            (2, TextEditFailure::Overlapping),
+            (3, TextEditFailure::Overlapping),
+        ]);
+        assert_eq!(buffer.data(), "scribe library");
+    }
+
+    #[test]
+    fn apply_lsp_edits_handles_edits_ending_after_the_last_line() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary\n");
+        let edits = vec![edit((1, 0), (3, 0), "editor\n")];
+
+        assert!(buffer.apply_lsp_edits(&edits).is_empty());
+        assert_eq!(buffer.data(), "scribe\neditor\n");
+    }
+
+    #[test]
+    fn apply_lsp_edits_rejects_the_batch_if_any_edits_are_invalid() {
+        let mut buffer = Buffer::new();
+        buffer.insert("😀 scribe\nlibrary");
+        let edits = vec![
+            edit((0, 3), (0, 9), "text"),
+            edit((0, 1), (0, 2), ""),
+            edit((0, 5), (1, 2), ""),
+            edit((3, 0), (3, 1), ""),
+            edit((1, 2), (1, 0), ""),
+            edit((1, 2), (1, 4), "b"),
+        ];
+
+        assert_eq!(buffer.apply_lsp_edits(&edits), vec![
+            (1, TextEditFailure::SplitsCharacter),
+            (2, TextEditFailure::Overlapping),
+            (3, TextEditFailure::OutOfBounds),
+            (4, TextEditFailure::ReversedRange),
+        ]);
+        assert_eq!(buffer.data(), "😀 scribe\nlibrary");
+    }
+
+    #[test]
+    fn apply_lsp_edits_keeps_the_cursor_anchored_to_its_content() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe library");
+        buffer.cursor.move_to(Position{ line: 0, offset: 7 });
+        buffer.apply_lsp_edits(&[edit((0, 0), (0, 6), "the\neditor")]);
+
+        assert_eq!(buffer.data(), "the\neditor library");
+        assert_eq!(*buffer.cursor, Position{ line: 1, offset: 7 });
+    }
+}
@@ -0,0 +1,119 @@
+//! Named positions that stay anchored to content as the buffer changes.
+
+use buffer::{Buffer, Position};
+
+impl Buffer {
+    /// Sets the named mark to the specified position, replacing any existing
+    /// mark with the same name. The position is tracked (see `track_position`),
+    /// so the mark is adjusted as content before it changes. Returns false if
+    /// the position is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::Position;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("library");
+    /// buffer.set_mark('a', Position{ line: 0, offset: 3 });
+    /// buffer.insert("scribe\n");
+    ///
+    /// assert_eq!(buffer.mark('a'), Some(Position{ line: 1, offset: 3 }));
+    /// assert!(buffer.jump_to_mark('a'));
+    /// assert_eq!(*buffer.cursor, Position{ line: 1, offset: 3 });
+    /// ```
+    pub fn set_mark(&mut self, name: char, position: Position) -> bool {
+        if !self.is_valid(&position) {
+            return false
+        }
+
+        self.delete_mark(name);
+        let id = self.track_position(position);
+        self.marks.push((name, id));
+
+        true
+    }
+
+    /// Returns the named mark's current position, if it's set.
+    pub fn mark(&self, name: char) -> Option<Position> {
+        self.marks.iter()
+            .find(|&&(mark_name, _)| mark_name == name)
+            .and_then(|&(_, id)| self.tracked_position(id))
+    }
+
+    /// Moves the cursor to the named mark, returning false if it isn't set.
+    pub fn jump_to_mark(&mut self, name: char) -> bool {
+        match self.mark(name) {
+            Some(position) => self.cursor.move_to(position),
+            None => false,
+        }
+    }
+
+    /// Returns the buffer's marks and their positions, ordered by name.
+    pub fn marks(&self) -> Vec<(char, Position)> {
+        let mut marks: Vec<(char, Position)> = self.marks.iter()
+            .filter_map(|&(name, id)| self.tracked_position(id).map(|position| (name, position)))
+            .collect();
+        marks.sort_by_key(|&(name, _)| name);
+
+        marks
+    }
+
+    /// Removes the named mark, if it's set.
+    pub fn delete_mark(&mut self, name: char) {
+        if let Some(index) = self.marks.iter().position(|&(mark_name, _)| mark_name == name) {
+            let (_, id) = self.marks.remove(index);
+            self.untrack(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, Position, Range};
+
+    #[test]
+    fn set_mark_rejects_out_of_bounds_positions() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+
+        assert!(!buffer.set_mark('a', Position{ line: 1, offset: 0 }));
+        assert_eq!(buffer.mark('a'), None);
+    }
+
+    #[test]
+    fn set_mark_replaces_existing_marks() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        buffer.set_mark('b', Position{ line: 0, offset: 1 });
+        buffer.set_mark('a', Position{ line: 0, offset: 2 });
+        buffer.set_mark('b', Position{ line: 0, offset: 3 });
+
+        assert_eq!(buffer.marks(), vec![
+            ('a', Position{ line: 0, offset: 2 }),
+            ('b', Position{ line: 0, offset: 3 }),
+        ]);
+    }
+
+    #[test]
+    fn marks_within_deleted_content_move_to_the_start_of_the_deletion() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe library");
+        buffer.set_mark('a', Position{ line: 0, offset: 10 });
+        buffer.delete_range(Range::new(Position{ line: 0, offset: 6 }, Position{ line: 0, offset: 14 }));
+
+        assert_eq!(buffer.mark('a'), Some(Position{ line: 0, offset: 6 }));
+    }
+
+    #[test]
+    fn delete_mark_removes_the_mark() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        buffer.set_mark('a', Position{ line: 0, offset: 1 });
+        buffer.delete_mark('a');
+
+        assert!(buffer.marks().is_empty());
+        assert!(!buffer.jump_to_mark('a'));
+    }
+}
@@ -0,0 +1,304 @@
+//! Three-way merging, with conflicts tracked alongside the merged content.
+
+use buffer::{Buffer, ConflictResolution, Position, Range, TrackingId};
+use buffer::diff::{edit_script, Edit};
+use buffer::operation::history::History;
+use buffer::patch::lines;
+
+/// A region of a merged buffer whose changes conflict. The region initially
+/// holds our side of the conflict, and is tracked as the buffer is edited.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MergeConflict {
+    /// An ID used to resolve the conflict.
+    pub id: TrackingId,
+
+    /// The conflict's current location in the buffer.
+    pub range: Range,
+
+    /// The common ancestor's content.
+    pub base: String,
+
+    /// Our side of the conflict.
+    pub ours: String,
+
+    /// Their side of the conflict.
+    pub theirs: String,
+}
+
+// The content of each side of a merge conflict.
+pub struct ConflictSides {
+    base: String,
+    ours: String,
+    theirs: String,
+}
+
+// A merged region, sourced from one or both sides, or conflicting.
+enum Chunk<'a> {
+    Merged(Vec<&'a str>),
+    Conflict(Vec<&'a str>, Vec<&'a str>, Vec<&'a str>),
+}
+
+impl Buffer {
+    /// Creates a buffer by merging the changes made to a common ancestor
+    /// (`base`) by two descendants (`ours` and `theirs`), line by line.
+    /// Changes made by only one side (or identically by both) are applied,
+    /// whereas regions changed differently by both are recorded as merge
+    /// conflicts (see `merge_conflicts`), initially holding our side.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    ///
+    /// let buffer = Buffer::from_merge(
+    ///     "scribe\nlibrary\neditor\n",
+    ///     "Scribe\nlibrary\ntext\n",
+    ///     "scribe\nlibrary\ntoolkit\n"
+    /// );
+    /// assert_eq!(buffer.data(), "Scribe\nlibrary\ntext\n");
+    ///
+    /// let conflicts = buffer.merge_conflicts();
+    /// assert_eq!(conflicts.len(), 1);
+    /// assert_eq!(conflicts[0].base, "editor\n");
+    /// assert_eq!(conflicts[0].theirs, "toolkit\n");
+    /// ```
+    pub fn from_merge(base: &str, ours: &str, theirs: &str) -> Buffer {
+        let mut content = String::new();
+        let mut conflicts = Vec::new();
+        let mut line = 0;
+
+        for chunk in merge_chunks(&lines(base), &lines(ours), &lines(theirs)) {
+            match chunk {
+                Chunk::Merged(chunk_lines) => {
+                    line += chunk_lines.len();
+                    content.push_str(&chunk_lines.concat());
+                },
+                Chunk::Conflict(base_lines, our_lines, their_lines) => {
+                    conflicts.push((line, our_lines.len(), ConflictSides{
+                        base: base_lines.concat(),
+                        ours: our_lines.concat(),
+                        theirs: their_lines.concat(),
+                    }));
+                    line += our_lines.len();
+                    content.push_str(&our_lines.concat());
+                },
+            }
+        }
+
+        // Load the merged content without recording it as an undoable change.
+        let mut buffer = Buffer::new();
+        buffer.insert(content);
+        buffer.history = History::new();
+
+        for (line, line_count, sides) in conflicts {
+            let range = Range::new(
+                Position{ line, offset: 0 },
+                buffer.clamp(Position{ line: line + line_count, offset: 0 })
+            );
+            let id = buffer.track_range(range);
+            buffer.merge_conflicts.push((id, sides));
+        }
+
+        buffer
+    }
+
+    /// Returns the buffer's unresolved merge conflicts (see `from_merge`), in
+    /// the order in which they appear. Conflicts whose content is deleted
+    /// entirely are considered resolved.
+    pub fn merge_conflicts(&self) -> Vec<MergeConflict> {
+        let mut conflicts: Vec<MergeConflict> = self.merge_conflicts.iter().filter_map(|&(id, ref sides)| {
+            self.tracked_range(id).map(|range| MergeConflict{
+                id,
+                range,
+                base: sides.base.clone(),
+                ours: sides.ours.clone(),
+                theirs: sides.theirs.clone(),
+            })
+        }).collect();
+        conflicts.sort_by(|a, b| a.range.start().partial_cmp(&b.range.start()).unwrap());
+
+        conflicts
+    }
+
+    /// Replaces the specified merge conflict's content with one or both of
+    /// its sides, as a single undoable operation, moving the cursor to the
+    /// start of the replacement. Returns false if the conflict isn't found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::ConflictResolution;
+    ///
+    /// let mut buffer = Buffer::from_merge("scribe\n", "library\n", "editor\n");
+    /// let id = buffer.merge_conflicts()[0].id;
+    ///
+    /// assert!(buffer.resolve_merge_conflict(id, ConflictResolution::Both));
+    /// assert_eq!(buffer.data(), "library\neditor\n");
+    /// assert!(buffer.merge_conflicts().is_empty());
+    /// ```
+    pub fn resolve_merge_conflict(&mut self, id: TrackingId, resolution: ConflictResolution) -> bool {
+        let conflict = match self.merge_conflicts().into_iter().find(|conflict| conflict.id == id) {
+            Some(conflict) => conflict,
+            None => return false,
+        };
+
+        let content = match resolution {
+            ConflictResolution::Ours => conflict.ours,
+            ConflictResolution::Theirs => conflict.theirs,
+            ConflictResolution::Both => conflict.ours + &conflict.theirs,
+        };
+
+        let start = conflict.range.start();
+        self.replace(conflict.range, content);
+        self.cursor.move_to(start);
+
+        self.merge_conflicts.retain(|&(conflict_id, _)| conflict_id != id);
+        self.untrack(id);
+
+        true
+    }
+}
+
+// Splits the merge into regions, synchronizing both sides'
+// edit scripts on base lines left unchanged by both.
+fn merge_chunks<'a>(base: &[&'a str], ours: &[&'a str], theirs: &[&'a str]) -> Vec<Chunk<'a>> {
+    let our_matches = base_matches(base.len(), &edit_script(base, ours));
+    let their_matches = base_matches(base.len(), &edit_script(base, theirs));
+    let mut chunks = Vec::new();
+    let (mut base_index, mut our_index, mut their_index) = (0, 0, 0);
+
+    while base_index < base.len() || our_index < ours.len() || their_index < theirs.len() {
+        // Lines unchanged by both sides.
+        let mut stable_lines = Vec::new();
+        while base_index < base.len() &&
+            our_matches[base_index] == Some(our_index) &&
+            their_matches[base_index] == Some(their_index) {
+            stable_lines.push(base[base_index]);
+            base_index += 1;
+            our_index += 1;
+            their_index += 1;
+        }
+        if !stable_lines.is_empty() {
+            chunks.push(Chunk::Merged(stable_lines));
+            continue;
+        }
+
+        // Find the next line unchanged by both sides; everything before it changed.
+        let (base_end, our_end, their_end) = (base_index..base.len())
+            .filter_map(|index| match (our_matches[index], their_matches[index]) {
+                (Some(our_end), Some(their_end)) => Some((index, our_end, their_end)),
+                _ => None,
+            })
+            .next()
+            .unwrap_or((base.len(), ours.len(), theirs.len()));
+
+        let base_lines = &base[base_index..base_end];
+        let our_lines = &ours[our_index..our_end];
+        let their_lines = &theirs[their_index..their_end];
+
+        if our_lines == base_lines {
+            chunks.push(Chunk::Merged(their_lines.to_vec()));
+        } else if their_lines == base_lines || our_lines == their_lines {
+            chunks.push(Chunk::Merged(our_lines.to_vec()));
+        } else {
+            chunks.push(Chunk::Conflict(base_lines.to_vec(), our_lines.to_vec(), their_lines.to_vec()));
+        }
+
+        base_index = base_end;
+        our_index = our_end;
+        their_index = their_end;
+    }
+
+    chunks
+}
+
+// Maps each base line to its index in the other content, if it was unchanged.
+fn base_matches(base_length: usize, edits: &[Edit]) -> Vec<Option<usize>> {
+    let mut matches = vec![None; base_length];
+    let (mut base_index, mut index) = (0, 0);
+
+    for edit in edits {
+        match *edit {
+            Edit::Equal => {
+                matches[base_index] = Some(index);
+                base_index += 1;
+                index += 1;
+            },
+            Edit::Delete => base_index += 1,
+            Edit::Insert => index += 1,
+        }
+    }
+
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, ConflictResolution, Position};
+
+    #[test]
+    fn from_merge_applies_non_conflicting_changes_from_both_sides() {
+        let buffer = Buffer::from_merge(
+            "scribe\nlibrary\neditor\ntext\n",
+            "scribe\nlibrary\ntext editor\ntext\n",
+            "Scribe\nlibrary\neditor\ntext\ntoolkit\n"
+        );
+
+        assert_eq!(buffer.data(), "Scribe\nlibrary\ntext editor\ntext\ntoolkit\n");
+        assert!(buffer.merge_conflicts().is_empty());
+    }
+
+    #[test]
+    fn from_merge_treats_adjacent_changes_as_conflicts() {
+        let buffer = Buffer::from_merge("scribe\nlibrary\n", "Scribe\nlibrary\n", "scribe\nLibrary\n");
+
+        assert_eq!(buffer.data(), "Scribe\nlibrary\n");
+        assert_eq!(buffer.merge_conflicts()[0].theirs, "scribe\nLibrary\n");
+    }
+
+    #[test]
+    fn from_merge_cannot_be_undone() {
+        let mut buffer = Buffer::from_merge("scribe", "library", "scribe");
+        buffer.undo();
+
+        assert_eq!(buffer.data(), "library");
+    }
+
+    #[test]
+    fn merge_conflicts_are_tracked_through_edits() {
+        let mut buffer = Buffer::from_merge("scribe\neditor", "scribe\nlibrary", "scribe\ntoolkit");
+        buffer.insert("text\n");
+
+        let conflicts = buffer.merge_conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].range.start(), Position{ line: 2, offset: 0 });
+        assert_eq!(conflicts[0].range.end(), Position{ line: 2, offset: 7 });
+        assert_eq!(conflicts[0].ours, "library");
+    }
+
+    #[test]
+    fn resolve_merge_conflict_is_undone_as_a_single_operation() {
+        let mut buffer = Buffer::from_merge("a\nb\nc\n", "a\nB\nc\n", "a\nb2\nc\n");
+        let id = buffer.merge_conflicts()[0].id;
+
+        assert!(buffer.resolve_merge_conflict(id, ConflictResolution::Theirs));
+        assert_eq!(buffer.data(), "a\nb2\nc\n");
+        assert_eq!(*buffer.cursor, Position{ line: 1, offset: 0 });
+        assert!(!buffer.resolve_merge_conflict(id, ConflictResolution::Ours));
+
+        buffer.undo();
+        assert_eq!(buffer.data(), "a\nB\nc\n");
+    }
+
+    #[test]
+    fn merge_conflicts_handle_conflicting_deletions() {
+        let buffer = Buffer::from_merge("scribe\nlibrary\n", "scribe\n", "scribe\ntoolkit\n");
+        let conflicts = buffer.merge_conflicts();
+
+        assert_eq!(buffer.data(), "scribe\n");
+        assert_eq!(conflicts[0].range.start(), Position{ line: 1, offset: 0 });
+        assert_eq!(conflicts[0].range.end(), Position{ line: 1, offset: 0 });
+        assert_eq!(conflicts[0].theirs, "toolkit\n");
+    }
+}
@@ -0,0 +1,143 @@
+//! Arbitrary key-value data associated with a buffer by embedders.
+
+use buffer::Buffer;
+use std::collections::BTreeMap;
+
+/// A value stored in a buffer's metadata.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub enum MetadataValue {
+    Boolean(bool),
+    Integer(i64),
+    Text(String),
+}
+
+impl MetadataValue {
+    /// Returns the value if it's a boolean.
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            MetadataValue::Boolean(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it's an integer.
+    pub fn as_integer(&self) -> Option<i64> {
+        match *self {
+            MetadataValue::Integer(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if it's text.
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            MetadataValue::Text(ref value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl From<bool> for MetadataValue {
+    fn from(value: bool) -> MetadataValue {
+        MetadataValue::Boolean(value)
+    }
+}
+
+impl From<i64> for MetadataValue {
+    fn from(value: i64) -> MetadataValue {
+        MetadataValue::Integer(value)
+    }
+}
+
+impl<'a> From<&'a str> for MetadataValue {
+    fn from(value: &'a str) -> MetadataValue {
+        MetadataValue::Text(value.to_string())
+    }
+}
+
+impl From<String> for MetadataValue {
+    fn from(value: String) -> MetadataValue {
+        MetadataValue::Text(value)
+    }
+}
+
+impl Buffer {
+    /// Stores a value in the buffer's metadata, returning the value previously
+    /// stored using the key, if any. Unlike most buffer state, metadata is
+    /// persisted when the buffer is reloaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.set_metadata("lsp.version", 1);
+    /// buffer.set_metadata("language", "rust");
+    ///
+    /// assert_eq!(buffer.metadata("lsp.version").and_then(|value| value.as_integer()), Some(1));
+    /// assert_eq!(buffer.metadata("language").and_then(|value| value.as_str()), Some("rust"));
+    /// assert_eq!(buffer.metadata("language").and_then(|value| value.as_bool()), None);
+    /// ```
+    pub fn set_metadata<K: Into<String>, V: Into<MetadataValue>>(&mut self, key: K, value: V) -> Option<MetadataValue> {
+        self.metadata.insert(key.into(), value.into())
+    }
+
+    /// Returns the value stored in the buffer's metadata using the key, if any.
+    pub fn metadata(&self, key: &str) -> Option<&MetadataValue> {
+        self.metadata.get(key)
+    }
+
+    /// Removes and returns the value stored using the key, if any.
+    pub fn remove_metadata(&mut self, key: &str) -> Option<MetadataValue> {
+        self.metadata.remove(key)
+    }
+
+    /// Returns all of the buffer's metadata, ordered by key
+    /// (e.g. to include it in a serialized session).
+    pub fn metadata_entries(&self) -> &BTreeMap<String, MetadataValue> {
+        &self.metadata
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, MetadataValue};
+    #[cfg(feature = "system")]
+    use std::path::Path;
+
+    #[test]
+    fn set_metadata_returns_the_previous_value() {
+        let mut buffer = Buffer::new();
+
+        assert_eq!(buffer.set_metadata("wrap", true), None);
+        assert_eq!(buffer.set_metadata("wrap", false), Some(MetadataValue::Boolean(true)));
+        assert_eq!(buffer.remove_metadata("wrap"), Some(MetadataValue::Boolean(false)));
+        assert!(buffer.metadata_entries().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "system")]
+    fn metadata_survives_reloads() {
+        let mut buffer = Buffer::from_file(Path::new("tests/sample/file")).unwrap();
+        buffer.set_metadata("language", "rust");
+        buffer.reload().unwrap();
+
+        assert_eq!(buffer.metadata("language"), Some(&MetadataValue::Text("rust".to_string())));
+    }
+
+    #[cfg(feature = "serialization")]
+    #[test]
+    fn metadata_can_be_serialized_and_deserialized() {
+        use serde_json;
+        use std::collections::BTreeMap;
+
+        let mut buffer = Buffer::new();
+        buffer.set_metadata("lsp.version", 3);
+        let json = serde_json::to_string(buffer.metadata_entries()).unwrap();
+
+        let deserialized_metadata: BTreeMap<String, MetadataValue> = serde_json::from_str(&json).unwrap();
+        assert_eq!(&deserialized_metadata, buffer.metadata_entries());
+    }
+}
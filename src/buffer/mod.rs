@@ -4,14 +4,51 @@
 extern crate luthor;
 
 // Published API
-pub use self::gap_buffer::GapBuffer;
+pub use self::gap_buffer::{GapBuffer, GapBufferLines};
 pub use self::distance::Distance;
+pub use self::encoding::{EncodedPosition, EncodedRange, PositionEncoding};
 
 pub use self::position::Position;
 pub use self::range::Range;
 pub use self::line_range::LineRange;
+pub use self::lines::Lines;
+pub use self::annotations::{Annotation, AnnotationProvider};
+pub use self::case::Case;
+pub use self::comments::CommentTokens;
+pub use self::conflicts::{Conflict, ConflictResolution};
 pub use self::cursor::Cursor;
-pub use self::token::{Lexeme, Token, TokenSet};
+pub use self::diff::{Hunk, HunkKind};
+pub use self::document_sync::{DocumentNotification, DocumentState, DocumentSync, SyncKind};
+pub use self::folding::FoldStrategy;
+pub use self::gutter::{FoldMarker, GutterLine};
+pub use self::indentation::{Indentation, IndentationStyle};
+pub use self::layout::{Layout, VisualPosition, VisualRow};
+pub use self::lsp::{ContentChange, DocumentChanges, TextEdit, TextEditFailure};
+pub use self::merge::MergeConflict;
+pub use self::metadata::MetadataValue;
+pub use self::modeline::Modeline;
+pub use self::modified_lines::ModifiedLines;
+pub use self::operation::OperationState;
+#[cfg(feature = "syntax")]
+pub use self::overlays::Overlay;
+#[cfg(feature = "syntax")]
+pub use self::render_cache::{RenderCache, StyledLine, StyledSpan};
+pub use self::segments::{LineSegment, LineSegments};
+pub use self::settings::{BufferSettings, LineEnding, SaveTransforms};
+#[cfg(feature = "syntax")]
+pub use self::semantic_tokens::SemanticToken;
+#[cfg(feature = "syntax")]
+pub use self::spelling::{Dictionary, Misspelling};
+pub use self::state::{BufferState, BUFFER_STATE_VERSION};
+pub use self::subscriptions::SubscriptionId;
+#[cfg(feature = "syntax")]
+pub use self::token::{Lexeme, OverlayTokenIterator, SemanticTokenIterator, Token, TokenSet};
+pub use self::tracking::TrackingId;
+pub use self::vcs::{BaselineProvider, LineChange};
+#[cfg(feature = "git")]
+pub use self::vcs::GitBaselineProvider;
+pub use self::viewport::Viewport;
+#[cfg(feature = "syntax")]
 pub use syntect::parsing::{Scope, ScopeStack};
 
 // Child modules
@@ -20,24 +57,94 @@ mod distance;
 mod position;
 mod range;
 mod line_range;
+mod lines;
+mod bounds;
+mod abbreviations;
+mod annotations;
+mod case;
+mod columns;
+mod comments;
+mod conflicts;
 mod cursor;
+mod diff;
+mod document_sync;
+mod duplicate;
+mod encoding;
+#[cfg(feature = "syntax")]
+mod export;
+#[cfg(feature = "system")]
+mod filter;
+mod folding;
+mod formatting;
+mod gutter;
+mod increment;
+#[cfg(debug_assertions)]
+mod invariants;
+mod indentation;
+mod layout;
+mod lsp;
+mod marks;
+mod merge;
+mod metadata;
+mod modeline;
+mod modified_lines;
+mod move_lines;
 mod operation;
 mod operations;
+#[cfg(feature = "syntax")]
+mod overlays;
+mod pairs;
+mod patch;
+mod reflow;
+mod registers;
+#[cfg(feature = "syntax")]
+mod render_cache;
+mod search;
+mod segments;
+mod settings;
+#[cfg(feature = "syntax")]
+mod semantic_tokens;
+#[cfg(feature = "syntax")]
+mod spelling;
+mod state;
+mod subscriptions;
+#[cfg(feature = "syntax")]
 mod token;
+mod tracking;
+mod transpose;
+mod vcs;
+mod viewport;
+mod words;
 
 // Buffer type implementation
+#[cfg(feature = "syntax")]
 use errors::*;
 use std::rc::Rc;
+#[cfg(feature = "system")]
+use std::borrow::Cow;
 use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
 use std::default::Default;
+#[cfg(feature = "system")]
 use std::fs::File;
+#[cfg(feature = "system")]
 use std::io;
+#[cfg(feature = "system")]
 use std::io::{Read, Write};
+#[cfg(feature = "system")]
 use std::mem;
 use std::ops::Fn;
-use std::path::{Path, PathBuf};
+#[cfg(feature = "system")]
+use std::path::Path;
+use std::path::PathBuf;
 use self::operation::{Operation, OperationGroup};
 use self::operation::history::History;
+use self::lsp::ChangeLog;
+use self::annotations::LineAnchor;
+use self::merge::ConflictSides;
+use self::subscriptions::Subscriptions;
+use self::tracking::Tracker;
+#[cfg(feature = "syntax")]
 use syntect::parsing::SyntaxDefinition;
 
 /// A feature-rich wrapper around an underlying gap buffer.
@@ -48,7 +155,24 @@ use syntect::parsing::SyntaxDefinition;
 ///
 /// If the buffer is configured with a `change_callback`, it will be called with
 /// a position whenever the buffer is modified; it's particularly useful for
-/// cache invalidation.
+/// cache invalidation. Additional observers can be added using `subscribe`.
+///
+/// If the buffer is configured with a `baseline_provider`, its lines' changes
+/// relative to the provided baseline are available through `line_changes`.
+/// Lines changed since the buffer was last saved are tracked as it's edited,
+/// and are available through `modified_lines`.
+///
+/// The buffer's `abbreviations` map short forms to the text they're
+/// expanded into when typed using `insert_expanding`.
+///
+/// Positions exchanged with external peers measure columns using the
+/// buffer's `position_encoding`. This applies to encoded positions and ranges
+/// (see `encode_position`), `search_encoded` results, content changes sent to
+/// language servers, and the text edits and semantic tokens received from
+/// them. Other APIs use grapheme offsets.
+///
+/// Read-only buffers (see `read_only`) ignore edits, as well as undo and
+/// redo requests; they can still be reloaded.
 pub struct Buffer {
     pub id: Option<usize>,
     data: Rc<RefCell<GapBuffer>>,
@@ -56,8 +180,37 @@ pub struct Buffer {
     pub cursor: Cursor,
     history: History,
     operation_group: Option<OperationGroup>,
+    #[cfg(feature = "syntax")]
     pub syntax_definition: Option<SyntaxDefinition>,
     pub change_callback: Option<Box<Fn(Position)>>,
+    subscriptions: Subscriptions,
+    pub baseline_provider: Option<Box<BaselineProvider>>,
+    pub abbreviations: HashMap<String, String>,
+    pub position_encoding: PositionEncoding,
+    pub read_only: bool,
+    indentation: Indentation,
+    line_ending: LineEnding,
+    mixed_line_endings: bool,
+    save_transforms: SaveTransforms,
+    comment_tokens: Option<CommentTokens>,
+    tracker: Tracker,
+    content_changes: ChangeLog,
+    modified_lines: ModifiedLines,
+    folds: Vec<TrackingId>,
+    #[cfg(feature = "syntax")]
+    overlays: Vec<(TrackingId, Scope)>,
+    #[cfg(feature = "syntax")]
+    semantic_tokens: Vec<(TrackingId, Scope)>,
+    merge_conflicts: Vec<(TrackingId, ConflictSides)>,
+    annotations: Vec<(TrackingId, LineAnchor, String, String)>,
+    marks: Vec<(char, TrackingId)>,
+    metadata: BTreeMap<String, MetadataValue>,
+    modeline: Option<Modeline>,
+    #[cfg(feature = "syntax")]
+    render_cache: RefCell<RenderCache>,
+
+    // The path for which the baseline was last provided, and its baseline.
+    baseline: RefCell<Option<(PathBuf, Option<String>)>>,
 }
 
 impl Default for Buffer {
@@ -74,8 +227,35 @@ impl Default for Buffer {
             cursor,
             history: History::new(),
             operation_group: None,
+            #[cfg(feature = "syntax")]
             syntax_definition: None,
             change_callback: None,
+            subscriptions: Subscriptions::default(),
+            baseline_provider: None,
+            abbreviations: HashMap::new(),
+            position_encoding: PositionEncoding::default(),
+            read_only: false,
+            indentation: Indentation::default(),
+            line_ending: LineEnding::default(),
+            mixed_line_endings: false,
+            save_transforms: SaveTransforms::default(),
+            comment_tokens: None,
+            tracker: Tracker::default(),
+            content_changes: ChangeLog::default(),
+            modified_lines: ModifiedLines::default(),
+            folds: Vec::new(),
+            #[cfg(feature = "syntax")]
+            overlays: Vec::new(),
+            #[cfg(feature = "syntax")]
+            semantic_tokens: Vec::new(),
+            merge_conflicts: Vec::new(),
+            annotations: Vec::new(),
+            marks: Vec::new(),
+            metadata: BTreeMap::new(),
+            modeline: None,
+            #[cfg(feature = "syntax")]
+            render_cache: RefCell::new(RenderCache::default()),
+            baseline: RefCell::new(None),
         }
     }
 }
@@ -115,12 +295,30 @@ impl Buffer {
     /// # assert_eq!(buffer.cursor.line, 0);
     /// # assert_eq!(buffer.cursor.offset, 0);
     /// ```
+    #[cfg(feature = "system")]
     pub fn from_file(path: &Path) -> io::Result<Buffer> {
         // Try to open and read the file, returning any errors encountered.
         let mut file = File::open(path)?;
         let mut data = String::new();
         file.read_to_string(&mut data)?;
 
+        // Follow the file's existing indentation convention,
+        // unless its modeline specifies otherwise.
+        let modeline = Modeline::detect(&data);
+        let mut indentation = Indentation::detect(&data).unwrap_or_default();
+        if let Some(ref modeline) = modeline {
+            indentation = modeline.apply_to(indentation);
+        }
+
+        // Positions treat "\r\n" as a single grapheme rather than a line
+        // break, so line endings are normalized, and restored when saved.
+        let line_ending = LineEnding::detect(&data);
+        let mixed_line_endings = LineEnding::mixed(&data);
+        let data = match LineEnding::Lf.apply(&data) {
+            Cow::Owned(normalized_data) => normalized_data,
+            Cow::Borrowed(_) => data,
+        };
+
         let data = Rc::new(RefCell::new(GapBuffer::new(data)));
         let cursor = Cursor::new(data.clone(), Position{ line: 0, offset: 0 });
 
@@ -132,8 +330,35 @@ impl Buffer {
             cursor,
             history: History::new(),
             operation_group: None,
+            #[cfg(feature = "syntax")]
             syntax_definition: None,
             change_callback: None,
+            subscriptions: Subscriptions::default(),
+            baseline_provider: None,
+            abbreviations: HashMap::new(),
+            position_encoding: PositionEncoding::default(),
+            read_only: false,
+            indentation,
+            line_ending,
+            mixed_line_endings,
+            save_transforms: SaveTransforms::default(),
+            comment_tokens: None,
+            tracker: Tracker::default(),
+            content_changes: ChangeLog::default(),
+            modified_lines: ModifiedLines::default(),
+            folds: Vec::new(),
+            #[cfg(feature = "syntax")]
+            overlays: Vec::new(),
+            #[cfg(feature = "syntax")]
+            semantic_tokens: Vec::new(),
+            merge_conflicts: Vec::new(),
+            annotations: Vec::new(),
+            marks: Vec::new(),
+            metadata: BTreeMap::new(),
+            modeline,
+            #[cfg(feature = "syntax")]
+            render_cache: RefCell::new(RenderCache::default()),
+            baseline: RefCell::new(None),
         };
 
         // We mark the history at points where the
@@ -184,6 +409,7 @@ impl Buffer {
     ///
     /// # std::fs::remove_file(&write_path);
     /// ```
+    #[cfg(feature = "system")]
     pub fn save(&mut self) -> io::Result<()> {
         // Try to open and write to the file, returning any errors encountered.
         let mut file =
@@ -193,12 +419,18 @@ impl Buffer {
                 File::create(&PathBuf::new())?
             };
 
+        self.apply_save_transforms();
+
         // We use to_string here because we don't want to write the gap contents.
-        file.write_all(self.data().to_string().as_bytes())?;
+        let data = self.data();
+        file.write_all(self.line_ending.apply(&data).as_bytes())?;
+        self.mixed_line_endings = false;
 
         // We mark the history at points where the
         // buffer is in sync with its file equivalent.
         self.history.mark();
+        self.modified_lines.clear();
+        self.invalidate_baseline();
 
         Ok(())
     }
@@ -206,6 +438,7 @@ impl Buffer {
     /// Produces a set of tokens based on the buffer data
     /// suitable for colorized display, using a lexer for the
     /// buffer data's language and/or format.
+    #[cfg(feature = "syntax")]
     pub fn tokens(&self) -> Result<TokenSet> {
         if let Some(ref def) = self.syntax_definition {
             Ok(TokenSet::new(self.data(), def))
@@ -248,6 +481,7 @@ impl Buffer {
     ///     )
     /// );
     /// ```
+    #[cfg(feature = "syntax")]
     pub fn current_scope(&self) -> Result<ScopeStack> {
         let mut scope = None;
         let tokens = self.tokens()?;
@@ -272,10 +506,10 @@ impl Buffer {
     ///
     /// ```
     /// use scribe::Buffer;
-    /// use std::path::Path;
+    /// use std::path::PathBuf;
     ///
-    /// let file_path = Path::new("tests/sample/file");
-    /// let buffer = Buffer::from_file(file_path).unwrap();
+    /// let mut buffer = Buffer::new();
+    /// buffer.path = Some(PathBuf::from("tests/sample/file"));
     /// assert_eq!(buffer.file_name().unwrap(), "file");
     /// ```
     pub fn file_name(&self) -> Option<String> {
@@ -322,6 +556,11 @@ impl Buffer {
     /// assert_eq!("", buffer.data());
     /// ```
     pub fn undo(&mut self) {
+        // Read-only buffers can't be modified.
+        if self.read_only {
+            return
+        }
+
         // Look for an operation to undo. First, check if there's an open, non-empty
         // operation group. If not, try taking the last operation from the buffer history.
         let operation: Option<Box<Operation>> = match self.operation_group.take() {
@@ -339,6 +578,11 @@ impl Buffer {
         if let Some(mut op) = operation {
             op.reverse(self);
         }
+
+        // Changes made since the last save have been undone.
+        if !self.modified() {
+            self.modified_lines.clear();
+        }
     }
 
     /// Re-applies the last undone modification to the buffer.
@@ -358,10 +602,20 @@ impl Buffer {
     /// assert_eq!("scribe", buffer.data());
     /// ```
     pub fn redo(&mut self) {
+        // Read-only buffers can't be modified.
+        if self.read_only {
+            return
+        }
+
         // Look for an operation to apply.
         if let Some(mut op) = self.history.next() {
             op.run(self);
         }
+
+        // Changes undone since the last save have been redone.
+        if !self.modified() {
+            self.modified_lines.clear();
+        }
     }
 
     /// Tries to read the specified range from the buffer.
@@ -385,48 +639,6 @@ impl Buffer {
         self.data.borrow().read(range)
     }
 
-    /// Searches the buffer for (and returns positions
-    /// associated with) occurrences of `needle`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use scribe::Buffer;
-    /// use scribe::buffer::Position;
-    ///
-    /// let mut buffer = Buffer::new();
-    /// buffer.insert("scribe\nlibrary");
-    ///
-    /// assert_eq!(
-    ///     buffer.search("ib"),
-    ///     vec![
-    ///         Position{ line: 0, offset: 3 },
-    ///         Position{ line: 1, offset: 1 }
-    ///     ]
-    /// );
-    /// ```
-    pub fn search(&self, needle: &str) -> Vec<Position> {
-        let mut results = Vec::new();
-
-        for (line, data) in self.data().lines().enumerate() {
-            for (offset, _) in data.char_indices() {
-                let haystack = &data[offset..];
-
-                // Check haystack length before slicing it and comparing bytes with needle.
-                if haystack.len() >= needle.len() && needle.as_bytes() == &haystack.as_bytes()[..needle.len()] {
-                    results.push(
-                        Position{
-                            line,
-                            offset
-                        }
-                    );
-                }
-            }
-        }
-
-        results
-    }
-
     /// Whether or not the buffer has been modified since being read from or
     /// written to disk. Buffers without paths are always considered modified.
     ///
@@ -436,6 +648,7 @@ impl Buffer {
     /// use scribe::Buffer;
     /// use std::path::Path;
     ///
+    /// # #[cfg(feature = "system")] {
     /// let file_path = Path::new("tests/sample/file");
     /// let mut buffer = Buffer::from_file(file_path).unwrap();
     ///
@@ -452,11 +665,33 @@ impl Buffer {
     /// // Buffers without paths are always modified.
     /// buffer = Buffer::new();
     /// assert!(buffer.modified());
+    /// # }
     /// ```
     pub fn modified(&self) -> bool {
         !self.history.at_mark()
     }
 
+    /// Returns a number identifying the buffer's position in its history,
+    /// which changes whenever an operation is added, undone, or redone (e.g.
+    /// to check whether an edit is still the most recent one).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe");
+    /// let revision = buffer.history_revision();
+    ///
+    /// buffer.undo();
+    /// buffer.redo();
+    /// assert!(buffer.history_revision() != revision);
+    /// ```
+    pub fn history_revision(&self) -> usize {
+        self.history.revision()
+    }
+
     /// The number of lines in the buffer, including trailing newlines.
     ///
     /// # Examples
@@ -470,12 +705,17 @@ impl Buffer {
     /// assert_eq!(buffer.line_count(), 3);
     /// ```
     pub fn line_count(&self) -> usize {
-        self.data().chars().filter(|&c| c == '\n').count() + 1
+        self.data.borrow().line_count()
     }
 
     /// Reloads the buffer from disk, discarding any in-memory modifications and
     /// history, as well as resetting the cursor to its initial (0,0) position.
-    /// The buffer's ID and syntax definition are persisted.
+    /// The buffer's ID, syntax definition, change callback, subscriptions,
+    /// indentation, save transforms, comment tokens, baseline provider,
+    /// abbreviations, read-only flag, and metadata are persisted, whereas
+    /// tracked positions and ranges are invalidated, folds opened, and
+    /// overlays, semantic tokens, merge conflicts, annotations, and marks
+    /// removed.
     ///
     /// # Examples
     ///
@@ -493,6 +733,7 @@ impl Buffer {
     /// # buffer.undo();
     /// # assert_eq!(buffer.data(), "it works!\n");
     /// ```
+    #[cfg(feature = "system")]
     pub fn reload(&mut self) -> io::Result<()> {
         if let Some(ref path) = self.path.clone() {
             match Buffer::from_file(path) {
@@ -503,6 +744,23 @@ impl Buffer {
                     self.id = buf.id;
                     self.syntax_definition = buf.syntax_definition;
                     self.change_callback = buf.change_callback;
+                    self.subscriptions = buf.subscriptions;
+                    self.baseline_provider = buf.baseline_provider;
+                    self.abbreviations = buf.abbreviations;
+                    self.position_encoding = buf.position_encoding;
+                    self.read_only = buf.read_only;
+                    self.metadata = buf.metadata;
+                    self.indentation = buf.indentation;
+                    self.save_transforms = buf.save_transforms;
+                    self.comment_tokens = buf.comment_tokens;
+
+                    self.history.continue_revisions(&buf.history);
+
+                    // Tracked positions can't be remapped onto the new content.
+                    self.tracker = buf.tracker;
+                    self.tracker.invalidate();
+                    self.content_changes = buf.content_changes;
+                    self.content_changes.invalidate();
                 },
                 Err(e) => return Err(e),
             }
@@ -512,6 +770,7 @@ impl Buffer {
         if let Some(ref callback) = self.change_callback {
             callback(Position::new())
         }
+        self.subscriptions.notify(&Range::new(Position::new(), Position::new()), &self.tracker);
 
         Ok(())
     }
@@ -519,14 +778,40 @@ impl Buffer {
 
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "syntax")]
     extern crate syntect;
+    #[cfg(feature = "system")]
     use syntect::parsing::SyntaxSet;
+    #[cfg(feature = "system")]
     use std::cell::RefCell;
+    #[cfg(feature = "system")]
     use std::path::Path;
+    #[cfg(feature = "system")]
     use std::rc::Rc;
-    use buffer::{Buffer, Position};
+    use buffer::{Buffer, Position, Range};
 
     #[test]
+    fn read_only_buffers_ignore_edits_and_history_navigation() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        buffer.insert("library ");
+        buffer.undo();
+        buffer.read_only = true;
+
+        buffer.insert("the ");
+        buffer.delete();
+        buffer.replace(Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 1 }), "S");
+        buffer.undo();
+        buffer.redo();
+        assert_eq!(buffer.data(), "scribe");
+
+        buffer.read_only = false;
+        buffer.redo();
+        assert_eq!(buffer.data(), "library scribe");
+    }
+
+    #[test]
+    #[cfg(feature = "system")]
     fn reload_persists_id_and_syntax_definition() {
         let file_path = Path::new("tests/sample/file");
         let mut buffer = Buffer::from_file(file_path).unwrap();
@@ -547,6 +832,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "system")]
     fn reload_calls_change_callback_with_zero_position() {
         // Load a buffer with some data and modify it.
         let file_path = Path::new("tests/sample/file");
@@ -706,4 +992,12 @@ mod tests {
         // Use a matching term.
         assert!(buffer.search("scribé").len() > 0);
     }
+
+    #[test]
+    fn search_returns_grapheme_offsets() {
+        let mut buffer = Buffer::new();
+        buffer.insert("é😀 scribe");
+
+        assert_eq!(buffer.search("scribe"), vec![Position{ line: 0, offset: 3 }]);
+    }
 }
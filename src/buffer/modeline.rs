@@ -0,0 +1,269 @@
+//! Editor settings embedded in file content (vim and emacs modelines).
+
+use buffer::{Buffer, Indentation, IndentationStyle};
+
+// The number of lines at the start and end of content searched for modelines.
+const MODELINE_SEARCH_LINES: usize = 5;
+
+/// Settings specified by a vim (e.g. `vim: set ft=rust ts=4 et:`) or emacs
+/// (e.g. `-*- mode: rust; tab-width: 4; indent-tabs-mode: nil -*-`) modeline.
+/// Unrecognized settings are ignored.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Modeline {
+    /// The file type (used to select a syntax definition).
+    pub filetype: Option<String>,
+
+    /// The number of columns between tab stops.
+    pub tab_width: Option<usize>,
+
+    /// The width of a level of indentation (vim's `shiftwidth`),
+    /// which defaults to the tab width if it isn't specified.
+    pub indent_width: Option<usize>,
+
+    /// Whether to indent using spaces (rather than tabs).
+    pub expand_tab: Option<bool>,
+}
+
+impl Modeline {
+    /// Finds the first modeline in the first or last five lines
+    /// of the content, returning `None` if there isn't one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::Modeline;
+    ///
+    /// let modeline = Modeline::detect("scribe\nlibrary\n# vim: set ft=python ts=2 et:\n");
+    /// assert_eq!(modeline, Some(Modeline{
+    ///     filetype: Some("python".to_string()),
+    ///     tab_width: Some(2),
+    ///     indent_width: None,
+    ///     expand_tab: Some(true),
+    /// }));
+    /// ```
+    pub fn detect(data: &str) -> Option<Modeline> {
+        let lines: Vec<&str> = data.lines().collect();
+        let tail_start = lines.len().saturating_sub(MODELINE_SEARCH_LINES);
+        let tail_start = if tail_start < MODELINE_SEARCH_LINES { MODELINE_SEARCH_LINES } else { tail_start };
+
+        lines.iter()
+            .take(MODELINE_SEARCH_LINES)
+            .chain(lines.iter().skip(tail_start))
+            .filter_map(|line| Modeline::parse(line))
+            .next()
+    }
+
+    /// Parses a single line as a modeline, returning `None`
+    /// if it doesn't contain one or it has no settings.
+    pub fn parse(line: &str) -> Option<Modeline> {
+        let modeline = parse_emacs(line).or_else(|| parse_vim(line))?;
+
+        if modeline == Modeline::default() {
+            None
+        } else {
+            Some(modeline)
+        }
+    }
+
+    /// Returns the indentation resulting from applying the
+    /// modeline's settings to the specified indentation.
+    pub fn apply_to(&self, indentation: Indentation) -> Indentation {
+        let style = match self.expand_tab {
+            Some(true) => IndentationStyle::Spaces,
+            Some(false) => IndentationStyle::Tabs,
+            None => indentation.style,
+        };
+
+        let width = self.indent_width.or(self.tab_width).unwrap_or(indentation.width);
+
+        Indentation{ style, width }
+    }
+
+    fn set(&mut self, name: &str, value: Option<&str>) {
+        match (name, value) {
+            ("ft", Some(value)) | ("filetype", Some(value)) |
+            ("syn", Some(value)) | ("syntax", Some(value)) | ("mode", Some(value)) => {
+                self.filetype = Some(value.to_lowercase());
+            },
+            ("ts", Some(value)) | ("tabstop", Some(value)) | ("tab-width", Some(value)) => {
+                if let Some(width) = parse_width(value) {
+                    self.tab_width = Some(width);
+                }
+            },
+            // A zero shiftwidth uses the tab width, which is also the fallback.
+            ("sw", Some(value)) | ("shiftwidth", Some(value)) => {
+                if let Some(width) = parse_width(value) {
+                    self.indent_width = Some(width);
+                }
+            },
+            ("et", None) | ("expandtab", None) => self.expand_tab = Some(true),
+            ("noet", None) | ("noexpandtab", None) => self.expand_tab = Some(false),
+            ("indent-tabs-mode", Some(value)) => self.expand_tab = Some(value == "nil"),
+            _ => (),
+        }
+    }
+}
+
+impl Buffer {
+    /// Returns the modeline detected when the buffer was loaded from its file,
+    /// if any. Its indentation settings are applied to the buffer, and its
+    /// file type is used by workspaces to select a syntax definition.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{Indentation, IndentationStyle};
+    /// use std::path::Path;
+    ///
+    /// # #[cfg(feature = "system")] {
+    /// let buffer = Buffer::from_file(Path::new("tests/sample/modeline")).unwrap();
+    ///
+    /// assert_eq!(buffer.modeline().unwrap().filetype, Some("python".to_string()));
+    /// assert_eq!(buffer.indentation(), Indentation{ style: IndentationStyle::Spaces, width: 2 });
+    /// # }
+    /// ```
+    pub fn modeline(&self) -> Option<&Modeline> {
+        self.modeline.as_ref()
+    }
+}
+
+// Parses a (non-zero) width setting's value.
+fn parse_width(value: &str) -> Option<usize> {
+    value.parse().ok().filter(|&width| width > 0)
+}
+
+// Parses settings between "-*-" delimiters, either as "name: value" pairs
+// separated by semicolons, or a single value specifying the mode.
+fn parse_emacs(line: &str) -> Option<Modeline> {
+    let start = line.find("-*-")? + 3;
+    let end = start + line[start..].find("-*-")?;
+    let settings = line[start..end].trim();
+    let mut modeline = Modeline::default();
+
+    if settings.contains(':') {
+        for setting in settings.split(';') {
+            let mut parts = setting.splitn(2, ':');
+            let name = parts.next().unwrap_or("").trim().to_lowercase();
+            if let Some(value) = parts.next() {
+                modeline.set(&name, Some(value.trim()));
+            }
+        }
+    } else {
+        modeline.set("mode", Some(settings));
+    }
+
+    Some(modeline)
+}
+
+// Parses vim's "vim: set name=value ...:" and "vim: name=value ..." forms.
+fn parse_vim(line: &str) -> Option<Modeline> {
+    let settings = ["vim:", "vi:", "ex:", "Vim:"].iter()
+        .filter_map(|marker| {
+            // Markers must be preceded by whitespace (or start the line).
+            line.find(marker)
+                .filter(|&index| index == 0 || line[..index].ends_with(char::is_whitespace))
+                .map(|index| &line[index + marker.len()..])
+        })
+        .next()?
+        .trim_start();
+
+    let settings =
+        if settings.starts_with("set ") || settings.starts_with("se ") {
+            // The settings are terminated by a colon, after which there may be other text.
+            let settings = &settings[settings.find(' ').unwrap_or(0)..];
+            match settings.find(':') {
+                Some(end) => settings[..end].to_string(),
+                None => return None,
+            }
+        } else {
+            settings.replace(':', " ")
+        };
+
+    let mut modeline = Modeline::default();
+    for setting in settings.split_whitespace() {
+        let mut parts = setting.splitn(2, '=');
+        let name = parts.next().unwrap_or("");
+        modeline.set(name, parts.next());
+    }
+
+    Some(modeline)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Modeline;
+    use buffer::{Indentation, IndentationStyle};
+
+    #[test]
+    fn parse_handles_vim_modelines_without_set() {
+        assert_eq!(Modeline::parse("// vim: ft=rust:sw=2:noet"), Some(Modeline{
+            filetype: Some("rust".to_string()),
+            indent_width: Some(2),
+            expand_tab: Some(false),
+            ..Modeline::default()
+        }));
+    }
+
+    #[test]
+    fn parse_ignores_text_following_vim_set_modelines() {
+        assert_eq!(Modeline::parse("/* vim: set ts=8: ignore=me */"), Some(Modeline{
+            tab_width: Some(8),
+            ..Modeline::default()
+        }));
+    }
+
+    #[test]
+    fn parse_handles_emacs_modelines() {
+        assert_eq!(Modeline::parse("# -*- mode: Python; tab-width: 4; indent-tabs-mode: nil -*-"), Some(Modeline{
+            filetype: Some("python".to_string()),
+            tab_width: Some(4),
+            expand_tab: Some(true),
+            ..Modeline::default()
+        }));
+        assert_eq!(Modeline::parse(";; -*- lisp -*-").unwrap().filetype, Some("lisp".to_string()));
+    }
+
+    #[test]
+    fn parse_ignores_markers_within_words_and_empty_modelines() {
+        assert_eq!(Modeline::parse("see envim: ft=rust"), None);
+        assert_eq!(Modeline::parse("vim: unknown"), None);
+    }
+
+    #[test]
+    fn detect_ignores_lines_in_the_middle_of_the_content() {
+        let mut data = "\n".repeat(5);
+        data.push_str("vim: ts=2\n");
+        data.push_str(&"\n".repeat(5));
+
+        assert_eq!(Modeline::detect(&data), None);
+        assert!(Modeline::detect("vim: ts=2\n").is_some());
+    }
+
+    #[test]
+    fn parse_keeps_tab_and_indent_widths_separate() {
+        assert_eq!(Modeline::parse("/* vim: set ts=8 sw=4 et: */"), Some(Modeline{
+            tab_width: Some(8),
+            indent_width: Some(4),
+            expand_tab: Some(true),
+            ..Modeline::default()
+        }));
+        assert_eq!(Modeline::parse("# vim: ts=8 sw=0").unwrap().indent_width, None);
+    }
+
+    #[test]
+    fn apply_to_prefers_the_indent_width_to_the_tab_width() {
+        let indentation = Indentation{ style: IndentationStyle::Tabs, width: 2 };
+        let modeline = Modeline{ tab_width: Some(8), indent_width: Some(4), ..Modeline::default() };
+
+        assert_eq!(modeline.apply_to(indentation), Indentation{ style: IndentationStyle::Tabs, width: 4 });
+    }
+
+    #[test]
+    fn apply_to_retains_unspecified_settings() {
+        let indentation = Indentation{ style: IndentationStyle::Tabs, width: 8 };
+        let modeline = Modeline{ tab_width: Some(4), ..Modeline::default() };
+
+        assert_eq!(modeline.apply_to(indentation), Indentation{ style: IndentationStyle::Tabs, width: 4 });
+    }
+}
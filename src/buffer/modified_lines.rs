@@ -0,0 +1,285 @@
+//! Lines added or changed since the buffer was last saved.
+
+use buffer::{Buffer, GapBuffer, LineChange, Position, Range};
+use memchr::memchr_iter;
+use std::cmp;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The lines added or modified since the buffer was last saved (or loaded),
+/// remapped as the buffer is edited rather than found by diffing its content.
+/// Lines stay flagged if their changes are reverted by further edits or
+/// undone, unless the buffer is returned to its saved state.
+#[derive(Debug, Default)]
+pub struct ModifiedLines {
+    // Indexed by line number; lines beyond the end are unchanged.
+    lines: Vec<Option<LineChange>>,
+}
+
+impl ModifiedLines {
+    /// Returns the specified line's state (either `LineChange::Added` or
+    /// `LineChange::Modified`), or `None` if it hasn't been changed.
+    pub fn get(&self, line: usize) -> Option<LineChange> {
+        self.lines.get(line).cloned().unwrap_or(None)
+    }
+
+    /// Returns the changed lines' numbers and states, in order.
+    pub fn lines(&self) -> Vec<(usize, LineChange)> {
+        self.lines.iter().enumerate()
+            .filter_map(|(line, change)| change.map(|change| (line, change)))
+            .collect()
+    }
+
+    /// Whether or not any lines have been changed.
+    pub fn is_empty(&self) -> bool {
+        self.lines.iter().all(Option::is_none)
+    }
+
+    /// Flags lines changed by an insertion. This must be called before
+    /// the content is inserted, as positions refer to the prior state.
+    pub fn insert(&mut self, data: &GapBuffer, content: &str, position: &Position) {
+        // Out of bounds insertions are ignored by the gap buffer.
+        if let Some(line_length) = line_length(data, position.line) {
+            self.record_insertion(content, position, line_length);
+        }
+    }
+
+    /// Flags lines changed by a deletion. This must be called before
+    /// the range is deleted, as positions refer to the prior state.
+    pub fn delete(&mut self, data: &GapBuffer, range: &Range) {
+        if let Some(end) = deletion_end(data, range) {
+            self.record_deletion(&range.start(), &end);
+        }
+    }
+
+    /// Flags lines changed by a replacement (see `GapBuffer::replace`).
+    /// This must be called before the range is replaced.
+    pub fn replace(&mut self, data: &GapBuffer, range: &Range, content: &str) {
+        let start = range.start();
+        let end = match deletion_end(data, range) {
+            Some(end) => end,
+            None => return,
+        };
+        self.record_deletion(&start, &end);
+
+        // The deletion joins the start of the range's
+        // first line with the end of its last one.
+        let remaining_length = line_length(data, end.line).unwrap_or(end.offset) - end.offset;
+        self.record_insertion(content, &start, start.offset + remaining_length);
+    }
+
+    /// Discards all flags (e.g. when the buffer is saved).
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    fn record_insertion(&mut self, content: &str, position: &Position, line_length: usize) {
+        if content.is_empty() || position.offset > line_length {
+            return
+        }
+
+        let new_lines = memchr_iter(b'\n', content.as_bytes()).count();
+        if position.offset == 0 && content.ends_with('\n') {
+            // Whole lines were inserted before the line.
+            self.insert_lines(position.line, new_lines);
+        } else if position.offset == line_length && content.starts_with('\n') {
+            // Whole lines were inserted after the line.
+            self.insert_lines(position.line + 1, new_lines);
+        } else {
+            self.mark_modified(position.line);
+            self.insert_lines(position.line + 1, new_lines);
+        }
+    }
+
+    fn record_deletion(&mut self, start: &Position, end: &Position) {
+        if start.offset == 0 && end.offset == 0 {
+            // Whole lines were removed; the line following
+            // them moves up, and is otherwise unchanged.
+            self.remove_lines(start.line, end.line);
+        } else {
+            self.remove_lines(start.line + 1, end.line + 1);
+            self.mark_modified(start.line);
+        }
+    }
+
+    fn insert_lines(&mut self, line: usize, count: usize) {
+        if count == 0 {
+            return
+        }
+
+        if self.lines.len() < line {
+            self.lines.resize(line, None);
+        }
+        self.lines.splice(line..line, (0..count).map(|_| Some(LineChange::Added)));
+    }
+
+    fn remove_lines(&mut self, start: usize, end: usize) {
+        let end = cmp::min(end, self.lines.len());
+        if start < end {
+            self.lines.drain(start..end);
+        }
+    }
+
+    // Flags the line as modified, unless it's been added.
+    fn mark_modified(&mut self, line: usize) {
+        if self.lines.len() <= line {
+            self.lines.resize(line + 1, None);
+        }
+        if self.lines[line].is_none() {
+            self.lines[line] = Some(LineChange::Modified);
+        }
+    }
+}
+
+// Returns the number of graphemes on the line, if it exists.
+fn line_length(data: &GapBuffer, line: usize) -> Option<usize> {
+    data.line(line).map(|content| content.graphemes(true).count())
+}
+
+// Resolves the end of the content the gap buffer removes when deleting the
+// range: ends beyond their line extend to the start of the next line, or
+// the end of the buffer. Returns `None` if nothing would be removed.
+fn deletion_end(data: &GapBuffer, range: &Range) -> Option<Position> {
+    let (start, end) = (range.start(), range.end());
+    if start == end || line_length(data, start.line).map(|length| start.offset > length).unwrap_or(true) {
+        return None
+    }
+
+    match line_length(data, end.line) {
+        Some(length) if end.offset <= length => Some(end),
+        _ if end.line + 1 < data.line_count() => Some(Position{ line: end.line + 1, offset: 0 }),
+        _ => {
+            let last_line = data.line_count() - 1;
+            Some(Position{ line: last_line, offset: line_length(data, last_line).unwrap_or(0) })
+        },
+    }
+}
+
+impl Buffer {
+    /// Returns the lines added or modified since the buffer was last saved
+    /// (or loaded), which are cleared when it's saved, or when undoing or
+    /// redoing returns it to its saved state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{LineChange, Position};
+    /// use std::path::Path;
+    ///
+    /// # #[cfg(feature = "system")] {
+    /// let mut buffer = Buffer::from_file(Path::new("tests/sample/file")).unwrap();
+    /// buffer.insert("scribe\n");
+    /// buffer.cursor.move_to(Position{ line: 1, offset: 2 });
+    /// buffer.insert(" really");
+    ///
+    /// assert_eq!(buffer.data(), "scribe\nit really works!\n");
+    /// assert_eq!(buffer.modified_lines().get(0), Some(LineChange::Added));
+    /// assert_eq!(buffer.modified_lines().get(1), Some(LineChange::Modified));
+    /// assert_eq!(buffer.modified_lines().get(2), None);
+    ///
+    /// // Returning to the saved state clears the flags.
+    /// buffer.undo();
+    /// buffer.undo();
+    /// assert!(buffer.modified_lines().is_empty());
+    /// # }
+    /// ```
+    pub fn modified_lines(&self) -> &ModifiedLines {
+        &self.modified_lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ModifiedLines;
+    use buffer::{Buffer, GapBuffer, LineChange, Position, Range};
+
+    fn saved_buffer(data: &str) -> Buffer {
+        let mut buffer = Buffer::new();
+        buffer.insert(data);
+        buffer.modified_lines.clear();
+
+        buffer
+    }
+
+    #[test]
+    fn lines_inserted_within_a_line_modify_it_and_add_the_rest() {
+        let mut buffer = saved_buffer("scribe\nlibrary");
+        buffer.cursor.move_to(Position{ line: 0, offset: 3 });
+        buffer.insert("text\neditor\n");
+
+        assert_eq!(buffer.modified_lines().lines(), vec![
+            (0, LineChange::Modified),
+            (1, LineChange::Added),
+            (2, LineChange::Added),
+        ]);
+    }
+
+    #[test]
+    fn lines_inserted_at_the_end_of_a_line_are_added() {
+        let mut buffer = saved_buffer("scribe\nlibrary");
+        buffer.cursor.move_to(Position{ line: 0, offset: 6 });
+        buffer.insert("\neditor");
+
+        assert_eq!(buffer.modified_lines().lines(), vec![(1, LineChange::Added)]);
+    }
+
+    #[test]
+    fn flags_are_remapped_when_lines_are_removed() {
+        let mut buffer = saved_buffer("scribe\nlibrary\neditor\ntext");
+        buffer.cursor.move_to(Position{ line: 3, offset: 0 });
+        buffer.insert("the ");
+        buffer.delete_range(Range::new(Position{ line: 0, offset: 0 }, Position{ line: 2, offset: 0 }));
+
+        assert_eq!(buffer.data(), "editor\nthe text");
+        assert_eq!(buffer.modified_lines().lines(), vec![(1, LineChange::Modified)]);
+    }
+
+    #[test]
+    fn joining_lines_modifies_the_remaining_line() {
+        let mut buffer = saved_buffer("scribe\nlibrary\neditor");
+        buffer.delete_range(Range::new(Position{ line: 0, offset: 3 }, Position{ line: 1, offset: 3 }));
+
+        assert_eq!(buffer.data(), "scrrary\neditor");
+        assert_eq!(buffer.modified_lines().lines(), vec![(0, LineChange::Modified)]);
+    }
+
+    #[test]
+    fn replacements_flag_the_lines_they_change() {
+        let mut buffer = saved_buffer("scribe\nlibrary\neditor");
+        buffer.replace(Range::new(Position{ line: 0, offset: 3 }, Position{ line: 1, offset: 3 }), "amp\ntext\n");
+
+        assert_eq!(buffer.data(), "scramp\ntext\nrary\neditor");
+        assert_eq!(buffer.modified_lines().lines(), vec![
+            (0, LineChange::Modified),
+            (1, LineChange::Added),
+            (2, LineChange::Added),
+        ]);
+    }
+
+    #[test]
+    fn out_of_bounds_changes_are_ignored() {
+        let data = GapBuffer::new("scribe".to_string());
+        let mut modified_lines = ModifiedLines::default();
+        modified_lines.insert(&data, "library", &Position{ line: 0, offset: 7 });
+        modified_lines.insert(&data, "library", &Position{ line: 1, offset: 0 });
+        modified_lines.delete(&data, &Range::new(Position{ line: 1, offset: 0 }, Position{ line: 2, offset: 0 }));
+
+        assert!(modified_lines.is_empty());
+    }
+
+    #[cfg(feature = "system")]
+    #[test]
+    fn saving_clears_flags() {
+        use std::path::Path;
+
+        let mut buffer = saved_buffer("scribe");
+        buffer.path = Some(Path::new("tests/sample/modified_lines").to_path_buf());
+        buffer.insert("library ");
+        assert!(!buffer.modified_lines().is_empty());
+
+        buffer.save().unwrap();
+        ::std::fs::remove_file("tests/sample/modified_lines").unwrap();
+
+        assert!(buffer.modified_lines().is_empty());
+    }
+}
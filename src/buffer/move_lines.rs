@@ -0,0 +1,187 @@
+//! Commands for moving lines up/down within a buffer.
+
+use buffer::{Buffer, LineRange, Position, Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+impl Buffer {
+    /// Moves the specified lines (excluding the line on which the range ends)
+    /// above the line that precedes them, as a single undoable operation.
+    ///
+    /// The cursor follows the moved lines, and the range of lines they now
+    /// occupy is returned, so that a selection over them can be preserved.
+    /// If the lines are already at the top of the buffer, or the range is
+    /// empty or extends beyond it, the buffer is left unchanged and `None` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::LineRange;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\nlibrary\neditor");
+    ///
+    /// let moved_lines = buffer.move_lines_up(&LineRange::new(1, 3));
+    /// assert_eq!(buffer.data(), "library\neditor\nscribe");
+    /// assert_eq!(moved_lines, Some(LineRange::new(0, 2)));
+    ///
+    /// // The move is undone as a single operation.
+    /// buffer.undo();
+    /// assert_eq!(buffer.data(), "scribe\nlibrary\neditor");
+    /// ```
+    pub fn move_lines_up(&mut self, lines: &LineRange) -> Option<LineRange> {
+        let data = self.data();
+        let buffer_lines: Vec<&str> = data.split('\n').collect();
+        if lines.start() == 0 || lines.is_empty() || lines.end() > buffer_lines.len() {
+            return None
+        }
+        let last_line = lines.end() - 1;
+
+        // Swap the moved lines with the line above them.
+        let mut content = buffer_lines[lines.start()..lines.end()].to_vec();
+        content.push(buffer_lines[lines.start() - 1]);
+        let range = Range::new(
+            Position{ line: lines.start() - 1, offset: 0 },
+            Position{
+                line: last_line,
+                offset: buffer_lines[last_line].graphemes(true).count()
+            }
+        );
+        self.replace(range, content.join("\n"));
+
+        // Keep the cursor on the same content.
+        let cursor_line =
+            if self.cursor.line == lines.start() - 1 {
+                last_line
+            } else if lines.includes(self.cursor.line) {
+                self.cursor.line - 1
+            } else {
+                self.cursor.line
+            };
+        let offset = self.cursor.offset;
+        self.cursor.move_to(Position{ line: cursor_line, offset });
+
+        Some(LineRange::new(lines.start() - 1, lines.end() - 1))
+    }
+
+    /// Moves the specified lines (excluding the line on which the range ends)
+    /// below the line that follows them, as a single undoable operation.
+    ///
+    /// The cursor follows the moved lines, and the range of lines they now
+    /// occupy is returned, so that a selection over them can be preserved.
+    /// If the lines are already at the bottom of the buffer, or the range is
+    /// empty or extends beyond it, the buffer is left unchanged and `None` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{LineRange, Position};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\nlibrary\neditor");
+    ///
+    /// let moved_lines = buffer.move_lines_down(&LineRange::new(0, 1));
+    /// assert_eq!(buffer.data(), "library\nscribe\neditor");
+    /// assert_eq!(moved_lines, Some(LineRange::new(1, 2)));
+    /// assert_eq!(*buffer.cursor, Position{ line: 1, offset: 0 });
+    /// ```
+    pub fn move_lines_down(&mut self, lines: &LineRange) -> Option<LineRange> {
+        let data = self.data();
+        let buffer_lines: Vec<&str> = data.split('\n').collect();
+        if lines.is_empty() || lines.end() >= buffer_lines.len() {
+            return None
+        }
+        let next_line = lines.end();
+
+        // Swap the moved lines with the line below them.
+        let mut content = vec![buffer_lines[next_line]];
+        content.extend_from_slice(&buffer_lines[lines.start()..lines.end()]);
+        let range = Range::new(
+            Position{ line: lines.start(), offset: 0 },
+            Position{
+                line: next_line,
+                offset: buffer_lines[next_line].graphemes(true).count()
+            }
+        );
+        self.replace(range, content.join("\n"));
+
+        // Keep the cursor on the same content.
+        let cursor_line =
+            if self.cursor.line == next_line {
+                lines.start()
+            } else if lines.includes(self.cursor.line) {
+                self.cursor.line + 1
+            } else {
+                self.cursor.line
+            };
+        let offset = self.cursor.offset;
+        self.cursor.move_to(Position{ line: cursor_line, offset });
+
+        Some(LineRange::new(lines.start() + 1, lines.end() + 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, LineRange, Position};
+
+    #[test]
+    fn move_lines_up_does_nothing_at_the_top_of_the_buffer() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+
+        assert!(buffer.move_lines_up(&LineRange::new(0, 2)).is_none());
+        assert_eq!(buffer.data(), "scribe\nlibrary");
+    }
+
+    #[test]
+    fn move_lines_down_does_nothing_at_the_bottom_of_the_buffer() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+
+        assert!(buffer.move_lines_down(&LineRange::new(1, 2)).is_none());
+        assert_eq!(buffer.data(), "scribe\nlibrary");
+    }
+
+    #[test]
+    fn move_lines_down_handles_trailing_newlines() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary\n");
+        buffer.move_lines_down(&LineRange::new(0, 1));
+
+        assert_eq!(buffer.data(), "library\nscribe\n");
+    }
+
+    #[test]
+    fn move_lines_up_keeps_the_cursor_on_the_moved_content() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nनी library\neditor");
+        buffer.cursor.move_to(Position{ line: 1, offset: 4 });
+        buffer.move_lines_up(&LineRange::new(1, 2));
+
+        assert_eq!(buffer.data(), "नी library\nscribe\neditor");
+        assert_eq!(*buffer.cursor, Position{ line: 0, offset: 4 });
+    }
+
+    #[test]
+    fn move_lines_ignore_empty_ranges() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary\neditor");
+
+        assert!(buffer.move_lines_up(&LineRange::new(1, 1)).is_none());
+        assert!(buffer.move_lines_down(&LineRange::new(1, 1)).is_none());
+        assert_eq!(buffer.data(), "scribe\nlibrary\neditor");
+    }
+
+    #[test]
+    fn move_lines_down_moves_ranges_from_line_range_iterators() {
+        let mut buffer = Buffer::new();
+        buffer.insert("a\nb\nc\nd");
+        let lines = LineRange::inclusive(0, 1);
+        assert_eq!(lines.iter().collect::<Vec<usize>>(), vec![0, 1]);
+
+        assert_eq!(buffer.move_lines_down(&lines), Some(LineRange::new(1, 3)));
+        assert_eq!(buffer.data(), "c\na\nb\nd");
+    }
+}
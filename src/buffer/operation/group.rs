@@ -1,4 +1,4 @@
-use super::Operation;
+use super::{Operation, OperationState};
 use buffer::Buffer;
 
 /// A collection of operations run as a single/atomic operation.
@@ -37,6 +37,10 @@ impl Operation for OperationGroup {
             operations: self.operations.iter().map(|o| (*o).clone_operation()).collect()
         })
     }
+
+    fn state(&self) -> OperationState {
+        OperationState::Group(self.operations.iter().map(|o| o.state()).collect())
+    }
 }
 
 impl OperationGroup {
@@ -1,4 +1,4 @@
-use buffer::operation::Operation;
+use buffer::operation::{Operation, OperationState};
 
 /// Tracks a series of operations.
 ///
@@ -8,7 +8,8 @@ use buffer::operation::Operation;
 pub struct History {
     previous: Vec<Box<Operation>>,
     next: Vec<Box<Operation>>,
-    marked_position: Option<usize>
+    marked_position: Option<usize>,
+    revision: usize
 }
 
 impl History {
@@ -17,7 +18,8 @@ impl History {
         History{
             previous: Vec::new(),
             next: Vec::new(),
-            marked_position: None
+            marked_position: None,
+            revision: 0
         }
     }
 
@@ -25,6 +27,7 @@ impl History {
     pub fn add(&mut self, operation: Box<Operation>) {
         self.previous.push(operation);
         self.next.clear();
+        self.revision += 1;
 
         // Clear marked position if we've replaced a prior operation.
         if let Some(position) = self.marked_position {
@@ -41,6 +44,7 @@ impl History {
                 // We've found a previous operation. Before we return it, store a
                 // clone of it so that it can be re-applied as a redo operation.
                 self.next.push(operation.clone_operation());
+                self.revision += 1;
                 Some(operation)
             },
             None => None
@@ -54,6 +58,7 @@ impl History {
                 // We've found a subsequent operation. Before we return it, store a
                 // clone of it so that it can be re-applied as an undo operation, again.
                 self.previous.push(operation.clone_operation());
+                self.revision += 1;
                 Some(operation)
             },
             None => None
@@ -71,6 +76,40 @@ impl History {
             false
         }
     }
+
+    /// A number identifying the history's current state, which is incremented
+    /// whenever an operation is added, or the history is navigated.
+    pub fn revision(&self) -> usize {
+        self.revision
+    }
+
+    /// Continues numbering revisions after those of another history (e.g.
+    /// one it's replacing), so that their revisions aren't confused.
+    #[cfg(feature = "system")]
+    pub fn continue_revisions(&mut self, history: &History) {
+        self.revision = history.revision + 1;
+    }
+
+    /// Returns the states of the operations that can be undone and redone
+    /// (each as a stack, whose last operation is reversed/applied first),
+    /// as well as the marked position.
+    pub fn state(&self) -> (Vec<OperationState>, Vec<OperationState>, Option<usize>) {
+        (
+            self.previous.iter().map(|operation| operation.state()).collect(),
+            self.next.iter().map(|operation| operation.state()).collect(),
+            self.marked_position
+        )
+    }
+
+    /// Rebuilds a history from the state of its operations (see `state`).
+    pub fn from_state(previous: Vec<OperationState>, next: Vec<OperationState>, marked_position: Option<usize>) -> History {
+        History{
+            previous: previous.into_iter().map(|state| state.into_operation()).collect(),
+            next: next.into_iter().map(|state| state.into_operation()).collect(),
+            marked_position,
+            revision: 0
+        }
+    }
 }
 
 #[cfg(test)]
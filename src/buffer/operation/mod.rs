@@ -1,4 +1,5 @@
-use buffer::Buffer;
+use buffer::{Buffer, Position, Range};
+use buffer::operations::{Delete, Insert};
 pub use self::group::OperationGroup;
 
 pub mod group;
@@ -17,4 +18,37 @@ pub trait Operation {
     fn run(&mut self, &mut Buffer);
     fn reverse(&mut self, &mut Buffer);
     fn clone_operation(&self) -> Box<Operation>;
+    fn state(&self) -> OperationState;
+}
+
+/// A representation of an operation that can be persisted (e.g. as part of
+/// a `BufferState`) and turned back into an equivalent operation.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub enum OperationState {
+    Insert { content: String, position: Position },
+
+    /// A deletion, along with the deleted content, once it's been run.
+    Delete { content: Option<String>, range: Range },
+
+    /// Operations run in order, and reversed in reverse order.
+    Group(Vec<OperationState>),
+}
+
+impl OperationState {
+    /// Builds the operation represented by the state.
+    pub fn into_operation(self) -> Box<Operation> {
+        match self {
+            OperationState::Insert{ content, position } => Box::new(Insert::new(content, position)),
+            OperationState::Delete{ content, range } => Box::new(Delete::with_content(range, content)),
+            OperationState::Group(states) => {
+                let mut group = OperationGroup::new();
+                for state in states {
+                    group.add(state.into_operation());
+                }
+
+                Box::new(group)
+            },
+        }
+    }
 }
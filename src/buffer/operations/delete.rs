@@ -1,4 +1,4 @@
-use buffer::operation::Operation;
+use buffer::operation::{Operation, OperationState};
 use buffer::{Buffer, Position, Range};
 use std::clone::Clone;
 
@@ -22,28 +22,46 @@ impl Operation for Delete {
         self.content = buffer.data.borrow().read(&self.range);
 
         // Delete the data.
+        buffer.content_changes.delete(&buffer.data.borrow(), &self.range, buffer.position_encoding);
+        buffer.modified_lines.delete(&buffer.data.borrow(), &self.range);
         buffer.data.borrow_mut().delete(&self.range);
+        buffer.tracker.delete(&self.range);
+        #[cfg(feature = "syntax")]
+        buffer.render_cache.borrow_mut().invalidate(self.range.start().line);
 
         // Run the change callback, if present.
         if let Some(ref callback) = buffer.change_callback {
             callback(self.range.start())
         }
+
+        buffer.subscriptions.notify(&Range::new(self.range.start(), self.range.start()), &buffer.tracker);
     }
 
     fn reverse(&mut self, buffer: &mut Buffer) {
         if let Some(ref content) = self.content {
+            buffer.content_changes.insert(&buffer.data.borrow(), content, &self.range.start(), buffer.position_encoding);
+            buffer.modified_lines.insert(&buffer.data.borrow(), content, &self.range.start());
             buffer.data.borrow_mut().insert(content, &self.range.start());
+            buffer.tracker.insert(content, &self.range.start());
+            #[cfg(feature = "syntax")]
+            buffer.render_cache.borrow_mut().invalidate(self.range.start().line);
 
             // Run the change callback, if present.
             if let Some(ref callback) = buffer.change_callback {
                 callback(self.range.start())
             }
+
+            buffer.subscriptions.notify(&self.range, &buffer.tracker);
         }
     }
 
     fn clone_operation(&self) -> Box<Operation> {
         Box::new(self.clone())
     }
+
+    fn state(&self) -> OperationState {
+        OperationState::Delete{ content: self.content.clone(), range: self.range.clone() }
+    }
 }
 
 impl Delete {
@@ -51,6 +69,12 @@ impl Delete {
     pub fn new(range: Range) -> Delete {
         Delete{ content: None, range }
     }
+
+    /// Creates a delete operation that has already been run,
+    /// and restores the deleted content when it's reversed.
+    pub fn with_content(range: Range, content: Option<String>) -> Delete {
+        Delete{ content, range }
+    }
 }
 
 impl Buffer {
@@ -112,6 +136,11 @@ impl Buffer {
     /// assert_eq!(buffer.data(), "scribe");
     /// ```
     pub fn delete_range(&mut self, range: Range) {
+        // Read-only buffers can't be modified.
+        if self.read_only {
+            return
+        }
+
         // Build and run a delete operation.
         let mut op = Delete::new(range);
         op.run(self);
@@ -1,8 +1,7 @@
-use buffer::operation::Operation;
-use buffer::{Buffer, Position, Range};
+use buffer::operation::{Operation, OperationState};
+use buffer::{Buffer, Distance, Position, Range};
 use std::clone::Clone;
 use std::convert::Into;
-use unicode_segmentation::UnicodeSegmentation;
 
 /// A reversible buffer insert operation.
 ///
@@ -20,59 +19,54 @@ pub struct Insert {
 
 impl Operation for Insert {
     fn run(&mut self, buffer: &mut Buffer) {
+        buffer.content_changes.insert(&buffer.data.borrow(), &self.content, &self.position, buffer.position_encoding);
+        buffer.modified_lines.insert(&buffer.data.borrow(), &self.content, &self.position);
         buffer.data.borrow_mut().insert(&self.content, &self.position);
+        buffer.tracker.insert(&self.content, &self.position);
+        #[cfg(feature = "syntax")]
+        buffer.render_cache.borrow_mut().invalidate(self.position.line);
 
         // Run the change callback, if present.
         if let Some(ref callback) = buffer.change_callback {
             callback(self.position)
         }
+
+        let inserted_range = Range::new(self.position, self.position + Distance::of_str(&self.content));
+        buffer.subscriptions.notify(&inserted_range, &buffer.tracker);
     }
 
     // We need to calculate the range of the inserted content.
     // The start of the range corresponds to the cursor position at the time of the insert,
-    // which we've stored. Finding the end of the range requires that we dig into the content.
+    // which we've stored; the end is found by adding the distance covered by the content.
     fn reverse(&mut self, buffer: &mut Buffer) {
-        // The line count of the content tells us the line number for the end of the
-        // range (just add the number of new lines to the starting line).
-        let line_count = self.content.chars().filter(|&c| c == '\n').count() + 1;
-        let end_line = self.position.line + line_count - 1;
-
-        let end_offset = if line_count == 1 {
-            // If there's only one line, the range starts and ends on the same line, and so its
-            // offset needs to take the original insertion location into consideration.
-            self.position.offset + self.content.graphemes(true).count()
-        } else {
-            // If there are multiple lines, the end of the range doesn't
-            // need to consider the original insertion location.
-            match self.content.split('\n').last() {
-                Some(line) => line.graphemes(true).count(),
-                None => return,
-            }
-        };
-
-        // Now that we have the required info,
-        // build the end position and total range.
-        let end_position = Position{
-            line: end_line,
-            offset: end_offset,
-        };
         let range = Range::new(
             self.position,
-            end_position
+            self.position + Distance::of_str(&self.content)
         );
 
         // Remove the content we'd previously inserted.
+        buffer.content_changes.delete(&buffer.data.borrow(), &range, buffer.position_encoding);
+        buffer.modified_lines.delete(&buffer.data.borrow(), &range);
         buffer.data.borrow_mut().delete(&range);
+        buffer.tracker.delete(&range);
+        #[cfg(feature = "syntax")]
+        buffer.render_cache.borrow_mut().invalidate(self.position.line);
 
         // Run the change callback, if present.
         if let Some(ref callback) = buffer.change_callback {
             callback(self.position)
         }
+
+        buffer.subscriptions.notify(&Range::new(self.position, self.position), &buffer.tracker);
     }
 
     fn clone_operation(&self) -> Box<Operation> {
         Box::new(self.clone())
     }
+
+    fn state(&self) -> OperationState {
+        OperationState::Insert{ content: self.content.clone(), position: self.position }
+    }
 }
 
 impl Insert {
@@ -95,6 +89,11 @@ impl Buffer {
     /// assert_eq!(buffer.data(), "scribe");
     /// ```
     pub fn insert<T: Into<String>>(&mut self, data: T) {
+        // Read-only buffers can't be modified.
+        if self.read_only {
+            return
+        }
+
         // Build and run an insert operation.
         let mut op = Insert::new(data.into(), self.cursor.position);
         op.run(self);
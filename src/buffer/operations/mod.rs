@@ -5,3 +5,4 @@ pub use self::insert::Insert;
 
 mod insert;
 mod delete;
+mod replace;
@@ -0,0 +1,172 @@
+use buffer::operation::{Operation, OperationState};
+use buffer::{Buffer, Distance, Range};
+use std::clone::Clone;
+use std::convert::Into;
+
+/// A reversible buffer replace operation.
+///
+/// Replaces the content at the specified range with the provided content,
+/// writing it directly into the space left by the replaced content (see
+/// `GapBuffer::replace`). Tracks the replaced content, and reverses the operation
+/// by replacing the inserted content with it, which allows multi-line edits to
+/// be stored in the history (and undone) as a single unit.
+///
+/// If the buffer is configured with a `change_callback`, it will be called with
+/// the starting position of this operation when it is run or reversed.
+#[derive(Clone)]
+pub struct Replace {
+    range: Range,
+    content: String,
+    replaced_content: Option<String>,
+}
+
+impl Operation for Replace {
+    fn run(&mut self, buffer: &mut Buffer) {
+        // Fetch and store the content we're about to replace.
+        self.replaced_content = buffer.data.borrow().read(&self.range);
+
+        replace(buffer, &self.range, &self.content);
+    }
+
+    fn reverse(&mut self, buffer: &mut Buffer) {
+        let start = self.range.start();
+        let inserted_range = Range::new(start, start + Distance::of_str(&self.content));
+        let replaced_content = self.replaced_content.as_ref().map(|content| content.as_str()).unwrap_or("");
+
+        replace(buffer, &inserted_range, replaced_content);
+    }
+
+    fn clone_operation(&self) -> Box<Operation> {
+        Box::new(self.clone())
+    }
+
+    // Replacements are persisted as their constituent operations.
+    fn state(&self) -> OperationState {
+        OperationState::Group(vec![
+            OperationState::Delete{ content: self.replaced_content.clone(), range: self.range.clone() },
+            OperationState::Insert{ content: self.content.clone(), position: self.range.start() },
+        ])
+    }
+}
+
+impl Replace {
+    /// Creates a new replace operation.
+    pub fn new(range: Range, content: String) -> Replace {
+        Replace{ range, content, replaced_content: None }
+    }
+}
+
+// Replaces the range's content, updating everything
+// that would be updated by a deletion and insertion.
+fn replace(buffer: &mut Buffer, range: &Range, content: &str) {
+    let start = range.start();
+
+    // Changes are recorded against the prior content; the insertion's position
+    // precedes the range, so it's unaffected by the recorded deletion.
+    buffer.content_changes.delete(&buffer.data.borrow(), range, buffer.position_encoding);
+    buffer.content_changes.insert(&buffer.data.borrow(), content, &start, buffer.position_encoding);
+    buffer.modified_lines.replace(&buffer.data.borrow(), range, content);
+    buffer.data.borrow_mut().replace(range, content);
+    buffer.tracker.delete(range);
+    buffer.tracker.insert(content, &start);
+    #[cfg(feature = "syntax")]
+    buffer.render_cache.borrow_mut().invalidate(start.line);
+
+    // Run the change callback, if present.
+    if let Some(ref callback) = buffer.change_callback {
+        callback(start)
+    }
+
+    let inserted_range = Range::new(start, start + Distance::of_str(content));
+    buffer.subscriptions.notify(&inserted_range, &buffer.tracker);
+}
+
+impl Buffer {
+    /// Replaces a range of characters in the buffer with `data`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{Position, Range};
+    ///
+    /// // Set up an example buffer.
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe library");
+    ///
+    /// // Set up the range we'd like to replace.
+    /// let start = Position{ line: 0, offset: 7 };
+    /// let end = Position{ line: 0, offset: 14 };
+    /// let range = Range::new(start, end);
+    ///
+    /// buffer.replace(range, "toolkit");
+    /// assert_eq!(buffer.data(), "scribe toolkit");
+    ///
+    /// // The replacement is undone as a single operation.
+    /// buffer.undo();
+    /// assert_eq!(buffer.data(), "scribe library");
+    /// ```
+    pub fn replace<T: Into<String>>(&mut self, range: Range, data: T) {
+        // Read-only buffers can't be modified.
+        if self.read_only {
+            return
+        }
+
+        // Build and run a replace operation.
+        let mut op = Replace::new(range, data.into());
+        op.run(self);
+
+        // Store the operation in the history
+        // object so that it can be undone.
+        match self.operation_group {
+            Some(ref mut group) => group.add(Box::new(op)),
+            None => self.history.add(Box::new(op)),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Replace;
+    use buffer::{Buffer, Position, Range};
+    use buffer::operation::Operation;
+
+    #[test]
+    fn run_and_reverse_replace_and_restore_content_with_newlines() {
+        // Set up a buffer with some data.
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary\neditor");
+
+        // Set up a range that spans the first two lines.
+        let start = Position{ line: 0, offset: 3 };
+        let end = Position{ line: 1, offset: 3 };
+        let replace_range = Range::new(start, end);
+
+        // Create the replace operation and run it.
+        let mut replace_operation = Replace::new(replace_range, "नी\nx\n".to_string());
+        replace_operation.run(&mut buffer);
+
+        assert_eq!(buffer.data(), "scrनी\nx\nrary\neditor");
+
+        replace_operation.reverse(&mut buffer);
+
+        assert_eq!(buffer.data(), "scribe\nlibrary\neditor");
+    }
+
+    #[test]
+    fn replace_is_undoable_and_redoable() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        buffer.replace(
+            Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 6 }),
+            "amp"
+        );
+        assert_eq!(buffer.data(), "amp");
+
+        buffer.undo();
+        assert_eq!(buffer.data(), "scribe");
+
+        buffer.redo();
+        assert_eq!(buffer.data(), "amp");
+    }
+}
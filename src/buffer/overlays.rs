@@ -0,0 +1,158 @@
+//! Styled ranges (e.g. search matches or diagnostics) layered over content.
+
+use buffer::{Buffer, Range, Scope, TrackingId};
+
+/// A range of content to be styled using the specified scope, in addition
+/// to its syntax highlighting. Overlays can be merged into a buffer's tokens
+/// using `OverlayTokenIterator`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Overlay {
+    pub range: Range,
+    pub scope: Scope,
+}
+
+impl Buffer {
+    /// Adds an overlay, styling the specified range using the scope. The range
+    /// is tracked (see `track_range`), and the overlay removed if its content
+    /// is deleted. Returns an ID that can be used to remove the overlay.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate scribe;
+    /// # extern crate syntect;
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{OverlayTokenIterator, Position, Range, Scope, Token};
+    /// # use syntect::parsing::SyntaxSet;
+    ///
+    /// # fn main() {
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe library");
+    ///
+    /// // Omitted code to set up the buffer's syntax definition.
+    /// # let syntax_set = SyntaxSet::load_defaults_newlines();
+    /// # buffer.syntax_definition = Some(syntax_set.find_syntax_plain_text().clone());
+    ///
+    /// let search = Scope::new("search").unwrap();
+    /// buffer.add_overlay(
+    ///     Range::new(Position{ line: 0, offset: 7 }, Position{ line: 0, offset: 14 }),
+    ///     search
+    /// );
+    ///
+    /// let tokens = buffer.tokens().unwrap();
+    /// let lexemes: Vec<String> = OverlayTokenIterator::new(tokens.iter(), buffer.overlays())
+    ///     .filter_map(|token| match token {
+    ///         Token::Lexeme(ref lexeme) if lexeme.scope.as_slice().contains(&search) => Some(lexeme.value.to_string()),
+    ///         _ => None,
+    ///     }).collect();
+    ///
+    /// assert_eq!(lexemes, vec!["library"]);
+    /// # }
+    /// ```
+    pub fn add_overlay(&mut self, range: Range, scope: Scope) -> TrackingId {
+        self.prune_overlays();
+
+        let id = self.track_range(range);
+        self.overlays.push((id, scope));
+
+        id
+    }
+
+    /// Removes the specified overlay.
+    pub fn remove_overlay(&mut self, id: TrackingId) {
+        self.overlays.retain(|&(overlay_id, _)| overlay_id != id);
+        self.untrack(id);
+    }
+
+    /// Removes all overlays whose scopes match the specified scope
+    /// (or any of its more specific descendants), e.g. clearing all
+    /// "diagnostic" overlays would remove "diagnostic.error" ones.
+    pub fn clear_overlays(&mut self, scope: Scope) {
+        self.prune_overlays();
+
+        let ids: Vec<TrackingId> = self.overlays.iter()
+            .filter(|&&(_, overlay_scope)| scope.is_prefix_of(overlay_scope))
+            .map(|&(id, _)| id)
+            .collect();
+
+        for id in ids {
+            self.remove_overlay(id);
+        }
+    }
+
+    // Removes overlays whose content has been deleted, and stops tracking them.
+    fn prune_overlays(&mut self) {
+        let ids: Vec<TrackingId> = self.overlays.iter()
+            .filter(|&&(id, _)| self.tracked_range(id).is_none())
+            .map(|&(id, _)| id)
+            .collect();
+
+        for id in ids {
+            self.remove_overlay(id);
+        }
+    }
+
+    /// Returns the buffer's overlays, in the order in which they were added.
+    pub fn overlays(&self) -> Vec<Overlay> {
+        self.overlays.iter().filter_map(|&(id, scope)| {
+            self.tracked_range(id).map(|range| Overlay{ range, scope })
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, Overlay, Position, Range, Scope};
+
+    #[test]
+    fn overlays_are_remapped_as_content_changes() {
+        let mut buffer = Buffer::new();
+        buffer.insert("library");
+        let scope = Scope::new("search").unwrap();
+        buffer.add_overlay(Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 7 }), scope);
+        buffer.insert("scribe ");
+
+        assert_eq!(buffer.overlays(), vec![Overlay{
+            range: Range::new(Position{ line: 0, offset: 7 }, Position{ line: 0, offset: 14 }),
+            scope
+        }]);
+    }
+
+    #[test]
+    fn clear_overlays_removes_overlays_with_more_specific_scopes() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe library");
+        let range = Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 6 });
+        let search = Scope::new("search").unwrap();
+        buffer.add_overlay(range.clone(), Scope::new("diagnostic.error").unwrap());
+        buffer.add_overlay(range.clone(), search);
+        buffer.clear_overlays(Scope::new("diagnostic").unwrap());
+
+        assert_eq!(buffer.overlays(), vec![Overlay{ range, scope: search }]);
+    }
+
+    #[test]
+    fn overlays_are_removed_when_their_content_is_deleted() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        buffer.add_overlay(
+            Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 6 }),
+            Scope::new("search").unwrap()
+        );
+        buffer.replace(Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 6 }), "");
+
+        assert!(buffer.overlays().is_empty());
+    }
+
+    #[test]
+    fn overlays_are_pruned_once_their_content_is_deleted() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe library");
+        let search = Scope::new("search").unwrap();
+        buffer.add_overlay(Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 6 }), search);
+        buffer.replace(Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 7 }), "");
+        buffer.add_overlay(Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 7 }), search);
+
+        assert_eq!(buffer.overlays.len(), 1);
+    }
+}
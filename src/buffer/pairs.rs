@@ -0,0 +1,186 @@
+//! Automatic pairing of brackets and quotes as they're typed.
+
+use buffer::{Buffer, Distance};
+#[cfg(feature = "syntax")]
+use buffer::Scope;
+use unicode_segmentation::UnicodeSegmentation;
+
+// Opening and closing characters inserted together.
+const PAIRS: &[(&str, &str)] = &[
+    ("(", ")"),
+    ("[", "]"),
+    ("{", "}"),
+    ("\"", "\""),
+    ("'", "'"),
+    ("`", "`"),
+];
+
+impl Buffer {
+    /// Inserts the character at the cursor and moves the cursor past it,
+    /// pairing brackets and quotes as they're typed: an opening character
+    /// is inserted alongside its closing counterpart (as a single undoable
+    /// operation), with the cursor placed between them, and typing a closing
+    /// character immediately before an identical one moves over it instead.
+    ///
+    /// Pairing is suppressed within strings and comments (if the buffer has a
+    /// syntax definition), before word characters, and, for quotes, after word
+    /// characters (e.g. for apostrophes).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::Position;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// for character in "scribe(".chars() {
+    ///     buffer.insert_paired(character);
+    /// }
+    /// assert_eq!(buffer.data(), "scribe()");
+    /// assert_eq!(*buffer.cursor, Position{ line: 0, offset: 7 });
+    ///
+    /// buffer.insert_paired(')');
+    /// assert_eq!(buffer.data(), "scribe()");
+    /// assert_eq!(*buffer.cursor, Position{ line: 0, offset: 8 });
+    /// ```
+    pub fn insert_paired(&mut self, character: char) {
+        let character = character.to_string();
+        let (previous, next) = self.characters_around_cursor();
+
+        // Move over an existing closing character.
+        let is_closer = PAIRS.iter().any(|&(_, closer)| closer == character);
+        if is_closer && next.as_ref() == Some(&character) {
+            self.cursor.move_right();
+            return
+        }
+
+        // The cursor is placed after the typed character, between any pair.
+        let end = *self.cursor + Distance::of_str(&character);
+        let pair = PAIRS.iter().find(|&&(opener, _)| opener == character);
+        let content = match pair {
+            Some(&(opener, closer)) => {
+                let is_word = |c: &Option<String>| {
+                    c.as_ref().map(|c| c.chars().any(|c| c.is_alphanumeric() || c == '_')).unwrap_or(false)
+                };
+                let suppressed = is_word(&next) ||
+                    (opener == closer && is_word(&previous)) ||
+                    self.cursor_within_prose();
+
+                if suppressed {
+                    character
+                } else {
+                    format!("{}{}", opener, closer)
+                }
+            },
+            None => character,
+        };
+
+        self.insert(content);
+        self.cursor.move_to(end);
+    }
+
+    // Returns the characters immediately before and after the cursor, if any.
+    fn characters_around_cursor(&self) -> (Option<String>, Option<String>) {
+        let line_content = self.line(self.cursor.line);
+        let line: Vec<&str> = match line_content {
+            Some(ref line) => line.graphemes(true).collect(),
+            None => return (None, None),
+        };
+        let offset = self.cursor.offset;
+
+        let previous = if offset > 0 { line.get(offset - 1) } else { None };
+        let next = line.get(offset);
+
+        (previous.map(|c| c.to_string()), next.map(|c| c.to_string()))
+    }
+
+    // Whether or not the cursor is within a string or comment.
+    #[cfg(feature = "syntax")]
+    fn cursor_within_prose(&self) -> bool {
+        let prose_scopes = [Scope::new("string").unwrap(), Scope::new("comment").unwrap()];
+
+        self.current_scope()
+            .map(|scope| {
+                scope.as_slice().iter().any(|&scope| {
+                    prose_scopes.iter().any(|prose_scope| prose_scope.is_prefix_of(scope))
+                })
+            })
+            .unwrap_or(false)
+    }
+
+    // Without lexing support, strings and comments can't be identified.
+    #[cfg(not(feature = "syntax"))]
+    fn cursor_within_prose(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, Position};
+    #[cfg(feature = "syntax")]
+    use syntect::parsing::SyntaxSet;
+
+    fn type_into(buffer: &mut Buffer, content: &str) {
+        for character in content.chars() {
+            buffer.insert_paired(character);
+        }
+    }
+
+    #[test]
+    fn insert_paired_nests_pairs() {
+        let mut buffer = Buffer::new();
+        type_into(&mut buffer, "[{\"scribe");
+
+        assert_eq!(buffer.data(), "[{\"scribe\"}]");
+        assert_eq!(*buffer.cursor, Position{ line: 0, offset: 9 });
+
+        type_into(&mut buffer, "\"}]");
+        assert_eq!(buffer.data(), "[{\"scribe\"}]");
+        assert_eq!(*buffer.cursor, Position{ line: 0, offset: 12 });
+    }
+
+    #[test]
+    fn insert_paired_does_not_pair_before_words_or_quotes_after_words() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        type_into(&mut buffer, "(");
+        buffer.cursor.move_to(Position{ line: 0, offset: 7 });
+        type_into(&mut buffer, "'s library");
+
+        assert_eq!(buffer.data(), "(scribe's library");
+    }
+
+    #[test]
+    #[cfg(feature = "syntax")]
+    fn insert_paired_does_not_pair_within_strings_or_comments() {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let mut buffer = Buffer::new();
+        buffer.insert("let s = \"\";\n// \n");
+        buffer.syntax_definition = syntax_set.find_syntax_by_extension("rs").cloned();
+
+        buffer.cursor.move_to(Position{ line: 0, offset: 9 });
+        type_into(&mut buffer, "(");
+        buffer.cursor.move_to(Position{ line: 1, offset: 3 });
+        type_into(&mut buffer, "[");
+
+        assert_eq!(buffer.data(), "let s = \"(\";\n// [\n");
+    }
+
+    #[test]
+    fn insert_paired_pairs_in_a_single_undoable_operation() {
+        let mut buffer = Buffer::new();
+        type_into(&mut buffer, "a(");
+        buffer.undo();
+
+        assert_eq!(buffer.data(), "a");
+    }
+    #[test]
+    fn insert_paired_moves_to_the_next_line_after_newlines() {
+        let mut buffer = Buffer::new();
+        type_into(&mut buffer, "{\n");
+
+        assert_eq!(buffer.data(), "{\n}");
+        assert_eq!(*buffer.cursor, Position{ line: 1, offset: 0 });
+    }
+}
@@ -0,0 +1,516 @@
+//! Unified diff generation and application.
+
+use buffer::{Buffer, Position, Range};
+use buffer::diff::{edit_script, Edit};
+use errors::*;
+use std::cmp;
+
+// The maximum number of context lines that can be
+// ignored at either end of a hunk when applying it.
+const MAX_FUZZ: usize = 2;
+
+// A parsed unified diff hunk, with lines retaining their newline characters.
+struct PatchHunk<'a> {
+    old_start: usize,
+    old_lines: Vec<&'a str>,
+    new_lines: Vec<&'a str>,
+    leading_context: usize,
+    trailing_context: usize,
+}
+
+impl Buffer {
+    /// Returns a unified diff that turns the original content into the
+    /// buffer's, with the specified number of context lines surrounding
+    /// each change. Both sides are labelled using the buffer's path (or
+    /// "untitled", if it doesn't have one). An empty string is returned
+    /// if there are no differences.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\ntext\neditor\n");
+    ///
+    /// assert_eq!(
+    ///     buffer.unified_diff("scribe\nlibrary\neditor\n", 1),
+    ///     "--- untitled\n+++ untitled\n@@ -1,3 +1,3 @@\n scribe\n-library\n+text\n editor\n"
+    /// );
+    /// ```
+    pub fn unified_diff(&self, original: &str, context: usize) -> String {
+        let label = self.diff_label();
+
+        unified_diff(original, &self.data(), &label, &label, context)
+    }
+
+    /// Returns a unified diff that turns the original buffer's content into
+    /// this buffer's (see `unified_diff`), labelling each side using its
+    /// buffer's path.
+    pub fn unified_diff_from(&self, original: &Buffer, context: usize) -> String {
+        unified_diff(
+            &original.data(),
+            &self.data(),
+            &original.diff_label(),
+            &self.diff_label(),
+            context
+        )
+    }
+
+    /// Applies a unified diff to the buffer as a single undoable operation,
+    /// returning the (zero-based) indices of hunks that couldn't be applied.
+    /// Hunks are located near their specified lines, accounting for lines
+    /// added or removed by preceding hunks, and are matched with up to two
+    /// of their leading and trailing context lines ignored, if necessary.
+    /// File headers are ignored, so patches should only contain changes to
+    /// the buffer's file. Returns an error if the patch can't be parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\nlibrary\neditor\n");
+    ///
+    /// let patch = "--- a\n+++ b\n@@ -1,3 +1,3 @@\n scribe\n-library\n+text\n editor\n";
+    /// assert!(buffer.apply_patch(patch).unwrap().is_empty());
+    /// assert_eq!(buffer.data(), "scribe\ntext\neditor\n");
+    ///
+    /// buffer.undo();
+    /// assert_eq!(buffer.data(), "scribe\nlibrary\neditor\n");
+    /// ```
+    pub fn apply_patch(&mut self, patch: &str) -> Result<Vec<usize>> {
+        let hunks = parse_patch(patch)?;
+        let data = self.data();
+        let mut buffer_lines: Vec<String> = lines(&data).into_iter().map(|line| line.to_string()).collect();
+        let mut failed_hunks = Vec::new();
+        let mut line_delta: isize = 0;
+
+        let in_operation_group = self.operation_group.is_some();
+        self.start_operation_group();
+
+        for (index, hunk) in hunks.iter().enumerate() {
+            let expected_line = cmp::max(hunk.old_start as isize + line_delta, 0) as usize;
+            let location = (0..MAX_FUZZ + 1).filter_map(|fuzz| {
+                let leading = cmp::min(fuzz, hunk.leading_context);
+                let trailing = cmp::min(fuzz, hunk.trailing_context);
+                let old_lines = &hunk.old_lines[leading..hunk.old_lines.len() - trailing];
+
+                find_lines(&buffer_lines, old_lines, expected_line + leading)
+                    .map(|line| (line, leading, trailing))
+            }).next();
+
+            let (line, leading, trailing) = match location {
+                Some(location) => location,
+                None => {
+                    failed_hunks.push(index);
+                    continue;
+                }
+            };
+            let old_lines = &hunk.old_lines[leading..hunk.old_lines.len() - trailing];
+            let new_lines = &hunk.new_lines[leading..hunk.new_lines.len() - trailing];
+
+            let range = Range::new(
+                Position{ line, offset: 0 },
+                self.clamp(Position{ line: line + old_lines.len(), offset: 0 })
+            );
+            self.replace(range, new_lines.concat());
+
+            buffer_lines.splice(line..line + old_lines.len(), new_lines.iter().map(|line| line.to_string()));
+            line_delta = line as isize - (hunk.old_start + leading) as isize +
+                new_lines.len() as isize - old_lines.len() as isize;
+        }
+
+        if !in_operation_group {
+            self.end_operation_group();
+        }
+
+        // Keep the cursor within the bounds of the updated content.
+        let position = self.clamp(*self.cursor);
+        self.cursor.move_to(position);
+
+        Ok(failed_hunks)
+    }
+
+    fn diff_label(&self) -> String {
+        self.path.as_ref()
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "untitled".to_string())
+    }
+}
+
+// Splits content into lines, retaining their newline characters,
+// such that lines lacking them can be distinguished when diffing.
+pub fn lines(content: &str) -> Vec<&str> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+
+    for (index, c) in content.char_indices() {
+        if c == '\n' {
+            lines.push(&content[start..index + 1]);
+            start = index + 1;
+        }
+    }
+    if start < content.len() {
+        lines.push(&content[start..]);
+    }
+
+    lines
+}
+
+fn unified_diff(original: &str, modified: &str, original_label: &str, modified_label: &str, context: usize) -> String {
+    let old_lines = lines(original);
+    let new_lines = lines(modified);
+    let edits = edit_script(&old_lines, &new_lines);
+
+    // Pair each edit with the old/new line indices at which it occurs.
+    let mut indexed_edits = Vec::new();
+    let (mut old_index, mut new_index) = (0, 0);
+    for &edit in edits.iter() {
+        indexed_edits.push((edit, old_index, new_index));
+        match edit {
+            Edit::Equal => { old_index += 1; new_index += 1; },
+            Edit::Delete => old_index += 1,
+            Edit::Insert => new_index += 1,
+        }
+    }
+
+    // Group changes that are close enough to share context lines.
+    let changes: Vec<usize> = indexed_edits.iter()
+        .enumerate()
+        .filter(|&(_, &(edit, _, _))| edit != Edit::Equal)
+        .map(|(index, _)| index)
+        .collect();
+    let mut groups: Vec<(usize, usize)> = Vec::new();
+    for &change in changes.iter() {
+        let start = change.saturating_sub(context);
+        let end = cmp::min(change + context, indexed_edits.len() - 1);
+
+        let merged = match groups.last_mut() {
+            Some(group) if start <= group.1 + 1 => {
+                group.1 = end;
+                true
+            },
+            _ => false,
+        };
+        if !merged {
+            groups.push((start, end));
+        }
+    }
+
+    if groups.is_empty() {
+        return String::new()
+    }
+
+    let mut diff = format!("--- {}\n+++ {}\n", original_label, modified_label);
+    for (start, end) in groups {
+        let group = &indexed_edits[start..end + 1];
+        let old_count = group.iter().filter(|&&(edit, _, _)| edit != Edit::Insert).count();
+        let new_count = group.iter().filter(|&&(edit, _, _)| edit != Edit::Delete).count();
+        let (_, old_start, new_start) = group[0];
+
+        diff.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_count),
+            hunk_range(new_start, new_count)
+        ));
+
+        for &(edit, old_index, new_index) in group {
+            let (prefix, line) = match edit {
+                Edit::Equal => (' ', old_lines[old_index]),
+                Edit::Delete => ('-', old_lines[old_index]),
+                Edit::Insert => ('+', new_lines[new_index]),
+            };
+
+            diff.push(prefix);
+            diff.push_str(line);
+            if !line.ends_with('\n') {
+                diff.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+
+    diff
+}
+
+// Parses the hunks in a unified diff, ignoring any file headers.
+fn parse_patch(patch: &str) -> Result<Vec<PatchHunk>> {
+    let patch_lines = lines(patch);
+    let mut hunks = Vec::new();
+    let mut index = 0;
+
+    while index < patch_lines.len() {
+        if !patch_lines[index].starts_with("@@ ") {
+            index += 1;
+            continue;
+        }
+
+        let (old_start, mut old_count, mut new_count) = parse_hunk_header(patch_lines[index])
+            .ok_or_else(|| ErrorKind::InvalidPatch(index + 1))?;
+        let mut hunk = PatchHunk{
+            old_start,
+            old_lines: Vec::new(),
+            new_lines: Vec::new(),
+            leading_context: 0,
+            trailing_context: 0,
+        };
+        let mut last_kind = None;
+        let mut changed = false;
+        index += 1;
+
+        while index < patch_lines.len() {
+            let line = patch_lines[index];
+            let kind = line.chars().next();
+
+            // Blank context lines are sometimes stripped of their leading space.
+            let content = match kind {
+                Some('\n') | None => line,
+                Some(kind) => &line[kind.len_utf8()..],
+            };
+
+            match kind {
+                Some('\\') => {
+                    // The preceding line is missing its newline.
+                    match last_kind {
+                        Some('-') => strip_newline(hunk.old_lines.last_mut()),
+                        Some('+') => strip_newline(hunk.new_lines.last_mut()),
+                        Some(_) => {
+                            strip_newline(hunk.old_lines.last_mut());
+                            strip_newline(hunk.new_lines.last_mut());
+                        },
+                        None => bail!(ErrorKind::InvalidPatch(index + 1)),
+                    }
+                },
+                _ if old_count == 0 && new_count == 0 => break,
+                Some(' ') | Some('\n') if old_count > 0 && new_count > 0 => {
+                    hunk.old_lines.push(content);
+                    hunk.new_lines.push(content);
+                    if changed {
+                        hunk.trailing_context += 1;
+                    } else {
+                        hunk.leading_context += 1;
+                    }
+                    old_count -= 1;
+                    new_count -= 1;
+                },
+                Some('-') if old_count > 0 => {
+                    hunk.old_lines.push(content);
+                    hunk.trailing_context = 0;
+                    changed = true;
+                    old_count -= 1;
+                },
+                Some('+') if new_count > 0 => {
+                    hunk.new_lines.push(content);
+                    hunk.trailing_context = 0;
+                    changed = true;
+                    new_count -= 1;
+                },
+                _ => bail!(ErrorKind::InvalidPatch(index + 1)),
+            }
+
+            last_kind = kind;
+            index += 1;
+        }
+
+        if old_count > 0 || new_count > 0 {
+            bail!(ErrorKind::InvalidPatch(index + 1))
+        }
+        hunks.push(hunk);
+    }
+
+    Ok(hunks)
+}
+
+// Parses a hunk header's starting (zero-based) old line and line counts.
+fn parse_hunk_header(header: &str) -> Option<(usize, usize, usize)> {
+    let mut ranges = header.trim_end().split(' ').skip(1);
+    let (old_start, old_count) = parse_hunk_range(ranges.next()?, '-')?;
+    let (_, new_count) = parse_hunk_range(ranges.next()?, '+')?;
+
+    // Empty ranges refer to the line preceding them.
+    let old_start = if old_count == 0 { old_start } else { old_start.checked_sub(1)? };
+
+    Some((old_start, old_count, new_count))
+}
+
+fn parse_hunk_range(range: &str, prefix: char) -> Option<(usize, usize)> {
+    if !range.starts_with(prefix) {
+        return None
+    }
+
+    let mut parts = range[1..].splitn(2, ',');
+    let start = parts.next()?.parse().ok()?;
+    let count = match parts.next() {
+        Some(count) => count.parse().ok()?,
+        None => 1,
+    };
+
+    Some((start, count))
+}
+
+fn strip_newline(line: Option<&mut &str>) {
+    if let Some(line) = line {
+        if line.ends_with('\n') {
+            *line = &line[..line.len() - 1];
+        }
+    }
+}
+
+// Finds the specified lines, searching outwards from the expected line.
+fn find_lines(buffer_lines: &[String], lines: &[&str], expected_line: usize) -> Option<usize> {
+    if lines.len() > buffer_lines.len() {
+        return None
+    }
+
+    let last_line = buffer_lines.len() - lines.len();
+    let matches = |line: usize| {
+        buffer_lines[line..line + lines.len()].iter().zip(lines.iter()).all(|(a, b)| a == b)
+    };
+    let expected_line = cmp::min(expected_line, last_line);
+
+    for distance in 0..last_line + 1 {
+        if distance <= expected_line && matches(expected_line - distance) {
+            return Some(expected_line - distance)
+        }
+        if expected_line + distance <= last_line && matches(expected_line + distance) {
+            return Some(expected_line + distance)
+        }
+    }
+
+    None
+}
+
+// Formats a hunk's line range; empty ranges refer to the line preceding them.
+fn hunk_range(start: usize, count: usize) -> String {
+    if count == 0 {
+        format!("{},0", start)
+    } else {
+        format!("{},{}", start + 1, count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::Buffer;
+    #[cfg(feature = "system")]
+    use std::path::Path;
+
+    #[test]
+    fn unified_diff_returns_an_empty_string_for_identical_content() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\n");
+
+        assert_eq!(buffer.unified_diff("scribe\n", 3), "");
+    }
+
+    #[test]
+    fn unified_diff_splits_distant_changes_into_separate_hunks() {
+        let mut buffer = Buffer::new();
+        buffer.insert("a\nB\nc\nd\ne\nf\nG\n");
+
+        assert_eq!(
+            buffer.unified_diff("a\nb\nc\nd\ne\nf\ng\n", 1),
+            "--- untitled\n+++ untitled\n\
+             @@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n\
+             @@ -6,2 +6,2 @@\n f\n-g\n+G\n"
+        );
+    }
+
+    #[test]
+    fn unified_diff_marks_missing_trailing_newlines() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+
+        assert_eq!(
+            buffer.unified_diff("scribe\n", 3),
+            "--- untitled\n+++ untitled\n@@ -1,1 +1,1 @@\n-scribe\n+scribe\n\\ No newline at end of file\n"
+        );
+    }
+
+    #[test]
+    fn unified_diff_uses_empty_ranges_for_insertions_into_empty_content() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\n");
+
+        assert_eq!(
+            buffer.unified_diff("", 3),
+            "--- untitled\n+++ untitled\n@@ -0,0 +1,1 @@\n+scribe\n"
+        );
+    }
+
+    #[test]
+    fn apply_patch_reverses_unified_diff() {
+        let original = "a\nb\nc\nd\ne\nf\ng\nh\ni";
+        let mut buffer = Buffer::new();
+        buffer.insert("a\nB\nc\nd\ne\nf\nh\ni\nj\n");
+        let patch = buffer.unified_diff(original, 1);
+
+        let mut patched_buffer = Buffer::new();
+        patched_buffer.insert(original);
+        assert_eq!(patched_buffer.apply_patch(&patch).unwrap(), Vec::<usize>::new());
+        assert_eq!(patched_buffer.data(), buffer.data());
+    }
+
+    #[test]
+    fn apply_patch_locates_moved_hunks() {
+        let mut buffer = Buffer::new();
+        buffer.insert("x\ny\nscribe\nlibrary\neditor\n");
+        let patch = "@@ -1,3 +1,3 @@\n scribe\n-library\n+text\n editor\n";
+
+        assert_eq!(buffer.apply_patch(patch).unwrap(), Vec::<usize>::new());
+        assert_eq!(buffer.data(), "x\ny\nscribe\ntext\neditor\n");
+    }
+
+    #[test]
+    fn apply_patch_ignores_mismatched_context_with_fuzz() {
+        let mut buffer = Buffer::new();
+        buffer.insert("a\nb\nc\nd\ne\n");
+        let patch = "@@ -1,5 +1,5 @@\n x\n b\n-c\n+C\n d\n y\n";
+
+        assert_eq!(buffer.apply_patch(patch).unwrap(), Vec::<usize>::new());
+        assert_eq!(buffer.data(), "a\nb\nC\nd\ne\n");
+    }
+
+    #[test]
+    fn apply_patch_reports_hunks_that_cannot_be_applied() {
+        let mut buffer = Buffer::new();
+        buffer.insert("a\nb\nc\n");
+        let patch = "@@ -1,1 +1,1 @@\n-x\n+y\n@@ -3,1 +3,1 @@\n-c\n+C\n";
+
+        assert_eq!(buffer.apply_patch(patch).unwrap(), vec![0]);
+        assert_eq!(buffer.data(), "a\nb\nC\n");
+    }
+
+    #[test]
+    fn apply_patch_rejects_truncated_hunks() {
+        let mut buffer = Buffer::new();
+        buffer.insert("a\nb\n");
+
+        assert!(buffer.apply_patch("@@ -1,2 +1,2 @@\n a\n").is_err());
+        assert!(buffer.apply_patch("@@ -1,2 +1,2 @@\n a\n?b\n").is_err());
+        assert_eq!(buffer.data(), "a\nb\n");
+    }
+
+    #[test]
+    fn apply_patch_rejects_lines_with_multibyte_prefixes() {
+        let mut buffer = Buffer::new();
+        buffer.insert("a\nb\n");
+
+        assert!(buffer.apply_patch("@@ -1,2 +1,2 @@\n a\nनीb\n").is_err());
+        assert_eq!(buffer.data(), "a\nb\n");
+    }
+
+    #[test]
+    #[cfg(feature = "system")]
+    fn unified_diff_from_labels_sides_using_buffer_paths() {
+        let original = Buffer::from_file(Path::new("tests/sample/file")).unwrap();
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\n");
+        let diff = buffer.unified_diff_from(&original, 0);
+        let header: Vec<&str> = diff.lines().take(2).collect();
+
+        assert!(header[0].starts_with("--- /") && header[0].ends_with("tests/sample/file"));
+        assert_eq!(header[1], "+++ untitled");
+    }
+}
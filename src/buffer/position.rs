@@ -1,13 +1,14 @@
 use buffer::Distance;
 use std::cmp::{PartialOrd, Ordering};
 use std::default::Default;
-use std::ops::{Add, AddAssign};
+use std::ops::{Add, AddAssign, Sub};
 
 /// A two (zero-based) coordinate value representing a location in a buffer.
 /// The `offset` field is so named to emphasize that positions point to
 /// locations before/after characters, not characters themselves, in an effort
 /// to avoid fencepost errors.
 #[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
 pub struct Position {
     pub line:   usize,
     pub offset: usize,
@@ -61,6 +62,23 @@ impl AddAssign<Distance> for Position {
     }
 }
 
+impl Sub for Position {
+    type Output = Distance;
+
+    /// Calculates the distance from the other position to this one, such
+    /// that adding it to the other position produces this one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the other position follows this one. `Distance::between`
+    /// can be used for positions that may be in either order.
+    fn sub(self, other: Position) -> Self::Output {
+        assert!(other <= self, "subtracted position {:?} follows {:?}", other, self);
+
+        Distance::between(&other, &self)
+    }
+}
+
 impl Position {
     /// Creates a new position with a line/offset of 0.
     ///
@@ -126,4 +144,34 @@ mod tests {
             offset: 7
         });
     }
+
+    #[test]
+    fn sub_produces_a_distance_that_can_be_added_back() {
+        let start = Position{ line: 1, offset: 8 };
+        let end = Position{ line: 4, offset: 3 };
+
+        assert_eq!(start + (end - start), end);
+    }
+
+    #[test]
+    #[should_panic(expected = "follows")]
+    fn sub_panics_if_the_subtracted_position_follows_the_other() {
+        let start = Position{ line: 1, offset: 8 };
+        let end = Position{ line: 4, offset: 3 };
+
+        let _ = start - end;
+    }
+
+    #[cfg(feature = "serialization")]
+    #[test]
+    fn positions_can_be_serialized_and_deserialized() {
+        use serde_json;
+
+        let position = Position{ line: 1, offset: 3 };
+        let json = serde_json::to_string(&position).unwrap();
+        assert_eq!(json, r#"{"line":1,"offset":3}"#);
+
+        let deserialized_position: Position = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized_position, position);
+    }
 }
@@ -2,11 +2,29 @@ use buffer::Position;
 
 /// A two-position type, representing a span of characters.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialization", serde(from = "RangeFields"))]
 pub struct Range {
     start: Position,
     end:   Position,
 }
 
+// Deserialized ranges are built using Range::new,
+// so that their positions are ordered.
+#[cfg(feature = "serialization")]
+#[derive(Deserialize)]
+struct RangeFields {
+    start: Position,
+    end:   Position,
+}
+
+#[cfg(feature = "serialization")]
+impl From<RangeFields> for Range {
+    fn from(fields: RangeFields) -> Range {
+        Range::new(fields.start, fields.end)
+    }
+}
+
 impl Range {
     /// Creates a new buffer range. Checks and swaps arguments
     /// in the event that the end precedes the start.
@@ -89,4 +107,17 @@ mod tests {
         assert_eq!(range.start(), end);
         assert_eq!(range.end(), start);
     }
+
+    #[cfg(feature = "serialization")]
+    #[test]
+    fn deserialize_orders_the_range_positions() {
+        use serde_json;
+
+        let json = r#"{"start":{"line":1,"offset":0},"end":{"line":0,"offset":2}}"#;
+        let range: Range = serde_json::from_str(json).unwrap();
+
+        assert_eq!(range.start(), Position{ line: 0, offset: 2 });
+        assert_eq!(range.end(), Position{ line: 1, offset: 0 });
+        assert_eq!(serde_json::from_str::<Range>(&serde_json::to_string(&range).unwrap()).unwrap(), range);
+    }
 }
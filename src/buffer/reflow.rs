@@ -0,0 +1,200 @@
+//! Commands for re-wrapping prose and comments to a column width.
+
+use buffer::{Buffer, Position, Range};
+use std::cmp;
+use unicode_segmentation::UnicodeSegmentation;
+
+// Line prefixes that are preserved when reflowing content, in order of
+// precedence (longer leaders need to come before their own prefixes).
+const LEADERS: &[&str] = &["///", "//!", "//", "#", "--", ";;", ";", "%", "*", ">"];
+
+impl Buffer {
+    /// Re-wraps the lines spanned by the specified range (including the line
+    /// on which it ends) so that they fit within the specified width, as a
+    /// single undoable operation, and moves the cursor to the end of the
+    /// reflowed content.
+    ///
+    /// The prefix of the first non-blank line (its indentation, followed by a
+    /// comment leader like `// ` or `# `, if present) is detected and removed
+    /// from every line before wrapping, and is added to every produced line.
+    /// Blank lines separate paragraphs, which are reflowed individually.
+    /// Words that don't fit within the width are placed on their own line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{Position, Range};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("    // scribe is a\n    // text editor library");
+    /// buffer.reflow(
+    ///     Range::new(Position{ line: 0, offset: 0 }, Position{ line: 1, offset: 0 }),
+    ///     20
+    /// );
+    ///
+    /// assert_eq!(buffer.data(), "    // scribe is a\n    // text editor\n    // library");
+    /// ```
+    pub fn reflow(&mut self, range: Range, width: usize) {
+        let data = self.data();
+        let buffer_lines: Vec<&str> = data.split('\n').collect();
+        let start_line = range.start().line;
+        if start_line >= buffer_lines.len() {
+            return
+        }
+        let end_line = cmp::min(range.end().line, buffer_lines.len() - 1);
+        let original_lines = &buffer_lines[start_line..end_line + 1];
+
+        let prefix = match original_lines.iter().find(|line| !line.trim().is_empty()) {
+            Some(line) => detect_prefix(line),
+            None => return,
+        };
+
+        // Group the lines' words into paragraphs, separated by blank lines.
+        let mut paragraphs: Vec<Vec<&str>> = vec![Vec::new()];
+        for line in original_lines {
+            let content = strip_prefix(line, &prefix);
+            if content.trim().is_empty() {
+                paragraphs.push(Vec::new());
+            } else if let Some(paragraph) = paragraphs.last_mut() {
+                paragraph.extend(content.split_whitespace());
+            }
+        }
+
+        let blank_line = prefix.trim_end().to_string();
+        let mut updated_lines = Vec::new();
+        for (index, paragraph) in paragraphs.iter().enumerate() {
+            if index > 0 {
+                updated_lines.push(blank_line.clone());
+            }
+            updated_lines.extend(wrap(paragraph, &prefix, width));
+        }
+
+        // Don't add a no-op to the history.
+        if updated_lines.iter().zip(original_lines).all(|(a, b)| a == b) &&
+            updated_lines.len() == original_lines.len() {
+            return
+        }
+
+        let range = Range::new(
+            Position{ line: start_line, offset: 0 },
+            Position{ line: end_line, offset: buffer_lines[end_line].graphemes(true).count() }
+        );
+        self.replace(range, updated_lines.join("\n"));
+
+        let last_line = updated_lines.last().map(|line| line.graphemes(true).count()).unwrap_or(0);
+        self.cursor.move_to(Position{
+            line: start_line + updated_lines.len() - 1,
+            offset: last_line
+        });
+    }
+}
+
+// Returns the line's indentation, followed by its comment
+// leader and the whitespace that follows it, if present.
+fn detect_prefix(line: &str) -> String {
+    let content = line.trim_start();
+    let mut prefix = line[..line.len() - content.len()].to_string();
+
+    if let Some(leader) = LEADERS.iter().find(|leader| content.starts_with(*leader)) {
+        let remainder = &content[leader.len()..];
+        let spacing = remainder.len() - remainder.trim_start().len();
+        prefix.push_str(&content[..leader.len() + spacing]);
+    }
+
+    prefix
+}
+
+// Removes the prefix from the line, falling back to removing its
+// indentation and comment leader if it doesn't match exactly.
+fn strip_prefix<'a>(line: &'a str, prefix: &str) -> &'a str {
+    if line.starts_with(prefix) {
+        return &line[prefix.len()..]
+    }
+
+    let leader = prefix.trim();
+    let content = line.trim_start();
+    if !leader.is_empty() && content.starts_with(leader) {
+        &content[leader.len()..]
+    } else {
+        content
+    }
+}
+
+// Greedily wraps the words into prefixed lines that fit within the width.
+fn wrap(words: &[&str], prefix: &str, width: usize) -> Vec<String> {
+    let prefix_length = prefix.graphemes(true).count();
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut line_length = prefix_length;
+
+    for word in words {
+        let word_length = word.graphemes(true).count();
+        if !line.is_empty() && line_length + 1 + word_length > width {
+            lines.push(format!("{}{}", prefix, line));
+            line.clear();
+            line_length = prefix_length;
+        }
+
+        if !line.is_empty() {
+            line.push(' ');
+            line_length += 1;
+        }
+        line.push_str(word);
+        line_length += word_length;
+    }
+
+    if !line.is_empty() {
+        lines.push(format!("{}{}", prefix, line));
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, Position, Range};
+
+    fn line_span(start: usize, end: usize) -> Range {
+        Range::new(Position{ line: start, offset: 0 }, Position{ line: end, offset: 0 })
+    }
+
+    #[test]
+    fn reflow_joins_short_lines_and_preserves_paragraphs() {
+        let mut buffer = Buffer::new();
+        buffer.insert("# scribe\n# is a\n#\n# text editor\n# library");
+        buffer.reflow(line_span(0, 4), 80);
+
+        assert_eq!(buffer.data(), "# scribe is a\n#\n# text editor library");
+        assert_eq!(*buffer.cursor, Position{ line: 2, offset: 21 });
+    }
+
+    #[test]
+    fn reflow_places_long_words_on_their_own_line() {
+        let mut buffer = Buffer::new();
+        buffer.insert("a scribelibrary b");
+        buffer.reflow(line_span(0, 0), 5);
+
+        assert_eq!(buffer.data(), "a\nscribelibrary\nb");
+    }
+
+    #[test]
+    fn reflow_handles_lines_without_a_prefix() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe is a text editor library");
+        buffer.reflow(line_span(0, 0), 15);
+
+        assert_eq!(buffer.data(), "scribe is a\ntext editor\nlibrary");
+    }
+
+    #[test]
+    fn reflow_is_undone_as_a_single_operation() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+        buffer.reflow(line_span(0, 1), 80);
+        assert_eq!(buffer.data(), "scribe library");
+
+        buffer.undo();
+        assert_eq!(buffer.data(), "scribe\nlibrary");
+    }
+}
@@ -0,0 +1,318 @@
+//! Yanking content into, and pasting it from, registers.
+
+use buffer::{Buffer, Distance, Position, Range};
+use registers::{Register, RegisterKind};
+use unicode_segmentation::UnicodeSegmentation;
+
+impl Buffer {
+    /// Reads the specified range into a register of the specified kind.
+    /// Linewise registers hold the entirety of the lines the range spans,
+    /// and blockwise registers hold the columns between the range's start
+    /// and end offsets on each of those lines. Returns `None` if the range
+    /// is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::{Buffer, RegisterKind};
+    /// use scribe::buffer::{Position, Range};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\nlibrary\neditor");
+    /// let range = Range::new(Position{ line: 0, offset: 1 }, Position{ line: 1, offset: 3 });
+    ///
+    /// assert_eq!(buffer.yank(&range, RegisterKind::Characterwise).unwrap().content, "cribe\nlib");
+    /// assert_eq!(buffer.yank(&range, RegisterKind::Linewise).unwrap().content, "scribe\nlibrary\n");
+    /// assert_eq!(buffer.yank(&range, RegisterKind::Blockwise).unwrap().content, "cr\nib");
+    /// ```
+    pub fn yank(&self, range: &Range, kind: RegisterKind) -> Option<Register> {
+        let (start, end) = (range.start(), range.end());
+        if !self.is_valid(&start) || !self.is_valid(&end) {
+            return None
+        }
+
+        let content =
+            match kind {
+                RegisterKind::Characterwise => self.read(range)?,
+                RegisterKind::Linewise => {
+                    let data = self.data();
+                    let mut content = String::new();
+                    for line in data.split('\n').skip(start.line).take(end.line - start.line + 1) {
+                        content.push_str(line);
+                        content.push('\n');
+                    }
+
+                    content
+                },
+                RegisterKind::Blockwise => {
+                    let data = self.data();
+                    let (left, right) =
+                        if start.offset < end.offset {
+                            (start.offset, end.offset)
+                        } else {
+                            (end.offset, start.offset)
+                        };
+
+                    let rows: Vec<String> = data.split('\n')
+                        .skip(start.line)
+                        .take(end.line - start.line + 1)
+                        .map(|line| line.graphemes(true).skip(left).take(right - left).collect())
+                        .collect();
+
+                    rows.join("\n")
+                },
+            };
+
+        Some(Register::new(content, kind))
+    }
+
+    /// Pastes a register's content as a single undoable operation.
+    /// Characterwise content is inserted at the cursor, which is moved to
+    /// its end. Linewise content is inserted above the cursor's line, with
+    /// the cursor moved to its start. Blockwise content is inserted at the
+    /// cursor's offset on it and successive lines (padding short lines with
+    /// spaces and adding lines as needed), leaving the cursor in place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::{Buffer, Register, RegisterKind};
+    /// use scribe::buffer::Position;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\nlibrary");
+    /// buffer.cursor.move_to(Position{ line: 0, offset: 1 });
+    /// buffer.paste(&Register::new("12\n34\n56", RegisterKind::Blockwise));
+    ///
+    /// assert_eq!(buffer.data(), "s12cribe\nl34ibrary\n 56");
+    ///
+    /// buffer.undo();
+    /// assert_eq!(buffer.data(), "scribe\nlibrary");
+    /// ```
+    pub fn paste(&mut self, register: &Register) {
+        let position = *self.cursor;
+
+        match register.kind {
+            RegisterKind::Characterwise => {
+                self.insert(register.content.clone());
+                self.cursor.move_to(position + Distance::of_str(&register.content));
+            },
+            RegisterKind::Linewise => {
+                let mut content = register.content.clone();
+                if !content.ends_with('\n') {
+                    content.push('\n');
+                }
+
+                let start = Position{ line: position.line, offset: 0 };
+                self.cursor.move_to(start);
+                self.insert(content);
+                self.cursor.move_to(start);
+            },
+            RegisterKind::Blockwise => self.paste_block(&register.content, position),
+        }
+    }
+
+    /// Pastes a register's content (see `paste`), first adjusting the
+    /// indentation of its lines to match that of the cursor's line. Lines keep
+    /// their indentation relative to the least indented of them, expressed
+    /// using the buffer's unit of indentation. For characterwise content, the
+    /// first line (which is pasted at the cursor) is left as-is and excluded
+    /// from the comparison. Blockwise content is pasted unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::{Buffer, Register, RegisterKind};
+    /// use scribe::buffer::Position;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("fn main() {\n    scribe();\n}");
+    /// buffer.cursor.move_to(Position{ line: 1, offset: 4 });
+    /// buffer.paste_reindented(&Register::new("\tif ready {\n\t\tlibrary();\n\t}\n", RegisterKind::Linewise));
+    ///
+    /// assert_eq!(buffer.data(), "fn main() {\n    if ready {\n        library();\n    }\n    scribe();\n}");
+    /// ```
+    pub fn paste_reindented(&mut self, register: &Register) {
+        if register.kind == RegisterKind::Blockwise {
+            return self.paste(register)
+        }
+
+        let current_line = self.line(self.cursor.line).unwrap_or_default();
+        let target = &current_line[..current_line.len() - current_line.trim_start_matches(is_indentation).len()];
+        let width = ::std::cmp::max(self.indentation.width, 1);
+        let unit = self.indentation.unit();
+
+        // Lines pasted at the cursor keep their existing indentation.
+        let skipped_lines = match register.kind {
+            RegisterKind::Characterwise => 1,
+            _ => 0,
+        };
+        let lines: Vec<&str> = register.content.split('\n').collect();
+        let base = lines.iter()
+            .skip(skipped_lines)
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| indentation_columns(line, width))
+            .min()
+            .unwrap_or(0);
+
+        let reindented_lines: Vec<String> = lines.iter().enumerate().map(|(index, line)| {
+            if index < skipped_lines || line.trim().is_empty() {
+                return line.to_string()
+            }
+
+            let excess = indentation_columns(line, width) - base;
+            format!(
+                "{}{}{}{}",
+                target,
+                unit.repeat(excess / width),
+                " ".repeat(excess % width),
+                line.trim_start_matches(is_indentation)
+            )
+        }).collect();
+
+        self.paste(&Register::new(reindented_lines.join("\n"), register.kind));
+    }
+
+    // Inserts each row of a block at the specified position's offset on
+    // successive lines, replacing the affected lines in a single operation.
+    fn paste_block(&mut self, content: &str, position: Position) {
+        let data = self.data();
+        let lines: Vec<&str> = data.split('\n').collect();
+        let rows: Vec<&str> = content.split('\n').collect();
+
+        let mut new_lines = Vec::new();
+        for (index, row) in rows.iter().enumerate() {
+            let line = lines.get(position.line + index).cloned().unwrap_or("");
+            let graphemes: Vec<&str> = line.graphemes(true).collect();
+            let mut new_line = String::new();
+
+            if graphemes.len() < position.offset {
+                new_line.push_str(line);
+                new_line.push_str(&" ".repeat(position.offset - graphemes.len()));
+                new_line.push_str(row);
+            } else {
+                new_line.push_str(&graphemes[..position.offset].concat());
+                new_line.push_str(row);
+                new_line.push_str(&graphemes[position.offset..].concat());
+            }
+
+            new_lines.push(new_line);
+        }
+
+        // Replace the existing lines the block overlaps; any
+        // remaining rows are added as new lines after them.
+        let last_line = ::std::cmp::min(position.line + rows.len(), lines.len()) - 1;
+        let range = Range::new(
+            Position{ line: position.line, offset: 0 },
+            Position{ line: last_line, offset: lines[last_line].graphemes(true).count() }
+        );
+        self.replace(range, new_lines.join("\n"));
+        self.cursor.move_to(position);
+    }
+}
+
+fn is_indentation(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+// Measures a line's leading whitespace in columns,
+// with tabs advancing to the next tab stop.
+fn indentation_columns(line: &str, width: usize) -> usize {
+    line.chars().take_while(|&c| is_indentation(c)).fold(0, |columns, c| {
+        if c == '\t' {
+            (columns / width + 1) * width
+        } else {
+            columns + 1
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, Indentation, IndentationStyle, Position, Range};
+    use registers::{Register, RegisterKind};
+
+    #[test]
+    fn yank_returns_none_for_out_of_bounds_ranges() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        let range = Range::new(Position{ line: 0, offset: 0 }, Position{ line: 1, offset: 0 });
+
+        assert!(buffer.yank(&range, RegisterKind::Linewise).is_none());
+    }
+
+    #[test]
+    fn yank_blockwise_handles_reversed_offsets_and_short_lines() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nli\neditor");
+        let range = Range::new(Position{ line: 0, offset: 4 }, Position{ line: 2, offset: 2 });
+
+        assert_eq!(buffer.yank(&range, RegisterKind::Blockwise).unwrap().content, "ri\n\nit");
+    }
+
+    #[test]
+    fn paste_characterwise_moves_the_cursor_to_the_end_of_the_content() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        buffer.paste(&Register::new("library\ntext ", RegisterKind::Characterwise));
+
+        assert_eq!(buffer.data(), "library\ntext scribe");
+        assert_eq!(*buffer.cursor, Position{ line: 1, offset: 5 });
+    }
+
+    #[test]
+    fn paste_linewise_inserts_content_above_the_current_line() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\neditor");
+        buffer.cursor.move_to(Position{ line: 1, offset: 3 });
+        buffer.paste(&Register::new("library", RegisterKind::Linewise));
+
+        assert_eq!(buffer.data(), "scribe\nlibrary\neditor");
+        assert_eq!(*buffer.cursor, Position{ line: 1, offset: 0 });
+    }
+
+    #[test]
+    fn paste_blockwise_is_undone_as_a_single_operation() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary\neditor");
+        buffer.cursor.move_to(Position{ line: 1, offset: 0 });
+        buffer.paste(&Register::new("a\nb", RegisterKind::Blockwise));
+        assert_eq!(buffer.data(), "scribe\nalibrary\nbeditor");
+        assert_eq!(*buffer.cursor, Position{ line: 1, offset: 0 });
+
+        buffer.undo();
+        assert_eq!(buffer.data(), "scribe\nlibrary\neditor");
+    }
+
+    #[test]
+    fn paste_reindented_rebases_characterwise_content_after_its_first_line() {
+        let mut buffer = Buffer::new();
+        buffer.insert("\tscribe(\n");
+        buffer.cursor.move_to(Position{ line: 0, offset: 8 });
+        buffer.paste_reindented(&Register::new("library,\n        editor,\n\n      text)", RegisterKind::Characterwise));
+
+        assert_eq!(buffer.data(), "\tscribe(library,\n\t  editor,\n\n\ttext)\n");
+        assert_eq!(*buffer.cursor, Position{ line: 3, offset: 6 });
+    }
+
+    #[test]
+    fn paste_reindented_uses_the_buffers_indentation_style() {
+        let mut buffer = Buffer::new();
+        buffer.set_indentation(Indentation{ style: IndentationStyle::Tabs, width: 2 });
+        buffer.insert("\tscribe");
+        buffer.paste_reindented(&Register::new("library\n    editor", RegisterKind::Linewise));
+
+        assert_eq!(buffer.data(), "\tlibrary\n\t\t\teditor\n\tscribe");
+    }
+
+    #[test]
+    fn paste_reindented_is_undone_as_a_single_operation() {
+        let mut buffer = Buffer::new();
+        buffer.insert("  scribe");
+        buffer.paste_reindented(&Register::new("library\neditor", RegisterKind::Linewise));
+        assert_eq!(buffer.data(), "  library\n  editor\n  scribe");
+
+        buffer.undo();
+        assert_eq!(buffer.data(), "  scribe");
+    }
+}
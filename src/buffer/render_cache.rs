@@ -0,0 +1,238 @@
+//! Caching of styled (tokenized and themed) lines for rendering.
+
+use buffer::{Buffer, LineRange, Scope, SemanticTokenIterator, Token};
+use errors::*;
+use std::cmp;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+use syntect::highlighting::{Highlighter, Style, Theme};
+
+/// A span of a line's content, along with its resolved theme style.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StyledSpan {
+    pub value: String,
+    pub style: Style,
+
+    /// The grapheme offset at which the span starts.
+    pub offset: usize,
+}
+
+/// A line's content, broken into styled spans.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StyledLine {
+    pub spans: Vec<StyledSpan>,
+}
+
+/// Styled lines, keyed by their line number and content hash, for a single
+/// theme and syntax definition. Since a line's styling can depend on the
+/// lines preceding it (e.g. an unterminated block comment), changes to the
+/// buffer invalidate all lines at or after the change.
+#[derive(Default)]
+pub struct RenderCache {
+    theme_id: Option<String>,
+    syntax: Option<Scope>,
+    lines: Vec<Option<(u64, Rc<StyledLine>)>>,
+}
+
+impl RenderCache {
+    /// Discards cached lines at or after the specified line.
+    pub fn invalidate(&mut self, line: usize) {
+        self.lines.truncate(line);
+    }
+
+    // Returns the cached line, provided its content hasn't changed.
+    fn get(&self, line: usize, hash: u64) -> Option<Rc<StyledLine>> {
+        match self.lines.get(line) {
+            Some(&Some((cached_hash, ref styled_line))) if cached_hash == hash => {
+                Some(styled_line.clone())
+            },
+            _ => None,
+        }
+    }
+
+    fn set(&mut self, line: usize, hash: u64, styled_line: Rc<StyledLine>) {
+        if self.lines.len() <= line {
+            self.lines.resize(line + 1, None);
+        }
+        self.lines[line] = Some((hash, styled_line));
+    }
+}
+
+impl Buffer {
+    /// Returns the styled lines in the specified range (excluding the line on
+    /// which it ends, as well as lines beyond the end of the buffer), using
+    /// the specified theme, which is identified by `theme_id`. Lines are
+    /// cached, such that lines unchanged since the last call (using the same
+    /// theme and syntax definition) are returned without tokenizing or styling
+    /// them. Using a different theme ID discards all cached lines. The buffer's
+    /// semantic tokens (see `set_semantic_tokens`) are merged into its syntax
+    /// highlighting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate scribe;
+    /// # extern crate syntect;
+    /// use scribe::Buffer;
+    /// use scribe::buffer::LineRange;
+    /// use syntect::highlighting::ThemeSet;
+    /// # use syntect::parsing::SyntaxSet;
+    ///
+    /// # fn main() {
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("fn main() {\n}");
+    ///
+    /// // Omitted code to set up the buffer's syntax definition.
+    /// # let syntax_set = SyntaxSet::load_defaults_newlines();
+    /// # buffer.syntax_definition = syntax_set.find_syntax_by_extension("rs").cloned();
+    ///
+    /// let theme_set = ThemeSet::load_defaults();
+    /// let theme = &theme_set.themes["base16-ocean.dark"];
+    /// let lines = buffer.styled_lines(&LineRange::new(0, 2), theme, "base16-ocean.dark").unwrap();
+    ///
+    /// assert_eq!(lines[0].spans[0].value, "fn");
+    /// # }
+    /// ```
+    pub fn styled_lines(&self, lines: &LineRange, theme: &Theme, theme_id: &str) -> Result<Vec<Rc<StyledLine>>> {
+        let tokens = self.tokens()?;
+        let syntax = self.syntax_definition.as_ref().map(|def| def.scope);
+        let data = self.data();
+        let hashes: Vec<u64> = data.split('\n').map(|line| {
+            let mut hasher = DefaultHasher::new();
+            line.hash(&mut hasher);
+            hasher.finish()
+        }).collect();
+        let end_line = cmp::min(lines.end(), hashes.len());
+        if lines.start() >= end_line {
+            return Ok(Vec::new())
+        }
+
+        let mut cache = self.render_cache.borrow_mut();
+        if cache.theme_id.as_ref().map(|id| id.as_str()) != Some(theme_id) || cache.syntax != syntax {
+            *cache = RenderCache{
+                theme_id: Some(theme_id.to_string()),
+                syntax,
+                lines: Vec::new(),
+            };
+        }
+
+        let missing = (lines.start()..end_line).any(|line| cache.get(line, hashes[line]).is_none());
+        if missing {
+            // Tokenizing depends on the preceding lines' state,
+            // so we need to start at the beginning of the buffer.
+            let highlighter = Highlighter::new(theme);
+            let mut current_line = 0;
+            let mut styled_line = StyledLine::default();
+
+            for token in SemanticTokenIterator::new(tokens.iter(), self.semantic_tokens()) {
+                match token {
+                    Token::Newline => {
+                        if cache.get(current_line, hashes[current_line]).is_none() {
+                            cache.set(current_line, hashes[current_line], Rc::new(styled_line));
+                        }
+                        styled_line = StyledLine::default();
+                        current_line += 1;
+
+                        if current_line >= end_line {
+                            break;
+                        }
+                    },
+                    Token::Lexeme(lexeme) => {
+                        styled_line.spans.push(StyledSpan{
+                            value: lexeme.value.to_string(),
+                            style: highlighter.style_for_stack(lexeme.scope.as_slice()),
+                            offset: lexeme.position.offset,
+                        });
+                    },
+                }
+            }
+
+            // The last line isn't followed by a newline token.
+            if current_line < end_line && cache.get(current_line, hashes[current_line]).is_none() {
+                cache.set(current_line, hashes[current_line], Rc::new(styled_line));
+            }
+        }
+
+        Ok((lines.start()..end_line).map(|line| {
+            cache.get(line, hashes[line]).unwrap_or_default()
+        }).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, LineRange, Position, Scope};
+    use std::rc::Rc;
+    use syntect::highlighting::ThemeSet;
+    use syntect::parsing::SyntaxSet;
+
+    fn buffer_with_syntax(data: &str, extension: &str) -> Buffer {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let mut buffer = Buffer::new();
+        buffer.insert(data);
+        buffer.syntax_definition = syntax_set.find_syntax_by_extension(extension).cloned();
+
+        buffer
+    }
+
+    #[test]
+    fn styled_lines_reuses_unchanged_lines() {
+        let buffer = buffer_with_syntax("fn main() {\n}", "rs");
+        let theme_set = ThemeSet::load_defaults();
+        let theme = &theme_set.themes["base16-ocean.dark"];
+
+        let first = buffer.styled_lines(&LineRange::new(0, 2), theme, "dark").unwrap();
+        let second = buffer.styled_lines(&LineRange::new(0, 2), theme, "dark").unwrap();
+        assert!(Rc::ptr_eq(&first[0], &second[0]));
+        assert!(Rc::ptr_eq(&first[1], &second[1]));
+    }
+
+    #[test]
+    fn styled_lines_restyles_lines_following_a_change() {
+        let mut buffer = buffer_with_syntax("scribe\nlibrary\neditor", "rs");
+        let theme_set = ThemeSet::load_defaults();
+        let theme = &theme_set.themes["base16-ocean.dark"];
+
+        let original = buffer.styled_lines(&LineRange::new(0, 3), theme, "dark").unwrap();
+        buffer.cursor.move_to(Position{ line: 1, offset: 0 });
+        buffer.insert("/* ");
+        let updated = buffer.styled_lines(&LineRange::new(0, 3), theme, "dark").unwrap();
+
+        assert!(Rc::ptr_eq(&original[0], &updated[0]));
+        assert_ne!(original[2].spans[0].style, updated[2].spans[0].style);
+    }
+
+    #[test]
+    fn styled_lines_discards_lines_styled_with_another_theme() {
+        let buffer = buffer_with_syntax("fn main() {}", "rs");
+        let theme_set = ThemeSet::load_defaults();
+
+        let dark = buffer.styled_lines(&LineRange::new(0, 1), &theme_set.themes["base16-ocean.dark"], "dark").unwrap();
+        let light = buffer.styled_lines(&LineRange::new(0, 1), &theme_set.themes["InspiredGitHub"], "light").unwrap();
+        assert_ne!(dark[0].spans[0].style, light[0].spans[0].style);
+    }
+
+    #[test]
+    fn styled_lines_includes_semantic_tokens() {
+        let mut buffer = buffer_with_syntax("scribe", "rs");
+        let theme_set = ThemeSet::load_defaults();
+        let theme = &theme_set.themes["base16-ocean.dark"];
+
+        let original = buffer.styled_lines(&LineRange::new(0, 1), theme, "dark").unwrap();
+        buffer.set_semantic_tokens(&[0, 0, 6, 0, 0], &[Scope::new("keyword").unwrap()]);
+        let updated = buffer.styled_lines(&LineRange::new(0, 1), theme, "dark").unwrap();
+
+        assert_ne!(original[0].spans[0].style, updated[0].spans[0].style);
+    }
+
+    #[test]
+    fn styled_lines_skips_lines_beyond_the_end_of_the_buffer() {
+        let buffer = buffer_with_syntax("scribe\n", "rs");
+        let theme_set = ThemeSet::load_defaults();
+        let lines = buffer.styled_lines(&LineRange::new(0, 5), &theme_set.themes["base16-ocean.dark"], "dark").unwrap();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].spans.is_empty());
+    }
+}
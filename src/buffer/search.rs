@@ -0,0 +1,202 @@
+//! Literal (exact string) search, without regular expression overhead.
+
+use buffer::{Buffer, Position};
+use memchr::memchr2;
+use memchr::memmem::Finder;
+use unicode_segmentation::UnicodeSegmentation;
+
+impl Buffer {
+    /// Searches the buffer for (and returns positions
+    /// associated with) occurrences of `needle`.
+    /// Matches are found within individual lines, and may overlap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::Position;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\nlibrary");
+    ///
+    /// assert_eq!(
+    ///     buffer.search("ib"),
+    ///     vec![
+    ///         Position{ line: 0, offset: 3 },
+    ///         Position{ line: 1, offset: 1 }
+    ///     ]
+    /// );
+    /// ```
+    pub fn search(&self, needle: &str) -> Vec<Position> {
+        let finder = Finder::new(needle);
+
+        self.search_lines(needle, |haystack| finder.find(haystack))
+    }
+
+    /// Searches the buffer like `search`, treating ASCII letters in the
+    /// needle and buffer as equal regardless of their case. Other characters
+    /// must match exactly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::Position;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("Scribe\nSCRIBE library");
+    ///
+    /// assert_eq!(
+    ///     buffer.search_ignoring_ascii_case("scribe"),
+    ///     vec![
+    ///         Position{ line: 0, offset: 0 },
+    ///         Position{ line: 1, offset: 0 }
+    ///     ]
+    /// );
+    /// ```
+    pub fn search_ignoring_ascii_case(&self, needle: &str) -> Vec<Position> {
+        let needle = needle.to_ascii_lowercase();
+
+        self.search_lines(&needle, |haystack| find_ignoring_ascii_case(haystack, needle.as_bytes()))
+    }
+
+    // Searches each line using the callback, which finds the byte offset of
+    // the first match in its haystack, reporting matches that start on
+    // grapheme boundaries as positions.
+    fn search_lines<F>(&self, needle: &str, mut find: F) -> Vec<Position>
+        where F: FnMut(&[u8]) -> Option<usize> {
+        let mut results = Vec::new();
+        if needle.is_empty() {
+            return results
+        }
+
+        let lines = self.lines();
+        for (line_number, line) in lines.iter() {
+            let content = line.strip_suffix('\n').unwrap_or(&line);
+
+            // Find all (potentially overlapping) matches on the line.
+            let mut match_offsets = Vec::new();
+            let mut start = 0;
+            while let Some(offset) = find(&content.as_bytes()[start..]) {
+                match_offsets.push(start + offset);
+                start += offset + 1;
+            }
+            if match_offsets.is_empty() {
+                continue
+            }
+
+            // Report offsets in graphemes, like all other buffer positions.
+            let mut match_offsets = match_offsets.into_iter().peekable();
+            for (offset, (index, _)) in content.grapheme_indices(true).enumerate() {
+                while match_offsets.peek().map(|&match_offset| match_offset < index).unwrap_or(false) {
+                    match_offsets.next();
+                }
+
+                match match_offsets.peek() {
+                    Some(&match_offset) if match_offset == index => {
+                        results.push(Position{ line: line_number, offset });
+                    },
+                    Some(_) => (),
+                    None => break,
+                }
+            }
+        }
+
+        results
+    }
+}
+
+// Finds the first occurrence of the (lowercase) needle in the haystack,
+// ignoring the case of ASCII letters. Candidates are located by scanning for
+// either case of the needle's first byte, and then compared in full.
+fn find_ignoring_ascii_case(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if haystack.len() < needle.len() {
+        return None
+    }
+
+    let first_byte = needle[0];
+    let candidates = &haystack[..haystack.len() - needle.len() + 1];
+    let mut start = 0;
+
+    while let Some(offset) = memchr2(first_byte, first_byte.to_ascii_uppercase(), &candidates[start..]) {
+        let candidate = start + offset;
+        if haystack[candidate..candidate + needle.len()].eq_ignore_ascii_case(needle) {
+            return Some(candidate)
+        }
+
+        start = candidate + 1;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_ignoring_ascii_case;
+    use buffer::{Buffer, Position};
+
+    #[test]
+    fn search_includes_overlapping_matches() {
+        let mut buffer = Buffer::new();
+        buffer.insert("aaa");
+
+        assert_eq!(buffer.search("aa"), vec![
+            Position{ line: 0, offset: 0 },
+            Position{ line: 0, offset: 1 }
+        ]);
+    }
+
+    #[test]
+    fn search_finds_matches_straddling_the_gap() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe library");
+        buffer.cursor.move_to(Position{ line: 0, offset: 3 });
+        buffer.insert("i");
+        buffer.delete();
+
+        assert_eq!(buffer.search("scribe"), vec![Position{ line: 0, offset: 0 }]);
+    }
+
+    #[test]
+    fn search_does_not_match_within_grapheme_clusters() {
+        let mut buffer = Buffer::new();
+        buffer.insert("e\u{301} e");
+
+        assert_eq!(buffer.search("\u{301}"), Vec::new());
+        assert_eq!(buffer.search("e"), vec![
+            Position{ line: 0, offset: 0 },
+            Position{ line: 0, offset: 2 }
+        ]);
+    }
+
+    #[test]
+    fn search_does_not_match_across_lines() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+
+        assert!(buffer.search("scribe\nlibrary").is_empty());
+    }
+
+    #[test]
+    fn search_returns_nothing_for_empty_needles() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+
+        assert!(buffer.search("").is_empty());
+        assert!(buffer.search_ignoring_ascii_case("").is_empty());
+    }
+
+    #[test]
+    fn search_ignoring_ascii_case_matches_non_ascii_characters_exactly() {
+        let mut buffer = Buffer::new();
+        buffer.insert("SCRIBÉ scribé");
+
+        assert_eq!(buffer.search_ignoring_ascii_case("Scribé"), vec![Position{ line: 0, offset: 7 }]);
+    }
+
+    #[test]
+    fn find_ignoring_ascii_case_handles_needles_longer_than_the_haystack() {
+        assert_eq!(find_ignoring_ascii_case(b"scr", b"scribe"), None);
+        assert_eq!(find_ignoring_ascii_case(b"a SCRIBE", b"scribe"), Some(2));
+    }
+}
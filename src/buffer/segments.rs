@@ -0,0 +1,300 @@
+//! Segmentation of long lines for windowed rendering.
+
+use buffer::Lines;
+#[cfg(feature = "syntax")]
+use buffer::{StyledLine, StyledSpan};
+use buffer::columns::grapheme_width;
+use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A portion of a line, starting at the specified grapheme offset.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineSegment {
+    pub offset: usize,
+    pub content: String,
+}
+
+/// An iterator over a line's segments, each of which (apart from the last)
+/// fills, without exceeding, the same number of display columns (a grapheme
+/// wider than that is given a segment of its own). Tabs are expanded to the
+/// next multiple of the tab width, relative to the start of the line.
+/// Skipping segments (e.g. using `nth`) doesn't allocate their content.
+pub struct LineSegments<'a> {
+    line: Cow<'a, str>,
+    byte_offset: usize,
+    offset: usize,
+    column: usize,
+    width: usize,
+    tab_width: usize,
+}
+
+impl<'a> LineSegments<'a> {
+    // Finds the end of the segment starting at the current position,
+    // returning its byte offset, and the number of graphemes and the
+    // display column that the segment ends at.
+    fn segment_end(&self) -> (usize, usize, usize) {
+        let mut length = 0;
+        let mut column = self.column;
+
+        for (index, grapheme) in self.line[self.byte_offset..].grapheme_indices(true) {
+            let grapheme_width = grapheme_width(grapheme, column, self.tab_width);
+            if length > 0 && column + grapheme_width - self.column > self.width {
+                return (self.byte_offset + index, length, column)
+            }
+
+            length += 1;
+            column += grapheme_width;
+        }
+
+        (self.line.len(), length, column)
+    }
+
+    // Moves past the current segment, returning the byte offset it ends at.
+    fn advance(&mut self) -> usize {
+        let (end, length, column) = self.segment_end();
+        self.byte_offset = end;
+        self.offset += length;
+        self.column = column;
+
+        end
+    }
+}
+
+impl<'a> Iterator for LineSegments<'a> {
+    type Item = LineSegment;
+
+    fn next(&mut self) -> Option<LineSegment> {
+        if self.byte_offset >= self.line.len() {
+            return None
+        }
+
+        let (start, offset) = (self.byte_offset, self.offset);
+        let end = self.advance();
+
+        Some(LineSegment{ offset, content: self.line[start..end].to_string() })
+    }
+
+    fn nth(&mut self, n: usize) -> Option<LineSegment> {
+        for _ in 0..n {
+            if self.byte_offset >= self.line.len() {
+                return None
+            }
+            self.advance();
+        }
+
+        self.next()
+    }
+}
+
+impl<'a> Lines<'a> {
+    /// Returns the specified line's segments (see `LineSegments`), starting
+    /// at the specified grapheme offset (e.g. that of the first segment in a
+    /// renderer's window), without copying the rest of the line. Segments are
+    /// `width` columns wide, and lines that are no wider than `threshold`
+    /// columns are returned as a single segment. Returns `None` if the line
+    /// doesn't exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::LineSegment;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe library");
+    ///
+    /// let lines = buffer.lines();
+    /// let mut segments = lines.segments(0, 4, 10, 4, 4).unwrap();
+    /// assert_eq!(
+    ///     segments.nth(1),
+    ///     Some(LineSegment{ offset: 8, content: "ibra".to_string() })
+    /// );
+    /// assert_eq!(
+    ///     segments.next(),
+    ///     Some(LineSegment{ offset: 12, content: "ry".to_string() })
+    /// );
+    /// assert_eq!(segments.next(), None);
+    /// ```
+    pub fn segments(&self, line: usize, offset: usize, threshold: usize, width: usize, tab_width: usize) -> Option<LineSegments> {
+        let line = self.line(line)?;
+
+        // Only measure as much of the line as is needed to compare it to the threshold.
+        let mut column = 0;
+        let exceeds_threshold = line.graphemes(true).any(|grapheme| {
+            column += grapheme_width(grapheme, column, tab_width);
+            column > threshold
+        });
+
+        let mut segments = LineSegments{
+            byte_offset: 0,
+            offset: 0,
+            column: 0,
+            width: if exceeds_threshold { width } else { usize::max_value() },
+            tab_width,
+            line,
+        };
+
+        // Move to the requested offset (or the end of the line).
+        let mut start = segments.line.len();
+        for (index, grapheme) in segments.line.grapheme_indices(true) {
+            if segments.offset == offset {
+                start = index;
+                break;
+            }
+            segments.offset += 1;
+            segments.column += grapheme_width(grapheme, segments.column, tab_width);
+        }
+        segments.byte_offset = start;
+
+        Some(segments)
+    }
+}
+
+#[cfg(feature = "syntax")]
+impl StyledLine {
+    /// Returns the line's styled spans covering the specified segment (e.g.
+    /// one produced by `Lines::segments` for the same line), split at the
+    /// segment's boundaries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate scribe;
+    /// # extern crate syntect;
+    /// use scribe::buffer::{LineSegment, StyledLine, StyledSpan};
+    /// use syntect::highlighting::{Color, FontStyle, Style};
+    ///
+    /// # fn main() {
+    /// let style = Style{ foreground: Color::BLACK, background: Color::WHITE, font_style: FontStyle::empty() };
+    /// let span = |value: &str, offset| StyledSpan{ value: value.to_string(), style, offset };
+    /// let line = StyledLine{ spans: vec![span("scribe", 0), span(" ", 6), span("library", 7)] };
+    /// let segment = LineSegment{ offset: 4, content: "be l".to_string() };
+    ///
+    /// assert_eq!(line.segment(&segment), vec![span("be", 4), span(" ", 6), span("l", 7)]);
+    /// # }
+    /// ```
+    pub fn segment(&self, segment: &LineSegment) -> Vec<StyledSpan> {
+        let start = segment.offset;
+        let end = start + segment.content.graphemes(true).count();
+
+        // Spans are ordered by offset, so skip those preceding the segment.
+        let first_span = self.spans.partition_point(|span| span.offset <= start).saturating_sub(1);
+
+        self.spans[first_span..].iter()
+            .take_while(|span| span.offset < end)
+            .filter_map(|span| {
+                let span_start = span.offset.max(start);
+                let value: String = span.value.graphemes(true)
+                    .skip(span_start - span.offset)
+                    .take(end - span_start)
+                    .collect();
+
+                if value.is_empty() {
+                    None
+                } else {
+                    Some(StyledSpan{ value, style: span.style, offset: span_start })
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, LineSegment};
+    #[cfg(feature = "syntax")]
+    use buffer::LineRange;
+    #[cfg(feature = "syntax")]
+    use syntect::highlighting::ThemeSet;
+    #[cfg(feature = "syntax")]
+    use syntect::parsing::SyntaxSet;
+
+    #[test]
+    fn segments_does_not_split_lines_within_the_threshold() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+        let segments: Vec<LineSegment> = buffer.lines().segments(1, 0, 7, 2, 4).unwrap().collect();
+
+        assert_eq!(segments, vec![LineSegment{ offset: 0, content: "library".to_string() }]);
+    }
+
+    #[test]
+    fn segments_uses_grapheme_offsets() {
+        let mut buffer = Buffer::new();
+        buffer.insert("नीनीनी");
+        let segments: Vec<LineSegment> = buffer.lines().segments(0, 0, 0, 2, 4).unwrap().collect();
+
+        assert_eq!(segments, vec![
+            LineSegment{ offset: 0, content: "नीनी".to_string() },
+            LineSegment{ offset: 2, content: "नी".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn segments_measures_display_columns() {
+        let mut buffer = Buffer::new();
+        buffer.insert("漢字a\tb");
+        let segments: Vec<LineSegment> = buffer.lines().segments(0, 0, 0, 3, 4).unwrap().collect();
+
+        // The second wide character doesn't fit alongside the first,
+        // and the tab (expanded to the next tab stop) gets its own segment.
+        assert_eq!(segments, vec![
+            LineSegment{ offset: 0, content: "漢".to_string() },
+            LineSegment{ offset: 1, content: "字a".to_string() },
+            LineSegment{ offset: 3, content: "\t".to_string() },
+            LineSegment{ offset: 4, content: "b".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn segments_starts_at_the_requested_offset() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe library");
+        let lines = buffer.lines();
+
+        assert_eq!(
+            lines.segments(0, 7, 0, 4, 4).unwrap().next(),
+            Some(LineSegment{ offset: 7, content: "libr".to_string() })
+        );
+        assert_eq!(lines.segments(0, 20, 0, 4, 4).unwrap().next(), None);
+    }
+
+    #[test]
+    fn segments_yields_nothing_for_empty_lines() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\n");
+
+        assert_eq!(buffer.lines().segments(1, 0, 0, 4, 4).unwrap().next(), None);
+        assert!(buffer.lines().segments(2, 0, 0, 4, 4).is_none());
+    }
+
+    #[test]
+    fn nth_returns_none_beyond_the_last_segment() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+
+        assert_eq!(buffer.lines().segments(0, 0, 0, 2, 4).unwrap().nth(3), None);
+    }
+
+    #[test]
+    #[cfg(feature = "syntax")]
+    fn styled_line_segments_cover_the_segment_content() {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let theme_set = ThemeSet::load_defaults();
+        let mut buffer = Buffer::new();
+        buffer.insert("fn main() { let scribe = 1; }");
+        buffer.syntax_definition = syntax_set.find_syntax_by_extension("rs").cloned();
+
+        let theme = &theme_set.themes["base16-ocean.dark"];
+        let styled_line = buffer.styled_lines(&LineRange::new(0, 1), theme, "base16-ocean.dark").unwrap();
+        let lines = buffer.lines();
+
+        for segment in lines.segments(0, 0, 0, 7, 4).unwrap() {
+            let spans = styled_line[0].segment(&segment);
+            let content: String = spans.iter().map(|span| span.value.as_str()).collect();
+
+            assert_eq!(content, segment.content);
+            assert_eq!(spans[0].offset, segment.offset);
+        }
+    }
+}
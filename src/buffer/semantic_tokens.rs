@@ -0,0 +1,153 @@
+//! Semantic token ranges (e.g. from a language server) layered over syntax highlighting.
+
+use buffer::{Buffer, Position, Range, Scope, TrackingId};
+use buffer::encoding::decode_offset;
+
+/// A range of content classified by a semantic analysis (e.g. as a
+/// parameter or type), whose scope supersedes its syntax highlighting.
+/// Semantic tokens can be merged into a buffer's tokens using
+/// `SemanticTokenIterator`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SemanticToken {
+    pub range: Range,
+    pub scope: Scope,
+}
+
+impl Buffer {
+    /// Replaces the buffer's semantic tokens with those in the specified data,
+    /// encoded as in the Language Server Protocol's `SemanticTokens` response:
+    /// five integers per token (its line and start column, relative to the
+    /// preceding token, followed by its length, type, and modifiers). Columns
+    /// and lengths are measured using the buffer's `position_encoding`. Token
+    /// types are mapped to scopes using their index in the legend; tokens with
+    /// types that aren't in the legend, or ranges that don't exist in the
+    /// buffer, are skipped.
+    ///
+    /// Token ranges are tracked (see `track_range`), so that they can be used
+    /// until the server provides updated tokens.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{Position, Range, Scope, SemanticToken};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("let scribe = library;");
+    ///
+    /// let legend = vec![Scope::new("variable.other").unwrap()];
+    /// buffer.set_semantic_tokens(&[0, 4, 6, 0, 0, 0, 9, 7, 0, 0], &legend);
+    ///
+    /// assert_eq!(buffer.semantic_tokens(), vec![
+    ///     SemanticToken{
+    ///         range: Range::new(Position{ line: 0, offset: 4 }, Position{ line: 0, offset: 10 }),
+    ///         scope: legend[0],
+    ///     },
+    ///     SemanticToken{
+    ///         range: Range::new(Position{ line: 0, offset: 13 }, Position{ line: 0, offset: 20 }),
+    ///         scope: legend[0],
+    ///     },
+    /// ]);
+    /// ```
+    pub fn set_semantic_tokens(&mut self, data: &[u32], legend: &[Scope]) {
+        self.clear_semantic_tokens();
+
+        let content = self.data();
+        let lines: Vec<&str> = content.split('\n').collect();
+        let mut line = 0;
+        let mut start = 0;
+
+        for token in data.chunks(5).filter(|token| token.len() == 5) {
+            if token[0] > 0 {
+                line += token[0] as usize;
+                start = token[1] as usize;
+            } else {
+                start += token[1] as usize;
+            }
+
+            let scope = match legend.get(token[3] as usize) {
+                Some(&scope) => scope,
+                None => continue,
+            };
+            let line_content = match lines.get(line) {
+                Some(line_content) => line_content,
+                None => continue,
+            };
+            let start_offset = decode_offset(line_content, start, self.position_encoding);
+            let end_offset = decode_offset(line_content, start + token[2] as usize, self.position_encoding);
+
+            if let (Some(start_offset), Some(end_offset)) = (start_offset, end_offset) {
+                let id = self.track_range(Range::new(
+                    Position{ line, offset: start_offset },
+                    Position{ line, offset: end_offset }
+                ));
+                self.semantic_tokens.push((id, scope));
+            }
+        }
+
+        self.render_cache.borrow_mut().invalidate(0);
+    }
+
+    /// Removes all of the buffer's semantic tokens.
+    pub fn clear_semantic_tokens(&mut self) {
+        let ids: Vec<TrackingId> = self.semantic_tokens.drain(..).map(|(id, _)| id).collect();
+        for id in ids {
+            self.untrack(id);
+        }
+
+        self.render_cache.borrow_mut().invalidate(0);
+    }
+
+    /// Returns the buffer's semantic tokens, in document order.
+    pub fn semantic_tokens(&self) -> Vec<SemanticToken> {
+        self.semantic_tokens.iter().filter_map(|&(id, scope)| {
+            self.tracked_range(id).map(|range| SemanticToken{ range, scope })
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, Position, Range, Scope, SemanticToken};
+
+    #[test]
+    fn set_semantic_tokens_skips_invalid_tokens() {
+        let mut buffer = Buffer::new();
+        buffer.insert("😀 scribe\nlibrary");
+        let legend = vec![Scope::new("type").unwrap()];
+
+        // The first token splits the emoji, the second has an unknown
+        // type, and the fourth refers to a line beyond the buffer.
+        buffer.set_semantic_tokens(&[0, 1, 2, 0, 0, 0, 2, 6, 1, 0, 1, 0, 7, 0, 0, 1, 0, 1, 0, 0], &legend);
+
+        assert_eq!(buffer.semantic_tokens(), vec![SemanticToken{
+            range: Range::new(Position{ line: 1, offset: 0 }, Position{ line: 1, offset: 7 }),
+            scope: legend[0],
+        }]);
+    }
+
+    #[test]
+    fn set_semantic_tokens_replaces_existing_tokens() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe library");
+        let legend = vec![Scope::new("type").unwrap()];
+        buffer.set_semantic_tokens(&[0, 0, 6, 0, 0], &legend);
+        buffer.set_semantic_tokens(&[0, 7, 7, 0, 0], &legend);
+
+        assert_eq!(buffer.semantic_tokens().len(), 1);
+        assert_eq!(buffer.semantic_tokens()[0].range.start(), Position{ line: 0, offset: 7 });
+    }
+
+    #[test]
+    fn semantic_tokens_are_remapped_as_content_changes() {
+        let mut buffer = Buffer::new();
+        buffer.insert("library");
+        buffer.set_semantic_tokens(&[0, 0, 7, 0, 0], &[Scope::new("type").unwrap()]);
+        buffer.insert("scribe\n");
+
+        assert_eq!(
+            buffer.semantic_tokens()[0].range,
+            Range::new(Position{ line: 1, offset: 0 }, Position{ line: 1, offset: 7 })
+        );
+    }
+}
@@ -0,0 +1,364 @@
+//! Line endings, save transforms, and other settings that vary by file type.
+
+use buffer::{Buffer, CommentTokens, Indentation, Position, Range};
+use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The character sequence terminating lines when a buffer is saved.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub enum LineEnding {
+    /// Lines end with a line feed (`\n`).
+    Lf,
+
+    /// Lines end with a carriage return and line feed (`\r\n`).
+    Crlf,
+}
+
+impl Default for LineEnding {
+    fn default() -> Self {
+        LineEnding::Lf
+    }
+}
+
+impl LineEnding {
+    /// Infers the line ending used by the specified content from the most
+    /// common of its line endings, falling back to the default (`Lf`) if
+    /// it's a single line, or both are equally common.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::LineEnding;
+    ///
+    /// assert_eq!(LineEnding::detect("scribe\r\nlibrary"), LineEnding::Crlf);
+    /// assert_eq!(LineEnding::detect("scribe\nlibrary\r\neditor\r\n"), LineEnding::Crlf);
+    /// assert_eq!(LineEnding::detect("scribe\nlibrary"), LineEnding::Lf);
+    /// ```
+    pub fn detect(data: &str) -> LineEnding {
+        let (line_feeds, crlfs) = count_line_endings(data);
+
+        if crlfs > line_feeds {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    /// Whether the specified content uses both kinds of line ending.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::LineEnding;
+    ///
+    /// assert!(LineEnding::mixed("scribe\nlibrary\r\n"));
+    /// assert!(!LineEnding::mixed("scribe\r\nlibrary\r\n"));
+    /// ```
+    pub fn mixed(data: &str) -> bool {
+        let (line_feeds, crlfs) = count_line_endings(data);
+
+        line_feeds > 0 && crlfs > 0
+    }
+
+    /// Converts the content's line endings (of either kind) to this one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::buffer::LineEnding;
+    ///
+    /// assert_eq!(LineEnding::Crlf.apply("scribe\nlibrary\r\n"), "scribe\r\nlibrary\r\n");
+    /// assert_eq!(LineEnding::Lf.apply("scribe\nlibrary\r\n"), "scribe\nlibrary\n");
+    /// ```
+    pub fn apply<'a>(&self, data: &'a str) -> Cow<'a, str> {
+        match *self {
+            LineEnding::Lf if data.contains("\r\n") => Cow::Owned(data.replace("\r\n", "\n")),
+            // Convert line feeds that aren't already preceded by a carriage return.
+            LineEnding::Crlf if data.matches('\n').count() > data.matches("\r\n").count() => {
+                Cow::Owned(LineEnding::Lf.apply(data).replace('\n', "\r\n"))
+            },
+            _ => Cow::Borrowed(data),
+        }
+    }
+}
+
+// Counts the content's line endings, returning the number of
+// line feeds not preceded by a carriage return, and of CRLFs.
+fn count_line_endings(data: &str) -> (usize, usize) {
+    let crlfs = data.matches("\r\n").count();
+
+    (data.matches('\n').count() - crlfs, crlfs)
+}
+
+/// Changes made to a buffer's content when it's saved.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct SaveTransforms {
+    /// Removes whitespace from the end of every line.
+    pub trim_trailing_whitespace: bool,
+
+    /// Adds a newline to the end of non-empty content that lacks one.
+    pub ensure_final_newline: bool,
+}
+
+/// Settings applied to buffers as a group (e.g. those configured for a file
+/// type using `Workspace::set_filetype_settings`).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BufferSettings {
+    pub indentation: Option<Indentation>,
+    pub line_ending: Option<LineEnding>,
+    pub save_transforms: Option<SaveTransforms>,
+    pub comment_tokens: Option<CommentTokens>,
+}
+
+impl Buffer {
+    /// Returns the line ending used when saving the buffer. Buffers loaded
+    /// from a file use the line ending detected in its contents (which are
+    /// normalized to line feeds while the buffer is edited).
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    /// Whether the buffer's file used both kinds of line ending when it was
+    /// loaded. All of its lines are saved using the buffer's `line_ending`,
+    /// so editors may want to warn before saving such buffers. This is reset
+    /// once the buffer is saved.
+    pub fn mixed_line_endings(&self) -> bool {
+        self.mixed_line_endings
+    }
+
+    /// Sets the line ending used when saving the buffer. Existing
+    /// line endings are converted when the buffer is next saved.
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+    }
+
+    /// Returns the changes made to the buffer's content when it's saved.
+    pub fn save_transforms(&self) -> SaveTransforms {
+        self.save_transforms
+    }
+
+    /// Sets the changes made to the buffer's content when it's saved.
+    pub fn set_save_transforms(&mut self, save_transforms: SaveTransforms) {
+        self.save_transforms = save_transforms;
+    }
+
+    /// Applies the specified settings to the buffer. Indentation and line
+    /// endings that aren't specified are left unchanged (since they're
+    /// detected when a buffer is loaded), whereas save transforms and comment
+    /// tokens are reset to their defaults. Indentation specified by the
+    /// buffer's modeline takes precedence over the settings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{BufferSettings, Indentation, IndentationStyle, LineEnding};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.apply_settings(&BufferSettings{
+    ///     indentation: Some(Indentation{ style: IndentationStyle::Tabs, width: 8 }),
+    ///     line_ending: Some(LineEnding::Crlf),
+    ///     ..BufferSettings::default()
+    /// });
+    ///
+    /// assert_eq!(buffer.indentation().style, IndentationStyle::Tabs);
+    /// assert_eq!(buffer.line_ending(), LineEnding::Crlf);
+    /// ```
+    pub fn apply_settings(&mut self, settings: &BufferSettings) {
+        if let Some(indentation) = settings.indentation {
+            self.indentation = match self.modeline {
+                Some(ref modeline) => modeline.apply_to(indentation),
+                None => indentation,
+            };
+        }
+        if let Some(line_ending) = settings.line_ending {
+            self.line_ending = line_ending;
+        }
+        self.save_transforms = settings.save_transforms.unwrap_or_default();
+        self.set_comment_tokens(settings.comment_tokens);
+    }
+
+    /// Applies the buffer's save transforms to its content,
+    /// as a single undoable operation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::SaveTransforms;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe  \nlibrary\t");
+    /// buffer.set_save_transforms(SaveTransforms{
+    ///     trim_trailing_whitespace: true,
+    ///     ensure_final_newline: true,
+    /// });
+    /// buffer.apply_save_transforms();
+    ///
+    /// assert_eq!(buffer.data(), "scribe\nlibrary\n");
+    /// ```
+    pub fn apply_save_transforms(&mut self) {
+        let transforms = self.save_transforms;
+        if !transforms.trim_trailing_whitespace && !transforms.ensure_final_newline {
+            return
+        }
+
+        let cursor = self.track_position(*self.cursor);
+        let in_operation_group = self.operation_group.is_some();
+        self.start_operation_group();
+
+        if transforms.trim_trailing_whitespace {
+            // Deletions are confined to their lines,
+            // so they don't affect other lines' positions.
+            for line in (0..self.line_count()).rev() {
+                let trailing_whitespace = self.line(line).and_then(|content| {
+                    let trimmed = content.trim_end();
+
+                    if trimmed.len() < content.len() {
+                        Some((trimmed.graphemes(true).count(), content.graphemes(true).count()))
+                    } else {
+                        None
+                    }
+                });
+
+                if let Some((start, end)) = trailing_whitespace {
+                    self.delete_range(Range::new(
+                        Position{ line, offset: start },
+                        Position{ line, offset: end }
+                    ));
+                }
+            }
+        }
+
+        if transforms.ensure_final_newline {
+            let data = self.data();
+            if !data.is_empty() && !data.ends_with('\n') {
+                let last_line = self.line_count() - 1;
+                let end = Position{
+                    line: last_line,
+                    offset: self.line(last_line).map(|line| line.graphemes(true).count()).unwrap_or(0)
+                };
+
+                self.cursor.move_to(end);
+                self.insert("\n");
+            }
+        }
+
+        if !in_operation_group {
+            self.end_operation_group();
+        }
+
+        if let Some(position) = self.tracked_position(cursor) {
+            self.cursor.move_to(position);
+        }
+        self.untrack(cursor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, BufferSettings, CommentTokens, LineEnding, Position, SaveTransforms};
+
+    #[test]
+    fn crlf_conversion_leaves_existing_crlf_line_endings_intact() {
+        assert_eq!(LineEnding::Crlf.apply("scribe\r\nlibrary\n"), "scribe\r\nlibrary\r\n");
+        assert_eq!(LineEnding::Crlf.apply("scribe\r\n"), "scribe\r\n");
+    }
+
+    #[cfg(feature = "system")]
+    #[test]
+    fn crlf_files_are_normalized_when_loaded_and_restored_when_saved() {
+        use std::fs;
+        use std::path::Path;
+
+        fs::write("tests/sample/crlf", "scribe\r\nlibrary\r\n").unwrap();
+        let mut buffer = Buffer::from_file(Path::new("tests/sample/crlf")).unwrap();
+        assert_eq!(buffer.data(), "scribe\nlibrary\n");
+        assert_eq!(buffer.line_ending(), LineEnding::Crlf);
+
+        buffer.insert("the ");
+        buffer.save().unwrap();
+        let saved_data = fs::read_to_string("tests/sample/crlf").unwrap();
+        fs::remove_file("tests/sample/crlf").unwrap();
+
+        assert_eq!(saved_data, "the scribe\r\nlibrary\r\n");
+    }
+
+    #[cfg(feature = "system")]
+    #[test]
+    fn files_with_mixed_line_endings_are_flagged_until_saved() {
+        use std::fs;
+        use std::path::Path;
+
+        fs::write("tests/sample/mixed_line_endings", "scribe\r\nlibrary\neditor\r\n").unwrap();
+        let mut buffer = Buffer::from_file(Path::new("tests/sample/mixed_line_endings")).unwrap();
+        assert!(buffer.mixed_line_endings());
+        assert_eq!(buffer.line_ending(), LineEnding::Crlf);
+
+        buffer.save().unwrap();
+        let saved_data = fs::read_to_string("tests/sample/mixed_line_endings").unwrap();
+        fs::remove_file("tests/sample/mixed_line_endings").unwrap();
+
+        assert!(!buffer.mixed_line_endings());
+        assert_eq!(saved_data, "scribe\r\nlibrary\r\neditor\r\n");
+    }
+
+    #[test]
+    fn apply_save_transforms_is_a_single_undoable_operation() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe \nlibrary ");
+        buffer.set_save_transforms(SaveTransforms{ trim_trailing_whitespace: true, ensure_final_newline: true });
+        buffer.apply_save_transforms();
+        buffer.undo();
+
+        assert_eq!(buffer.data(), "scribe \nlibrary ");
+    }
+
+    #[test]
+    fn apply_save_transforms_keeps_the_cursor_in_bounds() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe  ");
+        buffer.cursor.move_to(Position{ line: 0, offset: 8 });
+        buffer.set_save_transforms(SaveTransforms{ trim_trailing_whitespace: true, ensure_final_newline: false });
+        buffer.apply_save_transforms();
+
+        assert_eq!(*buffer.cursor, Position{ line: 0, offset: 6 });
+    }
+
+    #[test]
+    fn apply_settings_resets_unspecified_save_transforms_and_comment_tokens() {
+        let mut buffer = Buffer::new();
+        buffer.apply_settings(&BufferSettings{
+            save_transforms: Some(SaveTransforms{ trim_trailing_whitespace: true, ensure_final_newline: true }),
+            comment_tokens: Some(CommentTokens{ line: Some("#"), block: None }),
+            ..BufferSettings::default()
+        });
+        buffer.apply_settings(&BufferSettings::default());
+
+        assert_eq!(buffer.save_transforms(), SaveTransforms::default());
+        assert!(buffer.comment_tokens(0).is_err());
+    }
+
+    #[cfg(feature = "system")]
+    #[test]
+    fn save_applies_transforms_and_line_endings() {
+        use std::fs;
+        use std::path::Path;
+
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe \nlibrary");
+        buffer.path = Some(Path::new("tests/sample/settings").to_path_buf());
+        buffer.set_line_ending(LineEnding::Crlf);
+        buffer.set_save_transforms(SaveTransforms{ trim_trailing_whitespace: true, ensure_final_newline: true });
+        buffer.save().unwrap();
+
+        let saved_data = fs::read_to_string("tests/sample/settings").unwrap();
+        fs::remove_file("tests/sample/settings").unwrap();
+
+        assert_eq!(saved_data, "scribe\r\nlibrary\r\n");
+        assert_eq!(buffer.data(), "scribe\nlibrary\n");
+        assert!(!buffer.modified());
+    }
+}
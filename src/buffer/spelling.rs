@@ -0,0 +1,229 @@
+//! Spell checking of prose (comments, strings, and text) within a buffer.
+
+use buffer::{Buffer, Position, Range, Scope, Token};
+use errors::*;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A source of correctly spelled words (e.g. a hunspell binding).
+pub trait Dictionary {
+    /// Whether or not the word is spelled correctly.
+    fn check(&self, word: &str) -> bool;
+
+    /// Returns replacements for a misspelled word, best first.
+    fn suggestions(&self, word: &str) -> Vec<String>;
+}
+
+/// A word not found in the dictionary, alongside suggested replacements.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Misspelling {
+    pub range: Range,
+    pub word: String,
+    pub suggestions: Vec<String>,
+}
+
+impl Buffer {
+    /// Checks the spelling of words within prose, i.e. lexemes with comment,
+    /// string, or text (e.g. plain text or markup content) scopes. Markup
+    /// that isn't prose (e.g. HTML tags, Markdown code, LaTeX commands, or
+    /// embedded source code) is skipped, as are escape sequences, and words
+    /// containing digits or underscores (which are likely identifiers).
+    /// Returns an error if the buffer doesn't have a syntax definition.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate scribe;
+    /// # extern crate syntect;
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{Dictionary, Position, Range};
+    /// # use syntect::parsing::SyntaxSet;
+    ///
+    /// struct Words(Vec<&'static str>);
+    ///
+    /// impl Dictionary for Words {
+    ///     fn check(&self, word: &str) -> bool {
+    ///         self.0.contains(&word)
+    ///     }
+    ///
+    ///     fn suggestions(&self, _: &str) -> Vec<String> {
+    ///         vec!["library".to_string()]
+    ///     }
+    /// }
+    ///
+    /// # fn main() {
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("// scribe librray\nlet misspeled = 1;");
+    ///
+    /// // Omitted code to set up the buffer's syntax definition.
+    /// # let syntax_set = SyntaxSet::load_defaults_newlines();
+    /// # buffer.syntax_definition = syntax_set.find_syntax_by_extension("rs").cloned();
+    ///
+    /// let misspellings = buffer.spell_check(&Words(vec!["scribe"])).unwrap();
+    /// assert_eq!(misspellings.len(), 1);
+    /// assert_eq!(misspellings[0].word, "librray");
+    /// assert_eq!(misspellings[0].suggestions, vec!["library".to_string()]);
+    /// assert_eq!(
+    ///     misspellings[0].range,
+    ///     Range::new(Position{ line: 0, offset: 10 }, Position{ line: 0, offset: 17 })
+    /// );
+    /// # }
+    /// ```
+    pub fn spell_check(&self, dictionary: &Dictionary) -> Result<Vec<Misspelling>> {
+        let prose_scopes = ProseScopes::new();
+        let escape_scope = Scope::new("constant.character.escape").unwrap();
+        let tokens = self.tokens()?;
+        let mut misspellings = Vec::new();
+
+        for token in tokens.iter() {
+            let lexeme = match token {
+                Token::Lexeme(lexeme) => lexeme,
+                Token::Newline => continue,
+            };
+
+            let scopes = lexeme.scope.as_slice();
+            let is_escape = scopes.iter().any(|&scope| escape_scope.is_prefix_of(scope));
+            if !prose_scopes.includes(scopes) || is_escape {
+                continue;
+            }
+
+            // Convert the word boundaries' byte offsets to grapheme offsets.
+            let mut offset = lexeme.position.offset;
+            for segment in lexeme.value.split_word_bounds() {
+                let length = segment.graphemes(true).count();
+
+                if is_checkable(segment) && !dictionary.check(segment) {
+                    misspellings.push(Misspelling{
+                        range: Range::new(
+                            Position{ line: lexeme.position.line, offset },
+                            Position{ line: lexeme.position.line, offset: offset + length }
+                        ),
+                        word: segment.to_string(),
+                        suggestions: dictionary.suggestions(segment),
+                    });
+                }
+
+                offset += length;
+            }
+        }
+
+        Ok(misspellings)
+    }
+}
+
+// Scopes distinguishing prose from code. Tags and raw (e.g. code) markup
+// are never prose. Otherwise, a lexeme's innermost comment, string, or code
+// (e.g. keyword or source) scope decides, with lexemes in none of them
+// being prose only if they're part of a text document.
+struct ProseScopes {
+    markup: Vec<Scope>,
+    prose: Vec<Scope>,
+    code: Vec<Scope>,
+    text: Scope,
+}
+
+impl ProseScopes {
+    fn new() -> ProseScopes {
+        let scopes = |names: &[&str]| names.iter().map(|name| Scope::new(name).unwrap()).collect();
+
+        ProseScopes{
+            markup: scopes(&["meta.tag", "entity.name.tag", "markup.raw"]),
+            prose: scopes(&["comment", "string"]),
+            code: scopes(&["keyword", "support", "source"]),
+            text: Scope::new("text").unwrap(),
+        }
+    }
+
+    fn includes(&self, scopes: &[Scope]) -> bool {
+        let matches = |prefixes: &[Scope], scope: Scope| {
+            prefixes.iter().any(|prefix| prefix.is_prefix_of(scope))
+        };
+
+        if scopes.iter().any(|&scope| matches(&self.markup, scope)) {
+            return false
+        }
+
+        for &scope in scopes.iter().rev() {
+            if matches(&self.prose, scope) {
+                return true
+            } else if matches(&self.code, scope) {
+                return false
+            }
+        }
+
+        scopes.first().map(|&scope| self.text.is_prefix_of(scope)).unwrap_or(false)
+    }
+}
+
+// Whether or not the segment is a word that should be spell-checked.
+fn is_checkable(segment: &str) -> bool {
+    segment.chars().any(|c| c.is_alphabetic()) &&
+        segment.chars().all(|c| c.is_alphabetic() || c == '\'' || c == '’')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Dictionary, Misspelling};
+    use buffer::{Buffer, Position, Range};
+    use syntect::parsing::SyntaxSet;
+
+    struct Words(Vec<&'static str>);
+
+    impl Dictionary for Words {
+        fn check(&self, word: &str) -> bool {
+            self.0.contains(&word)
+        }
+
+        fn suggestions(&self, _: &str) -> Vec<String> {
+            Vec::new()
+        }
+    }
+
+    fn misspelled_words(buffer: &Buffer) -> Vec<String> {
+        buffer.spell_check(&Words(vec!["scribe"])).unwrap()
+            .into_iter()
+            .map(|misspelling| misspelling.word)
+            .collect()
+    }
+
+    #[test]
+    fn spell_check_includes_all_plain_text() {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nनी library");
+        buffer.syntax_definition = Some(syntax_set.find_syntax_plain_text().clone());
+
+        assert_eq!(buffer.spell_check(&Words(vec!["scribe", "नी"])).unwrap(), vec![Misspelling{
+            range: Range::new(Position{ line: 1, offset: 2 }, Position{ line: 1, offset: 9 }),
+            word: "library".to_string(),
+            suggestions: Vec::new(),
+        }]);
+    }
+
+    #[test]
+    fn spell_check_includes_strings_and_skips_escapes_and_identifiers() {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let mut buffer = Buffer::new();
+        buffer.insert("let editor = \"scribe\\ntext library_2 it's\";");
+        buffer.syntax_definition = syntax_set.find_syntax_by_extension("rs").cloned();
+
+        assert_eq!(misspelled_words(&buffer), vec!["text".to_string(), "it's".to_string()]);
+    }
+
+    #[test]
+    fn spell_check_skips_html_tags_and_embedded_source() {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let mut buffer = Buffer::new();
+        buffer.insert("<p class=\"lede\">scribe librray</p>\n<script>var mispeled = 1;</script>");
+        buffer.syntax_definition = syntax_set.find_syntax_by_extension("html").cloned();
+
+        assert_eq!(misspelled_words(&buffer), vec!["librray".to_string()]);
+    }
+
+    #[test]
+    fn spell_check_requires_a_syntax_definition() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+
+        assert!(buffer.spell_check(&Words(vec![])).is_err());
+    }
+}
@@ -0,0 +1,250 @@
+//! Snapshots of a buffer's complete state, for session restoration.
+
+use buffer::{Buffer, GapBuffer, Indentation, LineEnding, MetadataValue, Modeline, OperationState, Position};
+use buffer::operation::Operation;
+use buffer::operation::history::History;
+use errors::*;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+#[cfg(feature = "syntax")]
+use syntect::parsing::SyntaxSet;
+
+/// The version of the `BufferState` format produced by this library.
+pub const BUFFER_STATE_VERSION: u32 = 1;
+
+/// A buffer's content, path, cursor, marks, undo history, syntax, and metadata,
+/// which can be persisted (using the `serialization` feature) to restore the
+/// buffer later, or in another process. States carry a format version, which
+/// is checked when they're restored.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct BufferState {
+    pub version: u32,
+    pub content: String,
+    pub path: Option<PathBuf>,
+    pub cursor: Position,
+    pub marks: Vec<(char, Position)>,
+
+    /// Operations that can be undone; the last is undone first.
+    pub undo: Vec<OperationState>,
+
+    /// Operations that can be redone; the last is redone first.
+    pub redo: Vec<OperationState>,
+
+    /// The number of undoable operations when the buffer was last saved.
+    pub saved_at: Option<usize>,
+
+    /// The name of the buffer's syntax definition.
+    pub syntax: Option<String>,
+    pub metadata: BTreeMap<String, MetadataValue>,
+
+    /// The line ending used when saving the buffer. Since content is
+    /// normalized to line feeds, it can't be detected when restoring.
+    #[cfg_attr(feature = "serialization", serde(default))]
+    pub line_ending: LineEnding,
+}
+
+impl Buffer {
+    /// Captures the buffer's state. An open operation group
+    /// is captured as if it had been ended.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::Position;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe");
+    /// buffer.set_mark('a', Position{ line: 0, offset: 3 });
+    ///
+    /// let state = buffer.state();
+    /// assert_eq!(state.content, "scribe");
+    /// assert_eq!(state.marks, vec![('a', Position{ line: 0, offset: 3 })]);
+    /// assert_eq!(state.undo.len(), 1);
+    /// ```
+    pub fn state(&self) -> BufferState {
+        let (mut undo, redo, saved_at) = self.history.state();
+        if let Some(ref group) = self.operation_group {
+            if !group.is_empty() {
+                undo.push(group.state());
+            }
+        }
+
+        #[cfg(feature = "syntax")]
+        let syntax = self.syntax_definition.as_ref().map(|definition| definition.name.clone());
+        #[cfg(not(feature = "syntax"))]
+        let syntax = None;
+
+        BufferState{
+            version: BUFFER_STATE_VERSION,
+            content: self.data(),
+            path: self.path.clone(),
+            cursor: *self.cursor,
+            marks: self.marks(),
+            undo,
+            redo,
+            saved_at,
+            syntax,
+            metadata: self.metadata.clone(),
+            line_ending: self.line_ending,
+        }
+    }
+
+    /// Restores a buffer from its state, using the syntax set to find its
+    /// syntax definition (which is left unset if it can't be found). Its
+    /// indentation and modeline are detected from its content. Returns an
+    /// error if the state's format version isn't supported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate scribe;
+    /// extern crate syntect;
+    ///
+    /// use scribe::Buffer;
+    /// use scribe::buffer::Position;
+    /// use syntect::parsing::SyntaxSet;
+    ///
+    /// # fn main() {
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe");
+    /// buffer.set_mark('a', Position{ line: 0, offset: 3 });
+    ///
+    /// let mut restored = Buffer::from_state(buffer.state(), &SyntaxSet::new()).unwrap();
+    /// assert_eq!(restored.data(), "scribe");
+    /// assert_eq!(restored.mark('a'), Some(Position{ line: 0, offset: 3 }));
+    ///
+    /// restored.undo();
+    /// assert_eq!(restored.data(), "");
+    /// # }
+    /// ```
+    #[cfg(feature = "syntax")]
+    pub fn from_state(state: BufferState, syntax_set: &SyntaxSet) -> Result<Buffer> {
+        let syntax = state.syntax.clone();
+        let mut buffer = Buffer::restore(state)?;
+        buffer.syntax_definition = syntax
+            .and_then(|name| syntax_set.find_syntax_by_name(&name).cloned());
+
+        Ok(buffer)
+    }
+
+    // Restores everything but the buffer's syntax definition,
+    // which doesn't exist without the `syntax` feature.
+    pub(crate) fn restore(state: BufferState) -> Result<Buffer> {
+        if state.version != BUFFER_STATE_VERSION {
+            bail!(ErrorKind::UnsupportedStateVersion(state.version));
+        }
+
+        let mut buffer = Buffer::new();
+        let modeline = Modeline::detect(&state.content);
+        buffer.indentation = Indentation::detect(&state.content).unwrap_or_default();
+        if let Some(ref modeline) = modeline {
+            buffer.indentation = modeline.apply_to(buffer.indentation);
+        }
+        buffer.modeline = modeline;
+        buffer.line_ending = state.line_ending;
+
+        // The cursor shares the buffer's data, so it's replaced in place.
+        *buffer.data.borrow_mut() = GapBuffer::new(state.content);
+        buffer.cursor.move_to(state.cursor);
+        for (name, position) in state.marks {
+            buffer.set_mark(name, position);
+        }
+
+        buffer.path = state.path;
+        buffer.history = History::from_state(state.undo, state.redo, state.saved_at);
+        buffer.metadata = state.metadata;
+
+        Ok(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BUFFER_STATE_VERSION;
+    use buffer::{Buffer, LineEnding, Position, Range};
+    #[cfg(feature = "syntax")]
+    use syntect::parsing::SyntaxSet;
+
+    #[test]
+    fn restore_rejects_unsupported_versions() {
+        let mut state = Buffer::new().state();
+        state.version = BUFFER_STATE_VERSION + 1;
+
+        assert!(Buffer::restore(state).is_err());
+    }
+
+    #[test]
+    fn restored_buffers_can_undo_and_redo_their_history() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe library");
+        buffer.replace(
+            Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 6 }),
+            "amp"
+        );
+        buffer.delete_range(Range::new(Position{ line: 0, offset: 3 }, Position{ line: 0, offset: 11 }));
+        buffer.undo();
+
+        let mut restored = Buffer::restore(buffer.state()).unwrap();
+        assert_eq!(restored.data(), "amp library");
+
+        restored.redo();
+        assert_eq!(restored.data(), "amp");
+        restored.undo();
+        restored.undo();
+        assert_eq!(restored.data(), "scribe library");
+        restored.undo();
+        assert_eq!(restored.data(), "");
+    }
+
+    #[test]
+    fn state_includes_open_operation_groups() {
+        let mut buffer = Buffer::new();
+        buffer.start_operation_group();
+        buffer.insert("scribe");
+        buffer.insert(" ");
+
+        let mut restored = Buffer::restore(buffer.state()).unwrap();
+        restored.undo();
+
+        assert_eq!(restored.data(), "");
+    }
+
+    #[test]
+    #[cfg(feature = "syntax")]
+    fn state_records_the_syntax_definition_by_name() {
+        let mut syntax_set = SyntaxSet::load_defaults_newlines();
+        syntax_set.link_syntaxes();
+        let mut buffer = Buffer::new();
+        buffer.syntax_definition = syntax_set.find_syntax_by_extension("rs").cloned();
+
+        let restored = Buffer::from_state(buffer.state(), &syntax_set).unwrap();
+
+        assert_eq!(restored.syntax_definition.unwrap().name, "Rust");
+    }
+
+    #[cfg(feature = "serialization")]
+    #[test]
+    fn state_can_be_serialized() {
+        use serde_json;
+
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+        buffer.set_metadata("language", "rust");
+        let state = buffer.state();
+
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(serde_json::from_str::<super::BufferState>(&json).unwrap(), state);
+    }
+
+    #[test]
+    fn restored_buffers_keep_their_line_ending() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+        buffer.set_line_ending(LineEnding::Crlf);
+
+        let restored = Buffer::restore(buffer.state()).unwrap();
+        assert_eq!(restored.line_ending(), LineEnding::Crlf);
+    }
+}
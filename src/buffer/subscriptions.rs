@@ -0,0 +1,369 @@
+//! Observers notified of changes to a buffer's content, optionally debounced.
+
+use buffer::{Buffer, Position, Range, TrackingId};
+use buffer::tracking::Tracker;
+#[cfg(feature = "system")]
+use std::time::{Duration, Instant};
+
+/// An identifier for a subscription to a buffer's changes.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct SubscriptionId(usize);
+
+enum Delivery {
+    Immediate,
+
+    // The time of the last change and the earliest
+    // position changed since subscribers were notified.
+    // Reading the clock requires the `system` feature.
+    #[cfg(feature = "system")]
+    Debounced { quiet_period: Duration, pending: Option<(Instant, Position)> },
+}
+
+// A tracked range to which a subscription's notifications are limited.
+struct RangeFilter {
+    id: TrackingId,
+    invalidated: bool,
+}
+
+impl RangeFilter {
+    // Whether or not the change affects the tracked range. Changes adjoining
+    // the range are included, and the change that invalidates it is the last.
+    fn matches(&mut self, changed_range: &Range, tracker: &Tracker) -> bool {
+        match tracker.range(self.id) {
+            Some(range) => changed_range.start() <= range.end() && range.start() <= changed_range.end(),
+            None if !self.invalidated => {
+                self.invalidated = true;
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+struct Subscription {
+    id: SubscriptionId,
+    callback: Box<Fn(Position)>,
+    delivery: Delivery,
+    filter: Option<RangeFilter>,
+}
+
+/// A buffer's change subscriptions.
+#[derive(Default)]
+pub struct Subscriptions {
+    next_id: usize,
+    entries: Vec<Subscription>,
+}
+
+impl Subscriptions {
+    /// Notifies subscribers of a change to the specified range (in terms of
+    /// the buffer's updated content), deferring notifications to debounced
+    /// subscribers. Range-filtered subscribers are only notified if the
+    /// change affects their range, as remapped by the tracker.
+    pub fn notify(&mut self, changed_range: &Range, tracker: &Tracker) {
+        // Only debounced subscribers need the time of the change.
+        #[cfg(feature = "system")]
+        let mut now = None;
+        let position = changed_range.start();
+
+        for subscription in &mut self.entries {
+            if let Some(ref mut filter) = subscription.filter {
+                if !filter.matches(changed_range, tracker) {
+                    continue
+                }
+            }
+
+            match subscription.delivery {
+                Delivery::Immediate => (subscription.callback)(position),
+                #[cfg(feature = "system")]
+                Delivery::Debounced{ ref mut pending, .. } => {
+                    let earliest = match *pending {
+                        Some((_, pending_position)) if pending_position < position => pending_position,
+                        _ => position,
+                    };
+                    *pending = Some((*now.get_or_insert_with(Instant::now), earliest));
+                },
+            }
+        }
+    }
+
+    fn add(&mut self, callback: Box<Fn(Position)>, delivery: Delivery, filter: Option<RangeFilter>) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.entries.push(Subscription{ id, callback, delivery, filter });
+
+        id
+    }
+}
+
+impl Buffer {
+    /// Subscribes to changes to the buffer's content. Like the buffer's
+    /// `change_callback`, the callback is called with the position of each
+    /// change (including those made by undo, redo, and reloads) as it's made,
+    /// but any number of subscriptions can be added.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::Position;
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// let changes = Rc::new(RefCell::new(Vec::new()));
+    /// let subscriber_changes = changes.clone();
+    /// let id = buffer.subscribe(Box::new(move |position| {
+    ///     subscriber_changes.borrow_mut().push(position)
+    /// }));
+    ///
+    /// buffer.insert("scribe");
+    /// buffer.unsubscribe(id);
+    /// buffer.insert("library");
+    ///
+    /// assert_eq!(*changes.borrow(), vec![Position{ line: 0, offset: 0 }]);
+    /// ```
+    pub fn subscribe(&mut self, callback: Box<Fn(Position)>) -> SubscriptionId {
+        self.subscriptions.add(callback, Delivery::Immediate, None)
+    }
+
+    /// Subscribes to changes affecting (i.e. intersecting or adjoining) the
+    /// specified range, such as the visible portion of the buffer; a window
+    /// of lines can be subscribed to using `LineRange::to_range`. The range
+    /// is tracked (see `track_range`), so that it stays anchored to the same
+    /// content as the buffer changes. If its content is deleted entirely (or
+    /// the buffer is reloaded), the range is invalidated: subscribers are
+    /// notified of that change, but no further ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{LineRange, Position};
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\nlibrary\neditor");
+    ///
+    /// let changes = Rc::new(RefCell::new(Vec::new()));
+    /// let subscriber_changes = changes.clone();
+    /// buffer.subscribe_to_range(LineRange::new(1, 2).to_range(), Box::new(move |position| {
+    ///     subscriber_changes.borrow_mut().push(position)
+    /// }));
+    ///
+    /// // Insert a line above the range, moving it down.
+    /// buffer.insert("\n");
+    ///
+    /// // Change the (remapped) range's content.
+    /// buffer.cursor.move_to(Position{ line: 2, offset: 0 });
+    /// buffer.insert("rust ");
+    ///
+    /// // Change content beyond the range.
+    /// buffer.cursor.move_to(Position{ line: 3, offset: 6 });
+    /// buffer.insert("s");
+    ///
+    /// assert_eq!(*changes.borrow(), vec![Position{ line: 2, offset: 0 }]);
+    /// ```
+    pub fn subscribe_to_range(&mut self, range: Range, callback: Box<Fn(Position)>) -> SubscriptionId {
+        let id = self.track_range(range);
+
+        self.subscriptions.add(callback, Delivery::Immediate, Some(RangeFilter{ id, invalidated: false }))
+    }
+
+    /// Subscribes to changes to the buffer's content, coalescing successive
+    /// changes into a single notification, delivered once the quiet period
+    /// has elapsed without further changes (e.g. for expensive consumers,
+    /// such as linters). The callback is called with the earliest position
+    /// changed since the previous notification.
+    ///
+    /// Notifications are delivered by `notify_debounced_subscribers`, which
+    /// should be called periodically (e.g. from the embedder's event loop).
+    /// Changes are timed using the system clock, so debouncing requires the
+    /// `system` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::Position;
+    /// use std::cell::RefCell;
+    /// use std::rc::Rc;
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// let changes = Rc::new(RefCell::new(Vec::new()));
+    /// let subscriber_changes = changes.clone();
+    /// let quiet_period = Duration::from_millis(250);
+    /// buffer.subscribe_debounced(quiet_period, Box::new(move |position| {
+    ///     subscriber_changes.borrow_mut().push(position)
+    /// }));
+    ///
+    /// buffer.insert("scribe\n");
+    /// buffer.cursor.move_down();
+    /// buffer.insert("library");
+    ///
+    /// // The quiet period hasn't elapsed yet.
+    /// buffer.notify_debounced_subscribers(Instant::now());
+    /// assert!(changes.borrow().is_empty());
+    ///
+    /// buffer.notify_debounced_subscribers(Instant::now() + quiet_period);
+    /// assert_eq!(*changes.borrow(), vec![Position{ line: 0, offset: 0 }]);
+    /// ```
+    #[cfg(feature = "system")]
+    pub fn subscribe_debounced(&mut self, quiet_period: Duration, callback: Box<Fn(Position)>) -> SubscriptionId {
+        self.subscriptions.add(callback, Delivery::Debounced{ quiet_period, pending: None }, None)
+    }
+
+    /// Removes the specified subscription.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) {
+        let filters: Vec<TrackingId> = self.subscriptions.entries.iter()
+            .filter(|subscription| subscription.id == id)
+            .filter_map(|subscription| subscription.filter.as_ref().map(|filter| filter.id))
+            .collect();
+        for filter in filters {
+            self.untrack(filter);
+        }
+
+        self.subscriptions.entries.retain(|subscription| subscription.id != id);
+    }
+
+    /// Notifies debounced subscribers whose quiet period has elapsed (as of
+    /// the specified time) since the buffer was last changed. Returns the
+    /// time at which the next pending notification is due, if any, which
+    /// can be used to schedule the next call.
+    #[cfg(feature = "system")]
+    pub fn notify_debounced_subscribers(&mut self, now: Instant) -> Option<Instant> {
+        let mut next_deadline: Option<Instant> = None;
+
+        for subscription in &mut self.subscriptions.entries {
+            if let Delivery::Debounced{ quiet_period, ref mut pending } = subscription.delivery {
+                if let Some((changed_at, position)) = *pending {
+                    let deadline = changed_at + quiet_period;
+
+                    if deadline <= now {
+                        *pending = None;
+                        (subscription.callback)(position);
+                    } else if next_deadline.map(|next| deadline < next).unwrap_or(true) {
+                        next_deadline = Some(deadline);
+                    }
+                }
+            }
+        }
+
+        next_deadline
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, Position, Range};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    #[cfg(feature = "system")]
+    use std::time::{Duration, Instant};
+
+    #[cfg(feature = "system")]
+    fn debounced_buffer(quiet_period: Duration) -> (Buffer, Rc<RefCell<Vec<Position>>>) {
+        let mut buffer = Buffer::new();
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let subscriber_changes = changes.clone();
+        buffer.subscribe_debounced(quiet_period, Box::new(move |position| {
+            subscriber_changes.borrow_mut().push(position)
+        }));
+
+        (buffer, changes)
+    }
+
+    #[test]
+    fn subscribers_are_notified_of_undos() {
+        let mut buffer = Buffer::new();
+        let count = Rc::new(RefCell::new(0));
+        let subscriber_count = count.clone();
+        buffer.subscribe(Box::new(move |_| *subscriber_count.borrow_mut() += 1));
+        buffer.insert("scribe");
+        buffer.undo();
+
+        assert_eq!(*count.borrow(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "system")]
+    fn notify_debounced_subscribers_returns_the_next_deadline() {
+        let quiet_period = Duration::from_secs(60);
+        let (mut buffer, changes) = debounced_buffer(quiet_period);
+        assert_eq!(buffer.notify_debounced_subscribers(Instant::now()), None);
+
+        let before_change = Instant::now();
+        buffer.insert("scribe");
+        let deadline = buffer.notify_debounced_subscribers(Instant::now()).unwrap();
+
+        assert!(deadline >= before_change + quiet_period);
+        assert!(changes.borrow().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "system")]
+    fn debounced_subscribers_are_notified_once_per_quiet_period() {
+        let quiet_period = Duration::from_millis(10);
+        let (mut buffer, changes) = debounced_buffer(quiet_period);
+        buffer.insert("scribe");
+        buffer.notify_debounced_subscribers(Instant::now() + quiet_period);
+        buffer.notify_debounced_subscribers(Instant::now() + quiet_period);
+
+        buffer.cursor.move_to(Position{ line: 0, offset: 6 });
+        buffer.insert(" library");
+        buffer.notify_debounced_subscribers(Instant::now() + quiet_period);
+
+        assert_eq!(*changes.borrow(), vec![Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 6 }]);
+    }
+
+    #[test]
+    fn range_subscribers_are_notified_of_adjoining_changes() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe library");
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let subscriber_changes = changes.clone();
+        let range = Range::new(Position{ line: 0, offset: 7 }, Position{ line: 0, offset: 14 });
+        buffer.subscribe_to_range(range, Box::new(move |position| {
+            subscriber_changes.borrow_mut().push(position)
+        }));
+
+        buffer.cursor.move_to(Position{ line: 0, offset: 0 });
+        buffer.insert("a");
+        buffer.cursor.move_to(Position{ line: 0, offset: 8 });
+        buffer.insert(" ");
+
+        assert_eq!(*changes.borrow(), vec![Position{ line: 0, offset: 8 }]);
+    }
+
+    #[test]
+    fn range_subscribers_are_notified_of_invalidation_once() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe library");
+        let count = Rc::new(RefCell::new(0));
+        let subscriber_count = count.clone();
+        let range = Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 6 });
+        buffer.subscribe_to_range(range.clone(), Box::new(move |_| *subscriber_count.borrow_mut() += 1));
+
+        buffer.delete_range(range);
+        buffer.insert("editor");
+
+        assert_eq!(*count.borrow(), 1);
+    }
+
+    #[test]
+    fn unsubscribed_range_subscribers_are_not_notified() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        let count = Rc::new(RefCell::new(0));
+        let subscriber_count = count.clone();
+        let id = buffer.subscribe_to_range(
+            Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 6 }),
+            Box::new(move |_| *subscriber_count.borrow_mut() += 1)
+        );
+        buffer.unsubscribe(id);
+        buffer.insert("library ");
+
+        assert_eq!(*count.borrow(), 0);
+    }
+}
@@ -1,21 +1,80 @@
+mod overlay_iterator;
+mod semantic_iterator;
 mod token_iterator;
 mod token_set;
+mod whitespace_iterator;
 
+pub use self::overlay_iterator::OverlayTokenIterator;
+pub use self::semantic_iterator::SemanticTokenIterator;
 pub use self::token_iterator::TokenIterator;
 pub use self::token_set::TokenSet;
+pub use self::whitespace_iterator::WhitespaceTokenIterator;
 
 use buffer::Position;
-use syntect::parsing::ScopeStack;
+use std::borrow::Cow;
+use syntect::parsing::{Scope, ScopeStack};
+use unicode_segmentation::UnicodeSegmentation;
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
 pub enum Token<'a> {
     Newline,
-    Lexeme(Lexeme<'a>)
+    Lexeme(#[cfg_attr(feature = "serialization", serde(borrow))] Lexeme<'a>)
 }
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
 pub struct Lexeme<'a> {
-    pub value: &'a str,
+    #[cfg_attr(feature = "serialization", serde(borrow))]
+    pub value: Cow<'a, str>,
     pub scope: ScopeStack,
     pub position: Position,
 }
+
+// Splits a lexeme into runs of graphemes that share the same additional scopes
+// (determined by the callback, using each grapheme's position and content),
+// which are pushed onto the resulting lexemes' scope stacks.
+fn split_lexeme<'a, F>(lexeme: Lexeme<'a>, mut scopes_for: F) -> Vec<Lexeme<'a>>
+    where F: FnMut(Position, &str) -> Vec<Scope> {
+    let mut lexemes = Vec::new();
+    let mut run: Option<(usize, usize, Vec<Scope>)> = None;
+    let mut push_run = |(start, offset, scopes): (usize, usize, Vec<Scope>), end: usize| {
+        let mut scope = lexeme.scope.clone();
+        for additional_scope in scopes {
+            scope.push(additional_scope);
+        }
+
+        lexemes.push(Lexeme{
+            value: match lexeme.value {
+                Cow::Borrowed(value) => Cow::Borrowed(&value[start..end]),
+                Cow::Owned(ref value) => Cow::Owned(value[start..end].to_string()),
+            },
+            scope,
+            position: Position{
+                line: lexeme.position.line,
+                offset: lexeme.position.offset + offset,
+            },
+        });
+    };
+
+    for (index, (byte_offset, grapheme)) in lexeme.value.grapheme_indices(true).enumerate() {
+        let position = Position{ line: lexeme.position.line, offset: lexeme.position.offset + index };
+        let scopes = scopes_for(position, grapheme);
+
+        let continues_run = run.as_ref()
+            .map(|&(_, _, ref run_scopes)| *run_scopes == scopes)
+            .unwrap_or(false);
+        if !continues_run {
+            if let Some(previous_run) = run.take() {
+                push_run(previous_run, byte_offset);
+            }
+            run = Some((byte_offset, index, scopes));
+        }
+    }
+
+    if let Some(last_run) = run {
+        push_run(last_run, lexeme.value.len());
+    }
+
+    lexemes
+}
@@ -0,0 +1,91 @@
+use std::collections::VecDeque;
+use buffer::{Overlay, Token};
+use buffer::token::split_lexeme;
+
+/// Wraps a token iterator, splitting lexemes at overlay boundaries and
+/// pushing the scopes of the overlays covering them onto their scope stacks
+/// (in the order the overlays are provided), so that overlays can be
+/// rendered from a single stream of tokens.
+pub struct OverlayTokenIterator<'a, I: Iterator<Item=Token<'a>>> {
+    tokens: I,
+    overlays: Vec<Overlay>,
+    pending: VecDeque<Token<'a>>,
+}
+
+impl<'a, I: Iterator<Item=Token<'a>>> OverlayTokenIterator<'a, I> {
+    pub fn new(tokens: I, overlays: Vec<Overlay>) -> OverlayTokenIterator<'a, I> {
+        OverlayTokenIterator{ tokens, overlays, pending: VecDeque::new() }
+    }
+}
+
+impl<'a, I: Iterator<Item=Token<'a>>> Iterator for OverlayTokenIterator<'a, I> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(token) = self.pending.pop_front() {
+            return Some(token)
+        }
+
+        match self.tokens.next() {
+            Some(Token::Lexeme(lexeme)) => {
+                let overlays = &self.overlays;
+                let split_lexemes = split_lexeme(lexeme, |position, _| {
+                    overlays.iter()
+                        .filter(|overlay| overlay.range.includes(&position))
+                        .map(|overlay| overlay.scope)
+                        .collect()
+                });
+                self.pending.extend(split_lexemes.into_iter().map(Token::Lexeme));
+
+                self.pending.pop_front()
+            },
+            token => token,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OverlayTokenIterator;
+    use buffer::{Overlay, Position, Range, Token};
+    use buffer::token::TokenIterator;
+    use syntect::parsing::{Scope, SyntaxSet};
+
+    #[test]
+    fn overlay_token_iterator_splits_lexemes_at_overlay_boundaries() {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let def = syntax_set.find_syntax_plain_text();
+        let search = Scope::new("search").unwrap();
+        let selection = Scope::new("selection").unwrap();
+        let overlays = vec![
+            Overlay{
+                range: Range::new(Position{ line: 0, offset: 2 }, Position{ line: 1, offset: 1 }),
+                scope: search,
+            },
+            Overlay{
+                range: Range::new(Position{ line: 0, offset: 4 }, Position{ line: 0, offset: 5 }),
+                scope: selection,
+            },
+        ];
+
+        let lexemes: Vec<(String, Vec<Scope>)> = OverlayTokenIterator::new(TokenIterator::new("scribe\nlibrary", def), overlays)
+            .filter_map(|token| {
+                match token {
+                    Token::Lexeme(lexeme) => Some((
+                        lexeme.value.to_string(),
+                        lexeme.scope.as_slice()[1..].to_vec()
+                    )),
+                    Token::Newline => None,
+                }
+            }).collect();
+
+        assert_eq!(lexemes, vec![
+            ("sc".to_string(), vec![]),
+            ("ri".to_string(), vec![search]),
+            ("b".to_string(), vec![search, selection]),
+            ("e".to_string(), vec![search]),
+            ("l".to_string(), vec![search]),
+            ("ibrary".to_string(), vec![]),
+        ]);
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::{HashMap, VecDeque};
+use buffer::{SemanticToken, Token};
+use buffer::token::split_lexeme;
+use syntect::parsing::ScopeStack;
+
+/// Wraps a token iterator, splitting lexemes at semantic token boundaries
+/// and re-scoping the portions they cover: the innermost syntax scope is
+/// replaced by the semantic token's scope, retaining the enclosing scopes
+/// (e.g. the language and function definition), so that both sources of
+/// highlighting can be rendered from a single stream of tokens.
+pub struct SemanticTokenIterator<'a, I: Iterator<Item=Token<'a>>> {
+    tokens: I,
+    semantic_tokens: HashMap<usize, Vec<SemanticToken>>,
+    pending: VecDeque<Token<'a>>,
+}
+
+impl<'a, I: Iterator<Item=Token<'a>>> SemanticTokenIterator<'a, I> {
+    pub fn new(tokens: I, semantic_tokens: Vec<SemanticToken>) -> SemanticTokenIterator<'a, I> {
+        // Index the semantic tokens by line, so that lexemes
+        // are only compared with those that could overlap them.
+        let mut lines: HashMap<usize, Vec<SemanticToken>> = HashMap::new();
+        for semantic_token in semantic_tokens {
+            for line in semantic_token.range.start().line..semantic_token.range.end().line + 1 {
+                lines.entry(line).or_insert_with(Vec::new).push(semantic_token.clone());
+            }
+        }
+
+        SemanticTokenIterator{ tokens, semantic_tokens: lines, pending: VecDeque::new() }
+    }
+}
+
+impl<'a, I: Iterator<Item=Token<'a>>> Iterator for SemanticTokenIterator<'a, I> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(token) = self.pending.pop_front() {
+            return Some(token)
+        }
+
+        match self.tokens.next() {
+            Some(Token::Lexeme(lexeme)) => {
+                let semantic_tokens = match self.semantic_tokens.get(&lexeme.position.line) {
+                    Some(semantic_tokens) => semantic_tokens,
+                    None => return Some(Token::Lexeme(lexeme)),
+                };
+                let depth = lexeme.scope.len();
+
+                // Later tokens take precedence over earlier, overlapping ones.
+                let split_lexemes = split_lexeme(lexeme, |position, _| {
+                    semantic_tokens.iter().rev()
+                        .find(|semantic_token| semantic_token.range.includes(&position))
+                        .map(|semantic_token| vec![semantic_token.scope])
+                        .unwrap_or_default()
+                });
+
+                self.pending.extend(split_lexemes.into_iter().map(|mut lexeme| {
+                    if lexeme.scope.len() > depth {
+                        let mut scopes = lexeme.scope.as_slice().to_vec();
+                        let semantic_scope = scopes.pop().unwrap();
+
+                        // Keep the root (language) scope.
+                        if depth > 1 {
+                            scopes.pop();
+                        }
+                        scopes.push(semantic_scope);
+                        lexeme.scope = ScopeStack::from_vec(scopes);
+                    }
+
+                    Token::Lexeme(lexeme)
+                }));
+
+                self.pending.pop_front()
+            },
+            token => token,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SemanticTokenIterator;
+    use buffer::{Position, Range, SemanticToken, Token};
+    use buffer::token::TokenIterator;
+    use syntect::parsing::{Scope, SyntaxSet};
+
+    #[test]
+    fn semantic_token_iterator_replaces_the_innermost_scope_of_covered_content() {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let def = syntax_set.find_syntax_by_extension("rs").unwrap();
+        let parameter = Scope::new("variable.parameter").unwrap();
+        let semantic_tokens = vec![SemanticToken{
+            range: Range::new(Position{ line: 1, offset: 2 }, Position{ line: 1, offset: 4 }),
+            scope: parameter,
+        }];
+
+        let lexemes: Vec<(String, Vec<Scope>)> = SemanticTokenIterator::new(TokenIterator::new("// x\nscribe;", def), semantic_tokens)
+            .filter_map(|token| {
+                match token {
+                    Token::Lexeme(lexeme) => Some((lexeme.value.to_string(), lexeme.scope.as_slice().to_vec())),
+                    Token::Newline => None,
+                }
+            }).collect();
+        let rust = Scope::new("source.rust").unwrap();
+
+        assert_eq!(lexemes[lexemes.len() - 4..].to_vec(), vec![
+            ("sc".to_string(), vec![rust]),
+            ("ri".to_string(), vec![rust, parameter]),
+            ("be".to_string(), vec![rust]),
+            (";".to_string(), vec![rust, Scope::new("punctuation.terminator.rust").unwrap()]),
+        ]);
+    }
+}
@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::cmp;
 use buffer::{Lexeme, Position, Token};
 use syntect::parsing::{ParseState, ScopeStack, ScopeStackOp, SyntaxDefinition};
@@ -12,6 +13,7 @@ pub struct TokenIterator<'a> {
     current_byte_offset: usize,
     current_position: Position,
     line_events: Vec<(usize, ScopeStackOp)>,
+    line_event_index: usize,
 }
 
 impl<'a> TokenIterator<'a> {
@@ -24,6 +26,7 @@ impl<'a> TokenIterator<'a> {
             current_byte_offset: 0,
             current_position: Position{ line: 0, offset: 0 },
             line_events: Vec::new(),
+            line_event_index: 0,
         };
 
         // Preload the first line
@@ -58,7 +61,9 @@ impl<'a> TokenIterator<'a> {
                 line.len()
             };
 
-            while let Some((event_offset, scope_change)) = self.line_events.pop() {
+            while let Some(&(event_offset, ref scope_change)) = self.line_events.get(self.line_event_index) {
+                self.line_event_index += 1;
+
                 // We want to capture the full scope for a given token, so we
                 // need to make sure we apply all of them and only capture it
                 // once we've moved on to another token/offset.
@@ -68,7 +73,7 @@ impl<'a> TokenIterator<'a> {
 
                     lexeme = Some(
                         Token::Lexeme(Lexeme{
-                            value: &line[self.current_byte_offset..end_of_token],
+                            value: Cow::Borrowed(&line[self.current_byte_offset..end_of_token]),
                             scope: self.scopes.clone(),
                             position: self.current_position,
                         })
@@ -86,7 +91,7 @@ impl<'a> TokenIterator<'a> {
 
                 // Apply the scope and keep a reference to it, so
                 // that we can pair it with a token later on.
-                self.scopes.apply(&scope_change);
+                self.scopes.apply(scope_change);
 
                 if lexeme.is_some() { return lexeme }
             }
@@ -95,7 +100,7 @@ impl<'a> TokenIterator<'a> {
             if self.current_byte_offset < end_of_line {
                 lexeme = Some(
                     Token::Lexeme(Lexeme{
-                        value: &line[self.current_byte_offset..end_of_line],
+                        value: Cow::Borrowed(&line[self.current_byte_offset..end_of_line]),
                         scope: self.scopes.clone(),
                         position: self.current_position,
                     })
@@ -111,11 +116,12 @@ impl<'a> TokenIterator<'a> {
 
     fn parse_next_line(&mut self) {
         if let Some((line_number, line)) = self.lines.next() {
-            // We reverse the line elements so that we can pop them off one at a
-            // time, handling each event while allowing us to stop at any point.
-            let mut line_events = self.parser.parse_line(line);
-            line_events.reverse();
-            self.line_events = line_events;
+            // The parser returns a newly allocated vector of events for each
+            // line (it can't fill an existing one), so that allocation remains.
+            // We walk the events in place, handling one at a time while
+            // allowing us to stop at any point, rather than reversing them.
+            self.line_events = self.parser.parse_line(line);
+            self.line_event_index = 0;
 
             // Keep a reference to the line so that we can create slices of it.
             self.current_line = Some(line);
@@ -142,6 +148,7 @@ impl<'a> Iterator for TokenIterator<'a> {
 #[cfg(test)]
 mod tests {
     use super::TokenIterator;
+    use std::borrow::Cow;
     use buffer::{Lexeme, Position, ScopeStack, Token};
     use syntect::parsing::{Scope, SyntaxSet};
 
@@ -156,32 +163,32 @@ mod tests {
         scope_stack.push(Scope::new("meta.struct.rust").unwrap());
         scope_stack.push(Scope::new("storage.type.struct.rust").unwrap());
         expected_tokens.push(Token::Lexeme(Lexeme{
-            value: "struct",
+            value: Cow::Borrowed("struct"),
             scope: scope_stack.clone(),
             position: Position{ line: 0, offset: 0 }
         }));
         scope_stack.pop();
         expected_tokens.push(Token::Lexeme(Lexeme{
-            value: " ",
+            value: Cow::Borrowed(" "),
             scope: scope_stack.clone(),
             position: Position{ line: 0, offset: 6 }
         }));
         scope_stack.push(Scope::new("entity.name.struct.rust").unwrap());
         expected_tokens.push(Token::Lexeme(Lexeme{
-            value: "Buffer",
+            value: Cow::Borrowed("Buffer"),
             scope: scope_stack.clone(),
             position: Position{ line: 0, offset: 7 }
         }));
         scope_stack.pop();
         expected_tokens.push(Token::Lexeme(Lexeme{
-            value: " ",
+            value: Cow::Borrowed(" "),
             scope: scope_stack.clone(),
             position: Position{ line: 0, offset: 13 }
         }));
         scope_stack.push(Scope::new("meta.block.rust").unwrap());
         scope_stack.push(Scope::new("punctuation.section.block.begin.rust").unwrap());
         expected_tokens.push(Token::Lexeme(Lexeme{
-            value: "{",
+            value: Cow::Borrowed("{"),
             scope: scope_stack.clone(),
             position: Position{ line: 0, offset: 14 }
         }));
@@ -190,46 +197,46 @@ mod tests {
         scope_stack.push(Scope::new("comment.line.double-slash.rust").unwrap());
         scope_stack.push(Scope::new("punctuation.definition.comment.rust").unwrap());
         expected_tokens.push(Token::Lexeme(Lexeme{
-            value: "//",
+            value: Cow::Borrowed("//"),
             scope: scope_stack.clone(),
             position: Position{ line: 1, offset: 0 }
         }));
         scope_stack.pop();
         expected_tokens.push(Token::Lexeme(Lexeme{
-            value: " comment",
+            value: Cow::Borrowed(" comment"),
             scope: scope_stack.clone(),
             position: Position{ line: 1, offset: 2 }
         }));
         expected_tokens.push(Token::Newline);
         scope_stack.pop();
         expected_tokens.push(Token::Lexeme(Lexeme{
-            value: "  ",
+            value: Cow::Borrowed("  "),
             scope: scope_stack.clone(),
             position: Position{ line: 2, offset: 0 }
         }));
         scope_stack.push(Scope::new("variable.other.member.rust").unwrap());
         expected_tokens.push(Token::Lexeme(Lexeme{
-            value: "data",
+            value: Cow::Borrowed("data"),
             scope: scope_stack.clone(),
             position: Position{ line: 2, offset: 2 }
         }));
         scope_stack.pop();
         scope_stack.push(Scope::new("punctuation.separator.rust").unwrap());
         expected_tokens.push(Token::Lexeme(Lexeme{
-            value: ":",
+            value: Cow::Borrowed(":"),
             scope: scope_stack.clone(),
             position: Position{ line: 2, offset: 6 }
         }));
         scope_stack.pop();
         expected_tokens.push(Token::Lexeme(Lexeme{
-            value: " String",
+            value: Cow::Borrowed(" String"),
             scope: scope_stack.clone(),
             position: Position{ line: 2, offset: 7 }
         }));
         expected_tokens.push(Token::Newline);
         scope_stack.push(Scope::new("punctuation.section.block.end.rust").unwrap());
         expected_tokens.push(Token::Lexeme(Lexeme{
-            value: "}",
+            value: Cow::Borrowed("}"),
             scope: scope_stack.clone(),
             position: Position{ line: 3, offset: 0 }
         }));
@@ -237,7 +244,7 @@ mod tests {
         scope_stack.pop();
         scope_stack.pop();
         expected_tokens.push(Token::Lexeme(Lexeme{
-            value: "garbage",
+            value: Cow::Borrowed("garbage"),
             scope: scope_stack.clone(),
             position: Position{ line: 3, offset: 1 }
         }));
@@ -262,7 +269,7 @@ mod tests {
         let mut expected_tokens = Vec::new();
         expected_tokens.push(
             Token::Lexeme(Lexeme{
-                value: "struct",
+                value: Cow::Borrowed("struct"),
                 scope: ScopeStack::from_vec(vec![
                     Scope::new("text.plain").unwrap(),
                 ]),
@@ -286,13 +293,13 @@ mod tests {
         let mut expected_tokens = Vec::new();
         scope_stack.push(Scope::new("source.rust").unwrap());
         expected_tokens.push(Token::Lexeme(Lexeme{
-            value: "€",
+            value: Cow::Borrowed("€"),
             scope: scope_stack.clone(),
             position: Position{ line: 0, offset: 0 }
         }));
         scope_stack.push(Scope::new("constant.numeric.integer.decimal.rust").unwrap());
         expected_tokens.push(Token::Lexeme(Lexeme{
-            value: "16",
+            value: Cow::Borrowed("16"),
             scope: scope_stack.clone(),
             position: Position{ line: 0, offset: 1 }
         }));
@@ -302,4 +309,48 @@ mod tests {
             assert_eq!(token, actual_tokens[index]);
         }
     }
+
+    #[test]
+    fn token_iterator_carries_scopes_across_lines() {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let def = syntax_set.find_syntax_by_extension("rs");
+        let tokens: Vec<Token> = TokenIterator::new("/* scribe\nlibrary */", def.unwrap()).collect();
+        let comment = Scope::new("comment.block.rust").unwrap();
+
+        assert_eq!(tokens.len(), 5);
+        for token in tokens {
+            if let Token::Lexeme(lexeme) = token {
+                assert!(lexeme.scope.as_slice().contains(&comment));
+            }
+        }
+    }
+
+    #[cfg(feature = "serialization")]
+    #[test]
+    fn tokens_can_be_serialized_and_deserialized() {
+        use serde_json;
+
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let def = syntax_set.find_syntax_by_extension("rs");
+        let tokens: Vec<Token> = TokenIterator::new("let x\n", def.unwrap()).collect();
+        let json = serde_json::to_string(&tokens).unwrap();
+
+        let deserialized_tokens: Vec<Token> = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized_tokens, tokens);
+    }
+
+    #[cfg(feature = "serialization")]
+    #[test]
+    fn tokens_with_escaped_content_can_be_serialized_and_deserialized() {
+        use serde_json;
+
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let def = syntax_set.find_syntax_by_extension("rs");
+        let tokens: Vec<Token> = TokenIterator::new("let x = \"\\t\";\t// \"quoted\"\n", def.unwrap()).collect();
+        let json = serde_json::to_string(&tokens).unwrap();
+        assert!(json.contains(r#"\"quoted\""#));
+
+        let deserialized_tokens: Vec<Token> = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized_tokens, tokens);
+    }
 }
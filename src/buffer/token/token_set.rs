@@ -1,5 +1,5 @@
 use syntect::parsing::SyntaxDefinition;
-use buffer::token::TokenIterator;
+use buffer::token::{TokenIterator, WhitespaceTokenIterator};
 
 pub struct TokenSet<'a> {
     data: String,
@@ -17,4 +17,11 @@ impl<'a> TokenSet<'a> {
     pub fn iter(&self) -> TokenIterator {
         TokenIterator::new(&self.data, self.syntax_definition)
     }
+
+    /// Iterates over the tokens, emitting distinct lexemes for tabs, non-breaking
+    /// spaces, and trailing whitespace (see `WhitespaceTokenIterator`), so that
+    /// they can be made visible when rendering.
+    pub fn iter_with_whitespace(&self) -> WhitespaceTokenIterator {
+        WhitespaceTokenIterator::new(self.iter())
+    }
 }
@@ -0,0 +1,157 @@
+use std::collections::VecDeque;
+use buffer::Token;
+use buffer::token::{split_lexeme, TokenIterator};
+use syntect::parsing::Scope;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Wraps a token iterator, splitting lexemes so that tabs, non-breaking
+/// spaces, and trailing whitespace are emitted as distinct lexemes, with
+/// `whitespace.tab`, `whitespace.non-breaking-space`, and `whitespace.trailing`
+/// scopes (respectively) pushed onto their scope stacks. Trailing tabs and
+/// non-breaking spaces have both scopes pushed, with the trailing scope first.
+pub struct WhitespaceTokenIterator<'a> {
+    tokens: TokenIterator<'a>,
+    pending: VecDeque<Token<'a>>,
+    tab: Scope,
+    non_breaking_space: Scope,
+    trailing: Scope,
+}
+
+impl<'a> WhitespaceTokenIterator<'a> {
+    pub fn new(tokens: TokenIterator<'a>) -> WhitespaceTokenIterator<'a> {
+        WhitespaceTokenIterator{
+            tokens,
+            pending: VecDeque::new(),
+            tab: Scope::new("whitespace.tab").unwrap(),
+            non_breaking_space: Scope::new("whitespace.non-breaking-space").unwrap(),
+            trailing: Scope::new("whitespace.trailing").unwrap(),
+        }
+    }
+
+    // Reads the next line's lexemes, splitting them into pending tokens.
+    fn process_next_line(&mut self) {
+        let mut lexemes = Vec::new();
+        let mut newline = false;
+
+        while let Some(token) = self.tokens.next() {
+            match token {
+                Token::Lexeme(lexeme) => lexemes.push(lexeme),
+                Token::Newline => {
+                    newline = true;
+                    break;
+                }
+            }
+        }
+
+        // Find the offset at which the line's trailing whitespace starts.
+        let mut trailing_start = 0;
+        for lexeme in lexemes.iter() {
+            for (index, grapheme) in lexeme.value.graphemes(true).enumerate() {
+                if !is_whitespace(grapheme) {
+                    trailing_start = lexeme.position.offset + index + 1;
+                }
+            }
+        }
+
+        let (tab, non_breaking_space, trailing) = (self.tab, self.non_breaking_space, self.trailing);
+        for lexeme in lexemes {
+            let split_lexemes = split_lexeme(lexeme, |position, grapheme| {
+                let mut scopes = Vec::new();
+                if position.offset >= trailing_start {
+                    scopes.push(trailing);
+                }
+                if grapheme == "\t" {
+                    scopes.push(tab);
+                } else if grapheme == "\u{a0}" {
+                    scopes.push(non_breaking_space);
+                }
+
+                scopes
+            });
+            self.pending.extend(split_lexemes.into_iter().map(Token::Lexeme));
+        }
+
+        if newline {
+            self.pending.push_back(Token::Newline);
+        }
+    }
+}
+
+impl<'a> Iterator for WhitespaceTokenIterator<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pending.is_empty() {
+            self.process_next_line();
+        }
+
+        self.pending.pop_front()
+    }
+}
+
+fn is_whitespace(grapheme: &str) -> bool {
+    grapheme.chars().all(|c| c.is_whitespace())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WhitespaceTokenIterator;
+    use buffer::{Position, Token};
+    use buffer::token::TokenIterator;
+    use syntect::parsing::{Scope, SyntaxSet};
+
+    fn lexemes(data: &str) -> Vec<(String, Vec<String>, Position)> {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let def = syntax_set.find_syntax_plain_text();
+        let whitespace = Scope::new("whitespace").unwrap();
+
+        WhitespaceTokenIterator::new(TokenIterator::new(data, def)).filter_map(|token| {
+            match token {
+                Token::Lexeme(lexeme) => Some((
+                    lexeme.value.to_string(),
+                    lexeme.scope.as_slice().iter()
+                        .filter(|&&scope| whitespace.is_prefix_of(scope))
+                        .map(|scope| scope.build_string())
+                        .collect(),
+                    lexeme.position
+                )),
+                Token::Newline => None,
+            }
+        }).collect()
+    }
+
+    #[test]
+    fn whitespace_token_iterator_splits_tabs_and_non_breaking_spaces() {
+        assert_eq!(lexemes("a\t\tb\u{a0}c"), vec![
+            ("a".to_string(), vec![], Position{ line: 0, offset: 0 }),
+            ("\t\t".to_string(), vec!["whitespace.tab".to_string()], Position{ line: 0, offset: 1 }),
+            ("b".to_string(), vec![], Position{ line: 0, offset: 3 }),
+            ("\u{a0}".to_string(), vec!["whitespace.non-breaking-space".to_string()], Position{ line: 0, offset: 4 }),
+            ("c".to_string(), vec![], Position{ line: 0, offset: 5 }),
+        ]);
+    }
+
+    #[test]
+    fn whitespace_token_iterator_splits_trailing_whitespace() {
+        assert_eq!(lexemes("a b \t\nc"), vec![
+            ("a b".to_string(), vec![], Position{ line: 0, offset: 0 }),
+            (" ".to_string(), vec!["whitespace.trailing".to_string()], Position{ line: 0, offset: 3 }),
+            (
+                "\t".to_string(),
+                vec!["whitespace.trailing".to_string(), "whitespace.tab".to_string()],
+                Position{ line: 0, offset: 4 }
+            ),
+            ("c".to_string(), vec![], Position{ line: 1, offset: 0 }),
+        ]);
+    }
+
+    #[test]
+    fn whitespace_token_iterator_preserves_newlines() {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let def = syntax_set.find_syntax_plain_text();
+        let tokens: Vec<Token> = WhitespaceTokenIterator::new(TokenIterator::new("a\n\nb", def)).collect();
+
+        assert_eq!(tokens.iter().filter(|&token| *token == Token::Newline).count(), 2);
+        assert_eq!(tokens.len(), 4);
+    }
+}
@@ -0,0 +1,311 @@
+//! Positions and ranges that are kept anchored to buffer content as it changes.
+
+use buffer::{Buffer, Distance, Position, Range};
+use std::collections::HashMap;
+
+/// An identifier for a position or range tracked by a buffer.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct TrackingId(usize);
+
+#[derive(Clone, Debug, PartialEq)]
+enum Tracked {
+    Position(Position),
+    Range(Range),
+}
+
+/// Tracked positions and ranges, remapped as content is inserted and deleted.
+/// Entries that are invalidated (i.e. ranges whose content is deleted) are
+/// kept as `None`, so that they can be distinguished from unknown IDs.
+#[derive(Default)]
+pub struct Tracker {
+    next_id: usize,
+    entries: HashMap<usize, Option<Tracked>>,
+}
+
+impl Tracker {
+    fn add(&mut self, entry: Tracked) -> TrackingId {
+        let id = self.next_id;
+        self.entries.insert(id, Some(entry));
+        self.next_id += 1;
+
+        TrackingId(id)
+    }
+
+    /// Shifts entries following the insertion point to account for the
+    /// inserted content. Ranges starting at the insertion point are shifted,
+    /// whereas ranges ending there aren't, so that they don't grow to include
+    /// content inserted immediately after them.
+    pub fn insert(&mut self, content: &str, position: &Position) {
+        let distance = Distance::of_str(content);
+
+        for entry in self.entries.values_mut() {
+            match *entry {
+                Some(Tracked::Position(ref mut tracked_position)) => {
+                    if *tracked_position >= *position {
+                        *tracked_position = shift(*tracked_position, position, distance);
+                    }
+                },
+                Some(Tracked::Range(ref mut range)) => {
+                    let start =
+                        if range.start() >= *position {
+                            shift(range.start(), position, distance)
+                        } else {
+                            range.start()
+                        };
+                    let end =
+                        if range.end() > *position {
+                            shift(range.end(), position, distance)
+                        } else {
+                            range.end()
+                        };
+
+                    // Empty ranges are treated like positions.
+                    *range = Range::new(start, if range.start() == range.end() { start } else { end });
+                },
+                None => (),
+            }
+        }
+    }
+
+    /// Collapses entries within the deleted range onto its start, and shifts
+    /// entries following it. Non-empty ranges whose content is entirely
+    /// deleted are invalidated.
+    pub fn delete(&mut self, deleted_range: &Range) {
+        for entry in self.entries.values_mut() {
+            let invalidated = match *entry {
+                Some(Tracked::Position(ref mut position)) => {
+                    *position = unshift(*position, deleted_range);
+                    false
+                },
+                Some(Tracked::Range(ref mut range)) => {
+                    let invalidated = range.start() != range.end() &&
+                        deleted_range.start() <= range.start() &&
+                        range.end() <= deleted_range.end();
+                    *range = Range::new(
+                        unshift(range.start(), deleted_range),
+                        unshift(range.end(), deleted_range)
+                    );
+
+                    invalidated
+                },
+                None => false,
+            };
+
+            if invalidated {
+                *entry = None;
+            }
+        }
+    }
+
+    /// Invalidates all entries (e.g. when the buffer's content is replaced).
+    #[cfg_attr(not(feature = "system"), allow(dead_code))]
+    pub fn invalidate(&mut self) {
+        for entry in self.entries.values_mut() {
+            *entry = None;
+        }
+    }
+
+    /// Returns the current location of the tracked range, or `None` if the
+    /// ID doesn't refer to a tracked range, or it has been invalidated.
+    pub fn range(&self, id: TrackingId) -> Option<Range> {
+        match self.entries.get(&id.0) {
+            Some(&Some(Tracked::Range(ref range))) => Some(range.clone()),
+            _ => None,
+        }
+    }
+}
+
+// Moves a position at or after the insertion point by the inserted distance.
+fn shift(position: Position, insertion_point: &Position, distance: Distance) -> Position {
+    if position.line == insertion_point.line {
+        *insertion_point + distance + Distance{
+            lines: 0,
+            offset: position.offset - insertion_point.offset
+        }
+    } else {
+        Position{ line: position.line + distance.lines, offset: position.offset }
+    }
+}
+
+// Moves a position to account for the deletion of the specified range.
+fn unshift(position: Position, deleted_range: &Range) -> Position {
+    let start = deleted_range.start();
+    let end = deleted_range.end();
+
+    if position <= start {
+        position
+    } else if position < end {
+        start
+    } else if position.line == end.line {
+        Position{ line: start.line, offset: start.offset + position.offset - end.offset }
+    } else {
+        Position{ line: position.line - (end.line - start.line), offset: position.offset }
+    }
+}
+
+impl Buffer {
+    /// Starts tracking the specified position, which will be remapped as
+    /// content is inserted and deleted (including by undo and redo), so that
+    /// it stays anchored to the same content. Positions within deleted
+    /// content are moved to the start of the deletion.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::Position;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("library");
+    /// let id = buffer.track_position(Position{ line: 0, offset: 3 });
+    ///
+    /// buffer.cursor.move_to(Position{ line: 0, offset: 0 });
+    /// buffer.insert("scribe\n");
+    /// assert_eq!(buffer.tracked_position(id), Some(Position{ line: 1, offset: 3 }));
+    ///
+    /// buffer.undo();
+    /// assert_eq!(buffer.tracked_position(id), Some(Position{ line: 0, offset: 3 }));
+    /// ```
+    pub fn track_position(&mut self, position: Position) -> TrackingId {
+        self.tracker.add(Tracked::Position(position))
+    }
+
+    /// Starts tracking the specified range, which will be remapped as content
+    /// is inserted and deleted (including by undo and redo), so that it stays
+    /// anchored to the same content. Content inserted at the start or end of
+    /// the range isn't included in it. If the range's content is deleted
+    /// entirely, the range is invalidated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{Position, Range};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe library");
+    /// let id = buffer.track_range(Range::new(
+    ///     Position{ line: 0, offset: 7 },
+    ///     Position{ line: 0, offset: 14 }
+    /// ));
+    ///
+    /// // Delete "scribe ".
+    /// buffer.cursor.move_to(Position{ line: 0, offset: 0 });
+    /// for _ in 0..7 { buffer.delete(); }
+    /// assert_eq!(buffer.tracked_range(id), Some(Range::new(
+    ///     Position{ line: 0, offset: 0 },
+    ///     Position{ line: 0, offset: 7 }
+    /// )));
+    ///
+    /// // Delete "library".
+    /// for _ in 0..7 { buffer.delete(); }
+    /// assert_eq!(buffer.tracked_range(id), None);
+    /// ```
+    pub fn track_range(&mut self, range: Range) -> TrackingId {
+        self.tracker.add(Tracked::Range(range))
+    }
+
+    /// Returns the current location of the tracked position, or `None` if the
+    /// ID doesn't refer to a tracked position, or it has been invalidated.
+    pub fn tracked_position(&self, id: TrackingId) -> Option<Position> {
+        match self.tracker.entries.get(&id.0) {
+            Some(&Some(Tracked::Position(position))) => Some(position),
+            _ => None,
+        }
+    }
+
+    /// Returns the current location of the tracked range, or `None` if the
+    /// ID doesn't refer to a tracked range, or it has been invalidated.
+    pub fn tracked_range(&self, id: TrackingId) -> Option<Range> {
+        self.tracker.range(id)
+    }
+
+    /// Stops tracking the specified position or range.
+    pub fn untrack(&mut self, id: TrackingId) {
+        self.tracker.entries.remove(&id.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, Position, Range};
+    #[cfg(feature = "system")]
+    use std::path::Path;
+
+    #[test]
+    fn tracked_positions_before_an_edit_are_unchanged() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        let id = buffer.track_position(Position{ line: 0, offset: 2 });
+
+        buffer.cursor.move_to(Position{ line: 0, offset: 3 });
+        buffer.insert("\nनी");
+        assert_eq!(buffer.tracked_position(id), Some(Position{ line: 0, offset: 2 }));
+    }
+
+    #[test]
+    fn tracked_positions_are_shifted_by_grapheme_offsets() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        let id = buffer.track_position(Position{ line: 0, offset: 3 });
+
+        buffer.cursor.move_to(Position{ line: 0, offset: 1 });
+        buffer.insert("नीनी");
+        assert_eq!(buffer.tracked_position(id), Some(Position{ line: 0, offset: 5 }));
+    }
+
+    #[test]
+    fn tracked_positions_within_deleted_content_collapse_to_its_start() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary\neditor");
+        let inside = buffer.track_position(Position{ line: 1, offset: 3 });
+        let after = buffer.track_position(Position{ line: 2, offset: 4 });
+
+        buffer.replace(
+            Range::new(Position{ line: 0, offset: 2 }, Position{ line: 2, offset: 1 }),
+            ""
+        );
+        assert_eq!(buffer.data(), "scditor");
+        assert_eq!(buffer.tracked_position(inside), Some(Position{ line: 0, offset: 2 }));
+        assert_eq!(buffer.tracked_position(after), Some(Position{ line: 0, offset: 5 }));
+    }
+
+    #[test]
+    fn tracked_ranges_do_not_grow_when_content_is_inserted_at_their_boundaries() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        let id = buffer.track_range(Range::new(
+            Position{ line: 0, offset: 0 },
+            Position{ line: 0, offset: 6 }
+        ));
+
+        buffer.insert(" ");
+        buffer.cursor.move_to(Position{ line: 0, offset: 7 });
+        buffer.insert(" library");
+        assert_eq!(buffer.data(), " scribe library");
+        assert_eq!(buffer.tracked_range(id), Some(Range::new(
+            Position{ line: 0, offset: 1 },
+            Position{ line: 0, offset: 7 }
+        )));
+    }
+
+    #[test]
+    fn untracked_and_mismatched_ids_return_none() {
+        let mut buffer = Buffer::new();
+        let id = buffer.track_position(Position::new());
+        assert!(buffer.tracked_range(id).is_none());
+
+        buffer.untrack(id);
+        assert!(buffer.tracked_position(id).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "system")]
+    fn reload_invalidates_tracked_positions() {
+        let mut buffer = Buffer::from_file(Path::new("tests/sample/file")).unwrap();
+        let id = buffer.track_position(Position{ line: 0, offset: 2 });
+        buffer.reload().unwrap();
+
+        assert!(buffer.tracked_position(id).is_none());
+    }
+}
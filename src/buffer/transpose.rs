@@ -0,0 +1,191 @@
+//! Commands for transposing characters, words, and lines.
+
+use buffer::{Buffer, LineRange, Position, Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+impl Buffer {
+    /// Swaps the characters on either side of the cursor, as a single undoable
+    /// operation, and moves the cursor past them. At the end of a line, the
+    /// two characters preceding the cursor are swapped instead. The buffer is
+    /// left unchanged at the start of a line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::Position;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scirbe");
+    /// buffer.cursor.move_to(Position{ line: 0, offset: 3 });
+    /// buffer.transpose_characters();
+    ///
+    /// assert_eq!(buffer.data(), "scribe");
+    /// assert_eq!(*buffer.cursor, Position{ line: 0, offset: 4 });
+    /// ```
+    pub fn transpose_characters(&mut self) {
+        let position = *self.cursor;
+        let line_content = self.line(position.line);
+        let line: Vec<&str> = match line_content {
+            Some(ref line) => line.graphemes(true).collect(),
+            None => return,
+        };
+        if position.offset == 0 || line.len() < 2 {
+            return
+        }
+
+        // Fall back to the preceding characters at the end of the line.
+        let offset =
+            if position.offset >= line.len() {
+                line.len() - 2
+            } else {
+                position.offset - 1
+            };
+        let range = Range::new(
+            Position{ line: position.line, offset },
+            Position{ line: position.line, offset: offset + 2 }
+        );
+        self.replace(range, format!("{}{}", line[offset + 1], line[offset]));
+        self.cursor.move_to(Position{ line: position.line, offset: offset + 2 });
+    }
+
+    /// Swaps the word at the cursor (see `word_range_at`) with the word that
+    /// follows it on the same line (or, if it's the last word on the line, the
+    /// word that precedes it), as a single undoable operation. If the cursor
+    /// is between words, the words on either side of it are swapped. The
+    /// cursor is moved to the end of the swapped words.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::Position;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("library, scribe");
+    /// buffer.transpose_words();
+    ///
+    /// assert_eq!(buffer.data(), "scribe, library");
+    /// assert_eq!(*buffer.cursor, Position{ line: 0, offset: 15 });
+    /// ```
+    pub fn transpose_words(&mut self) {
+        let position = *self.cursor;
+        let words = self.word_ranges(position.line);
+        let current_word = self.word_range_at(position)
+            .and_then(|word| words.iter().position(|w| *w == word));
+
+        let (first, second) = match current_word {
+            Some(index) if index + 1 < words.len() => (&words[index], &words[index + 1]),
+            Some(index) if index > 0 => (&words[index - 1], &words[index]),
+            Some(_) => return,
+            None => {
+                let preceding_word = words.iter().rev().find(|w| w.end().offset <= position.offset);
+                let following_word = words.iter().find(|w| w.start().offset >= position.offset);
+                match (preceding_word, following_word) {
+                    (Some(first), Some(second)) => (first, second),
+                    _ => return,
+                }
+            },
+        };
+
+        let between = Range::new(first.end(), second.start());
+        let content = match (self.read(first), self.read(&between), self.read(second)) {
+            (Some(first), Some(between), Some(second)) => format!("{}{}{}", second, between, first),
+            _ => return,
+        };
+        self.replace(Range::new(first.start(), second.end()), content);
+        self.cursor.move_to(second.end());
+    }
+
+    /// Swaps the current line with the one that precedes it, as a single
+    /// undoable operation. The cursor follows the current line's content.
+    /// The buffer is left unchanged if the cursor is on the first line.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::Position;
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("library\nscribe");
+    /// buffer.cursor.move_to(Position{ line: 1, offset: 2 });
+    /// buffer.transpose_lines();
+    ///
+    /// assert_eq!(buffer.data(), "scribe\nlibrary");
+    /// assert_eq!(*buffer.cursor, Position{ line: 0, offset: 2 });
+    /// ```
+    pub fn transpose_lines(&mut self) {
+        let line = self.cursor.line;
+        self.move_lines_up(&LineRange::new(line, line + 1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, Position};
+
+    #[test]
+    fn transpose_characters_swaps_preceding_characters_at_the_end_of_a_line() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribne\nlibrary");
+        buffer.cursor.move_to(Position{ line: 0, offset: 7 });
+        buffer.transpose_characters();
+
+        assert_eq!(buffer.data(), "scriben\nlibrary");
+        assert_eq!(*buffer.cursor, Position{ line: 0, offset: 7 });
+    }
+
+    #[test]
+    fn transpose_characters_does_nothing_at_the_start_of_a_line() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe");
+        buffer.cursor.move_to(Position{ line: 0, offset: 0 });
+        buffer.transpose_characters();
+
+        assert_eq!(buffer.data(), "scribe");
+    }
+
+    #[test]
+    fn transpose_characters_swaps_graphemes() {
+        let mut buffer = Buffer::new();
+        buffer.insert("नीa");
+        buffer.cursor.move_to(Position{ line: 0, offset: 1 });
+        buffer.transpose_characters();
+        assert_eq!(buffer.data(), "aनी");
+
+        buffer.undo();
+        assert_eq!(buffer.data(), "नीa");
+    }
+
+    #[test]
+    fn transpose_words_swaps_the_last_word_with_its_predecessor() {
+        let mut buffer = Buffer::new();
+        buffer.insert("a scribe library");
+        buffer.cursor.move_to(Position{ line: 0, offset: 16 });
+        buffer.transpose_words();
+
+        assert_eq!(buffer.data(), "a library scribe");
+        assert_eq!(*buffer.cursor, Position{ line: 0, offset: 16 });
+    }
+
+    #[test]
+    fn transpose_words_swaps_the_words_surrounding_the_cursor() {
+        let mut buffer = Buffer::new();
+        buffer.insert("library  -  scribe");
+        buffer.cursor.move_to(Position{ line: 0, offset: 9 });
+        buffer.transpose_words();
+
+        assert_eq!(buffer.data(), "scribe  -  library");
+    }
+
+    #[test]
+    fn transpose_lines_does_nothing_on_the_first_line() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\nlibrary");
+        buffer.cursor.move_to(Position{ line: 0, offset: 0 });
+        buffer.transpose_lines();
+
+        assert_eq!(buffer.data(), "scribe\nlibrary");
+    }
+}
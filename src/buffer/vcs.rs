@@ -0,0 +1,245 @@
+//! Per-line change states relative to a version-controlled baseline.
+
+use buffer::{Buffer, HunkKind};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[cfg(feature = "git")]
+use git2::Repository;
+
+/// Supplies the baseline version of a file (e.g. its content as of the
+/// latest commit), against which a buffer's changes are determined.
+pub trait BaselineProvider {
+    /// Returns the baseline content for the file at the specified (absolute)
+    /// path, or `None` if the file isn't under version control. Its line
+    /// endings are normalized to line feeds when it's compared to a buffer.
+    fn baseline(&self, path: &Path) -> Option<String>;
+}
+
+/// Supplies baselines from the `HEAD` commit of the
+/// git repository containing the file, if any.
+#[cfg(feature = "git")]
+pub struct GitBaselineProvider;
+
+#[cfg(feature = "git")]
+impl BaselineProvider for GitBaselineProvider {
+    fn baseline(&self, path: &Path) -> Option<String> {
+        let repo = Repository::discover(path).ok()?;
+        let workdir = repo.workdir()?.canonicalize().ok()?;
+        let relative_path = path.strip_prefix(&workdir).ok()?;
+
+        let tree = repo.head().ok()?.peel_to_tree().ok()?;
+        let object = tree.get_path(relative_path).ok()?.to_object(&repo).ok()?;
+        let blob = object.peel_to_blob().ok()?;
+
+        String::from_utf8(blob.content().to_vec()).ok()
+    }
+}
+
+/// The state of a buffer line relative to its baseline.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineChange {
+    Added,
+    Modified,
+
+    /// Lines were removed after this one (or before it, for the first line).
+    Deleted,
+}
+
+impl Buffer {
+    /// Returns the change states of the buffer's lines relative to the
+    /// baseline supplied by its `baseline_provider`, keyed by line number.
+    /// Unchanged lines are omitted, and nothing is returned if the buffer
+    /// doesn't have a provider, a path, or a baseline for that path.
+    ///
+    /// The baseline is cached until the buffer is saved, reloaded, or moved
+    /// to another path, or until it's invalidated using `invalidate_baseline`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{BaselineProvider, LineChange};
+    /// use std::path::{Path, PathBuf};
+    ///
+    /// struct SavedBaseline;
+    ///
+    /// impl BaselineProvider for SavedBaseline {
+    ///     fn baseline(&self, _: &Path) -> Option<String> {
+    ///         Some("it works!\n".to_string())
+    ///     }
+    /// }
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("it works!\n");
+    /// buffer.path = Some(PathBuf::from("tests/sample/file"));
+    /// buffer.baseline_provider = Some(Box::new(SavedBaseline));
+    /// buffer.insert("scribe\n");
+    ///
+    /// let changes = buffer.line_changes();
+    /// assert_eq!(changes.get(&0), Some(&LineChange::Added));
+    /// assert_eq!(changes.get(&1), None);
+    /// ```
+    pub fn line_changes(&self) -> HashMap<usize, LineChange> {
+        let (provider, path) = match (self.baseline_provider.as_ref(), self.path.as_ref()) {
+            (Some(provider), Some(path)) => (provider, path),
+            _ => return HashMap::new(),
+        };
+
+        let mut cache = self.baseline.borrow_mut();
+        let cached = cache.as_ref().map(|&(ref cached_path, _)| cached_path == path).unwrap_or(false);
+        if !cached {
+            *cache = Some((path.clone(), provider.baseline(path)));
+        }
+
+        cache.as_ref()
+            .and_then(|&(_, ref baseline)| baseline.as_ref())
+            .map(|baseline| self.line_changes_against(baseline))
+            .unwrap_or_default()
+    }
+
+    /// Discards the buffer's cached baseline (see `line_changes`), so that
+    /// it's requested from the buffer's `baseline_provider` the next time
+    /// it's needed (e.g. after the baseline is committed to, or the
+    /// provider is replaced).
+    pub fn invalidate_baseline(&mut self) {
+        *self.baseline.borrow_mut() = None;
+    }
+
+    /// Returns the change states of the buffer's lines relative
+    /// to the specified baseline (see `line_changes`).
+    pub fn line_changes_against(&self, baseline: &str) -> HashMap<usize, LineChange> {
+        let mut changes = HashMap::new();
+
+        for hunk in self.diff_against(baseline) {
+            match hunk.kind {
+                HunkKind::Added | HunkKind::Changed => {
+                    let change =
+                        if hunk.kind == HunkKind::Added {
+                            LineChange::Added
+                        } else {
+                            LineChange::Modified
+                        };
+
+                    for line in hunk.new_lines.iter() {
+                        changes.insert(line, change);
+                    }
+                },
+                HunkKind::Removed => {
+                    // Mark the line preceding the removed lines.
+                    let line = hunk.new_lines.start().saturating_sub(1);
+                    changes.entry(line).or_insert(LineChange::Deleted);
+                },
+            }
+        }
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{BaselineProvider, Buffer, LineChange};
+    use std::cell::Cell;
+    use std::path::{Path, PathBuf};
+    use std::rc::Rc;
+
+    struct StaticBaseline(&'static str);
+
+    impl BaselineProvider for StaticBaseline {
+        fn baseline(&self, _: &Path) -> Option<String> {
+            Some(self.0.to_string())
+        }
+    }
+
+    #[test]
+    fn line_changes_against_reports_modified_and_deleted_lines() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe\ntext\neditor");
+        let changes = buffer.line_changes_against("scribe\nlibrary\neditor\ntoolkit");
+
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes.get(&1), Some(&LineChange::Modified));
+        assert_eq!(changes.get(&2), Some(&LineChange::Deleted));
+    }
+
+    #[test]
+    fn line_changes_against_reports_deletions_at_the_start_on_the_first_line() {
+        let mut buffer = Buffer::new();
+        buffer.insert("library");
+
+        assert_eq!(buffer.line_changes_against("scribe\nlibrary").get(&0), Some(&LineChange::Deleted));
+    }
+
+    #[test]
+    fn line_changes_ignores_crlf_line_endings_in_the_baseline() {
+        let mut buffer = Buffer::new();
+        buffer.insert("it works!\n");
+        buffer.path = Some(PathBuf::from("/scribe"));
+        buffer.baseline_provider = Some(Box::new(StaticBaseline("it works!\r\n")));
+
+        assert!(buffer.line_changes().is_empty());
+    }
+
+    struct CountingBaseline(Rc<Cell<usize>>);
+
+    impl BaselineProvider for CountingBaseline {
+        fn baseline(&self, _: &Path) -> Option<String> {
+            self.0.set(self.0.get() + 1);
+            Some("scribe".to_string())
+        }
+    }
+
+    #[test]
+    fn line_changes_caches_the_baseline_until_it_is_invalidated() {
+        let requests = Rc::new(Cell::new(0));
+        let mut buffer = Buffer::new();
+        buffer.insert("library");
+        buffer.path = Some(PathBuf::from("/scribe"));
+        buffer.baseline_provider = Some(Box::new(CountingBaseline(requests.clone())));
+
+        buffer.line_changes();
+        buffer.line_changes();
+        assert_eq!(requests.get(), 1);
+
+        buffer.invalidate_baseline();
+        assert_eq!(buffer.line_changes().get(&0), Some(&LineChange::Modified));
+        assert_eq!(requests.get(), 2);
+
+        buffer.path = Some(PathBuf::from("/library"));
+        buffer.line_changes();
+        assert_eq!(requests.get(), 3);
+    }
+
+    #[test]
+    #[cfg(feature = "system")]
+    fn line_changes_requests_the_baseline_again_after_reloading() {
+        let requests = Rc::new(Cell::new(0));
+        let mut buffer = Buffer::from_file(Path::new("tests/sample/file")).unwrap();
+        buffer.baseline_provider = Some(Box::new(CountingBaseline(requests.clone())));
+
+        buffer.line_changes();
+        buffer.reload().unwrap();
+        buffer.line_changes();
+        assert_eq!(requests.get(), 2);
+    }
+
+    #[test]
+    fn line_changes_requires_a_path() {
+        let mut buffer = Buffer::new();
+        buffer.baseline_provider = Some(Box::new(StaticBaseline("scribe")));
+        buffer.insert("library");
+
+        assert!(buffer.line_changes().is_empty());
+    }
+
+    #[cfg(feature = "git")]
+    #[test]
+    fn git_baseline_provider_reads_committed_content() {
+        use buffer::GitBaselineProvider;
+
+        let path = Path::new("tests/sample/file").canonicalize().unwrap();
+
+        assert_eq!(GitBaselineProvider.baseline(&path), Some("it works!\n".to_string()));
+    }
+}
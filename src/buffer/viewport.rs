@@ -0,0 +1,225 @@
+//! The visible region of a buffer.
+
+use buffer::{Buffer, LineRange, Position, TrackingId};
+use std::cmp;
+
+/// A window onto a buffer's lines, starting at its first visible line and
+/// spanning its height. The first line is tracked by the buffer, so that
+/// the viewport stays on the same content as lines are inserted or removed
+/// above it. Methods take the buffer the viewport was created for.
+#[derive(Debug, PartialEq)]
+pub struct Viewport {
+    anchor: TrackingId,
+    pub height: usize,
+}
+
+impl Viewport {
+    /// Creates a viewport with the specified height,
+    /// starting at the first line of the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{LineRange, Position, Viewport};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\nlibrary\neditor");
+    /// let mut viewport = Viewport::new(&mut buffer, 2);
+    /// viewport.scroll_down(&mut buffer, 1);
+    ///
+    /// // Lines inserted above the viewport move it.
+    /// buffer.cursor.move_to(Position{ line: 0, offset: 0 });
+    /// buffer.insert("text\n");
+    /// assert_eq!(viewport.lines(&buffer), LineRange::new(2, 4));
+    /// ```
+    pub fn new(buffer: &mut Buffer, height: usize) -> Viewport {
+        Viewport{ anchor: buffer.track_position(Position::new()), height }
+    }
+
+    /// The first visible line.
+    pub fn first_line(&self, buffer: &Buffer) -> usize {
+        buffer.tracked_position(self.anchor)
+            .map(|position| position.line)
+            .unwrap_or(0)
+    }
+
+    /// The visible lines, some of which may be beyond the end of the buffer.
+    pub fn lines(&self, buffer: &Buffer) -> LineRange {
+        let first_line = self.first_line(buffer);
+
+        LineRange::new(first_line, first_line + self.height)
+    }
+
+    /// Scrolls the viewport so that it starts at the specified line,
+    /// which is clamped to the buffer's last line.
+    pub fn scroll_to(&mut self, buffer: &mut Buffer, line: usize) {
+        let line = cmp::min(line, buffer.line_count() - 1);
+
+        buffer.untrack(self.anchor);
+        self.anchor = buffer.track_position(Position{ line, offset: 0 });
+    }
+
+    /// Scrolls the viewport the minimum amount needed to make the cursor
+    /// visible, while keeping the specified number of lines visible above
+    /// and below it (limited to half of the viewport's height).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{LineRange, Position, Viewport};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("a\nb\nc\nd\ne\nf\ng\nh");
+    /// let mut viewport = Viewport::new(&mut buffer, 4);
+    ///
+    /// buffer.cursor.move_to(Position{ line: 4, offset: 0 });
+    /// viewport.scroll_to_cursor(&mut buffer, 1);
+    /// assert_eq!(viewport.lines(&buffer), LineRange::new(2, 6));
+    /// ```
+    pub fn scroll_to_cursor(&mut self, buffer: &mut Buffer, scroll_offset: usize) {
+        if self.height == 0 {
+            return
+        }
+
+        let scroll_offset = cmp::min(scroll_offset, (self.height - 1) / 2);
+        let first_line = self.first_line(buffer);
+        let cursor_line = buffer.cursor.line;
+
+        if cursor_line < first_line + scroll_offset {
+            self.scroll_to(buffer, cursor_line.saturating_sub(scroll_offset));
+        } else if cursor_line + scroll_offset >= first_line + self.height {
+            self.scroll_to(buffer, cursor_line + scroll_offset + 1 - self.height);
+        }
+    }
+
+    /// Scrolls the viewport so that the specified position is in its middle.
+    pub fn center_on(&mut self, buffer: &mut Buffer, position: &Position) {
+        self.scroll_to(buffer, position.line.saturating_sub(self.height / 2));
+    }
+
+    /// Scrolls the viewport up by the specified number of lines.
+    pub fn scroll_up(&mut self, buffer: &mut Buffer, lines: usize) {
+        let first_line = self.first_line(buffer);
+        self.scroll_to(buffer, first_line.saturating_sub(lines));
+    }
+
+    /// Scrolls the viewport down by the specified number of lines.
+    pub fn scroll_down(&mut self, buffer: &mut Buffer, lines: usize) {
+        let first_line = self.first_line(buffer);
+        self.scroll_to(buffer, first_line + lines);
+    }
+
+    /// Scrolls the viewport up by half of its height.
+    pub fn scroll_half_page_up(&mut self, buffer: &mut Buffer) {
+        let lines = cmp::max(self.height / 2, 1);
+        self.scroll_up(buffer, lines);
+    }
+
+    /// Scrolls the viewport down by half of its height.
+    pub fn scroll_half_page_down(&mut self, buffer: &mut Buffer) {
+        let lines = cmp::max(self.height / 2, 1);
+        self.scroll_down(buffer, lines);
+    }
+
+    /// Scrolls the viewport up by its height.
+    pub fn scroll_page_up(&mut self, buffer: &mut Buffer) {
+        let lines = cmp::max(self.height, 1);
+        self.scroll_up(buffer, lines);
+    }
+
+    /// Scrolls the viewport down by its height.
+    pub fn scroll_page_down(&mut self, buffer: &mut Buffer) {
+        let lines = cmp::max(self.height, 1);
+        self.scroll_down(buffer, lines);
+    }
+
+    /// Stops tracking the viewport's position in the buffer.
+    pub fn release(self, buffer: &mut Buffer) {
+        buffer.untrack(self.anchor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, LineRange, Position, Range, Viewport};
+
+    fn buffer_with_lines(count: usize) -> Buffer {
+        let mut buffer = Buffer::new();
+        let lines: Vec<String> = (0..count).map(|line| line.to_string()).collect();
+        buffer.insert(lines.join("\n"));
+
+        buffer
+    }
+
+    #[test]
+    fn scroll_to_cursor_scrolls_up_to_the_cursor() {
+        let mut buffer = buffer_with_lines(20);
+        let mut viewport = Viewport::new(&mut buffer, 5);
+        viewport.scroll_to(&mut buffer, 10);
+        buffer.cursor.move_to(Position{ line: 9, offset: 0 });
+        viewport.scroll_to_cursor(&mut buffer, 2);
+
+        assert_eq!(viewport.first_line(&buffer), 7);
+    }
+
+    #[test]
+    fn scroll_to_cursor_leaves_visible_cursors_alone() {
+        let mut buffer = buffer_with_lines(20);
+        let mut viewport = Viewport::new(&mut buffer, 10);
+        buffer.cursor.move_to(Position{ line: 5, offset: 0 });
+        viewport.scroll_to_cursor(&mut buffer, 2);
+
+        assert_eq!(viewport.first_line(&buffer), 0);
+    }
+
+    #[test]
+    fn scroll_to_cursor_limits_the_scroll_offset() {
+        let mut buffer = buffer_with_lines(20);
+        let mut viewport = Viewport::new(&mut buffer, 3);
+        buffer.cursor.move_to(Position{ line: 10, offset: 0 });
+        viewport.scroll_to_cursor(&mut buffer, 5);
+
+        assert_eq!(viewport.lines(&buffer), LineRange::new(9, 12));
+    }
+
+    #[test]
+    fn center_on_places_the_position_in_the_middle() {
+        let mut buffer = buffer_with_lines(20);
+        let mut viewport = Viewport::new(&mut buffer, 5);
+        viewport.center_on(&mut buffer, &Position{ line: 10, offset: 0 });
+
+        assert_eq!(viewport.lines(&buffer), LineRange::new(8, 13));
+    }
+
+    #[test]
+    fn page_scrolling_is_clamped_to_the_buffer() {
+        let mut buffer = buffer_with_lines(12);
+        let mut viewport = Viewport::new(&mut buffer, 5);
+
+        viewport.scroll_half_page_down(&mut buffer);
+        assert_eq!(viewport.first_line(&buffer), 2);
+        viewport.scroll_page_down(&mut buffer);
+        viewport.scroll_page_down(&mut buffer);
+        assert_eq!(viewport.first_line(&buffer), 11);
+        viewport.scroll_page_up(&mut buffer);
+        assert_eq!(viewport.first_line(&buffer), 6);
+        viewport.scroll_page_up(&mut buffer);
+        viewport.scroll_half_page_up(&mut buffer);
+        assert_eq!(viewport.first_line(&buffer), 0);
+    }
+
+    #[test]
+    fn deleting_lines_above_the_viewport_moves_it_up() {
+        let mut buffer = buffer_with_lines(20);
+        let mut viewport = Viewport::new(&mut buffer, 5);
+        viewport.scroll_to(&mut buffer, 10);
+        buffer.replace(
+            Range::new(Position{ line: 2, offset: 0 }, Position{ line: 5, offset: 0 }),
+            ""
+        );
+
+        assert_eq!(viewport.first_line(&buffer), 7);
+    }
+}
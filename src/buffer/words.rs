@@ -0,0 +1,108 @@
+//! Word boundary lookups, used by word-based editing commands.
+
+use buffer::{Buffer, Position, Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+impl Buffer {
+    /// Returns the range of the word at the specified position, using Unicode
+    /// word boundaries. Positions immediately following a word (e.g. the
+    /// cursor after typing one) are considered to be part of it. Returns
+    /// `None` if there's no word at the position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{Position, Range};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe library");
+    ///
+    /// assert_eq!(
+    ///     buffer.word_range_at(Position{ line: 0, offset: 9 }),
+    ///     Some(Range::new(
+    ///         Position{ line: 0, offset: 7 },
+    ///         Position{ line: 0, offset: 14 }
+    ///     ))
+    /// );
+    /// ```
+    pub fn word_range_at(&self, position: Position) -> Option<Range> {
+        let word_ranges = self.word_ranges(position.line);
+
+        word_ranges.iter()
+            .find(|range| range.start().offset <= position.offset && position.offset < range.end().offset)
+            .or_else(|| word_ranges.iter().find(|range| range.end().offset == position.offset))
+            .cloned()
+    }
+
+    /// Returns the ranges of the words on the specified line, in order,
+    /// using Unicode word boundaries. Segments without any alphanumeric
+    /// characters (e.g. whitespace and punctuation) aren't considered words.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Buffer;
+    /// use scribe::buffer::{Position, Range};
+    ///
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe, library");
+    ///
+    /// assert_eq!(
+    ///     buffer.word_ranges(0),
+    ///     vec![
+    ///         Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 6 }),
+    ///         Range::new(Position{ line: 0, offset: 8 }, Position{ line: 0, offset: 15 })
+    ///     ]
+    /// );
+    /// ```
+    pub fn word_ranges(&self, line: usize) -> Vec<Range> {
+        let data = self.data();
+        let content = match data.split('\n').nth(line) {
+            Some(content) => content,
+            None => return Vec::new(),
+        };
+
+        // Convert the word boundaries' byte offsets to grapheme offsets.
+        let mut offset = 0;
+        let mut ranges = Vec::new();
+        for segment in content.split_word_bounds() {
+            let length = segment.graphemes(true).count();
+            if segment.chars().any(|c| c.is_alphanumeric()) {
+                ranges.push(Range::new(
+                    Position{ line, offset },
+                    Position{ line, offset: offset + length }
+                ));
+            }
+
+            offset += length;
+        }
+
+        ranges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use buffer::{Buffer, Position, Range};
+
+    #[test]
+    fn word_range_at_includes_positions_at_the_end_of_a_word() {
+        let mut buffer = Buffer::new();
+        buffer.insert("scribe library");
+
+        assert_eq!(
+            buffer.word_range_at(Position{ line: 0, offset: 6 }),
+            Some(Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 6 }))
+        );
+    }
+
+    #[test]
+    fn word_range_at_returns_none_for_whitespace_and_punctuation() {
+        let mut buffer = Buffer::new();
+        buffer.insert("a  ->  b");
+
+        assert!(buffer.word_range_at(Position{ line: 0, offset: 4 }).is_none());
+        assert!(buffer.word_range_at(Position{ line: 1, offset: 0 }).is_none());
+    }
+}
@@ -0,0 +1,87 @@
+//! Access to the system clipboard, for sharing content with other applications.
+
+#[cfg(feature = "clipboard")]
+use arboard;
+
+/// A source and destination for content shared with other applications.
+pub trait ClipboardProvider {
+    /// Returns the clipboard's content, if it holds any text.
+    fn contents(&mut self) -> Option<String>;
+
+    /// Replaces the clipboard's content, returning false if it can't be set.
+    fn set_contents(&mut self, content: &str) -> bool;
+}
+
+/// A clipboard that only shares content within the current process,
+/// used when the system clipboard is unavailable.
+#[derive(Debug, Default)]
+pub struct MemoryClipboard {
+    content: Option<String>,
+}
+
+impl ClipboardProvider for MemoryClipboard {
+    fn contents(&mut self) -> Option<String> {
+        self.content.clone()
+    }
+
+    fn set_contents(&mut self, content: &str) -> bool {
+        self.content = Some(content.to_string());
+
+        true
+    }
+}
+
+/// The system clipboard (using X11 or Wayland on Linux, and the
+/// native clipboard on macOS and Windows).
+#[cfg(feature = "clipboard")]
+pub struct SystemClipboard {
+    clipboard: arboard::Clipboard,
+}
+
+#[cfg(feature = "clipboard")]
+impl SystemClipboard {
+    /// Connects to the system clipboard, returning `None`
+    /// if it's unavailable (e.g. there's no display).
+    pub fn new() -> Option<SystemClipboard> {
+        arboard::Clipboard::new().ok().map(|clipboard| SystemClipboard{ clipboard })
+    }
+}
+
+#[cfg(feature = "clipboard")]
+impl ClipboardProvider for SystemClipboard {
+    fn contents(&mut self) -> Option<String> {
+        self.clipboard.get_text().ok()
+    }
+
+    fn set_contents(&mut self, content: &str) -> bool {
+        self.clipboard.set_text(content).is_ok()
+    }
+}
+
+/// Returns the system clipboard if it's available (and the `clipboard`
+/// feature is enabled), falling back to an in-memory clipboard.
+pub fn default_clipboard() -> Box<ClipboardProvider> {
+    #[cfg(feature = "clipboard")]
+    {
+        if let Some(clipboard) = SystemClipboard::new() {
+            return Box::new(clipboard)
+        }
+    }
+
+    Box::new(MemoryClipboard::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClipboardProvider, MemoryClipboard};
+
+    #[test]
+    fn memory_clipboard_returns_the_last_content_set() {
+        let mut clipboard = MemoryClipboard::default();
+        assert_eq!(clipboard.contents(), None);
+
+        assert!(clipboard.set_contents("scribe"));
+        assert!(clipboard.set_contents("library"));
+        assert_eq!(clipboard.contents(), Some("library".to_string()));
+    }
+}
@@ -1,4 +1,9 @@
 error_chain! {
+    foreign_links {
+        Io(::std::io::Error);
+        ThemeLoading(::syntect::LoadingError) #[cfg(feature = "syntax")];
+    }
+
     errors {
         EmptyWorkspace {
             description("the workspace is empty")
@@ -12,5 +17,33 @@ error_chain! {
             description("couldn't find any scopes at the cursor position")
             display("couldn't find any scopes at the cursor position")
         }
+        MissingCommentTokens {
+            description("couldn't find comment tokens for the buffer's syntax")
+            display("couldn't find comment tokens for the buffer's syntax")
+        }
+        InvalidPatch(line: usize) {
+            description("couldn't parse the patch")
+            display("couldn't parse the patch at line {}", line)
+        }
+        UnsupportedStateVersion(version: u32) {
+            description("unsupported buffer state version")
+            display("unsupported buffer state version: {}", version)
+        }
+        UnknownTheme(name: String) {
+            description("couldn't find the theme")
+            display("couldn't find a theme named {}", name)
+        }
+        InvalidThemePath(path: ::std::path::PathBuf) {
+            description("couldn't determine the theme's name from its path")
+            display("couldn't determine the theme's name from its path: {}", path.display())
+        }
+        MissingSearchIndex {
+            description("the workspace's search index hasn't been built")
+            display("the workspace's search index hasn't been built")
+        }
+        SearchIndexingFailed {
+            description("the search index couldn't be built")
+            display("the search index couldn't be built")
+        }
     }
 }
@@ -0,0 +1,571 @@
+//! A C API for embedding buffers in non-Rust editors and GUI shells.
+//!
+//! Buffers are exposed as opaque `ScribeBuffer` pointers, created using
+//! `scribe_buffer_new` and released using `scribe_buffer_free`. Strings passed
+//! to these functions must be NUL-terminated UTF-8, and strings they return
+//! are owned by the caller, who must release them using `scribe_string_free`.
+//! Null pointers are rejected (yielding `false` or a null pointer) rather than
+//! dereferenced, but all other pointers must be valid, which is why every
+//! function is `unsafe`. Panics don't unwind into the caller; they yield the
+//! same values as rejected arguments. Buffers and token iterators must only
+//! be used by the thread that created them.
+//!
+//! The API is exported by the crate's shared library, which can be built
+//! using `cargo rustc --release --features ffi --crate-type cdylib`. Its
+//! declarations are provided by the `include/scribe.h` C header.
+
+use buffer::{Buffer, Position, Range, Token};
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use syntect::parsing::SyntaxSet;
+use unicode_segmentation::UnicodeSegmentation;
+
+thread_local! {
+    // Loading syntax definitions is expensive, so they're shared by buffers.
+    static SYNTAX_SET: RefCell<Option<SyntaxSet>> = const { RefCell::new(None) };
+}
+
+/// An opaque buffer handle.
+pub struct ScribeBuffer {
+    buffer: Buffer,
+}
+
+/// A position within a buffer, whose offset is measured in grapheme clusters.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScribePosition {
+    pub line: usize,
+    pub offset: usize,
+}
+
+/// A range within a buffer (end exclusive).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScribeRange {
+    pub start: ScribePosition,
+    pub end: ScribePosition,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScribeTokenKind {
+    Lexeme,
+    Newline,
+}
+
+/// A token produced by `scribe_tokens_next`. For lexemes, `value` holds their
+/// content and `scope` their innermost scope (e.g. "keyword.control.rust"),
+/// both of which remain valid until the token iterator is released. For
+/// newlines, both are null.
+#[repr(C)]
+#[derive(Debug)]
+pub struct ScribeToken {
+    pub kind: ScribeTokenKind,
+    pub value: *const c_char,
+    pub scope: *const c_char,
+    pub position: ScribePosition,
+}
+
+/// An opaque token iterator handle.
+pub struct ScribeTokens {
+    tokens: Vec<ScribeToken>,
+    next: usize,
+
+    // Owns the strings referenced by the tokens.
+    #[allow(dead_code)]
+    strings: Vec<CString>,
+}
+
+impl From<ScribePosition> for Position {
+    fn from(position: ScribePosition) -> Position {
+        Position{ line: position.line, offset: position.offset }
+    }
+}
+
+impl From<Position> for ScribePosition {
+    fn from(position: Position) -> ScribePosition {
+        ScribePosition{ line: position.line, offset: position.offset }
+    }
+}
+
+impl From<ScribeRange> for Range {
+    fn from(range: ScribeRange) -> Range {
+        Range::new(range.start.into(), range.end.into())
+    }
+}
+
+// Borrows a NUL-terminated UTF-8 string, returning None if it's null or invalid.
+unsafe fn str_from<'a>(content: *const c_char) -> Option<&'a str> {
+    if content.is_null() {
+        return None
+    }
+
+    CStr::from_ptr(content).to_str().ok()
+}
+
+// Hands ownership of the string to the caller, replacing
+// interior NUL characters, which C strings can't represent.
+fn into_c_string(content: String) -> *mut c_char {
+    CString::new(content.replace('\0', "\u{fffd}"))
+        .map(|content| content.into_raw())
+        .unwrap_or(ptr::null_mut())
+}
+
+// Runs the function's body, returning the fallback if it panics, since
+// unwinding into foreign code is undefined behaviour. Buffers are left as
+// they were when the panic occurred, which is why unwind safety is asserted.
+fn guard<T, F: FnOnce() -> T>(fallback: T, body: F) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(fallback)
+}
+
+/// Creates an empty buffer.
+#[no_mangle]
+pub extern "C" fn scribe_buffer_new() -> *mut ScribeBuffer {
+    guard(ptr::null_mut(), || {
+        Box::into_raw(Box::new(ScribeBuffer{ buffer: Buffer::new() }))
+    })
+}
+
+/// Releases the buffer. Does nothing if it's null.
+///
+/// # Safety
+///
+/// `buffer` must be null, or a buffer returned by `scribe_buffer_new` that
+/// hasn't already been released. It mustn't be used once it's released.
+#[no_mangle]
+pub unsafe extern "C" fn scribe_buffer_free(buffer: *mut ScribeBuffer) {
+    guard((), || {
+        if !buffer.is_null() {
+            drop(Box::from_raw(buffer));
+        }
+    })
+}
+
+/// Sets the buffer's syntax definition using the specified file extension
+/// (e.g. "rs"), which is required to iterate over its tokens. Returns false
+/// if no syntax definition matches the extension.
+///
+/// # Safety
+///
+/// `buffer` must be null, or a buffer returned by `scribe_buffer_new` that
+/// hasn't been released. `extension` must be null, or a NUL-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn scribe_buffer_set_syntax(buffer: *mut ScribeBuffer, extension: *const c_char) -> bool {
+    guard(false, || {
+        let (buffer, extension) = match (buffer.as_mut(), str_from(extension)) {
+            (Some(buffer), Some(extension)) => (buffer, extension),
+            _ => return false,
+        };
+
+        SYNTAX_SET.with(|syntax_set| {
+            let mut syntax_set = syntax_set.borrow_mut();
+            let syntax_set = syntax_set.get_or_insert_with(|| {
+                let mut syntax_set = SyntaxSet::load_defaults_newlines();
+                syntax_set.link_syntaxes();
+
+                syntax_set
+            });
+
+            match syntax_set.find_syntax_by_extension(extension) {
+                Some(definition) => {
+                    buffer.buffer.syntax_definition = Some(definition.clone());
+                    true
+                },
+                None => false,
+            }
+        })
+    })
+}
+
+/// Returns the buffer's content. The string must be released
+/// using `scribe_string_free`.
+///
+/// # Safety
+///
+/// `buffer` must be null, or a buffer returned by `scribe_buffer_new` that
+/// hasn't been released. The returned string is owned by the caller.
+#[no_mangle]
+pub unsafe extern "C" fn scribe_buffer_data(buffer: *const ScribeBuffer) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        match buffer.as_ref() {
+            Some(buffer) => into_c_string(buffer.buffer.data()),
+            None => ptr::null_mut(),
+        }
+    })
+}
+
+/// Returns the content within the specified range, or null if it's out
+/// of bounds. The string must be released using `scribe_string_free`.
+///
+/// # Safety
+///
+/// `buffer` must be null, or a buffer returned by `scribe_buffer_new` that
+/// hasn't been released. The returned string is owned by the caller.
+#[no_mangle]
+pub unsafe extern "C" fn scribe_buffer_read(buffer: *const ScribeBuffer, range: ScribeRange) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        buffer.as_ref()
+            .and_then(|buffer| buffer.buffer.read(&range.into()))
+            .map(into_c_string)
+            .unwrap_or(ptr::null_mut())
+    })
+}
+
+/// Returns the cursor's position.
+///
+/// # Safety
+///
+/// `buffer` must be null, or a buffer returned by `scribe_buffer_new` that
+/// hasn't been released.
+#[no_mangle]
+pub unsafe extern "C" fn scribe_buffer_cursor(buffer: *const ScribeBuffer) -> ScribePosition {
+    guard(ScribePosition{ line: 0, offset: 0 }, || {
+        match buffer.as_ref() {
+            Some(buffer) => (*buffer.buffer.cursor).into(),
+            None => ScribePosition{ line: 0, offset: 0 },
+        }
+    })
+}
+
+/// Moves the cursor to the specified position, returning false if it's out of bounds.
+///
+/// # Safety
+///
+/// `buffer` must be null, or a buffer returned by `scribe_buffer_new` that
+/// hasn't been released.
+#[no_mangle]
+pub unsafe extern "C" fn scribe_buffer_move_cursor(buffer: *mut ScribeBuffer, position: ScribePosition) -> bool {
+    guard(false, || {
+        match buffer.as_mut() {
+            Some(buffer) => buffer.buffer.cursor.move_to(position.into()),
+            None => false,
+        }
+    })
+}
+
+/// Inserts the content at the cursor.
+///
+/// # Safety
+///
+/// `buffer` must be null, or a buffer returned by `scribe_buffer_new` that
+/// hasn't been released. `content` must be null, or a NUL-terminated
+/// string, which remains owned by the caller.
+#[no_mangle]
+pub unsafe extern "C" fn scribe_buffer_insert(buffer: *mut ScribeBuffer, content: *const c_char) -> bool {
+    guard(false, || {
+        match (buffer.as_mut(), str_from(content)) {
+            (Some(buffer), Some(content)) => {
+                buffer.buffer.insert(content);
+                true
+            },
+            _ => false,
+        }
+    })
+}
+
+/// Deletes the character at the cursor.
+///
+/// # Safety
+///
+/// `buffer` must be null, or a buffer returned by `scribe_buffer_new` that
+/// hasn't been released.
+#[no_mangle]
+pub unsafe extern "C" fn scribe_buffer_delete(buffer: *mut ScribeBuffer) -> bool {
+    guard(false, || {
+        match buffer.as_mut() {
+            Some(buffer) => {
+                buffer.buffer.delete();
+                true
+            },
+            None => false,
+        }
+    })
+}
+
+/// Deletes the content within the specified range.
+///
+/// # Safety
+///
+/// `buffer` must be null, or a buffer returned by `scribe_buffer_new` that
+/// hasn't been released.
+#[no_mangle]
+pub unsafe extern "C" fn scribe_buffer_delete_range(buffer: *mut ScribeBuffer, range: ScribeRange) -> bool {
+    guard(false, || {
+        match buffer.as_mut() {
+            Some(buffer) => {
+                buffer.buffer.delete_range(range.into());
+                true
+            },
+            None => false,
+        }
+    })
+}
+
+/// Reverses the buffer's last change.
+///
+/// # Safety
+///
+/// `buffer` must be null, or a buffer returned by `scribe_buffer_new` that
+/// hasn't been released.
+#[no_mangle]
+pub unsafe extern "C" fn scribe_buffer_undo(buffer: *mut ScribeBuffer) -> bool {
+    guard(false, || {
+        match buffer.as_mut() {
+            Some(buffer) => {
+                buffer.buffer.undo();
+                true
+            },
+            None => false,
+        }
+    })
+}
+
+/// Re-applies the buffer's last undone change.
+///
+/// # Safety
+///
+/// `buffer` must be null, or a buffer returned by `scribe_buffer_new` that
+/// hasn't been released.
+#[no_mangle]
+pub unsafe extern "C" fn scribe_buffer_redo(buffer: *mut ScribeBuffer) -> bool {
+    guard(false, || {
+        match buffer.as_mut() {
+            Some(buffer) => {
+                buffer.buffer.redo();
+                true
+            },
+            None => false,
+        }
+    })
+}
+
+/// Returns an iterator over the buffer's tokens, or null if the buffer
+/// doesn't have a syntax definition (see `scribe_buffer_set_syntax`).
+/// The iterator must be released using `scribe_tokens_free`.
+///
+/// # Safety
+///
+/// `buffer` must be null, or a buffer returned by `scribe_buffer_new` that
+/// hasn't been released. The returned iterator is owned by the caller,
+/// and holds copies of the tokens' strings, so it remains valid if the buffer
+/// is changed or released.
+#[no_mangle]
+pub unsafe extern "C" fn scribe_buffer_tokens(buffer: *const ScribeBuffer) -> *mut ScribeTokens {
+    guard(ptr::null_mut(), || {
+        let token_set = match buffer.as_ref().and_then(|buffer| buffer.buffer.tokens().ok()) {
+            Some(token_set) => token_set,
+            None => return ptr::null_mut(),
+        };
+        let mut tokens = Vec::new();
+        let mut strings = Vec::new();
+
+        // The end of the last lexeme, at which newlines are positioned.
+        let mut line_end = ScribePosition{ line: 0, offset: 0 };
+
+        for token in token_set.iter() {
+            match token {
+                Token::Lexeme(lexeme) => {
+                    line_end = ScribePosition{
+                        line: lexeme.position.line,
+                        offset: lexeme.position.offset + lexeme.value.graphemes(true).count(),
+                    };
+
+                    let scope = lexeme.scope.as_slice().last()
+                        .map(|scope| scope.build_string())
+                        .unwrap_or_default();
+                    let value = CString::new(lexeme.value.replace('\0', "\u{fffd}")).unwrap_or_default();
+                    let scope = CString::new(scope).unwrap_or_default();
+
+                    // The strings' heap allocations don't move when they're
+                    // moved into the iterator, so these pointers remain valid.
+                    tokens.push(ScribeToken{
+                        kind: ScribeTokenKind::Lexeme,
+                        value: value.as_ptr(),
+                        scope: scope.as_ptr(),
+                        position: lexeme.position.into(),
+                    });
+                    strings.push(value);
+                    strings.push(scope);
+                },
+                Token::Newline => {
+                    tokens.push(ScribeToken{
+                        kind: ScribeTokenKind::Newline,
+                        value: ptr::null(),
+                        scope: ptr::null(),
+                        position: line_end,
+                    });
+                    line_end = ScribePosition{ line: line_end.line + 1, offset: 0 };
+                },
+            }
+        }
+
+        Box::into_raw(Box::new(ScribeTokens{ tokens, next: 0, strings }))
+    })
+}
+
+/// Returns the iterator's next token, or null once it's exhausted.
+/// The token remains valid until the iterator is released.
+///
+/// # Safety
+///
+/// `tokens` must be null, or an iterator returned by `scribe_buffer_tokens`
+/// that hasn't been released. The returned token (and its strings) are owned
+/// by the iterator, and mustn't be released using `scribe_string_free`.
+#[no_mangle]
+pub unsafe extern "C" fn scribe_tokens_next(tokens: *mut ScribeTokens) -> *const ScribeToken {
+    guard(ptr::null(), || {
+        let tokens = match tokens.as_mut() {
+            Some(tokens) => tokens,
+            None => return ptr::null(),
+        };
+
+        match tokens.tokens.get(tokens.next) {
+            Some(token) => {
+                tokens.next += 1;
+                token
+            },
+            None => ptr::null(),
+        }
+    })
+}
+
+/// Releases the token iterator and its tokens. Does nothing if it's null.
+///
+/// # Safety
+///
+/// `tokens` must be null, or an iterator returned by `scribe_buffer_tokens`
+/// that hasn't already been released. Neither it nor its tokens can be used
+/// once it's released.
+#[no_mangle]
+pub unsafe extern "C" fn scribe_tokens_free(tokens: *mut ScribeTokens) {
+    guard((), || {
+        if !tokens.is_null() {
+            drop(Box::from_raw(tokens));
+        }
+    })
+}
+
+/// Releases a string returned by this API. Does nothing if it's null.
+///
+/// # Safety
+///
+/// `content` must be null, or a string returned by `scribe_buffer_data` or
+/// `scribe_buffer_read` that hasn't already been released. It mustn't be used
+/// once it's released.
+#[no_mangle]
+pub unsafe extern "C" fn scribe_string_free(content: *mut c_char) {
+    guard((), || {
+        if !content.is_null() {
+            drop(CString::from_raw(content));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn c_string(content: &str) -> CString {
+        CString::new(content).unwrap()
+    }
+
+    unsafe fn take_string(content: *mut c_char) -> String {
+        let string = CStr::from_ptr(content).to_str().unwrap().to_string();
+        scribe_string_free(content);
+
+        string
+    }
+
+    #[test]
+    fn buffers_can_be_edited_and_read() {
+        unsafe {
+            let buffer = scribe_buffer_new();
+            assert!(scribe_buffer_insert(buffer, c_string("scribe library").as_ptr()));
+            assert!(scribe_buffer_move_cursor(buffer, ScribePosition{ line: 0, offset: 6 }));
+            assert!(!scribe_buffer_move_cursor(buffer, ScribePosition{ line: 1, offset: 0 }));
+            assert!(scribe_buffer_delete(buffer));
+            assert_eq!(scribe_buffer_cursor(buffer), ScribePosition{ line: 0, offset: 6 });
+
+            let range = ScribeRange{
+                start: ScribePosition{ line: 0, offset: 6 },
+                end: ScribePosition{ line: 0, offset: 13 },
+            };
+            assert_eq!(take_string(scribe_buffer_read(buffer, range)), "library");
+            assert!(scribe_buffer_delete_range(buffer, range));
+            assert_eq!(take_string(scribe_buffer_data(buffer)), "scribe");
+
+            assert!(scribe_buffer_undo(buffer));
+            assert_eq!(take_string(scribe_buffer_data(buffer)), "scribelibrary");
+            assert!(scribe_buffer_redo(buffer));
+            assert_eq!(take_string(scribe_buffer_data(buffer)), "scribe");
+
+            scribe_buffer_free(buffer);
+        }
+    }
+
+    #[test]
+    fn null_pointers_and_invalid_arguments_are_rejected() {
+        unsafe {
+            let buffer = scribe_buffer_new();
+            let out_of_bounds = ScribeRange{
+                start: ScribePosition{ line: 0, offset: 0 },
+                end: ScribePosition{ line: 2, offset: 0 },
+            };
+
+            assert!(!scribe_buffer_insert(buffer, ptr::null()));
+            assert!(!scribe_buffer_insert(ptr::null_mut(), c_string("scribe").as_ptr()));
+            assert!(scribe_buffer_read(buffer, out_of_bounds).is_null());
+            assert!(scribe_buffer_data(ptr::null()).is_null());
+            assert!(!scribe_buffer_set_syntax(buffer, c_string("unknown").as_ptr()));
+            assert!(scribe_buffer_tokens(buffer).is_null());
+            assert!(scribe_tokens_next(ptr::null_mut()).is_null());
+
+            scribe_buffer_free(buffer);
+            scribe_buffer_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn guard_returns_the_fallback_when_panicking() {
+        assert_eq!(guard(false, || true), true);
+        assert_eq!(guard(false, || -> bool { panic!("scribe") }), false);
+    }
+
+    #[test]
+    fn tokens_are_iterated_as_structs() {
+        unsafe {
+            let buffer = scribe_buffer_new();
+            scribe_buffer_insert(buffer, c_string("struct\nx").as_ptr());
+            assert!(scribe_buffer_set_syntax(buffer, c_string("rs").as_ptr()));
+
+            let tokens = scribe_buffer_tokens(buffer);
+            let mut lexemes = Vec::new();
+            let mut newlines = Vec::new();
+            loop {
+                let token = scribe_tokens_next(tokens);
+                if token.is_null() {
+                    break
+                }
+
+                match (*token).kind {
+                    ScribeTokenKind::Lexeme => lexemes.push((
+                        CStr::from_ptr((*token).value).to_str().unwrap().to_string(),
+                        CStr::from_ptr((*token).scope).to_str().unwrap().to_string(),
+                        (*token).position,
+                    )),
+                    ScribeTokenKind::Newline => newlines.push((*token).position),
+                }
+            }
+            scribe_tokens_free(tokens);
+            scribe_buffer_free(buffer);
+
+            assert_eq!(lexemes[0], ("struct".to_string(), "storage.type.struct.rust".to_string(), ScribePosition{ line: 0, offset: 0 }));
+            assert_eq!(newlines[0], ScribePosition{ line: 0, offset: 6 });
+            assert_eq!(lexemes.last().unwrap().2, ScribePosition{ line: 1, offset: 0 });
+        }
+    }
+}
@@ -1,18 +1,62 @@
 // Syntax highlighting
+#[cfg(feature = "syntax")]
 extern crate syntect;
 
 // Grapheme cluster iteration
 extern crate unicode_segmentation;
 
+// Display width of (e.g. East Asian wide) characters
+extern crate unicode_width;
+
+// Vectorized newline search
+extern crate memchr;
+
 // Error definition/handling
 #[macro_use]
 extern crate error_chain;
 
+// Serialization support
+#[cfg(feature = "serialization")]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(test)]
+#[cfg(feature = "serialization")]
+extern crate serde_json;
+
+// Version control baselines
+#[cfg(feature = "git")]
+extern crate git2;
+
+// System clipboard access
+#[cfg(feature = "clipboard")]
+extern crate arboard;
+
 pub mod buffer;
+pub mod clipboard;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod registers;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+#[cfg(feature = "syntax")]
+pub mod themes;
 pub mod util;
 mod errors;
+#[cfg(feature = "system")]
+mod recent_files;
+#[cfg(feature = "system")]
+mod search_index;
+#[cfg(feature = "system")]
 mod workspace;
 
 pub use errors::*;
 pub use buffer::Buffer;
-pub use workspace::Workspace;
+pub use registers::{Register, RegisterKind, Registers};
+#[cfg(feature = "syntax")]
+pub use themes::Themes;
+#[cfg(feature = "system")]
+pub use recent_files::{RecentFile, RecentFiles, DEFAULT_RECENT_FILES_LIMIT};
+#[cfg(feature = "system")]
+pub use search_index::{IndexMatch, TrigramIndex, MAX_INDEXED_FILE_SIZE};
+#[cfg(feature = "system")]
+pub use workspace::{BufferKind, BufferListing, GlobalMark, Workspace};
@@ -0,0 +1,240 @@
+//! Recently opened files, ranked for quick-open menus.
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The default number of files retained in a recent files list.
+pub const DEFAULT_RECENT_FILES_LIMIT: usize = 100;
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+/// A file opened through a workspace.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct RecentFile {
+    pub path: PathBuf,
+
+    /// When the file was last opened, in seconds since the Unix epoch.
+    pub opened_at: u64,
+
+    /// The number of times the file has been opened.
+    pub open_count: usize,
+}
+
+impl RecentFile {
+    // Weighs the number of times the file has been opened by how recently
+    // it was last opened, so that frequently opened files are ranked highly,
+    // without permanently outranking files that are only recently in use.
+    fn score(&self, now: u64) -> usize {
+        let age = now.saturating_sub(self.opened_at);
+        let weight =
+            if age < HOUR {
+                8
+            } else if age < DAY {
+                4
+            } else if age < WEEK {
+                2
+            } else {
+                1
+            };
+
+        self.open_count * weight
+    }
+}
+
+/// A bounded list of recently opened files, which workspaces record as
+/// buffers are opened (see `Workspace::open_buffer`). The list can be
+/// persisted across sessions by serializing its entries (using the
+/// `serialization` feature) and restoring them using `restore`.
+///
+/// # Examples
+///
+/// ```
+/// use scribe::RecentFiles;
+/// use std::path::Path;
+///
+/// let mut recent_files = RecentFiles::new();
+/// recent_files.record_at(Path::new("/scribe/src/lib.rs"), 1_000);
+/// recent_files.record_at(Path::new("/scribe/README.md"), 2_000);
+/// recent_files.record_at(Path::new("/scribe/src/lib.rs"), 3_000);
+///
+/// assert_eq!(recent_files.ranked_at(4_000), vec![
+///     Path::new("/scribe/src/lib.rs"),
+///     Path::new("/scribe/README.md"),
+/// ]);
+/// ```
+#[derive(Debug)]
+pub struct RecentFiles {
+    // Ordered from most to least recently opened.
+    files: Vec<RecentFile>,
+    limit: usize,
+}
+
+impl Default for RecentFiles {
+    fn default() -> Self {
+        RecentFiles{ files: Vec::new(), limit: DEFAULT_RECENT_FILES_LIMIT }
+    }
+}
+
+impl RecentFiles {
+    pub fn new() -> RecentFiles {
+        Default::default()
+    }
+
+    /// Records that the file at the specified path was opened just now.
+    pub fn record(&mut self, path: &Path) {
+        self.record_at(path, now());
+    }
+
+    /// Records that the file at the specified path was opened at the
+    /// specified time (in seconds since the Unix epoch), moving it to the
+    /// front of the list and discarding the least recently opened files
+    /// beyond the list's limit.
+    pub fn record_at(&mut self, path: &Path, opened_at: u64) {
+        let open_count = match self.files.iter().position(|file| file.path == path) {
+            Some(index) => self.files.remove(index).open_count + 1,
+            None => 1,
+        };
+
+        self.files.insert(0, RecentFile{ path: path.to_path_buf(), opened_at, open_count });
+        self.files.truncate(self.limit);
+    }
+
+    /// Returns the recorded files, ordered from most to least recently
+    /// opened (e.g. to persist them).
+    pub fn files(&self) -> &[RecentFile] {
+        &self.files
+    }
+
+    /// Returns the recorded paths, ranked for display in a quick-open menu
+    /// (see `ranked_at`).
+    pub fn ranked(&self) -> Vec<&Path> {
+        self.ranked_at(now())
+    }
+
+    /// Returns the recorded paths ranked as of the specified time (in
+    /// seconds since the Unix epoch). Files are ranked by the number of times
+    /// they've been opened, weighted by how recently they were last opened,
+    /// with ties going to the most recently opened file.
+    pub fn ranked_at(&self, now: u64) -> Vec<&Path> {
+        let mut files: Vec<&RecentFile> = self.files.iter().collect();
+
+        // The sort is stable, preserving recency order for equal scores.
+        files.sort_by(|a, b| b.score(now).cmp(&a.score(now)));
+
+        files.into_iter().map(|file| file.path.as_path()).collect()
+    }
+
+    /// Removes the file at the specified path from the list
+    /// (e.g. if it has been deleted).
+    pub fn remove(&mut self, path: &Path) {
+        self.files.retain(|file| file.path != path);
+    }
+
+    /// Replaces the list with previously recorded files
+    /// (e.g. restored from an earlier session).
+    pub fn restore(&mut self, mut files: Vec<RecentFile>) {
+        files.sort_by(|a, b| b.opened_at.cmp(&a.opened_at));
+        files.truncate(self.limit);
+        self.files = files;
+    }
+
+    /// Sets the maximum number of files retained in the list,
+    /// discarding the least recently opened files beyond it.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.files.truncate(limit);
+    }
+
+    pub fn clear(&mut self) {
+        self.files.clear();
+    }
+}
+
+// Returns the current time, in seconds since the Unix epoch.
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RecentFile, RecentFiles, DAY, WEEK};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn record_at_counts_repeated_opens() {
+        let mut recent_files = RecentFiles::new();
+        recent_files.record_at(Path::new("/scribe"), 1);
+        recent_files.record_at(Path::new("/library"), 2);
+        recent_files.record_at(Path::new("/scribe"), 3);
+
+        assert_eq!(recent_files.files(), &[
+            RecentFile{ path: PathBuf::from("/scribe"), opened_at: 3, open_count: 2 },
+            RecentFile{ path: PathBuf::from("/library"), opened_at: 2, open_count: 1 },
+        ]);
+    }
+
+    #[test]
+    fn record_at_discards_the_least_recently_opened_files_beyond_the_limit() {
+        let mut recent_files = RecentFiles::new();
+        recent_files.set_limit(2);
+        recent_files.record_at(Path::new("/scribe"), 1);
+        recent_files.record_at(Path::new("/library"), 2);
+        recent_files.record_at(Path::new("/editor"), 3);
+
+        assert_eq!(recent_files.ranked_at(3), vec![Path::new("/editor"), Path::new("/library")]);
+    }
+
+    #[test]
+    fn ranked_at_prefers_recent_files_over_frequently_opened_stale_ones() {
+        let mut recent_files = RecentFiles::new();
+        for _ in 0..3 {
+            recent_files.record_at(Path::new("/scribe"), 0);
+        }
+        recent_files.record_at(Path::new("/library"), 2 * WEEK);
+        recent_files.record_at(Path::new("/library"), 2 * WEEK);
+
+        // Opened three times two weeks ago, versus twice a day ago.
+        assert_eq!(
+            recent_files.ranked_at(2 * WEEK + DAY - 1),
+            vec![Path::new("/library"), Path::new("/scribe")]
+        );
+    }
+
+    #[test]
+    fn ranked_at_breaks_ties_using_recency() {
+        let mut recent_files = RecentFiles::new();
+        recent_files.record_at(Path::new("/scribe"), 1);
+        recent_files.record_at(Path::new("/library"), 2);
+
+        assert_eq!(recent_files.ranked_at(3), vec![Path::new("/library"), Path::new("/scribe")]);
+    }
+
+    #[test]
+    fn restore_orders_files_by_recency() {
+        let mut recent_files = RecentFiles::new();
+        recent_files.restore(vec![
+            RecentFile{ path: PathBuf::from("/scribe"), opened_at: 1, open_count: 1 },
+            RecentFile{ path: PathBuf::from("/library"), opened_at: 2, open_count: 1 },
+        ]);
+
+        assert_eq!(recent_files.files()[0].path, PathBuf::from("/library"));
+    }
+
+    #[cfg(feature = "serialization")]
+    #[test]
+    fn files_can_be_serialized_and_restored() {
+        use serde_json;
+
+        let mut recent_files = RecentFiles::new();
+        let mut restored_files = RecentFiles::new();
+        recent_files.record_at(Path::new("/scribe"), 1);
+        recent_files.record_at(Path::new("/scribe"), 2);
+        let json = serde_json::to_string(recent_files.files()).unwrap();
+
+        restored_files.restore(serde_json::from_str(&json).unwrap());
+        assert_eq!(restored_files.files(), recent_files.files());
+    }
+}
@@ -0,0 +1,348 @@
+//! Named storage for yanked content, shared between buffers.
+
+use clipboard::{self, ClipboardProvider};
+use std::collections::HashMap;
+
+/// The register used when one isn't specified, which
+/// always holds the most recently stored content.
+pub const UNNAMED_REGISTER: char = '"';
+
+/// The register holding the most recent yank made without a register name.
+pub const YANK_REGISTER: char = '0';
+
+/// A register that discards content stored in it.
+pub const BLACK_HOLE_REGISTER: char = '_';
+
+/// A register backed by the system clipboard.
+pub const CLIPBOARD_REGISTER: char = '+';
+
+/// A register backed by the system clipboard (an alias of `CLIPBOARD_REGISTER`).
+pub const SELECTION_REGISTER: char = '*';
+
+/// The default number of entries retained in a set of registers' history.
+pub const DEFAULT_HISTORY_LIMIT: usize = 50;
+
+/// How a register's content is pasted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub enum RegisterKind {
+    /// Content is pasted at the cursor.
+    Characterwise,
+
+    /// Content consists of whole (newline-terminated) lines,
+    /// pasted above the cursor's line.
+    Linewise,
+
+    /// Content consists of a column of text (with rows separated by
+    /// newlines), pasted at the cursor's offset on successive lines.
+    Blockwise,
+}
+
+/// Yanked content, alongside how it should be pasted.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct Register {
+    pub content: String,
+    pub kind: RegisterKind,
+}
+
+impl Register {
+    /// Creates a register holding the specified content.
+    pub fn new<T: Into<String>>(content: T, kind: RegisterKind) -> Register {
+        Register{ content: content.into(), kind }
+    }
+
+    // Appends another register's content, converting the register to a
+    // linewise one if either is linewise.
+    fn append(&mut self, register: Register) {
+        if self.kind == RegisterKind::Linewise || register.kind == RegisterKind::Linewise {
+            if !self.content.is_empty() && !self.content.ends_with('\n') {
+                self.content.push('\n');
+            }
+            self.content.push_str(&register.content);
+            if !self.content.ends_with('\n') {
+                self.content.push('\n');
+            }
+            self.kind = RegisterKind::Linewise;
+        } else if self.kind == RegisterKind::Blockwise || register.kind == RegisterKind::Blockwise {
+            self.content.push('\n');
+            self.content.push_str(&register.content);
+            self.kind = RegisterKind::Blockwise;
+        } else {
+            self.content.push_str(&register.content);
+        }
+    }
+}
+
+/// A set of registers, named using single characters, following vim's
+/// conventions: storing content in a register also stores it in the unnamed
+/// register, storing content using an uppercase letter appends it to the
+/// lowercase letter's register, and the black hole register discards it.
+///
+/// The clipboard and selection registers are backed by a clipboard provider
+/// (the system clipboard, by default), so that content can be exchanged
+/// with other applications.
+///
+/// Content stored in any register is also recorded in a bounded history,
+/// which can be used to cycle through recent yanks when pasting (see
+/// `Workspace::paste_previous`). The history can be persisted across sessions
+/// by serializing its entries (using the `serialization` feature) and
+/// restoring them using `restore_history`.
+///
+/// # Examples
+///
+/// ```
+/// use scribe::{Register, RegisterKind, Registers};
+/// use scribe::clipboard::MemoryClipboard;
+///
+/// let mut registers = Registers::with_clipboard(Box::new(MemoryClipboard::default()));
+/// registers.set('a', Register::new("scribe", RegisterKind::Characterwise));
+/// registers.set('A', Register::new(" library", RegisterKind::Characterwise));
+///
+/// assert_eq!(registers.get('a').unwrap().content, "scribe library");
+/// assert_eq!(registers.get('"').unwrap().content, "scribe library");
+/// ```
+pub struct Registers {
+    registers: HashMap<char, Register>,
+    clipboard: Box<ClipboardProvider>,
+    history: Vec<Register>,
+    history_limit: usize,
+}
+
+impl Default for Registers {
+    fn default() -> Self {
+        Registers::with_clipboard(clipboard::default_clipboard())
+    }
+}
+
+impl Registers {
+    /// Creates an empty set of registers, using the system
+    /// clipboard (see `clipboard::default_clipboard`).
+    pub fn new() -> Registers {
+        Default::default()
+    }
+
+    /// Creates an empty set of registers, using the specified clipboard.
+    pub fn with_clipboard(clipboard: Box<ClipboardProvider>) -> Registers {
+        Registers{
+            registers: HashMap::new(),
+            clipboard,
+            history: Vec::new(),
+            history_limit: DEFAULT_HISTORY_LIMIT,
+        }
+    }
+
+    /// Returns the content of the specified register, if any. Content read
+    /// from the clipboard is considered linewise if it ends with a newline,
+    /// unless it was stored using these registers, in which case its
+    /// original kind is retained.
+    pub fn get(&mut self, name: char) -> Option<&Register> {
+        let name = name.to_ascii_lowercase();
+
+        if is_clipboard_register(name) {
+            if let Some(content) = self.clipboard.contents() {
+                let stale = self.registers.get(&name).map_or(true, |register| register.content != content);
+                if stale {
+                    let kind =
+                        if content.ends_with('\n') {
+                            RegisterKind::Linewise
+                        } else {
+                            RegisterKind::Characterwise
+                        };
+                    self.registers.insert(name, Register::new(content, kind));
+                }
+            }
+        }
+
+        self.registers.get(&name)
+    }
+
+    /// Stores content in the specified register (and the unnamed register).
+    pub fn set(&mut self, name: char, register: Register) {
+        if name == BLACK_HOLE_REGISTER {
+            return
+        }
+
+        let append = name.is_ascii_uppercase();
+        let name = name.to_ascii_lowercase();
+        let register =
+            match self.get(name).cloned() {
+                Some(mut existing) if append => {
+                    existing.append(register);
+                    existing
+                },
+                _ => register,
+            };
+
+        // The register's content is retained even if the clipboard can't be
+        // set, which allows it to be used when the clipboard is unavailable.
+        if is_clipboard_register(name) {
+            self.clipboard.set_contents(&register.content);
+        }
+
+        if self.history.first() != Some(&register) {
+            self.history.insert(0, register.clone());
+            self.history.truncate(self.history_limit);
+        }
+
+        self.registers.insert(UNNAMED_REGISTER, register.clone());
+        self.registers.insert(name, register);
+    }
+
+    /// Stores yanked content in the specified register or, if a register
+    /// isn't specified, the yank register (as well as the unnamed register).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::{Register, RegisterKind, Registers};
+    ///
+    /// let mut registers = Registers::new();
+    /// registers.yank(None, Register::new("scribe", RegisterKind::Characterwise));
+    ///
+    /// assert_eq!(registers.get('0').unwrap().content, "scribe");
+    /// assert_eq!(registers.get('"').unwrap().content, "scribe");
+    /// ```
+    pub fn yank(&mut self, name: Option<char>, register: Register) {
+        self.set(name.unwrap_or(YANK_REGISTER), register);
+    }
+
+    /// Returns previously stored content, most recent first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::{Register, RegisterKind, Registers};
+    /// use scribe::clipboard::MemoryClipboard;
+    ///
+    /// let mut registers = Registers::with_clipboard(Box::new(MemoryClipboard::default()));
+    /// registers.set('a', Register::new("scribe", RegisterKind::Characterwise));
+    /// registers.set('b', Register::new("library", RegisterKind::Characterwise));
+    ///
+    /// let history: Vec<&str> = registers.history().iter().map(|register| register.content.as_str()).collect();
+    /// assert_eq!(history, vec!["library", "scribe"]);
+    /// ```
+    pub fn history(&self) -> &[Register] {
+        &self.history
+    }
+
+    /// Replaces the history (e.g. with one saved in a previous session),
+    /// discarding the oldest entries beyond the history limit.
+    pub fn restore_history(&mut self, mut history: Vec<Register>) {
+        history.truncate(self.history_limit);
+        self.history = history;
+    }
+
+    /// Sets the maximum number of entries retained in the history,
+    /// discarding the oldest entries beyond it.
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history_limit = limit;
+        self.history.truncate(limit);
+    }
+
+    /// Removes the content of all registers, as well as the history.
+    pub fn clear(&mut self) {
+        self.registers.clear();
+        self.history.clear();
+    }
+}
+
+fn is_clipboard_register(name: char) -> bool {
+    name == CLIPBOARD_REGISTER || name == SELECTION_REGISTER
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Register, RegisterKind, Registers};
+    use clipboard::ClipboardProvider;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    // A clipboard whose content can be changed by "other applications".
+    struct SharedClipboard(Rc<RefCell<Option<String>>>);
+
+    impl ClipboardProvider for SharedClipboard {
+        fn contents(&mut self) -> Option<String> {
+            self.0.borrow().clone()
+        }
+
+        fn set_contents(&mut self, content: &str) -> bool {
+            *self.0.borrow_mut() = Some(content.to_string());
+
+            true
+        }
+    }
+
+    fn registers() -> (Registers, Rc<RefCell<Option<String>>>) {
+        let content = Rc::new(RefCell::new(None));
+
+        (Registers::with_clipboard(Box::new(SharedClipboard(content.clone()))), content)
+    }
+
+    #[test]
+    fn set_appends_to_registers_using_uppercase_names() {
+        let (mut registers, _) = registers();
+        registers.set('a', Register::new("scribe", RegisterKind::Characterwise));
+        registers.set('A', Register::new("library\n", RegisterKind::Linewise));
+
+        assert_eq!(registers.get('a'), Some(&Register::new("scribe\nlibrary\n", RegisterKind::Linewise)));
+    }
+
+    #[test]
+    fn set_discards_content_stored_in_the_black_hole_register() {
+        let (mut registers, _) = registers();
+        registers.set('a', Register::new("scribe", RegisterKind::Characterwise));
+        registers.set('_', Register::new("library", RegisterKind::Characterwise));
+
+        assert!(registers.get('_').is_none());
+        assert_eq!(registers.get('"').unwrap().content, "scribe");
+    }
+
+    #[test]
+    fn history_is_bounded_and_skips_repeated_content() {
+        let (mut registers, _) = registers();
+        registers.set_history_limit(2);
+        registers.set('a', Register::new("scribe", RegisterKind::Characterwise));
+        registers.set('b', Register::new("library", RegisterKind::Characterwise));
+        registers.set('c', Register::new("library", RegisterKind::Characterwise));
+        registers.set('d', Register::new("editor", RegisterKind::Characterwise));
+
+        assert_eq!(registers.history(), &[
+            Register::new("editor", RegisterKind::Characterwise),
+            Register::new("library", RegisterKind::Characterwise),
+        ]);
+    }
+
+    #[cfg(feature = "serialization")]
+    #[test]
+    fn history_can_be_serialized_and_restored() {
+        use serde_json;
+
+        let (mut restored_registers, _) = registers();
+        let (mut registers, _) = registers();
+        registers.set('a', Register::new("scribe\n", RegisterKind::Linewise));
+        let json = serde_json::to_string(registers.history()).unwrap();
+
+        restored_registers.restore_history(serde_json::from_str(&json).unwrap());
+        assert_eq!(restored_registers.history(), registers.history());
+    }
+
+    #[test]
+    fn clipboard_registers_store_content_in_the_clipboard() {
+        let (mut registers, clipboard) = registers();
+        registers.set('+', Register::new("scribe\n", RegisterKind::Blockwise));
+
+        assert_eq!(*clipboard.borrow(), Some("scribe\n".to_string()));
+        assert_eq!(registers.get('*'), Some(&Register::new("scribe\n", RegisterKind::Linewise)));
+        assert_eq!(registers.get('+'), Some(&Register::new("scribe\n", RegisterKind::Blockwise)));
+    }
+
+    #[test]
+    fn clipboard_registers_read_content_set_by_other_applications() {
+        let (mut registers, clipboard) = registers();
+        registers.set('+', Register::new("scribe", RegisterKind::Characterwise));
+        *clipboard.borrow_mut() = Some("library".to_string());
+
+        assert_eq!(registers.get('+').unwrap().content, "library");
+    }
+}
@@ -0,0 +1,466 @@
+//! Trigram indexing of a directory's files, for fast workspace-wide search.
+
+use buffer::Position;
+use memchr::memmem::Finder;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use unicode_segmentation::UnicodeSegmentation;
+use util::LineIterator;
+
+/// Files larger than this (in bytes) aren't indexed.
+pub const MAX_INDEXED_FILE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// An occurrence of a search term in an indexed file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexMatch {
+    pub path: PathBuf,
+    pub position: Position,
+}
+
+struct Document {
+    path: PathBuf,
+    content: String,
+    trigrams: Vec<u32>,
+}
+
+/// An index mapping every three-byte sequence (trigram) to the files that
+/// contain it. Searches only scan files containing all of the search term's
+/// trigrams, rather than every file, which keeps them fast on large projects.
+///
+/// Files are indexed from disk (see `index_directory`), and can be updated
+/// with in-memory content (e.g. from an open buffer) using `update`.
+///
+/// # Examples
+///
+/// ```
+/// use scribe::TrigramIndex;
+/// use scribe::buffer::Position;
+/// use std::path::Path;
+///
+/// let mut index = TrigramIndex::new();
+/// index.update(Path::new("/scribe/lib.rs"), "pub mod buffer;\npub mod workspace;".to_string());
+/// index.update(Path::new("/scribe/README.md"), "A text editor toolkit.".to_string());
+///
+/// let matches = index.search("mod work");
+/// assert_eq!(matches.len(), 1);
+/// assert_eq!(matches[0].path, Path::new("/scribe/lib.rs"));
+/// assert_eq!(matches[0].position, Position{ line: 1, offset: 4 });
+/// ```
+#[derive(Default)]
+pub struct TrigramIndex {
+    // Indexed by document ID; removed documents leave empty
+    // slots, whose IDs are reused by subsequently added documents.
+    documents: Vec<Option<Document>>,
+    document_ids: HashMap<PathBuf, usize>,
+    free_ids: Vec<usize>,
+    postings: HashMap<u32, HashSet<usize>>,
+
+    // The indexed directories, relative to which
+    // changed files' paths are checked for hidden entries.
+    directories: Vec<PathBuf>,
+}
+
+impl TrigramIndex {
+    pub fn new() -> TrigramIndex {
+        Default::default()
+    }
+
+    /// Indexes the files in the specified directory on a background thread,
+    /// returning a handle through which the index can be retrieved.
+    pub fn build_in_background(path: &Path) -> JoinHandle<io::Result<TrigramIndex>> {
+        let path = path.to_path_buf();
+
+        thread::spawn(move || {
+            let mut index = TrigramIndex::new();
+            index.index_directory(&path)?;
+
+            Ok(index)
+        })
+    }
+
+    /// Indexes the files in the specified directory and its subdirectories.
+    /// Hidden files and directories (e.g. `.git`), symbolic links, files that
+    /// aren't valid UTF-8, and files larger than `MAX_INDEXED_FILE_SIZE` are
+    /// skipped, as are entries that can't be read. Returns an error only if
+    /// the directory itself can't be read.
+    pub fn index_directory(&mut self, path: &Path) -> io::Result<()> {
+        let entries = fs::read_dir(path)?;
+        self.directories.push(path.to_path_buf());
+        self.index_entries(entries);
+
+        Ok(())
+    }
+
+    /// Re-indexes the file at the specified path from disk (e.g. when it's
+    /// changed by another program), removing it from the index if it can't
+    /// be read, or would be skipped by `index_directory`.
+    pub fn update_file(&mut self, path: &Path) {
+        match indexable_content(path).filter(|_| !self.is_hidden(path)) {
+            Some(content) => self.update(path, content),
+            None => self.remove(path),
+        }
+    }
+
+    /// Indexes the specified content for the path, replacing its existing
+    /// content, if any.
+    pub fn update(&mut self, path: &Path, content: String) {
+        self.remove(path);
+
+        let mut trigrams: Vec<u32> = trigrams(content.as_bytes()).collect();
+        trigrams.sort();
+        trigrams.dedup();
+
+        let id = match self.free_ids.pop() {
+            Some(id) => id,
+            None => {
+                self.documents.push(None);
+                self.documents.len() - 1
+            },
+        };
+        for &trigram in &trigrams {
+            self.postings.entry(trigram).or_default().insert(id);
+        }
+        self.documents[id] = Some(Document{ path: path.to_path_buf(), content, trigrams });
+        self.document_ids.insert(path.to_path_buf(), id);
+    }
+
+    // Indexes the directory entries, and those of their subdirectories.
+    fn index_entries(&mut self, entries: fs::ReadDir) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            if is_hidden_name(&entry.file_name()) {
+                continue
+            }
+
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(_) => continue,
+            };
+            if file_type.is_dir() {
+                if let Ok(subdirectory_entries) = fs::read_dir(entry.path()) {
+                    self.index_entries(subdirectory_entries);
+                }
+            } else if file_type.is_file() {
+                if let Some(content) = indexable_content(&entry.path()) {
+                    self.update(&entry.path(), content);
+                }
+            }
+        }
+    }
+
+    // Whether the path is hidden or within a hidden directory, relative to the
+    // indexed directory containing it (or, failing that, whether its name is).
+    fn is_hidden(&self, path: &Path) -> bool {
+        let relative_path = self.directories.iter()
+            .filter_map(|directory| path.strip_prefix(directory).ok())
+            .next();
+
+        match relative_path {
+            Some(relative_path) => relative_path.components()
+                .any(|component| is_hidden_name(component.as_os_str())),
+            None => path.file_name().map(is_hidden_name).unwrap_or(true),
+        }
+    }
+
+    /// Removes the file at the specified path from the index.
+    pub fn remove(&mut self, path: &Path) {
+        let document = self.document_ids.remove(path)
+            .and_then(|id| self.documents[id].take().map(|document| (id, document)));
+
+        if let Some((id, document)) = document {
+            self.free_ids.push(id);
+            for trigram in document.trigrams {
+                let unused = match self.postings.get_mut(&trigram) {
+                    Some(ids) => {
+                        ids.remove(&id);
+                        ids.is_empty()
+                    },
+                    None => false,
+                };
+                if unused {
+                    self.postings.remove(&trigram);
+                }
+            }
+        }
+    }
+
+    /// Whether or not the file at the specified path is indexed.
+    pub fn contains(&self, path: &Path) -> bool {
+        self.document_ids.contains_key(path)
+    }
+
+    /// Searches the indexed files for occurrences of `needle`, like
+    /// `Buffer::search`: matches are found within individual lines, may
+    /// overlap, and start on grapheme boundaries. Matches are ordered by path
+    /// and position.
+    pub fn search(&self, needle: &str) -> Vec<IndexMatch> {
+        self.find(needle, false)
+    }
+
+    /// Searches the indexed files for occurrences of `symbol` that aren't
+    /// part of a larger word (e.g. to look up an identifier's uses).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::TrigramIndex;
+    /// use std::path::Path;
+    ///
+    /// let mut index = TrigramIndex::new();
+    /// index.update(Path::new("/scribe/lib.rs"), "let buffer = buffers[0];".to_string());
+    ///
+    /// assert_eq!(index.search("buffer").len(), 2);
+    /// assert_eq!(index.search_symbol("buffer").len(), 1);
+    /// ```
+    pub fn search_symbol(&self, symbol: &str) -> Vec<IndexMatch> {
+        self.find(symbol, true)
+    }
+
+    fn find(&self, needle: &str, whole_words: bool) -> Vec<IndexMatch> {
+        let mut results = Vec::new();
+        if needle.is_empty() {
+            return results
+        }
+
+        let finder = Finder::new(needle);
+        let mut candidates: Vec<&Document> = self.candidates(needle).into_iter()
+            .filter_map(|id| self.documents[id].as_ref())
+            .collect();
+        candidates.sort_by(|a, b| a.path.cmp(&b.path));
+
+        for document in candidates {
+            for (line_number, line) in LineIterator::new(&document.content) {
+                let content = line.strip_suffix('\n').unwrap_or(line);
+                for offset in line_matches(content, needle, &finder, whole_words) {
+                    results.push(IndexMatch{
+                        path: document.path.clone(),
+                        position: Position{ line: line_number, offset },
+                    });
+                }
+            }
+        }
+
+        results
+    }
+
+    // Returns the IDs of documents containing all of the needle's trigrams
+    // (or all documents, if the needle is too short to have any).
+    fn candidates(&self, needle: &str) -> Vec<usize> {
+        let mut needle_trigrams: Vec<u32> = trigrams(needle.as_bytes()).collect();
+        if needle_trigrams.is_empty() {
+            return self.document_ids.values().cloned().collect()
+        }
+
+        // Start with the rarest trigram, to minimize the intersection work.
+        let mut postings = Vec::new();
+        needle_trigrams.sort();
+        needle_trigrams.dedup();
+        for trigram in needle_trigrams {
+            match self.postings.get(&trigram) {
+                Some(ids) => postings.push(ids),
+                None => return Vec::new(),
+            }
+        }
+        postings.sort_by_key(|ids| ids.len());
+
+        postings[0].iter()
+            .filter(|id| postings[1..].iter().all(|ids| ids.contains(id)))
+            .cloned()
+            .collect()
+    }
+}
+
+// Whether the file or directory name is hidden (names that
+// aren't valid UTF-8 are treated as hidden, and skipped).
+fn is_hidden_name(name: &OsStr) -> bool {
+    name.to_str().map(|name| name.starts_with('.')).unwrap_or(true)
+}
+
+// Reads the file's content, if it's a regular file (rather than a symbolic
+// link or directory) no larger than MAX_INDEXED_FILE_SIZE, and valid UTF-8.
+fn indexable_content(path: &Path) -> Option<String> {
+    let metadata = fs::symlink_metadata(path).ok()?;
+    if !metadata.is_file() || metadata.len() > MAX_INDEXED_FILE_SIZE {
+        return None
+    }
+
+    fs::read_to_string(path).ok()
+}
+
+// Returns the trigrams in the data, packed into integers.
+fn trigrams<'a>(data: &'a [u8]) -> impl Iterator<Item = u32> + 'a {
+    data.windows(3).map(|window| {
+        (u32::from(window[0]) << 16) | (u32::from(window[1]) << 8) | u32::from(window[2])
+    })
+}
+
+// Finds (potentially overlapping) occurrences of the needle on the line,
+// returning the grapheme offsets of those that start on grapheme boundaries
+// (and, optionally, that aren't part of a larger word).
+fn line_matches(content: &str, needle: &str, finder: &Finder, whole_words: bool) -> Vec<usize> {
+    let mut byte_offsets = Vec::new();
+    let mut start = 0;
+    while let Some(offset) = finder.find(&content.as_bytes()[start..]) {
+        let match_offset = start + offset;
+        let end = match_offset + needle.len();
+        let bounded = !whole_words || (
+            !content[..match_offset].chars().next_back().map(is_word_character).unwrap_or(false) &&
+            !content[end..].chars().next().map(is_word_character).unwrap_or(false)
+        );
+        if bounded {
+            byte_offsets.push(match_offset);
+        }
+        start = match_offset + 1;
+
+        // Skip to the next character boundary.
+        while !content.is_char_boundary(start) {
+            start += 1;
+        }
+    }
+
+    let mut offsets = Vec::new();
+    let mut byte_offsets = byte_offsets.into_iter().peekable();
+    for (offset, (index, _)) in content.grapheme_indices(true).enumerate() {
+        while byte_offsets.peek().map(|&byte_offset| byte_offset < index).unwrap_or(false) {
+            byte_offsets.next();
+        }
+
+        match byte_offsets.peek() {
+            Some(&byte_offset) if byte_offset == index => offsets.push(offset),
+            Some(_) => (),
+            None => break,
+        }
+    }
+
+    offsets
+}
+
+fn is_word_character(character: char) -> bool {
+    character.is_alphanumeric() || character == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IndexMatch, TrigramIndex, MAX_INDEXED_FILE_SIZE};
+    use buffer::Position;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn update_replaces_existing_content() {
+        let mut index = TrigramIndex::new();
+        index.update(Path::new("/scribe"), "scribe library".to_string());
+        index.update(Path::new("/scribe"), "text editor".to_string());
+
+        assert!(index.search("library").is_empty());
+        assert_eq!(index.search("editor").len(), 1);
+        assert_eq!(index.postings.len(), "text editor".len() - 2);
+    }
+
+    #[test]
+    fn update_reuses_the_ids_of_removed_documents() {
+        let mut index = TrigramIndex::new();
+        index.update(Path::new("/scribe"), "scribe".to_string());
+        index.update(Path::new("/scribe"), "scribe library".to_string());
+        index.remove(Path::new("/scribe"));
+        index.update(Path::new("/library"), "library".to_string());
+
+        assert_eq!(index.documents.len(), 1);
+        assert_eq!(index.search("library").len(), 1);
+    }
+
+    #[test]
+    fn remove_drops_the_file_and_its_trigrams() {
+        let mut index = TrigramIndex::new();
+        index.update(Path::new("/scribe"), "scribe".to_string());
+        index.remove(Path::new("/scribe"));
+
+        assert!(!index.contains(Path::new("/scribe")));
+        assert!(index.search("scr").is_empty());
+        assert!(index.postings.is_empty());
+    }
+
+    #[test]
+    fn search_checks_candidates_for_contiguous_matches() {
+        let mut index = TrigramIndex::new();
+        index.update(Path::new("/a"), "abcd bcde".to_string());
+        index.update(Path::new("/b"), "xabcdex".to_string());
+
+        // Both files contain the needle's trigrams, but only one contains it.
+        assert_eq!(index.search("abcde"), vec![
+            IndexMatch{ path: PathBuf::from("/b"), position: Position{ line: 0, offset: 1 } }
+        ]);
+    }
+
+    #[test]
+    fn search_handles_short_needles_and_multibyte_content() {
+        let mut index = TrigramIndex::new();
+        index.update(Path::new("/scribe"), "नी a\n😀a".to_string());
+
+        assert_eq!(index.search("a"), vec![
+            IndexMatch{ path: PathBuf::from("/scribe"), position: Position{ line: 0, offset: 2 } },
+            IndexMatch{ path: PathBuf::from("/scribe"), position: Position{ line: 1, offset: 1 } },
+        ]);
+    }
+
+    #[test]
+    fn index_directory_skips_hidden_entries() {
+        let mut index = TrigramIndex::new();
+        index.index_directory(Path::new("tests/sample")).unwrap();
+
+        let path = Path::new("tests/sample/file");
+        assert!(index.contains(path));
+        assert!(index.search("works").contains(
+            &IndexMatch{ path: path.to_path_buf(), position: Position{ line: 0, offset: 3 } }
+        ));
+    }
+
+    #[test]
+    fn build_in_background_returns_the_index() {
+        let index = TrigramIndex::build_in_background(Path::new("tests/sample")).join().unwrap().unwrap();
+
+        assert!(index.contains(Path::new("tests/sample/modeline")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn index_directory_skips_unreadable_subdirectories() {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::create_dir_all("tests/sample/unreadable_index").unwrap();
+        fs::write("tests/sample/unreadable_index/file", "scribe").unwrap();
+        fs::set_permissions("tests/sample/unreadable_index", fs::Permissions::from_mode(0o000)).unwrap();
+
+        let mut index = TrigramIndex::new();
+        let result = index.index_directory(Path::new("tests/sample"));
+
+        fs::set_permissions("tests/sample/unreadable_index", fs::Permissions::from_mode(0o755)).unwrap();
+        fs::remove_dir_all("tests/sample/unreadable_index").unwrap();
+
+        assert!(result.is_ok());
+        assert!(index.contains(Path::new("tests/sample/file")));
+    }
+
+    #[test]
+    fn update_file_skips_files_that_index_directory_would() {
+        let mut index = TrigramIndex::new();
+        index.index_directory(Path::new("tests/sample")).unwrap();
+
+        fs::create_dir_all("tests/sample/.hidden_index").unwrap();
+        fs::write("tests/sample/.hidden_index/file", "scribe").unwrap();
+        fs::write("tests/sample/large_index", vec![b'a'; MAX_INDEXED_FILE_SIZE as usize + 1]).unwrap();
+        index.update_file(Path::new("tests/sample/.hidden_index/file"));
+        index.update_file(Path::new("tests/sample/large_index"));
+        index.update_file(Path::new("tests/sample/file"));
+
+        fs::remove_dir_all("tests/sample/.hidden_index").unwrap();
+        fs::remove_file("tests/sample/large_index").unwrap();
+
+        assert!(!index.contains(Path::new("tests/sample/.hidden_index/file")));
+        assert!(!index.contains(Path::new("tests/sample/large_index")));
+        assert!(index.contains(Path::new("tests/sample/file")));
+    }
+}
@@ -0,0 +1,338 @@
+//! Random action sequences for property-based and fuzz testing, along with
+//! a reference model of buffer behaviour to check their results against.
+
+use buffer::{Buffer, Position, Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+// Content is inserted using graphemes that can't combine with their neighbours
+// (e.g. combining marks), so that the graphemes in the buffer are predictable.
+const GRAPHEMES: [&str; 8] = ["a", "b", "c", " ", "\n", "\t", "é", "😀"];
+
+/// An action performed against a buffer.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    /// Inserts content at the cursor (see `Buffer::insert`).
+    Insert(String),
+
+    /// Deletes the character at the cursor (see `Buffer::delete`).
+    Delete,
+    DeleteRange(Range),
+
+    /// Moves the cursor, provided the position is in bounds.
+    MoveCursor(Position),
+    Undo,
+    Redo,
+}
+
+impl Action {
+    /// Performs the action against the buffer.
+    pub fn apply(&self, buffer: &mut Buffer) {
+        match *self {
+            Action::Insert(ref content) => buffer.insert(content.as_str()),
+            Action::Delete => buffer.delete(),
+            Action::DeleteRange(ref range) => buffer.delete_range(range.clone()),
+            Action::MoveCursor(position) => { buffer.cursor.move_to(position); },
+            Action::Undo => buffer.undo(),
+            Action::Redo => buffer.redo(),
+        }
+    }
+}
+
+/// Generates random actions, which are valid for the model's current state
+/// (with the exception of occasional out of bounds cursor movements). The
+/// sequence is determined by the generator's seed, so that failures can be
+/// reproduced.
+pub struct ActionGenerator {
+    state: u64,
+}
+
+impl ActionGenerator {
+    pub fn new(seed: u64) -> ActionGenerator {
+        // The state must be non-zero, and nearby seeds shouldn't
+        // produce similar sequences, so the seed is scrambled.
+        ActionGenerator{ state: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1 }
+    }
+
+    /// Generates an action for the model's current state.
+    pub fn generate(&mut self, model: &Model) -> Action {
+        match self.below(20) {
+            0..=6 => {
+                let length = 1 + self.below(6);
+                let content = (0..length).map(|_| GRAPHEMES[self.below(GRAPHEMES.len())]).collect();
+
+                Action::Insert(content)
+            },
+            7..=9 => Action::Delete,
+            10..=12 => {
+                let start = self.position(model);
+                let end = self.position(model);
+
+                Action::DeleteRange(Range::new(start, end))
+            },
+            13..=15 => Action::MoveCursor(self.position(model)),
+            16 => {
+                // Positions just beyond the end of a line are out of bounds.
+                let line = self.below(model.line_count());
+                let offset = model.line_length(line) + 1;
+
+                Action::MoveCursor(Position{ line, offset })
+            },
+            17..=18 => Action::Undo,
+            _ => Action::Redo,
+        }
+    }
+
+    // Returns a random position within the model's content.
+    fn position(&mut self, model: &Model) -> Position {
+        let line = self.below(model.line_count());
+        let offset = self.below(model.line_length(line) + 1);
+
+        Position{ line, offset }
+    }
+
+    // Returns a random number below the specified (non-zero) bound,
+    // advancing the generator's state (using xorshift64*).
+    fn below(&mut self, bound: usize) -> usize {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+
+        (self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 32) as usize % bound
+    }
+}
+
+/// A straightforward model of a buffer's content, cursor, and undo history,
+/// which snapshots its content for every change rather than recording
+/// reversible operations.
+#[derive(Debug, Default)]
+pub struct Model {
+    content: String,
+    cursor: Position,
+    undo: Vec<String>,
+    redo: Vec<String>,
+}
+
+impl Model {
+    pub fn new() -> Model {
+        Model::default()
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    pub fn cursor(&self) -> Position {
+        self.cursor
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.content.split('\n').count()
+    }
+
+    /// Returns the number of graphemes on the specified line (or zero if it
+    /// doesn't exist).
+    pub fn line_length(&self, line: usize) -> usize {
+        self.content.split('\n').nth(line).map(|line| line.graphemes(true).count()).unwrap_or(0)
+    }
+
+    /// Performs the action against the model.
+    pub fn apply(&mut self, action: &Action) {
+        match *action {
+            Action::Insert(ref content) => {
+                let mut updated_content = self.content.clone();
+                if let Some(offset) = self.offset(&self.cursor) {
+                    updated_content.insert_str(offset, content);
+                }
+                self.change(updated_content);
+            },
+            Action::Delete => {
+                let mut end = Position{ line: self.cursor.line, offset: self.cursor.offset + 1 };
+                if self.offset(&end).is_none() {
+                    end = Position{ line: self.cursor.line + 1, offset: 0 };
+                }
+                let range = Range::new(self.cursor, end);
+                self.delete(&range);
+            },
+            Action::DeleteRange(ref range) => self.delete(range),
+            Action::MoveCursor(position) => {
+                if self.offset(&position).is_some() {
+                    self.cursor = position;
+                }
+            },
+            Action::Undo => {
+                if let Some(content) = self.undo.pop() {
+                    let current_content = ::std::mem::replace(&mut self.content, content);
+                    self.redo.push(current_content);
+                }
+            },
+            Action::Redo => {
+                if let Some(content) = self.redo.pop() {
+                    let current_content = ::std::mem::replace(&mut self.content, content);
+                    self.undo.push(current_content);
+                }
+            },
+        }
+    }
+
+    /// Panics if the buffer's content or cursor differ from the model's.
+    pub fn assert_matches(&self, buffer: &Buffer) {
+        assert_eq!(buffer.data(), self.content, "buffer content differs from the model");
+        assert_eq!(*buffer.cursor, self.cursor, "buffer cursor differs from the model");
+    }
+
+    // Deletes the range, extending ends beyond their line to the start of
+    // the next line, or the end of the content, like the gap buffer does.
+    fn delete(&mut self, range: &Range) {
+        let mut updated_content = self.content.clone();
+        if let Some(start) = self.offset(&range.start()) {
+            let end = self.offset(&range.end())
+                .or_else(|| self.offset(&Position{ line: range.end().line + 1, offset: 0 }))
+                .unwrap_or_else(|| self.content.len());
+            updated_content.replace_range(start..end, "");
+        }
+        self.change(updated_content);
+    }
+
+    // Records a change in the undo history (even if the content is
+    // unchanged, since the buffer records out of bounds operations).
+    fn change(&mut self, content: String) {
+        let previous_content = ::std::mem::replace(&mut self.content, content);
+        self.undo.push(previous_content);
+        self.redo.clear();
+    }
+
+    // Maps a position to its byte offset in the content, if it's in bounds.
+    fn offset(&self, position: &Position) -> Option<usize> {
+        let mut line = 0;
+        let mut line_offset = 0;
+
+        for (offset, grapheme) in self.content.grapheme_indices(true) {
+            if line == position.line && line_offset == position.offset {
+                return Some(offset)
+            }
+
+            if grapheme == "\n" {
+                line += 1;
+                line_offset = 0;
+            } else {
+                line_offset += 1;
+            }
+        }
+
+        if line == position.line && line_offset == position.offset {
+            Some(self.content.len())
+        } else {
+            None
+        }
+    }
+}
+
+/// Performs the specified number of random actions (generated using the
+/// seed) against a new buffer and a model, checking the buffer against the
+/// model after each one. Returns the performed actions.
+///
+/// # Panics
+///
+/// Panics if the buffer diverges from the model, reporting the seed and
+/// actions that produced the difference.
+///
+/// # Examples
+///
+/// ```
+/// use scribe::testing::check_random_actions;
+///
+/// for seed in 0..10 {
+///     check_random_actions(seed, 100);
+/// }
+/// ```
+pub fn check_random_actions(seed: u64, count: usize) -> Vec<Action> {
+    let mut generator = ActionGenerator::new(seed);
+    let mut buffer = Buffer::new();
+    let mut model = Model::new();
+    let mut actions = Vec::new();
+
+    for _ in 0..count {
+        let action = generator.generate(&model);
+        action.apply(&mut buffer);
+        model.apply(&action);
+        actions.push(action);
+
+        let matches = buffer.data() == model.content() && *buffer.cursor == model.cursor();
+        if !matches {
+            panic!(
+                "buffer diverged from the model (seed {}) after {:?}\n  buffer: {:?} (cursor {:?})\n   model: {:?} (cursor {:?})",
+                seed, actions, buffer.data(), *buffer.cursor, model.content(), model.cursor()
+            );
+        }
+    }
+
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_random_actions, Action, ActionGenerator, Model};
+    use buffer::{Buffer, Position, Range};
+
+    #[test]
+    fn generators_with_the_same_seed_produce_the_same_actions() {
+        assert_eq!(check_random_actions(42, 50), check_random_actions(42, 50));
+        assert_ne!(check_random_actions(42, 50), check_random_actions(43, 50));
+    }
+
+    #[test]
+    fn generated_actions_include_every_kind_of_action() {
+        let actions = check_random_actions(0, 500);
+
+        assert!(actions.iter().any(|action| match *action { Action::Insert(_) => true, _ => false }));
+        assert!(actions.contains(&Action::Delete));
+        assert!(actions.iter().any(|action| match *action { Action::DeleteRange(_) => true, _ => false }));
+        assert!(actions.iter().any(|action| match *action { Action::MoveCursor(_) => true, _ => false }));
+        assert!(actions.contains(&Action::Undo));
+        assert!(actions.contains(&Action::Redo));
+    }
+
+    #[test]
+    fn generated_positions_are_within_the_model_content() {
+        let mut generator = ActionGenerator::new(7);
+        let mut model = Model::new();
+        model.apply(&Action::Insert("scribe\nlibrary".to_string()));
+
+        for _ in 0..100 {
+            let position = generator.position(&model);
+            assert!(model.offset(&position).is_some());
+        }
+    }
+
+    #[test]
+    fn model_deletes_newlines_and_undoes_like_the_buffer() {
+        let mut buffer = Buffer::new();
+        let mut model = Model::new();
+        let actions = vec![
+            Action::Insert("scribe\nlibrary".to_string()),
+            Action::DeleteRange(Range::new(Position{ line: 0, offset: 3 }, Position{ line: 1, offset: 2 })),
+            Action::MoveCursor(Position{ line: 0, offset: 8 }),
+            Action::MoveCursor(Position{ line: 0, offset: 3 }),
+            Action::Insert("\n".to_string()),
+            Action::MoveCursor(Position{ line: 0, offset: 3 }),
+            Action::Delete,
+            Action::Undo,
+            Action::Undo,
+            Action::Undo,
+            Action::Redo,
+        ];
+
+        for action in actions {
+            action.apply(&mut buffer);
+            model.apply(&action);
+            model.assert_matches(&buffer);
+        }
+    }
+
+    #[test]
+    fn buffers_match_the_model_for_random_actions() {
+        for seed in 0..50 {
+            check_random_actions(seed, 200);
+        }
+    }
+}
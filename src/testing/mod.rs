@@ -0,0 +1,276 @@
+//! Helpers for tests that build buffers from, and compare buffers against,
+//! annotated content. Annotations mark the cursor using `|`, and a selection
+//! using `<sel>` and `</sel>`:
+//!
+//! ```
+//! use scribe::buffer::{Position, Range};
+//! use scribe::testing::{annotated_buffer, assert_annotated};
+//!
+//! let (mut buffer, selection) = annotated_buffer("let |x = <sel>1</sel>;");
+//! assert_eq!(*buffer.cursor, Position{ line: 0, offset: 4 });
+//! assert_eq!(selection, Some(Range::new(
+//!     Position{ line: 0, offset: 8 },
+//!     Position{ line: 0, offset: 9 }
+//! )));
+//!
+//! buffer.insert("mut ");
+//! assert_annotated(&buffer, None, "let |mut x = 1;");
+//! ```
+//!
+//! Literal `|` characters, and `<` characters starting a literal `<sel>` or
+//! `</sel>`, are escaped using a backslash. Positions are measured in
+//! graphemes, like all other buffer positions.
+//!
+//! Random action sequences can also be checked against a reference model of
+//! buffer behaviour (see `check_random_actions`). These helpers are available
+//! when the `testing` feature is enabled.
+
+mod actions;
+
+pub use self::actions::{check_random_actions, Action, ActionGenerator, Model};
+
+use buffer::{Buffer, Position, Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+const CURSOR: &str = "|";
+const SELECTION_START: &str = "<sel>";
+const SELECTION_END: &str = "</sel>";
+const ESCAPE: &str = "\\";
+
+// Content, stripped of its annotations, along with the positions they mark.
+struct Annotations {
+    content: String,
+    cursor: Option<Position>,
+    selection: Option<Range>,
+}
+
+/// Builds a buffer with the annotated content, with its cursor placed at the
+/// marked position (or, if there isn't one, at the end of the selection or
+/// the start of the buffer), and returns it along with the selection. The
+/// buffer has an empty undo history.
+///
+/// # Panics
+///
+/// Panics if the content has multiple cursors or selections, or a
+/// selection that isn't terminated.
+pub fn annotated_buffer(annotated: &str) -> (Buffer, Option<Range>) {
+    let annotations = parse(annotated);
+    let cursor = annotations.cursor
+        .or_else(|| annotations.selection.as_ref().map(|selection| selection.end()))
+        .unwrap_or_default();
+
+    let mut state = Buffer::new().state();
+    state.content = annotations.content;
+    state.cursor = cursor;
+    let buffer = Buffer::restore(state)
+        .expect("fresh buffer states are always supported");
+
+    (buffer, annotations.selection)
+}
+
+/// Renders the buffer's content, annotated with its cursor and the selection.
+///
+/// # Examples
+///
+/// ```
+/// use scribe::Buffer;
+/// use scribe::buffer::{Position, Range};
+/// use scribe::testing::annotate;
+///
+/// let mut buffer = Buffer::new();
+/// buffer.insert("a | b");
+/// let selection = Range::new(Position{ line: 0, offset: 4 }, Position{ line: 0, offset: 5 });
+///
+/// assert_eq!(annotate(&buffer, Some(&selection)), "|a \\| <sel>b</sel>");
+/// ```
+pub fn annotate(buffer: &Buffer, selection: Option<&Range>) -> String {
+    let data = buffer.data();
+    let mut annotated = String::new();
+    let mut position = Position::new();
+    let mut graphemes = data.grapheme_indices(true);
+
+    loop {
+        if let Some(selection) = selection {
+            if selection.end() == position && selection.start() != position {
+                annotated.push_str(SELECTION_END);
+            }
+        }
+        if *buffer.cursor == position {
+            annotated.push_str(CURSOR);
+        }
+        if let Some(selection) = selection {
+            if selection.start() == position {
+                annotated.push_str(SELECTION_START);
+                if selection.end() == position {
+                    annotated.push_str(SELECTION_END);
+                }
+            }
+        }
+
+        let (index, grapheme) = match graphemes.next() {
+            Some(grapheme) => grapheme,
+            None => break,
+        };
+        let remaining = &data[index..];
+        if grapheme == CURSOR || remaining.starts_with(SELECTION_START) || remaining.starts_with(SELECTION_END) {
+            annotated.push_str(ESCAPE);
+        }
+        annotated.push_str(grapheme);
+        advance(&mut position, grapheme);
+    }
+
+    annotated
+}
+
+/// Asserts that the buffer's content, cursor, and the selection match the
+/// annotated content. The cursor isn't compared if the annotated content
+/// doesn't mark one.
+///
+/// # Panics
+///
+/// Panics if the buffer doesn't match, showing its annotated content
+/// alongside the expected content, or if the expected content is invalid
+/// (see `annotated_buffer`).
+pub fn assert_annotated(buffer: &Buffer, selection: Option<&Range>, expected: &str) {
+    let annotations = parse(expected);
+    let matches =
+        buffer.data() == annotations.content &&
+        annotations.cursor.map(|cursor| cursor == *buffer.cursor).unwrap_or(true) &&
+        selection == annotations.selection.as_ref();
+
+    if !matches {
+        panic!(
+            "buffer doesn't match its expected state\n  actual: {:?}\nexpected: {:?}",
+            annotate(buffer, selection),
+            expected
+        );
+    }
+}
+
+fn parse(annotated: &str) -> Annotations {
+    let mut annotations = Annotations{ content: String::new(), cursor: None, selection: None };
+    let mut position = Position::new();
+    let mut selection_start = None;
+    let mut remaining = annotated;
+
+    while !remaining.is_empty() {
+        let marker =
+            if remaining.starts_with(CURSOR) {
+                assert!(annotations.cursor.is_none(), "multiple cursors in {:?}", annotated);
+                annotations.cursor = Some(position);
+                CURSOR
+            } else if remaining.starts_with(SELECTION_START) {
+                assert!(
+                    selection_start.is_none() && annotations.selection.is_none(),
+                    "multiple selections in {:?}", annotated
+                );
+                selection_start = Some(position);
+                SELECTION_START
+            } else if remaining.starts_with(SELECTION_END) {
+                let start = selection_start.take()
+                    .unwrap_or_else(|| panic!("selection end without a start in {:?}", annotated));
+                annotations.selection = Some(Range::new(start, position));
+                SELECTION_END
+            } else {
+                ""
+            };
+        if !marker.is_empty() {
+            remaining = &remaining[marker.len()..];
+            continue
+        }
+
+        // Skip the escape character preceding a literal marker.
+        let escaped = &remaining[ESCAPE.len().min(remaining.len())..];
+        if remaining.starts_with(ESCAPE) &&
+            (escaped.starts_with(CURSOR) || escaped.starts_with(SELECTION_START) || escaped.starts_with(SELECTION_END)) {
+            remaining = escaped;
+        }
+
+        let grapheme = remaining.graphemes(true).next().unwrap_or(remaining);
+        annotations.content.push_str(grapheme);
+        advance(&mut position, grapheme);
+        remaining = &remaining[grapheme.len()..];
+    }
+
+    assert!(selection_start.is_none(), "unterminated selection in {:?}", annotated);
+
+    annotations
+}
+
+// Moves the position past the grapheme.
+fn advance(position: &mut Position, grapheme: &str) {
+    if grapheme == "\n" {
+        position.line += 1;
+        position.offset = 0;
+    } else {
+        position.offset += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{annotate, annotated_buffer, assert_annotated};
+    use buffer::{Position, Range};
+
+    #[test]
+    fn annotated_buffer_extracts_the_cursor_and_selection() {
+        let (buffer, selection) = annotated_buffer("scribe\nli|b<sel>rary\ned</sel>itor");
+
+        assert_eq!(buffer.data(), "scribe\nlibrary\neditor");
+        assert_eq!(*buffer.cursor, Position{ line: 1, offset: 2 });
+        assert_eq!(selection, Some(Range::new(Position{ line: 1, offset: 3 }, Position{ line: 2, offset: 2 })));
+    }
+
+    #[test]
+    fn annotated_buffer_places_the_cursor_at_the_end_of_the_selection_by_default() {
+        let (buffer, _) = annotated_buffer("<sel>scribe</sel> library");
+
+        assert_eq!(*buffer.cursor, Position{ line: 0, offset: 6 });
+    }
+
+    #[test]
+    fn annotated_buffer_has_no_history() {
+        let (mut buffer, _) = annotated_buffer("scribe|");
+        buffer.undo();
+
+        assert_eq!(buffer.data(), "scribe");
+    }
+
+    #[test]
+    fn annotate_escapes_literal_markers() {
+        let (buffer, selection) = annotated_buffer("a \\| b \\<sel> \\</sel>|");
+
+        assert_eq!(buffer.data(), "a | b <sel> </sel>");
+        assert_eq!(selection, None);
+        assert_eq!(annotate(&buffer, None), "a \\| b \\<sel> \\</sel>|");
+    }
+
+    #[test]
+    fn annotate_renders_empty_selections() {
+        let (buffer, selection) = annotated_buffer("sc|<sel></sel>ribe");
+
+        assert_eq!(annotate(&buffer, selection.as_ref()), "sc|<sel></sel>ribe");
+    }
+
+    #[test]
+    fn assert_annotated_ignores_marker_order() {
+        let (buffer, selection) = annotated_buffer("<sel>scribe</sel>|");
+
+        assert_annotated(&buffer, selection.as_ref(), "<sel>scribe|</sel>");
+        assert_annotated(&buffer, selection.as_ref(), "<sel>scribe</sel>");
+    }
+
+    #[test]
+    #[should_panic(expected = "buffer doesn't match")]
+    fn assert_annotated_compares_cursors() {
+        let (buffer, _) = annotated_buffer("scr|ibe");
+
+        assert_annotated(&buffer, None, "scri|be");
+    }
+
+    #[test]
+    #[should_panic(expected = "unterminated selection")]
+    fn unterminated_selections_are_rejected() {
+        annotated_buffer("<sel>scribe");
+    }
+}
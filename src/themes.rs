@@ -0,0 +1,171 @@
+//! Named syntax highlighting themes, and the selection of a current theme.
+
+use errors::*;
+use std::collections::BTreeMap;
+#[cfg(feature = "system")]
+use std::path::Path;
+use syntect::highlighting::{Theme, ThemeSet};
+
+/// The theme selected by default, which is one of syntect's bundled themes.
+pub const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+/// A collection of themes, keyed by name, one of which is selected for use.
+/// Syntect's bundled themes are always available, and user-supplied
+/// `.tmTheme` files can be loaded alongside (or in place of) them.
+///
+/// Themes are identified to render caches (see `Buffer::styled_lines`) using
+/// `current_id`, which changes whenever the selection or loaded themes
+/// change, so that lines styled using a previous theme aren't reused.
+///
+/// # Examples
+///
+/// ```
+/// use scribe::Themes;
+///
+/// let mut themes = Themes::new();
+/// assert_eq!(themes.current_name(), "base16-ocean.dark");
+///
+/// themes.select("InspiredGitHub").unwrap();
+/// assert_eq!(themes.current_name(), "InspiredGitHub");
+/// assert!(themes.select("missing").is_err());
+/// ```
+pub struct Themes {
+    themes: BTreeMap<String, Theme>,
+    current: String,
+    revision: usize,
+}
+
+impl Default for Themes {
+    fn default() -> Self {
+        Themes{
+            themes: ThemeSet::load_defaults().themes,
+            current: DEFAULT_THEME.to_string(),
+            revision: 0,
+        }
+    }
+}
+
+impl Themes {
+    /// Creates a collection of syntect's bundled themes,
+    /// with `DEFAULT_THEME` selected.
+    pub fn new() -> Themes {
+        Default::default()
+    }
+
+    /// Adds a theme, replacing any existing theme with the same name.
+    pub fn add<T: Into<String>>(&mut self, name: T, theme: Theme) {
+        self.themes.insert(name.into(), theme);
+        self.revision += 1;
+    }
+
+    /// Loads the `.tmTheme` file at the specified path, naming the theme
+    /// after the file (without its extension). Returns the theme's name.
+    #[cfg(feature = "system")]
+    pub fn load_file(&mut self, path: &Path) -> Result<String> {
+        let theme = ThemeSet::get_theme(path)?;
+        let name = path.file_stem()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| ErrorKind::InvalidThemePath(path.to_path_buf()))?
+            .to_string();
+        self.add(name.clone(), theme);
+
+        Ok(name)
+    }
+
+    /// Loads all of the `.tmTheme` files in the specified directory (and its
+    /// subdirectories), naming them after their files (see `load_file`).
+    /// Returns the loaded themes' names; if any of the files can't be
+    /// loaded, an error is returned and none of them are added.
+    #[cfg(feature = "system")]
+    pub fn load_directory(&mut self, path: &Path) -> Result<Vec<String>> {
+        let theme_set = ThemeSet::load_from_folder(path)?;
+        let names = theme_set.themes.keys().cloned().collect();
+        for (name, theme) in theme_set.themes {
+            self.add(name, theme);
+        }
+
+        Ok(names)
+    }
+
+    /// Returns the names of the available themes, in alphabetical order.
+    pub fn names(&self) -> Vec<&str> {
+        self.themes.keys().map(|name| name.as_str()).collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Theme> {
+        self.themes.get(name)
+    }
+
+    /// Selects the named theme, returning an error if it isn't available.
+    pub fn select(&mut self, name: &str) -> Result<()> {
+        if !self.themes.contains_key(name) {
+            bail!(ErrorKind::UnknownTheme(name.to_string()));
+        }
+        self.current = name.to_string();
+
+        Ok(())
+    }
+
+    /// Returns the selected theme. If it's been replaced, the replacement is
+    /// returned; it can't be removed, so it's always available.
+    pub fn current(&self) -> &Theme {
+        &self.themes[&self.current]
+    }
+
+    pub fn current_name(&self) -> &str {
+        &self.current
+    }
+
+    /// Returns an identifier for the selected theme, which also
+    /// changes whenever themes are added (e.g. when it's reloaded).
+    pub fn current_id(&self) -> String {
+        format!("{}#{}", self.current, self.revision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Themes, DEFAULT_THEME};
+    use syntect::highlighting::Theme;
+
+    #[test]
+    fn new_includes_the_bundled_themes() {
+        let themes = Themes::new();
+
+        assert!(themes.names().contains(&DEFAULT_THEME));
+        assert!(themes.names().contains(&"Solarized (light)"));
+    }
+
+    #[test]
+    fn current_id_changes_when_themes_are_selected_or_added() {
+        let mut themes = Themes::new();
+        let id = themes.current_id();
+
+        themes.add(DEFAULT_THEME, Theme::default());
+        assert!(themes.current_id() != id);
+        assert_eq!(themes.current().name, None);
+
+        let id = themes.current_id();
+        themes.select("InspiredGitHub").unwrap();
+        assert!(themes.current_id() != id);
+    }
+
+    #[test]
+    fn select_keeps_the_current_theme_for_unknown_names() {
+        let mut themes = Themes::new();
+
+        assert!(themes.select("missing").is_err());
+        assert_eq!(themes.current_name(), DEFAULT_THEME);
+    }
+
+    #[cfg(feature = "system")]
+    #[test]
+    fn load_file_reports_invalid_themes() {
+        use std::path::Path;
+
+        let mut themes = Themes::new();
+
+        assert!(themes.load_file(Path::new("tests/sample/file")).is_err());
+        assert!(themes.load_file(Path::new("tests/sample/missing.tmTheme")).is_err());
+    }
+}
@@ -1,3 +1,23 @@
+use memchr::memchr;
+use std::ops;
+
+/// A line produced by `LineSpans`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line<'a> {
+    /// The zero-based line number.
+    pub number: usize,
+
+    /// The line's content, including its trailing newline, if any.
+    pub content: &'a str,
+
+    /// The byte range of the line's content (including
+    /// its trailing newline) within the iterated data.
+    pub range: ops::Range<usize>,
+
+    /// Whether or not the line ends with a newline.
+    pub newline: bool,
+}
+
 pub struct LineIterator<'a> {
     data: &'a str,
     line_number: usize,
@@ -17,15 +37,30 @@ impl<'a> LineIterator<'a> {
         }
     }
 
+    /// Converts the iterator into one producing `Line` values, which
+    /// also include each line's byte range within the iterated data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::util::LineIterator;
+    ///
+    /// let ranges: Vec<(usize, usize)> = LineIterator::new("scribe\nlibrary")
+    ///     .spans()
+    ///     .map(|line| (line.range.start, line.range.end))
+    ///     .collect();
+    ///
+    /// assert_eq!(ranges, vec![(0, 7), (7, 14)]);
+    /// ```
+    pub fn spans(self) -> LineSpans<'a> {
+        LineSpans{ lines: self }
+    }
+
     fn out_of_data(&self) -> bool {
         self.line_end == self.data.len()
     }
-}
 
-impl<'a> Iterator for LineIterator<'a> {
-    type Item = (usize, &'a str);
-
-    fn next(&mut self) -> Option<Self::Item> {
+    fn next_line(&mut self) -> Option<Line<'a>> {
         if self.done {
             return None
         }
@@ -37,23 +72,21 @@ impl<'a> Iterator for LineIterator<'a> {
         // after one, we want to return one last line on the next iteration.
         let mut trailing_newline = false;
 
-        // Find the next line range.
-        for c in self.data[self.line_start..].chars() {
-            // Extend the current line range to include this char.
-            self.line_end += c.len_utf8();
-
-            if c == '\n' {
+        // Find the next line range, extending it to include its newline.
+        match memchr(b'\n', self.data[self.line_start..].as_bytes()) {
+            Some(newline_offset) => {
+                self.line_end = self.line_start + newline_offset + 1;
                 trailing_newline = true;
-                break;
-            }
+            },
+            None => self.line_end = self.data.len(),
         }
 
-        let line = Some((
-            self.line_number,
-            &self.data[
-                self.line_start..self.line_end
-            ]
-        ));
+        let line = Some(Line{
+            number: self.line_number,
+            content: &self.data[self.line_start..self.line_end],
+            range: self.line_start..self.line_end,
+            newline: trailing_newline,
+        });
 
         // Flag the iterator as done as soon as we've exhausted its data,
         // and have given one last line for data with a trailing newline.
@@ -67,9 +100,30 @@ impl<'a> Iterator for LineIterator<'a> {
     }
 }
 
+impl<'a> Iterator for LineIterator<'a> {
+    type Item = (usize, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_line().map(|line| (line.number, line.content))
+    }
+}
+
+/// An iterator over lines and their byte ranges (see `LineIterator::spans`).
+pub struct LineSpans<'a> {
+    lines: LineIterator<'a>,
+}
+
+impl<'a> Iterator for LineSpans<'a> {
+    type Item = Line<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.lines.next_line()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::LineIterator;
+    use super::{Line, LineIterator};
 
     #[test]
     fn next_produces_a_value_for_empty_data() {
@@ -92,4 +146,25 @@ mod tests {
         lines.next();
         assert_eq!(None, lines.next());
     }
+
+    #[test]
+    fn spans_include_trailing_newlines() {
+        let mut lines = LineIterator::new("line\nanother line\n").spans();
+        assert_eq!(Some(Line{ number: 0, content: "line\n", range: 0..5, newline: true }), lines.next());
+        assert_eq!(Some(Line{ number: 1, content: "another line\n", range: 5..18, newline: true }), lines.next());
+        assert_eq!(Some(Line{ number: 2, content: "", range: 18..18, newline: false }), lines.next());
+        assert_eq!(None, lines.next());
+    }
+
+    #[test]
+    fn spans_report_byte_ranges_of_multibyte_content() {
+        let data = "नी\n😀 scribe";
+        let ranges: Vec<(usize, usize)> = LineIterator::new(data)
+            .spans()
+            .map(|line| (line.range.start, line.range.end))
+            .collect();
+
+        assert_eq!(ranges, vec![(0, 7), (7, data.len())]);
+        assert_eq!(&data[7..], "😀 scribe");
+    }
 }
@@ -1,3 +1,3 @@
 mod line_iterator;
 
-pub use self::line_iterator::LineIterator;
+pub use self::line_iterator::{Line, LineIterator, LineSpans};
@@ -1,19 +1,101 @@
 //! Buffer and working directory management.
 
-use buffer::Buffer;
+use buffer::{Buffer, BufferSettings, LineRange, Position, Range, StyledLine, TrackingId};
 use errors::*;
+use recent_files::RecentFiles;
+use registers::{RegisterKind, Registers, UNNAMED_REGISTER};
+use search_index::{IndexMatch, TrigramIndex};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::io;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::thread::JoinHandle;
 use syntect::parsing::{SyntaxDefinition, SyntaxSet};
+use themes::Themes;
+
+// The most recent paste, which can be replaced with earlier
+// content from the registers' history using `paste_previous`.
+struct Paste {
+    buffer_id: Option<usize>,
+    position: Position,
+    history_index: Option<usize>,
+
+    // The buffer's history revision following the paste,
+    // which changes if anything else is done to it.
+    revision: usize,
+}
+
+/// A mark set on a position in one of the workspace's buffers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlobalMark {
+    pub name: char,
+    pub buffer_id: usize,
+    pub position: Position,
+}
+
+/// The role a buffer plays in a workspace, which determines
+/// how it's listed, switched to, and closed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BufferKind {
+    /// An ordinary buffer.
+    Normal,
+
+    /// A read-only buffer showing a file that hasn't been opened for editing
+    /// (e.g. from a quick-open menu). A workspace holds at most one preview,
+    /// which is replaced by subsequent ones; opening its file using
+    /// `open_buffer` turns it into a normal buffer.
+    Preview,
+
+    /// A short-lived buffer (e.g. a prompt or scratch space), which isn't
+    /// listed or selected when switching between buffers.
+    Transient,
+}
+
+/// A summary of one of a workspace's buffers, for display in a buffer list.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BufferListing {
+    pub id: usize,
+    pub path: Option<PathBuf>,
+    pub kind: BufferKind,
+    pub pinned: bool,
+    pub modified: bool,
+}
 
 /// An owned collection of buffers and associated path,
 /// representing a running editor environment.
+///
+/// Settings configured for a file type (see `set_filetype_settings`) are
+/// applied to buffers as they're added, and when their syntax is updated.
+/// The current buffer is styled using the workspace's selected theme.
+///
+/// An optional search index (see `build_search_index`) covers the
+/// workspace's files, with open buffers' content taking precedence over
+/// their files' content on disk.
 pub struct Workspace {
     pub path: PathBuf,
     buffers: Vec<Buffer>,
     next_buffer_id: usize,
     current_buffer_index: Option<usize>,
+    last_paste: Option<Paste>,
+    global_marks: Vec<(char, usize, TrackingId)>,
+    filetype_settings: HashMap<String, BufferSettings>,
+    buffer_kinds: HashMap<usize, BufferKind>,
+    pinned_buffers: HashSet<usize>,
+    search_index: Option<TrigramIndex>,
+    pending_search_index: Option<JoinHandle<io::Result<TrigramIndex>>>,
+
+    // IDs of buffers changed since they were last indexed,
+    // shared with the buffers' change subscriptions.
+    unindexed_buffers: Rc<RefCell<HashSet<usize>>>,
+
+    // Paths to re-index from disk (e.g. when they're changed
+    // by another program, or their buffers are closed).
+    unindexed_paths: HashSet<PathBuf>,
     pub syntax_set: SyntaxSet,
+    pub registers: Registers,
+    pub recent_files: RecentFiles,
+    pub themes: Themes,
 }
 
 impl Workspace {
@@ -29,6 +111,18 @@ impl Workspace {
             next_buffer_id: 0,
             current_buffer_index: None,
             syntax_set,
+            registers: Registers::new(),
+            last_paste: None,
+            global_marks: Vec::new(),
+            filetype_settings: HashMap::new(),
+            buffer_kinds: HashMap::new(),
+            pinned_buffers: HashSet::new(),
+            search_index: None,
+            pending_search_index: None,
+            unindexed_buffers: Rc::new(RefCell::new(HashSet::new())),
+            unindexed_paths: HashSet::new(),
+            recent_files: RecentFiles::new(),
+            themes: Themes::new(),
         })
     }
 
@@ -54,10 +148,47 @@ impl Workspace {
     /// let buf = Buffer::from_file(file_path).unwrap();
     /// workspace.add_buffer(buf);
     /// ```
-    pub fn add_buffer(&mut self, mut buf: Buffer) {
+    pub fn add_buffer(&mut self, buf: Buffer) {
+        self.add_buffer_with_kind(buf, BufferKind::Normal);
+    }
+
+    /// Adds a buffer of the specified kind to the workspace (see
+    /// `add_buffer`). Preview buffers are made read-only, and replace the
+    /// workspace's existing preview buffer, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::{Buffer, Workspace};
+    /// use scribe::BufferKind;
+    /// use std::path::Path;
+    ///
+    /// let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+    /// workspace.add_buffer(Buffer::new());
+    /// workspace.add_buffer_with_kind(Buffer::new(), BufferKind::Transient);
+    ///
+    /// // Transient buffers aren't listed.
+    /// assert_eq!(workspace.current_buffer_kind(), Some(BufferKind::Transient));
+    /// assert_eq!(workspace.list_buffers().len(), 1);
+    /// ```
+    pub fn add_buffer_with_kind(&mut self, mut buf: Buffer, kind: BufferKind) {
+        if kind == BufferKind::Preview {
+            self.close_preview_buffer();
+            buf.read_only = true;
+        }
+        self.buffer_kinds.insert(self.next_buffer_id, kind);
+
         // Set a unique buffer ID.
         buf.id = Some(self.next_buffer_id);
 
+        // Track changes to the buffer, so that they can be re-indexed.
+        let buffer_id = self.next_buffer_id;
+        let unindexed_buffers = self.unindexed_buffers.clone();
+        unindexed_buffers.borrow_mut().insert(buffer_id);
+        buf.subscribe(Box::new(move |_| {
+            unindexed_buffers.borrow_mut().insert(buffer_id);
+        }));
+
         // Increment the ID for the next time.
         self.next_buffer_id += 1;
 
@@ -68,6 +199,9 @@ impl Workspace {
         if buf.syntax_definition.is_none() {
             buf.syntax_definition = self.find_syntax_definition(&buf);
         }
+        if let Some(settings) = self.settings_for(&buf) {
+            buf.apply_settings(&settings);
+        }
 
         // Insert the buffer and select it.
         self.buffers.insert(target_index, buf);
@@ -80,6 +214,7 @@ impl Workspace {
     /// if a buffer with the specified path already exists,
     /// it is selected, rather than opening a duplicate buffer.
     /// Any errors encountered while opening the buffer are returned.
+    /// Opened paths are recorded in the workspace's recent files list.
     ///
     /// # Examples
     ///
@@ -96,34 +231,103 @@ impl Workspace {
     ///
     /// // Open a buffer in the workspace.
     /// workspace.open_buffer(file_path.clone());
+    ///
+    /// // The buffer's canonical path is added to the recent files.
+    /// assert_eq!(workspace.recent_files.ranked(), vec![file_path.canonicalize().unwrap()]);
     /// ```
     pub fn open_buffer(&mut self, path: &Path) -> io::Result<()> {
-        if self.contains_buffer_with_path(path) {
-            // We already have this buffer in the workspace.
-            // Loop through the buffers until it's selected.
-            let canonical_path = path.canonicalize().unwrap();
-            loop {
-                if let Some(buffer) = self.current_buffer() {
-                    if let Some(ref current_path) = buffer.path {
-                        if *current_path == canonical_path {
-                            break;
-                        }
-                    }
-                }
-
-                self.next_buffer()
+        if let Some(index) = self.buffer_index_with_path(path) {
+            // We already have this buffer in the workspace; select it,
+            // and keep it open if it was only being previewed.
+            self.current_buffer_index = Some(index);
+            if self.buffer_kind(index) == BufferKind::Preview {
+                self.set_current_buffer_kind(BufferKind::Normal).ok();
+            }
+            if let Some(path) = self.buffers[index].path.clone() {
+                self.recent_files.record(&path);
             }
 
             // Not going to run into IO errors if we're not opening a buffer.
             Ok(())
         } else {
             let buffer = try!(Buffer::from_file(path));
+            if let Some(ref path) = buffer.path {
+                self.recent_files.record(path);
+            }
             self.add_buffer(buffer);
 
             Ok(())
         }
     }
 
+    /// Opens a buffer using a location spec of the form `path:line:column`
+    /// or `path:line` (as reported by compilers, with one-based line and
+    /// column numbers), or a plain path (see `open_buffer`), and moves its
+    /// cursor to the specified location. Locations beyond the end of a line
+    /// (or the buffer) are clamped to it. Specs naming an existing file are
+    /// always treated as plain paths, even if they include colons.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Workspace;
+    /// use scribe::buffer::Position;
+    /// use std::path::Path;
+    ///
+    /// let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+    /// workspace.open_location("tests/sample/modeline:2:7").unwrap();
+    /// assert_eq!(*workspace.current_buffer().unwrap().cursor, Position{ line: 1, offset: 6 });
+    ///
+    /// workspace.open_location("tests/sample/modeline:10").unwrap();
+    /// assert_eq!(*workspace.current_buffer().unwrap().cursor, Position{ line: 2, offset: 0 });
+    /// ```
+    pub fn open_location(&mut self, spec: &str) -> io::Result<()> {
+        let (path, position) = parse_location(spec);
+        self.open_buffer(path)?;
+
+        if let Some(position) = position {
+            if let Some(buffer) = self.current_buffer() {
+                let position = buffer.clamp(position);
+                buffer.cursor.move_to(position);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Opens a read-only preview of the file at the specified path (see
+    /// `BufferKind::Preview`), replacing the workspace's existing preview, and
+    /// selects it. If a buffer with the specified path already exists, it is
+    /// selected instead. Previewed paths aren't recorded as recent files.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Workspace;
+    /// use scribe::BufferKind;
+    /// use std::path::Path;
+    ///
+    /// let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+    /// workspace.open_preview(Path::new("tests/sample/file")).unwrap();
+    /// workspace.open_preview(Path::new("tests/sample/modeline")).unwrap();
+    /// assert_eq!(workspace.list_buffers().len(), 1);
+    ///
+    /// // Opening the previewed file keeps it open for editing.
+    /// workspace.open_buffer(Path::new("tests/sample/modeline")).unwrap();
+    /// assert_eq!(workspace.current_buffer_kind(), Some(BufferKind::Normal));
+    /// assert!(!workspace.current_buffer().unwrap().read_only);
+    /// ```
+    pub fn open_preview(&mut self, path: &Path) -> io::Result<()> {
+        if let Some(index) = self.buffer_index_with_path(path) {
+            self.current_buffer_index = Some(index);
+        } else {
+            let buffer = try!(Buffer::from_file(path));
+            self.add_buffer_with_kind(buffer, BufferKind::Preview);
+        }
+
+        Ok(())
+    }
+
     /// Returns a mutable reference to the currently
     /// selected buffer, unless the workspace is empty.
     ///
@@ -216,19 +420,107 @@ impl Workspace {
     /// ```
     pub fn close_current_buffer(&mut self) {
         if let Some(index) = self.current_buffer_index {
-            self.buffers.remove(index);
+            self.remove_buffer(index);
+        };
+    }
+
+    /// Closes all buffers other than the current one, except pinned buffers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::{Buffer, Workspace};
+    /// use std::path::Path;
+    ///
+    /// let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+    /// workspace.add_buffer(Buffer::new());
+    /// workspace.set_current_buffer_pinned(true).unwrap();
+    /// workspace.add_buffer(Buffer::new());
+    /// workspace.add_buffer(Buffer::new());
+    ///
+    /// workspace.close_other_buffers();
+    /// assert_eq!(workspace.list_buffers().len(), 2);
+    /// ```
+    pub fn close_other_buffers(&mut self) {
+        let current_index = self.current_buffer_index;
+        self.close_buffers_where(|workspace, index| {
+            Some(index) != current_index && !workspace.is_pinned(index)
+        });
+    }
+
+    /// Closes all transient buffers.
+    pub fn close_transient_buffers(&mut self) {
+        self.close_buffers_where(|workspace, index| workspace.buffer_kind(index) == BufferKind::Transient);
+    }
+
+    /// Returns the current buffer's kind, unless the workspace is empty.
+    pub fn current_buffer_kind(&self) -> Option<BufferKind> {
+        self.current_buffer_index.map(|index| self.buffer_kind(index))
+    }
+
+    /// Changes the current buffer's kind. Preview buffers are made read-only
+    /// (replacing the workspace's existing preview buffer, if any), and
+    /// buffers that are no longer previews are made editable.
+    pub fn set_current_buffer_kind(&mut self, kind: BufferKind) -> Result<()> {
+        let index = self.current_buffer_index.ok_or(ErrorKind::EmptyWorkspace)?;
+        let previous_kind = self.buffer_kind(index);
+        if kind == previous_kind {
+            return Ok(())
+        }
+
+        if kind == BufferKind::Preview {
+            self.close_preview_buffer();
+        }
+        let index = self.current_buffer_index.ok_or(ErrorKind::EmptyWorkspace)?;
+        let buffer = &mut self.buffers[index];
+        buffer.read_only = kind == BufferKind::Preview;
+        if let Some(id) = buffer.id {
+            self.buffer_kinds.insert(id, kind);
+        }
+
+        Ok(())
+    }
 
-            if self.buffers.is_empty() {
-                self.current_buffer_index = None;
+    /// Pins or unpins the current buffer. Pinned buffers are listed first,
+    /// and aren't closed by `close_other_buffers`.
+    pub fn set_current_buffer_pinned(&mut self, pinned: bool) -> Result<()> {
+        let index = self.current_buffer_index.ok_or(ErrorKind::EmptyWorkspace)?;
+        if let Some(id) = self.buffers[index].id {
+            if pinned {
+                self.pinned_buffers.insert(id);
             } else {
-                self.current_buffer_index = index.checked_sub(1).or(Some(0));
+                self.pinned_buffers.remove(&id);
             }
-        };
+        }
+
+        Ok(())
+    }
+
+    /// Returns summaries of the workspace's buffers, excluding transient
+    /// buffers. Pinned buffers are listed first; buffers are otherwise
+    /// listed in the order they're arranged in the workspace.
+    pub fn list_buffers(&self) -> Vec<BufferListing> {
+        let mut listings: Vec<BufferListing> = self.buffers.iter().enumerate()
+            .filter(|&(index, _)| self.buffer_kind(index) != BufferKind::Transient)
+            .filter_map(|(index, buffer)| buffer.id.map(|id| BufferListing{
+                id,
+                path: buffer.path.clone(),
+                kind: self.buffer_kind(index),
+                pinned: self.is_pinned(index),
+                modified: buffer.modified(),
+            }))
+            .collect();
+
+        // The sort is stable, preserving the workspace's order otherwise.
+        listings.sort_by_key(|listing| !listing.pinned);
+
+        listings
     }
 
     /// Selects the previous buffer in the workspace (buffers are ordered as
     /// they are added to the workspace). If the currently selected buffer is
     /// the first in the collection, this will wrap and select the last buffer.
+    /// Transient buffers are skipped.
     ///
     /// # Examples
     ///
@@ -252,21 +544,14 @@ impl Workspace {
     /// workspace.previous_buffer();
     /// ```
     pub fn previous_buffer(&mut self) {
-        match self.current_buffer_index {
-            Some(index) => {
-                if index > 0 {
-                    self.current_buffer_index = Some(index-1);
-                } else {
-                    self.current_buffer_index = Some(self.buffers.len()-1);
-                }
-            },
-            None => return,
-        }
+        let count = self.buffers.len();
+        self.select_adjacent_buffer(|index| if index > 0 { index-1 } else { count-1 });
     }
 
     /// Selects the next buffer in the workspace (buffers are ordered as
     /// they are added to the workspace). If the currently selected buffer is
     /// the last in the collection, this will wrap and select the first buffer.
+    /// Transient buffers are skipped.
     ///
     /// # Examples
     ///
@@ -290,16 +575,8 @@ impl Workspace {
     /// workspace.next_buffer();
     /// ```
     pub fn next_buffer(&mut self) {
-        match self.current_buffer_index {
-            Some(index) => {
-                if index == self.buffers.len()-1 {
-                    self.current_buffer_index = Some(0);
-                } else {
-                    self.current_buffer_index = Some(index+1);
-                }
-            },
-            None => return,
-        }
+        let count = self.buffers.len();
+        self.select_adjacent_buffer(|index| if index == count-1 { 0 } else { index+1 });
     }
 
     /// Whether or not the workspace contains a buffer with the specified path.
@@ -383,141 +660,675 @@ impl Workspace {
     pub fn update_current_syntax(&mut self) -> Result<()> {
         let index = self.current_buffer_index.ok_or(ErrorKind::EmptyWorkspace)?;
         let syntax_definition = self.find_syntax_definition(&self.buffers[index]);
-        let buffer = &mut self.buffers[index];
-        buffer.syntax_definition = syntax_definition;
+        self.buffers[index].syntax_definition = syntax_definition;
+        if let Some(settings) = self.settings_for(&self.buffers[index]) {
+            self.buffers[index].apply_settings(&settings);
+        }
 
         Ok(())
     }
 
-    // Returns a syntax definition based on the buffer's file extension,
-    // falling back to a plain text definition if one cannot be found.
-    fn find_syntax_definition(&self, buffer: &Buffer) -> Option<SyntaxDefinition> {
-        // Find the syntax definition using the buffer's file extension.
-        buffer.path.as_ref().and_then(|path|
-            path.to_str().and_then(|p| p.split('.').last()).and_then(|ex|
-                self.syntax_set.find_syntax_by_extension(ex).and_then(|s|
-                    Some(s.clone())
-                )
-            )
-        ).or_else(||
-            // Fall back to a plain text definition.
-            Some(self.syntax_set.find_syntax_plain_text().clone())
-        )
+    /// Configures settings for buffers of the specified file type (matched
+    /// against their syntax definition's name, ignoring case), replacing any
+    /// previously configured for it. They're applied to the workspace's
+    /// existing buffers of that type, as well as those added later (see
+    /// `Buffer::apply_settings`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Workspace;
+    /// use scribe::buffer::{BufferSettings, LineEnding};
+    /// use std::path::Path;
+    ///
+    /// let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+    /// workspace.set_filetype_settings("python", BufferSettings{
+    ///     line_ending: Some(LineEnding::Crlf),
+    ///     ..BufferSettings::default()
+    /// });
+    ///
+    /// // This file's modeline specifies its file type.
+    /// workspace.open_buffer(Path::new("tests/sample/modeline")).unwrap();
+    /// assert_eq!(workspace.current_buffer().unwrap().line_ending(), LineEnding::Crlf);
+    /// ```
+    pub fn set_filetype_settings(&mut self, filetype: &str, settings: BufferSettings) {
+        let filetype = filetype.to_lowercase();
+        for buffer in self.buffers.iter_mut() {
+            if buffer_filetype(buffer).as_ref() == Some(&filetype) {
+                buffer.apply_settings(&settings);
+            }
+        }
+
+        self.filetype_settings.insert(filetype, settings);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::Workspace;
-    use buffer::Buffer;
-    use std::path::Path;
-    use std::env;
+    /// Returns the settings configured for the specified file type, if any.
+    pub fn filetype_settings(&self, filetype: &str) -> Option<&BufferSettings> {
+        self.filetype_settings.get(&filetype.to_lowercase())
+    }
 
-    #[test]
-    fn add_buffer_adds_and_selects_the_passed_buffer() {
-        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
-        let buf = Buffer::from_file(Path::new("tests/sample/file")).unwrap();
-        workspace.add_buffer(buf);
+    /// Returns the current buffer's styled lines in the specified range,
+    /// using the workspace's selected theme (see `Buffer::styled_lines`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Workspace;
+    /// use scribe::buffer::LineRange;
+    /// use std::path::Path;
+    ///
+    /// let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+    /// workspace.open_buffer(Path::new("tests/sample/file")).unwrap();
+    /// workspace.themes.select("Solarized (light)").unwrap();
+    ///
+    /// let lines = workspace.current_styled_lines(&LineRange::new(0, 1)).unwrap();
+    /// assert_eq!(lines[0].spans[0].value, "it works!");
+    /// ```
+    pub fn current_styled_lines(&self, lines: &LineRange) -> Result<Vec<Rc<StyledLine>>> {
+        let index = self.current_buffer_index.ok_or(ErrorKind::EmptyWorkspace)?;
 
-        assert_eq!(workspace.buffers.len(), 1);
-        assert_eq!(workspace.current_buffer().unwrap().data(), "it works!\n");
+        self.buffers[index].styled_lines(lines, self.themes.current(), &self.themes.current_id())
     }
 
-    #[test]
-    fn add_buffer_inserts_the_new_buffer_after_the_current_buffer() {
-        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
-        let mut buf1 = Buffer::new();
-        let mut buf2 = Buffer::new();
-        let mut buf3 = Buffer::new();
-        buf1.insert("one");
-        buf2.insert("two");
-        buf3.insert("three");
-        workspace.add_buffer(buf1);
-        workspace.add_buffer(buf2);
+    /// Renders the current buffer's lines in the specified range as HTML,
+    /// using the workspace's selected theme (see `Buffer::to_html`).
+    pub fn current_html(&self, lines: &LineRange) -> Result<String> {
+        let index = self.current_buffer_index.ok_or(ErrorKind::EmptyWorkspace)?;
 
-        // Move to the first buffer.
-        workspace.previous_buffer();
+        self.buffers[index].to_html(lines, self.themes.current(), &self.themes.current_id())
+    }
 
-        // Insert the last buffer.
-        workspace.add_buffer(buf3);
+    /// Renders the current buffer's lines in the specified range using ANSI
+    /// escape codes, using the workspace's selected theme (see `Buffer::to_ansi`).
+    pub fn current_ansi(&self, lines: &LineRange, background: bool) -> Result<String> {
+        let index = self.current_buffer_index.ok_or(ErrorKind::EmptyWorkspace)?;
 
-        // Make sure the newly inserted buffer was inserted after the current buffer.
-        workspace.previous_buffer();
-        assert_eq!(workspace.current_buffer().unwrap().data(), "one");
+        self.buffers[index].to_ansi(lines, self.themes.current(), &self.themes.current_id(), background)
     }
 
-    #[test]
-    fn add_buffer_populates_buffers_with_unique_id_values() {
-        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
-        let buf1 = Buffer::new();
-        let buf2 = Buffer::new();
-        let buf3 = Buffer::new();
-
-        workspace.add_buffer(buf1);
-        assert_eq!(workspace.current_buffer().unwrap().id.unwrap(), 0);
+    /// Starts indexing the workspace's files on a background thread (see
+    /// `TrigramIndex::build_in_background`), replacing any existing index.
+    /// Open buffers and file changes (see `file_changed`) are incorporated
+    /// into the index when it's next searched, which waits for indexing to
+    /// complete if it's still in progress.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::Workspace;
+    /// use scribe::buffer::Position;
+    /// use std::path::Path;
+    ///
+    /// let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+    /// workspace.build_search_index();
+    /// workspace.open_buffer(Path::new("tests/sample/file")).unwrap();
+    /// workspace.current_buffer().unwrap().insert("scribe: ");
+    ///
+    /// // The open buffer's unsaved content is searched.
+    /// let matches = workspace.search_workspace("scribe: it").unwrap();
+    /// assert_eq!(matches.len(), 1);
+    /// assert_eq!(matches[0].position, Position{ line: 0, offset: 0 });
+    /// ```
+    pub fn build_search_index(&mut self) {
+        self.pending_search_index = Some(TrigramIndex::build_in_background(&self.path));
+
+        // The index is built from disk, so open buffers need to be
+        // re-indexed, and pending file changes are already included.
+        let mut unindexed_buffers = self.unindexed_buffers.borrow_mut();
+        unindexed_buffers.extend(self.buffers.iter().filter_map(|buffer| buffer.id));
+        self.unindexed_paths.clear();
+    }
 
-        workspace.add_buffer(buf2);
-        assert_eq!(workspace.current_buffer().unwrap().id.unwrap(), 1);
+    /// Whether or not the workspace's search index has been built (or is
+    /// being built) and is in use.
+    pub fn search_index_enabled(&self) -> bool {
+        self.search_index.is_some() || self.pending_search_index.is_some()
+    }
 
-        workspace.add_buffer(buf3);
-        assert_eq!(workspace.current_buffer().unwrap().id.unwrap(), 2);
+    /// Discards the workspace's search index.
+    pub fn disable_search_index(&mut self) {
+        self.search_index = None;
+        self.pending_search_index = None;
     }
 
-    #[test]
-    fn add_buffer_populates_buffers_without_paths_using_plain_text_syntax() {
-        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
-        let buf = Buffer::new();
-        workspace.add_buffer(buf);
+    /// Notifies the search index that the file at the specified path has
+    /// changed (or been created or deleted), so that it's re-indexed from disk
+    /// before the next search. Files that are open in the workspace are
+    /// indexed using their buffer's content instead, and are unaffected.
+    pub fn file_changed(&mut self, path: &Path) {
+        if !self.search_index_enabled() {
+            return
+        }
 
-        let name = workspace
-          .current_buffer()
-          .and_then(|ref b| b.syntax_definition.as_ref().map(|sd| sd.name.clone()));
+        // Deleted files can't be canonicalized, but their directories can.
+        let canonical_path = path.canonicalize().ok().or_else(|| {
+            let directory = match path.parent() {
+                Some(directory) if directory != Path::new("") => directory.canonicalize().ok()?,
+                _ => Path::new(".").canonicalize().ok()?,
+            };
+
+            Some(directory.join(path.file_name()?))
+        });
+        if let Some(canonical_path) = canonical_path {
+            self.unindexed_paths.insert(canonical_path);
+        }
+    }
 
-        assert!(workspace.current_buffer().unwrap().syntax_definition.is_some());
-        assert_eq!(name, Some("Plain Text".to_string()));
+    /// Searches the workspace's files for occurrences of `needle`
+    /// (see `TrigramIndex::search`). Returns an error if the
+    /// search index hasn't been built (see `build_search_index`).
+    pub fn search_workspace(&mut self, needle: &str) -> Result<Vec<IndexMatch>> {
+        Ok(self.updated_search_index()?.search(needle))
     }
 
-    #[test]
-    fn add_buffer_populates_buffers_with_unknown_extensions_using_plain_text_syntax() {
-        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
-        let buf = Buffer::from_file(Path::new("tests/sample/file"));
-        workspace.add_buffer(buf.unwrap());
+    /// Searches the workspace's files for occurrences of `symbol` that
+    /// aren't part of a larger word (see `TrigramIndex::search_symbol`).
+    /// Returns an error if the search index hasn't been built.
+    pub fn find_symbol(&mut self, symbol: &str) -> Result<Vec<IndexMatch>> {
+        Ok(self.updated_search_index()?.search_symbol(symbol))
+    }
 
-        let name = workspace
-          .current_buffer()
-          .and_then(|ref b| b.syntax_definition.as_ref().map(|sd| sd.name.clone()));
+    // Returns the search index, once it's been built and
+    // updated with any changed buffers and files.
+    fn updated_search_index(&mut self) -> Result<&TrigramIndex> {
+        if let Some(handle) = self.pending_search_index.take() {
+            let index = handle.join().map_err(|_| ErrorKind::SearchIndexingFailed)??;
+            self.search_index = Some(index);
+        }
 
-        assert!(workspace.current_buffer().unwrap().syntax_definition.is_some());
-        assert_eq!(name, Some("Plain Text".to_string()));
+        let index = self.search_index.as_mut().ok_or(ErrorKind::MissingSearchIndex)?;
+        let unindexed_buffers: HashSet<usize> = self.unindexed_buffers.borrow_mut().drain().collect();
+        let mut buffer_paths = HashSet::new();
+
+        for buffer in &self.buffers {
+            if let Some(ref path) = buffer.path {
+                if path.starts_with(&self.path) {
+                    if buffer.id.map(|id| unindexed_buffers.contains(&id)).unwrap_or(false) {
+                        index.update(path, buffer.data());
+                    }
+                    buffer_paths.insert(path.clone());
+                }
+            }
+        }
+
+        for path in self.unindexed_paths.drain() {
+            if !buffer_paths.contains(&path) && path.starts_with(&self.path) {
+                index.update_file(&path);
+            }
+        }
+
+        Ok(index)
     }
 
-    #[test]
-    fn open_buffer_adds_and_selects_the_buffer_at_the_specified_path() {
-        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
-        workspace.open_buffer(Path::new("tests/sample/file")).unwrap();
+    // Selects the buffer that follows the current one, as determined by the
+    // step function (which maps an index to the next index), skipping
+    // transient buffers. The selection is unchanged if there isn't another
+    // buffer to select.
+    fn select_adjacent_buffer<F>(&mut self, step: F) where F: Fn(usize) -> usize {
+        let mut index = match self.current_buffer_index {
+            Some(index) => index,
+            None => return,
+        };
 
-        assert_eq!(workspace.buffers.len(), 1);
-        assert_eq!(workspace.current_buffer().unwrap().data(), "it works!\n");
+        for _ in 0..self.buffers.len() {
+            index = step(index);
+            if self.buffer_kind(index) != BufferKind::Transient {
+                self.current_buffer_index = Some(index);
+                return
+            }
+        }
     }
 
-    #[test]
-    fn open_buffer_does_not_open_a_buffer_already_in_the_workspace() {
-        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
-        workspace.open_buffer(Path::new("tests/sample/file")).unwrap();
-        workspace.open_buffer(Path::new("tests/sample/file")).unwrap();
+    fn buffer_kind(&self, index: usize) -> BufferKind {
+        self.buffers[index].id
+            .and_then(|id| self.buffer_kinds.get(&id).cloned())
+            .unwrap_or(BufferKind::Normal)
+    }
 
-        assert_eq!(workspace.buffers.len(), 1);
+    fn is_pinned(&self, index: usize) -> bool {
+        self.buffers[index].id.map(|id| self.pinned_buffers.contains(&id)).unwrap_or(false)
     }
 
-    #[test]
-    fn open_buffer_selects_buffer_if_it_already_exists_in_workspace() {
-        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
-        workspace.open_buffer(Path::new("tests/sample/file")).unwrap();
+    // Returns the index of the buffer with the specified (canonicalized) path.
+    fn buffer_index_with_path(&self, path: &Path) -> Option<usize> {
+        let canonical_path = path.canonicalize().ok()?;
 
-        // Add and select another buffer.
-        let mut buf = Buffer::new();
-        buf.insert("scribe");
-        workspace.add_buffer(buf);
+        self.buffers.iter().position(|buffer| buffer.path.as_ref() == Some(&canonical_path))
+    }
+
+    fn close_preview_buffer(&mut self) {
+        self.close_buffers_where(|workspace, index| workspace.buffer_kind(index) == BufferKind::Preview);
+    }
+
+    // Closes the buffers at the indices for which the predicate returns true.
+    fn close_buffers_where<F>(&mut self, predicate: F) where F: Fn(&Workspace, usize) -> bool {
+        // Remove buffers from the end of the collection
+        // backwards, so that removals don't affect indices.
+        for index in (0..self.buffers.len()).rev() {
+            if predicate(self, index) {
+                self.remove_buffer(index);
+            }
+        }
+    }
+
+    // Removes the buffer at the specified index, along with its global marks,
+    // kind, pinned state, and indexed content, keeping the current buffer
+    // selected. If it's the current buffer, the previous buffer is selected
+    // instead.
+    fn remove_buffer(&mut self, index: usize) -> Buffer {
+        let buffer = self.buffers.remove(index);
+        if let Some(id) = buffer.id {
+            self.global_marks.retain(|&(_, buffer_id, _)| buffer_id != id);
+            self.buffer_kinds.remove(&id);
+            self.pinned_buffers.remove(&id);
+            self.unindexed_buffers.borrow_mut().remove(&id);
+        }
+
+        // Revert the search index to the file's content on disk.
+        if let Some(ref path) = buffer.path {
+            if self.search_index_enabled() {
+                self.unindexed_paths.insert(path.clone());
+            }
+        }
+
+        self.current_buffer_index = match self.current_buffer_index {
+            _ if self.buffers.is_empty() => None,
+            Some(current_index) if current_index > index => Some(current_index - 1),
+            Some(current_index) if current_index == index => index.checked_sub(1).or(Some(0)),
+            current_index => current_index,
+        };
+
+        buffer
+    }
+
+    // Returns the settings configured for the buffer's file type, if any.
+    fn settings_for(&self, buffer: &Buffer) -> Option<BufferSettings> {
+        buffer_filetype(buffer).and_then(|filetype| self.filetype_settings.get(&filetype).cloned())
+    }
+
+    /// Yanks the specified range of the current buffer into a register
+    /// (see `Buffer::yank` and `Registers::yank`). Returns false if the
+    /// range is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::{Buffer, RegisterKind, Workspace};
+    /// use scribe::buffer::{Position, Range};
+    /// use std::path::Path;
+    ///
+    /// let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe library");
+    /// workspace.add_buffer(buffer);
+    ///
+    /// let range = Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 7 });
+    /// workspace.yank(Some('a'), &range, RegisterKind::Characterwise).unwrap();
+    /// workspace.paste(Some('a')).unwrap();
+    ///
+    /// assert_eq!(workspace.current_buffer().unwrap().data(), "scribe scribe library");
+    /// ```
+    pub fn yank(&mut self, register: Option<char>, range: &Range, kind: RegisterKind) -> Result<bool> {
+        let index = self.current_buffer_index.ok_or(ErrorKind::EmptyWorkspace)?;
+
+        self.last_paste = None;
+
+        Ok(match self.buffers[index].yank(range, kind) {
+            Some(content) => {
+                self.registers.yank(register, content);
+                true
+            },
+            None => false,
+        })
+    }
+
+    /// Pastes the content of the specified register (or the unnamed register,
+    /// if one isn't specified) into the current buffer (see `Buffer::paste`).
+    /// Returns false if the register is empty.
+    pub fn paste(&mut self, register: Option<char>) -> Result<bool> {
+        let index = self.current_buffer_index.ok_or(ErrorKind::EmptyWorkspace)?;
+
+        let buffer = &mut self.buffers[index];
+        let position = *buffer.cursor;
+        self.last_paste = None;
+
+        let content = match self.registers.get(register.unwrap_or(UNNAMED_REGISTER)) {
+            Some(content) => content.clone(),
+            None => return Ok(false),
+        };
+        let revision = buffer.history_revision();
+        buffer.paste(&content);
+
+        // Pastes into read-only buffers don't change them, and can't be replaced.
+        if buffer.history_revision() != revision {
+            self.last_paste = Some(Paste{
+                buffer_id: buffer.id,
+                position,
+                history_index: self.registers.history().iter().position(|entry| *entry == content),
+                revision: buffer.history_revision(),
+            });
+        }
+
+        Ok(true)
+    }
+
+    /// Replaces the content inserted by the preceding call to `paste` (or
+    /// `paste_previous`) with the next oldest entry in the registers' history,
+    /// wrapping around to the most recent entry. Returns false if the current
+    /// buffer wasn't the last to have content pasted into it, if it's been
+    /// changed (or its history navigated) since, or if the history is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::{Buffer, RegisterKind, Workspace};
+    /// use scribe::buffer::{Position, Range};
+    /// use std::path::Path;
+    ///
+    /// let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe library");
+    /// workspace.add_buffer(buffer);
+    ///
+    /// let scribe = Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 6 });
+    /// let library = Range::new(Position{ line: 0, offset: 7 }, Position{ line: 0, offset: 14 });
+    /// workspace.yank(None, &scribe, RegisterKind::Characterwise).unwrap();
+    /// workspace.yank(None, &library, RegisterKind::Characterwise).unwrap();
+    ///
+    /// workspace.paste(None).unwrap();
+    /// assert_eq!(workspace.current_buffer().unwrap().data(), "libraryscribe library");
+    ///
+    /// workspace.paste_previous().unwrap();
+    /// assert_eq!(workspace.current_buffer().unwrap().data(), "scribescribe library");
+    /// ```
+    pub fn paste_previous(&mut self) -> Result<bool> {
+        let index = self.current_buffer_index.ok_or(ErrorKind::EmptyWorkspace)?;
+        let buffer = &mut self.buffers[index];
+        let history = self.registers.history();
+
+        let paste = match self.last_paste {
+            Some(ref mut paste) if paste.buffer_id == buffer.id &&
+                paste.revision == buffer.history_revision() &&
+                !history.is_empty() => paste,
+            _ => return Ok(false),
+        };
+        let history_index = paste.history_index.map_or(0, |index| (index + 1) % history.len());
+
+        // Pastes are single operations, and can be replaced by undoing them.
+        buffer.undo();
+        buffer.cursor.move_to(paste.position);
+        buffer.paste(&history[history_index]);
+        paste.history_index = Some(history_index);
+        paste.revision = buffer.history_revision();
+
+        Ok(true)
+    }
+
+    /// Sets the named global mark to the current buffer's cursor position,
+    /// replacing any existing global mark with the same name. Like buffer
+    /// marks (see `Buffer::set_mark`), the position is adjusted as the buffer
+    /// is edited; the mark is removed when its buffer is closed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scribe::{Buffer, Workspace};
+    /// use scribe::buffer::Position;
+    /// use std::path::Path;
+    ///
+    /// let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+    /// let mut buffer = Buffer::new();
+    /// buffer.insert("scribe\nlibrary");
+    /// buffer.cursor.move_to(Position{ line: 1, offset: 2 });
+    /// workspace.add_buffer(buffer);
+    /// workspace.set_global_mark('A').unwrap();
+    ///
+    /// workspace.add_buffer(Buffer::new());
+    /// assert!(workspace.jump_to_global_mark('A'));
+    /// assert_eq!(workspace.current_buffer().unwrap().data(), "scribe\nlibrary");
+    /// assert_eq!(*workspace.current_buffer().unwrap().cursor, Position{ line: 1, offset: 2 });
+    /// ```
+    pub fn set_global_mark(&mut self, name: char) -> Result<()> {
+        let index = self.current_buffer_index.ok_or(ErrorKind::EmptyWorkspace)?;
+        self.delete_global_mark(name);
+
+        let buffer = &mut self.buffers[index];
+        let position = *buffer.cursor;
+        let id = buffer.track_position(position);
+        if let Some(buffer_id) = buffer.id {
+            self.global_marks.push((name, buffer_id, id));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the workspace's global marks, ordered by name.
+    pub fn global_marks(&self) -> Vec<GlobalMark> {
+        let mut marks: Vec<GlobalMark> = self.global_marks.iter().filter_map(|&(name, buffer_id, id)| {
+            self.buffers.iter()
+                .find(|buffer| buffer.id == Some(buffer_id))
+                .and_then(|buffer| buffer.tracked_position(id))
+                .map(|position| GlobalMark{ name, buffer_id, position })
+        }).collect();
+        marks.sort_by_key(|mark| mark.name);
+
+        marks
+    }
+
+    /// Selects the buffer containing the named global mark, and moves its
+    /// cursor to the mark. Returns false if the mark isn't set.
+    pub fn jump_to_global_mark(&mut self, name: char) -> bool {
+        let mark = match self.global_marks().into_iter().find(|mark| mark.name == name) {
+            Some(mark) => mark,
+            None => return false,
+        };
+
+        match self.buffers.iter().position(|buffer| buffer.id == Some(mark.buffer_id)) {
+            Some(index) => {
+                self.current_buffer_index = Some(index);
+                self.buffers[index].cursor.move_to(mark.position)
+            },
+            None => false,
+        }
+    }
+
+    /// Removes the named global mark, if it's set.
+    pub fn delete_global_mark(&mut self, name: char) {
+        if let Some(index) = self.global_marks.iter().position(|&(mark_name, _, _)| mark_name == name) {
+            let (_, buffer_id, id) = self.global_marks.remove(index);
+            if let Some(buffer) = self.buffers.iter_mut().find(|buffer| buffer.id == Some(buffer_id)) {
+                buffer.untrack(id);
+            }
+        }
+    }
+
+    // Returns a syntax definition based on the buffer's modeline or file
+    // extension, falling back to a plain text definition if one cannot be found.
+    fn find_syntax_definition(&self, buffer: &Buffer) -> Option<SyntaxDefinition> {
+        // Prefer the file type specified by the buffer's modeline.
+        let modeline_definition = buffer.modeline()
+            .and_then(|modeline| modeline.filetype.as_ref())
+            .and_then(|filetype| self.syntax_set.find_syntax_by_token(filetype));
+        if let Some(definition) = modeline_definition {
+            return Some(definition.clone())
+        }
+
+        // Find the syntax definition using the buffer's file extension.
+        buffer.path.as_ref().and_then(|path|
+            path.to_str().and_then(|p| p.split('.').last()).and_then(|ex|
+                self.syntax_set.find_syntax_by_extension(ex).and_then(|s|
+                    Some(s.clone())
+                )
+            )
+        ).or_else(||
+            // Fall back to a plain text definition.
+            Some(self.syntax_set.find_syntax_plain_text().clone())
+        )
+    }
+}
+
+// Splits a `path:line:column` or `path:line` location spec into its path and
+// (zero-based) position. Specs naming existing files, or without a numeric
+// line, are treated as plain paths.
+fn parse_location(spec: &str) -> (&Path, Option<Position>) {
+    let path = Path::new(spec);
+    if path.exists() {
+        return (path, None)
+    }
+
+    let (path, line, column) = match split_trailing_number(spec) {
+        Some((remainder, last)) => match split_trailing_number(remainder) {
+            Some((path, line)) => (path, line, last),
+            None => (remainder, last, 1),
+        },
+        None => return (path, None),
+    };
+    if path.is_empty() {
+        return (Path::new(spec), None)
+    }
+
+    (Path::new(path), Some(Position{ line: line.saturating_sub(1), offset: column.saturating_sub(1) }))
+}
+
+// Splits a colon-delimited number from the end of the string.
+fn split_trailing_number(value: &str) -> Option<(&str, usize)> {
+    let index = value.rfind(':')?;
+    let number = value[index + 1..].parse().ok()?;
+
+    Some((&value[..index], number))
+}
+
+// Returns the buffer's (lowercase) file type, based on its syntax definition.
+fn buffer_filetype(buffer: &Buffer) -> Option<String> {
+    buffer.syntax_definition.as_ref().map(|def| def.name.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_location, BufferKind, GlobalMark, Workspace};
+    use buffer::{Buffer, BufferSettings, CommentTokens, Indentation, IndentationStyle, LineRange, Position, Range, SaveTransforms};
+    use registers::RegisterKind;
+    use std::path::{Path, PathBuf};
+    use std::env;
+
+    #[test]
+    fn add_buffer_adds_and_selects_the_passed_buffer() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        let buf = Buffer::from_file(Path::new("tests/sample/file")).unwrap();
+        workspace.add_buffer(buf);
+
+        assert_eq!(workspace.buffers.len(), 1);
+        assert_eq!(workspace.current_buffer().unwrap().data(), "it works!\n");
+    }
+
+    #[test]
+    fn add_buffer_inserts_the_new_buffer_after_the_current_buffer() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        let mut buf1 = Buffer::new();
+        let mut buf2 = Buffer::new();
+        let mut buf3 = Buffer::new();
+        buf1.insert("one");
+        buf2.insert("two");
+        buf3.insert("three");
+        workspace.add_buffer(buf1);
+        workspace.add_buffer(buf2);
+
+        // Move to the first buffer.
+        workspace.previous_buffer();
+
+        // Insert the last buffer.
+        workspace.add_buffer(buf3);
+
+        // Make sure the newly inserted buffer was inserted after the current buffer.
+        workspace.previous_buffer();
+        assert_eq!(workspace.current_buffer().unwrap().data(), "one");
+    }
+
+    #[test]
+    fn add_buffer_populates_buffers_with_unique_id_values() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        let buf1 = Buffer::new();
+        let buf2 = Buffer::new();
+        let buf3 = Buffer::new();
+
+        workspace.add_buffer(buf1);
+        assert_eq!(workspace.current_buffer().unwrap().id.unwrap(), 0);
+
+        workspace.add_buffer(buf2);
+        assert_eq!(workspace.current_buffer().unwrap().id.unwrap(), 1);
+
+        workspace.add_buffer(buf3);
+        assert_eq!(workspace.current_buffer().unwrap().id.unwrap(), 2);
+    }
+
+    #[test]
+    fn add_buffer_populates_buffers_without_paths_using_plain_text_syntax() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        let buf = Buffer::new();
+        workspace.add_buffer(buf);
+
+        let name = workspace
+          .current_buffer()
+          .and_then(|ref b| b.syntax_definition.as_ref().map(|sd| sd.name.clone()));
+
+        assert!(workspace.current_buffer().unwrap().syntax_definition.is_some());
+        assert_eq!(name, Some("Plain Text".to_string()));
+    }
+
+    #[test]
+    fn add_buffer_populates_buffers_with_unknown_extensions_using_plain_text_syntax() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        let buf = Buffer::from_file(Path::new("tests/sample/file"));
+        workspace.add_buffer(buf.unwrap());
+
+        let name = workspace
+          .current_buffer()
+          .and_then(|ref b| b.syntax_definition.as_ref().map(|sd| sd.name.clone()));
+
+        assert!(workspace.current_buffer().unwrap().syntax_definition.is_some());
+        assert_eq!(name, Some("Plain Text".to_string()));
+    }
+
+    #[test]
+    fn open_buffer_adds_and_selects_the_buffer_at_the_specified_path() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        workspace.open_buffer(Path::new("tests/sample/file")).unwrap();
+
+        assert_eq!(workspace.buffers.len(), 1);
+        assert_eq!(workspace.current_buffer().unwrap().data(), "it works!\n");
+    }
+
+    #[test]
+    fn open_buffer_does_not_open_a_buffer_already_in_the_workspace() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        workspace.open_buffer(Path::new("tests/sample/file")).unwrap();
+        workspace.open_buffer(Path::new("tests/sample/file")).unwrap();
+
+        assert_eq!(workspace.buffers.len(), 1);
+    }
+
+    #[test]
+    fn open_buffer_records_reopened_files_in_the_recent_files() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        workspace.open_buffer(Path::new("tests/sample/file")).unwrap();
+        workspace.open_buffer(Path::new("tests/sample/file")).unwrap();
+        assert!(workspace.open_buffer(Path::new("tests/sample/missing")).is_err());
+
+        let files = workspace.recent_files.files();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, Path::new("tests/sample/file").canonicalize().unwrap());
+        assert_eq!(files[0].open_count, 2);
+    }
+
+    #[test]
+    fn open_buffer_selects_buffer_if_it_already_exists_in_workspace() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        workspace.open_buffer(Path::new("tests/sample/file")).unwrap();
+
+        // Add and select another buffer.
+        let mut buf = Buffer::new();
+        buf.insert("scribe");
+        workspace.add_buffer(buf);
         assert_eq!(workspace.current_buffer().unwrap().data(), "scribe");
 
         // Try to add the first buffer again.
@@ -690,4 +1501,400 @@ mod tests {
         workspace.next_buffer();
         assert_eq!(workspace.current_buffer().unwrap().data(), "third buffer");
     }
+
+    #[test]
+    fn yank_and_paste_use_the_unnamed_register_by_default() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        let mut buf = Buffer::new();
+        buf.insert("scribe\nlibrary");
+        workspace.add_buffer(buf);
+
+        let range = Range::new(Position{ line: 1, offset: 0 }, Position{ line: 1, offset: 0 });
+        assert!(workspace.yank(None, &range, RegisterKind::Linewise).unwrap());
+        assert!(workspace.paste(None).unwrap());
+        assert_eq!(workspace.current_buffer().unwrap().data(), "library\nscribe\nlibrary");
+    }
+
+    #[test]
+    fn paste_previous_cycles_through_the_history() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        let mut buf = Buffer::new();
+        buf.insert("ab");
+        workspace.add_buffer(buf);
+        for offset in 0..2 {
+            let range = Range::new(Position{ line: 0, offset }, Position{ line: 0, offset: offset + 1 });
+            workspace.yank(None, &range, RegisterKind::Characterwise).unwrap();
+        }
+
+        workspace.current_buffer().unwrap().cursor.move_to(Position{ line: 0, offset: 2 });
+        assert!(workspace.paste(Some('a')).is_ok());
+        assert!(workspace.paste(None).unwrap());
+        assert!(workspace.paste_previous().unwrap());
+        assert_eq!(workspace.current_buffer().unwrap().data(), "aba");
+        assert!(workspace.paste_previous().unwrap());
+        assert_eq!(workspace.current_buffer().unwrap().data(), "abb");
+
+        workspace.current_buffer().unwrap().undo();
+        assert_eq!(workspace.current_buffer().unwrap().data(), "ab");
+    }
+
+    #[test]
+    fn paste_previous_requires_a_preceding_paste() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        let mut buf = Buffer::new();
+        buf.insert("scribe");
+        workspace.add_buffer(buf);
+        let range = Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 6 });
+        workspace.yank(None, &range, RegisterKind::Characterwise).unwrap();
+
+        assert!(!workspace.paste_previous().unwrap());
+        assert!(workspace.paste(None).unwrap());
+        workspace.add_buffer(Buffer::new());
+        assert!(!workspace.paste_previous().unwrap());
+    }
+
+    #[test]
+    fn paste_previous_does_not_replace_pastes_followed_by_other_changes() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        let mut buf = Buffer::new();
+        buf.insert("ab");
+        workspace.add_buffer(buf);
+        for offset in 0..2 {
+            let range = Range::new(Position{ line: 0, offset }, Position{ line: 0, offset: offset + 1 });
+            workspace.yank(None, &range, RegisterKind::Characterwise).unwrap();
+        }
+
+        assert!(workspace.paste(None).unwrap());
+        workspace.current_buffer().unwrap().insert("c");
+        assert!(!workspace.paste_previous().unwrap());
+        assert_eq!(workspace.current_buffer().unwrap().data(), "bcab");
+
+        // Undoing the change doesn't make the paste replaceable again.
+        workspace.current_buffer().unwrap().undo();
+        assert!(!workspace.paste_previous().unwrap());
+        assert_eq!(workspace.current_buffer().unwrap().data(), "bab");
+    }
+
+    #[test]
+    fn paste_previous_ignores_pastes_into_read_only_buffers() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        let mut buf = Buffer::new();
+        buf.insert("scribe");
+        workspace.add_buffer(buf);
+        let range = Range::new(Position{ line: 0, offset: 0 }, Position{ line: 0, offset: 6 });
+        workspace.yank(None, &range, RegisterKind::Characterwise).unwrap();
+
+        workspace.current_buffer().unwrap().read_only = true;
+        workspace.paste(None).unwrap();
+        workspace.current_buffer().unwrap().read_only = false;
+
+        assert!(!workspace.paste_previous().unwrap());
+        assert_eq!(workspace.current_buffer().unwrap().data(), "scribe");
+    }
+
+    #[test]
+    fn global_marks_are_removed_when_their_buffers_are_closed() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        let mut buf = Buffer::new();
+        buf.insert("scribe");
+        workspace.add_buffer(buf);
+        workspace.set_global_mark('A').unwrap();
+        workspace.add_buffer(Buffer::new());
+        workspace.set_global_mark('B').unwrap();
+
+        workspace.previous_buffer();
+        workspace.close_current_buffer();
+        assert_eq!(workspace.global_marks(), vec![GlobalMark{
+            name: 'B',
+            buffer_id: 1,
+            position: Position{ line: 0, offset: 0 },
+        }]);
+        assert!(!workspace.jump_to_global_mark('A'));
+    }
+
+    #[test]
+    fn set_global_mark_replaces_marks_in_other_buffers() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        assert!(workspace.set_global_mark('A').is_err());
+
+        workspace.add_buffer(Buffer::new());
+        workspace.set_global_mark('A').unwrap();
+        workspace.add_buffer(Buffer::new());
+        workspace.set_global_mark('A').unwrap();
+        workspace.delete_global_mark('B');
+
+        let buffer_ids: Vec<usize> = workspace.global_marks().iter().map(|mark| mark.buffer_id).collect();
+        assert_eq!(buffer_ids, vec![1]);
+    }
+
+    #[test]
+    fn add_buffer_uses_the_modeline_file_type_to_select_a_syntax_definition() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        let buf = Buffer::from_file(Path::new("tests/sample/modeline")).unwrap();
+        workspace.add_buffer(buf);
+
+        assert_eq!(workspace.current_buffer().unwrap().syntax_definition.as_ref().unwrap().name, "Python");
+    }
+
+    #[test]
+    fn filetype_settings_are_applied_when_the_syntax_is_updated() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        let tokens = CommentTokens{ line: Some(";;"), block: None };
+        workspace.set_filetype_settings("Rust", BufferSettings{
+            comment_tokens: Some(tokens),
+            ..BufferSettings::default()
+        });
+        let mut buf = Buffer::new();
+        buf.insert("fn main() {}");
+        workspace.add_buffer(buf);
+        assert!(workspace.current_buffer().unwrap().comment_tokens(0).is_err());
+
+        workspace.current_buffer().unwrap().path = Some(PathBuf::from("main.rs"));
+        workspace.update_current_syntax().unwrap();
+
+        assert_eq!(workspace.current_buffer().unwrap().comment_tokens(0).unwrap(), tokens);
+    }
+
+    #[test]
+    fn set_filetype_settings_applies_them_to_matching_buffers() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        workspace.add_buffer(Buffer::new());
+        workspace.open_buffer(Path::new("tests/sample/modeline")).unwrap();
+
+        let indentation = Indentation{ style: IndentationStyle::Spaces, width: 2 };
+        workspace.set_filetype_settings("python", BufferSettings{
+            indentation: Some(indentation),
+            save_transforms: Some(SaveTransforms{ trim_trailing_whitespace: true, ensure_final_newline: false }),
+            ..BufferSettings::default()
+        });
+
+        assert!(workspace.current_buffer().unwrap().save_transforms().trim_trailing_whitespace);
+        workspace.previous_buffer();
+        assert!(!workspace.current_buffer().unwrap().save_transforms().trim_trailing_whitespace);
+        assert_eq!(workspace.filetype_settings("Python").and_then(|settings| settings.indentation), Some(indentation));
+    }
+
+    #[test]
+    fn current_styled_lines_restyles_lines_when_the_theme_changes() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        workspace.open_buffer(Path::new("tests/sample/file")).unwrap();
+        let dark_lines = workspace.current_styled_lines(&LineRange::new(0, 1)).unwrap();
+
+        workspace.themes.select("Solarized (light)").unwrap();
+        let light_lines = workspace.current_styled_lines(&LineRange::new(0, 1)).unwrap();
+
+        assert!(dark_lines[0].spans[0].style != light_lines[0].spans[0].style);
+        assert!(workspace.current_html(&LineRange::new(0, 1)).unwrap().contains("it works!"));
+    }
+
+    #[test]
+    fn current_styled_lines_requires_a_buffer() {
+        let workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+
+        assert!(workspace.current_styled_lines(&LineRange::new(0, 1)).is_err());
+        assert!(workspace.current_ansi(&LineRange::new(0, 1), false).is_err());
+    }
+
+    #[test]
+    fn add_buffer_with_kind_replaces_the_existing_preview() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        workspace.add_buffer(Buffer::new());
+        workspace.add_buffer_with_kind(Buffer::new(), BufferKind::Preview);
+        workspace.add_buffer(Buffer::new());
+        workspace.add_buffer_with_kind(Buffer::new(), BufferKind::Preview);
+
+        let kinds: Vec<BufferKind> = workspace.list_buffers().iter().map(|listing| listing.kind).collect();
+        assert_eq!(kinds, vec![BufferKind::Normal, BufferKind::Normal, BufferKind::Preview]);
+        assert!(workspace.current_buffer().unwrap().read_only);
+    }
+
+    #[test]
+    fn next_and_previous_buffer_skip_transient_buffers() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        let mut buf = Buffer::new();
+        buf.insert("scribe");
+        workspace.add_buffer(buf);
+        workspace.add_buffer_with_kind(Buffer::new(), BufferKind::Transient);
+        let mut buf = Buffer::new();
+        buf.insert("library");
+        workspace.add_buffer(buf);
+
+        workspace.next_buffer();
+        assert_eq!(workspace.current_buffer().unwrap().data(), "scribe");
+        workspace.next_buffer();
+        assert_eq!(workspace.current_buffer().unwrap().data(), "library");
+        workspace.previous_buffer();
+        assert_eq!(workspace.current_buffer().unwrap().data(), "scribe");
+    }
+
+    #[test]
+    fn close_transient_buffers_keeps_the_current_buffer_selected() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        workspace.add_buffer_with_kind(Buffer::new(), BufferKind::Transient);
+        let mut buf = Buffer::new();
+        buf.insert("scribe");
+        workspace.add_buffer(buf);
+        workspace.add_buffer_with_kind(Buffer::new(), BufferKind::Transient);
+        workspace.previous_buffer();
+
+        workspace.close_transient_buffers();
+
+        assert_eq!(workspace.buffers.len(), 1);
+        assert_eq!(workspace.current_buffer().unwrap().data(), "scribe");
+    }
+
+    #[test]
+    fn list_buffers_lists_pinned_buffers_first() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        workspace.add_buffer(Buffer::new());
+        workspace.add_buffer(Buffer::new());
+        workspace.set_current_buffer_pinned(true).unwrap();
+
+        let ids: Vec<usize> = workspace.list_buffers().iter().map(|listing| listing.id).collect();
+        assert_eq!(ids, vec![1, 0]);
+
+        workspace.set_current_buffer_pinned(false).unwrap();
+        let ids: Vec<usize> = workspace.list_buffers().iter().map(|listing| listing.id).collect();
+        assert_eq!(ids, vec![0, 1]);
+    }
+
+    #[test]
+    fn close_other_buffers_keeps_the_current_and_pinned_buffers() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        workspace.add_buffer(Buffer::new());
+        workspace.set_current_buffer_pinned(true).unwrap();
+        workspace.add_buffer(Buffer::new());
+        workspace.add_buffer_with_kind(Buffer::new(), BufferKind::Transient);
+        workspace.add_buffer(Buffer::new());
+        workspace.previous_buffer();
+
+        workspace.close_other_buffers();
+
+        let ids: Vec<Option<usize>> = workspace.buffers.iter().map(|buffer| buffer.id).collect();
+        assert_eq!(ids, vec![Some(0), Some(1)]);
+        assert_eq!(workspace.current_buffer().unwrap().id, Some(1));
+    }
+
+    #[test]
+    fn open_buffer_selects_transient_buffers_with_the_specified_path() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        let buf = Buffer::from_file(Path::new("tests/sample/file")).unwrap();
+        workspace.add_buffer_with_kind(buf, BufferKind::Transient);
+        workspace.add_buffer(Buffer::new());
+
+        workspace.open_buffer(Path::new("tests/sample/file")).unwrap();
+
+        assert_eq!(workspace.buffers.len(), 2);
+        assert_eq!(workspace.current_buffer_kind(), Some(BufferKind::Transient));
+    }
+
+    #[test]
+    fn paste_returns_false_for_empty_registers() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        assert!(workspace.paste(None).is_err());
+
+        workspace.add_buffer(Buffer::new());
+        assert!(!workspace.paste(Some('a')).unwrap());
+    }
+
+    #[test]
+    fn search_workspace_requires_a_search_index() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+
+        assert!(workspace.search_workspace("works").is_err());
+        assert!(workspace.find_symbol("works").is_err());
+    }
+
+    #[test]
+    fn search_workspace_reverts_to_disk_content_when_buffers_are_closed() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        workspace.build_search_index();
+        workspace.open_buffer(Path::new("tests/sample/file")).unwrap();
+        workspace.current_buffer().unwrap().insert("scribe ");
+        assert_eq!(workspace.search_workspace("scribe it").unwrap().len(), 1);
+
+        workspace.close_current_buffer();
+        let file_path = workspace.path.join("file");
+        assert!(workspace.search_workspace("scribe it").unwrap().is_empty());
+        assert!(workspace.search_workspace("it works").unwrap().iter().any(|m| m.path == file_path));
+    }
+
+    #[test]
+    fn closing_buffers_does_not_track_unindexed_paths_without_a_search_index() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        workspace.open_buffer(Path::new("tests/sample/file")).unwrap();
+        workspace.close_current_buffer();
+
+        assert!(workspace.unindexed_paths.is_empty());
+    }
+
+    #[test]
+    fn file_changed_reindexes_created_and_deleted_files() {
+        use std::fs;
+
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        workspace.build_search_index();
+        fs::write("tests/sample/indexed", "fn scribe_index() {}").unwrap();
+        workspace.file_changed(Path::new("tests/sample/indexed"));
+        let created_matches = workspace.find_symbol("scribe_index").unwrap();
+
+        fs::remove_file("tests/sample/indexed").unwrap();
+        workspace.file_changed(Path::new("tests/sample/indexed"));
+        let deleted_matches = workspace.find_symbol("scribe_index").unwrap();
+
+        assert_eq!(created_matches.len(), 1);
+        assert_eq!(created_matches[0].path, workspace.path.join("indexed"));
+        assert!(deleted_matches.is_empty());
+    }
+
+    #[test]
+    fn parse_location_splits_lines_and_columns_from_paths() {
+        assert_eq!(
+            parse_location("src/lib.rs:12:5"),
+            (Path::new("src/lib.rs"), Some(Position{ line: 11, offset: 4 }))
+        );
+        assert_eq!(
+            parse_location("src/lib.rs:12"),
+            (Path::new("src/lib.rs"), Some(Position{ line: 11, offset: 0 }))
+        );
+        assert_eq!(
+            parse_location("src/lib:rs:12"),
+            (Path::new("src/lib:rs"), Some(Position{ line: 11, offset: 0 }))
+        );
+    }
+
+    #[test]
+    fn parse_location_treats_specs_without_line_numbers_as_paths() {
+        assert_eq!(parse_location("src/lib.rs"), (Path::new("src/lib.rs"), None));
+        assert_eq!(parse_location("src/lib.rs:main"), (Path::new("src/lib.rs:main"), None));
+        assert_eq!(parse_location(":12"), (Path::new(":12"), None));
+    }
+
+    #[test]
+    fn open_location_positions_the_cursor_in_already_open_buffers() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        workspace.open_buffer(Path::new("tests/sample/file")).unwrap();
+        workspace.add_buffer(Buffer::new());
+
+        workspace.open_location("tests/sample/file:1:4").unwrap();
+
+        assert_eq!(workspace.buffers.len(), 2);
+        assert_eq!(*workspace.current_buffer().unwrap().cursor, Position{ line: 0, offset: 3 });
+    }
+
+    #[test]
+    fn open_location_clamps_columns_to_the_line() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+        workspace.open_location("tests/sample/file:1:100").unwrap();
+
+        assert_eq!(*workspace.current_buffer().unwrap().cursor, Position{ line: 0, offset: 9 });
+    }
+
+    #[test]
+    fn open_location_returns_errors_for_missing_files() {
+        let mut workspace = Workspace::new(Path::new("tests/sample")).unwrap();
+
+        assert!(workspace.open_location("tests/sample/missing:1:1").is_err());
+        assert!(workspace.current_buffer().is_none());
+    }
 }